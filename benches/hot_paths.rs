@@ -0,0 +1,309 @@
+//! Criterion benchmarks for the request hot paths.
+//!
+//! Run with `cargo bench`. Every group runs against synthetic fixtures (see
+//! [`support`]), so vnStat does not need to be installed. Criterion prints
+//! the measured numbers and, on subsequent runs, the change relative to the
+//! previous run stored under `target/criterion`; use
+//! `cargo bench -- --save-baseline <name>` / `--baseline <name>` to compare
+//! against a named baseline.
+
+mod support;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use futures_util::future::join_all;
+use http_body_util::BodyExt;
+use std::hint::black_box;
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+use tokio::sync::broadcast::error::RecvError;
+use tower::ServiceExt;
+use vnstat_rs_api::config::AppConfig;
+use vnstat_rs_api::config::vnstat::VnstatConfig;
+use vnstat_rs_api::model::jsend::JsendResponse;
+use vnstat_rs_api::model::vnstat::VnstatData;
+use vnstat_rs_api::router::{AppState, router_with_state};
+use vnstat_rs_api::service::aggregate::{TimeRange, aggregate_interfaces};
+use vnstat_rs_api::service::fields::TrafficFields;
+use vnstat_rs_api::service::stats::{Granularity, interface_stats};
+use vnstat_rs_api::service::vnstat_service::VnstatService;
+use vnstat_rs_api::task_handle::TaskMessage;
+use vnstat_rs_api::task_manager::TaskManager;
+use vnstat_rs_api::utils::traffic_unit::TrafficUnit;
+
+/// Number of live lines pushed through the fan-out benchmark per iteration.
+const LIVE_LINES: usize = 50;
+
+/// Number of concurrent requests per iteration of the throughput benchmark.
+const THROUGHPUT_REQUESTS: usize = 64;
+
+/// Traffic fields selected by the shaping benchmarks, as a dashboard
+/// showing daily and monthly figures would.
+const SHAPED_FIELDS: &str = "day,month,total";
+
+fn runtime() -> Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("build tokio runtime")
+}
+
+/// Deserialising a full `vnstat --json` document into [`VnstatData`].
+fn bench_parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    for fixture in support::fixtures() {
+        eprintln!(
+            "fixture `{}`: {} interfaces, {} bytes",
+            fixture.name,
+            fixture.interfaces,
+            fixture.json.len()
+        );
+        group.throughput(Throughput::Bytes(fixture.json.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(fixture.name),
+            &fixture.json,
+            |b, json| b.iter(|| serde_json::from_str::<VnstatData>(black_box(json)).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+/// The service fetch path: fake subprocess for the first call, then the
+/// cached result for every following call.
+fn bench_fetch(c: &mut Criterion) {
+    let rt = runtime();
+    let mut group = c.benchmark_group("fetch");
+    for fixture in support::fixtures() {
+        let service = VnstatService::new(VnstatConfig {
+            executable: support::fake_vnstat(&fixture).display().to_string(),
+            ..VnstatConfig::default()
+        });
+        rt.block_on(service.fetch_vnstat_data())
+            .expect("fake vnstat");
+
+        group.bench_function(BenchmarkId::new("all", fixture.name), |b| {
+            b.to_async(&rt)
+                .iter(|| async { service.fetch_vnstat_data().await.unwrap() })
+        });
+
+        let last = support::interface_name(fixture.interfaces - 1);
+        group.bench_function(BenchmarkId::new("interface", fixture.name), |b| {
+            b.to_async(&rt)
+                .iter(|| async { service.fetch_interface_stats(&last).await.unwrap() })
+        });
+    }
+    group.finish();
+}
+
+/// Looking up and serialising one interface of the long-history dataset,
+/// as `GET /vnstat/{if_name}` does on a warm cache. The bytes allocated per
+/// request are printed before the timings.
+fn bench_interface_alloc(c: &mut Criterion) {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("build tokio runtime");
+    let fixture = support::history_fixture();
+    let service = VnstatService::new(VnstatConfig {
+        executable: support::fake_vnstat(&fixture).display().to_string(),
+        ..VnstatConfig::default()
+    });
+    rt.block_on(service.fetch_vnstat_data())
+        .expect("fake vnstat");
+
+    let last = support::interface_name(fixture.interfaces - 1);
+    let request = || async {
+        let fetched = service.fetch_interface_stats(&last).await.unwrap();
+        serde_json::to_vec(&JsendResponse::success_with_data(fetched.value)).unwrap()
+    };
+
+    let (_, lookup) = support::alloc::allocated_by(|| {
+        rt.block_on(async { service.fetch_interface_stats(&last).await.unwrap() })
+    });
+    let (body, total) = support::alloc::allocated_by(|| rt.block_on(request()));
+    eprintln!(
+        "fixture `{}`: {} interfaces, interface lookup allocates {} bytes, \
+         lookup + serialisation {} bytes for a {}-byte body",
+        fixture.name,
+        fixture.interfaces,
+        lookup,
+        total,
+        body.len()
+    );
+
+    c.bench_function("interface_alloc/history", |b| b.to_async(&rt).iter(request));
+}
+
+/// Serialising the JSend envelope returned by `GET /vnstat/`.
+fn bench_serialize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("serialize");
+    for fixture in support::fixtures() {
+        let data: VnstatData = serde_json::from_str(&fixture.json).unwrap();
+        let response = JsendResponse::success_with_data(data);
+        group.bench_function(BenchmarkId::from_parameter(fixture.name), |b| {
+            b.iter(|| serde_json::to_vec(black_box(&response)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+/// Summaries computed from parsed data: the `/total` sums across all
+/// interfaces, and the `/{if_name}/stats` rate statistics of the last
+/// interface.
+fn bench_summary(c: &mut Criterion) {
+    let mut group = c.benchmark_group("summary");
+    for fixture in support::fixtures() {
+        let data: VnstatData = serde_json::from_str(&fixture.json).unwrap();
+        group.bench_function(BenchmarkId::new("total", fixture.name), |b| {
+            b.iter(|| aggregate_interfaces(black_box(&data), TimeRange::default()))
+        });
+
+        let interface = data.interfaces.last().unwrap();
+        group.bench_function(BenchmarkId::new("stats", fixture.name), |b| {
+            b.iter(|| {
+                interface_stats(
+                    black_box(interface),
+                    Granularity::FiveMinute,
+                    TimeRange::default(),
+                )
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Shaping one interface into a JSend body: `?include=` field selection,
+/// and `?unit=` conversion of every traffic value.
+fn bench_shaping(c: &mut Criterion) {
+    let fields = TrafficFields::from_query(Some(SHAPED_FIELDS), None)
+        .unwrap()
+        .unwrap();
+
+    let mut group = c.benchmark_group("shaping");
+    for fixture in support::fixtures() {
+        let data: VnstatData = serde_json::from_str(&fixture.json).unwrap();
+        let interface = data.interfaces.last().unwrap();
+
+        group.bench_function(BenchmarkId::new("fields", fixture.name), |b| {
+            b.iter(|| {
+                let view = fields.view(black_box(interface).clone());
+                serde_json::to_vec(&JsendResponse::success_with_data(view)).unwrap()
+            })
+        });
+        group.bench_function(BenchmarkId::new("unit", fixture.name), |b| {
+            b.iter(|| {
+                let mut value = serde_json::to_value(black_box(interface)).unwrap();
+                TrafficUnit::Gib.convert_json(&mut value);
+                serde_json::to_vec(&JsendResponse::success_with_data(value)).unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Requests per second on the traffic endpoint, `GET /vnstat/{if_name}`,
+/// through the whole router with a warm cache; vnStat is replaced by
+/// [`support::FixtureExecutor`]. Each iteration sends
+/// [`THROUGHPUT_REQUESTS`] concurrent requests, so the reported elements/s
+/// are requests/s, as is and with fields and unit shaping.
+fn bench_throughput(c: &mut Criterion) {
+    let rt = runtime();
+    let config = AppConfig::default();
+
+    let mut group = c.benchmark_group("throughput");
+    group.throughput(Throughput::Elements(THROUGHPUT_REQUESTS as u64));
+    for fixture in support::fixtures() {
+        let executor = Arc::new(support::FixtureExecutor::new(&fixture));
+        let service = VnstatService::with_executor(config.vnstat.clone(), executor);
+        let router = router_with_state(AppState::with_service(&config, Arc::new(service)));
+
+        let last = support::interface_name(fixture.interfaces - 1);
+        let shaped = format!("/vnstat/{}?include={}&unit=gib", last, SHAPED_FIELDS);
+        for (label, uri) in [("traffic", format!("/vnstat/{}", last)), ("shaped", shaped)] {
+            let request = || {
+                let router = router.clone();
+                let request = Request::get(uri.as_str()).body(Body::empty()).unwrap();
+                async move {
+                    let response = router.oneshot(request).await.unwrap();
+                    assert_eq!(response.status(), StatusCode::OK);
+                    response.into_body().collect().await.unwrap().to_bytes()
+                }
+            };
+            let body = rt.block_on(request());
+            eprintln!(
+                "fixture `{}`: {} answers {} bytes",
+                fixture.name,
+                uri,
+                body.len()
+            );
+
+            group.bench_function(BenchmarkId::new(label, fixture.name), |b| {
+                b.to_async(&rt)
+                    .iter(|| join_all((0..THROUGHPUT_REQUESTS).map(|_| request())))
+            });
+        }
+    }
+    group.finish();
+}
+
+/// One live process fanned out to N subscribers, measured until every
+/// subscriber has seen end-of-stream.
+fn bench_live_fanout(c: &mut Criterion) {
+    let rt = runtime();
+    let lines = support::write_scratch("live.jsonl", &support::live_lines(LIVE_LINES));
+    // The short delay lets every subscriber attach before output starts, so
+    // none of them miss end-of-stream.
+    let cmd = vec![
+        "sh".to_string(),
+        "-c".to_string(),
+        format!("sleep 0.01; exec cat '{}'", lines.display()),
+    ];
+
+    let mut group = c.benchmark_group("live_fanout");
+    group.throughput(Throughput::Elements(LIVE_LINES as u64));
+    for subscribers in [1usize, 10, 100] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(subscribers),
+            &subscribers,
+            |b, &subscribers| {
+                b.to_async(&rt).iter(|| {
+                    let cmd = cmd.clone();
+                    async move {
+                        let manager = Arc::new(TaskManager::new());
+                        let mut receivers = Vec::with_capacity(subscribers);
+                        for _ in 0..subscribers {
+                            let subscription = manager
+                                .subscribe("bench".to_string(), cmd.clone(), None)
+                                .await
+                                .expect("no limits configured");
+                            receivers.push(subscription);
+                        }
+                        for (mut rx, _replay, _guard) in receivers {
+                            loop {
+                                match rx.recv().await {
+                                    Ok(TaskMessage::Eof(_)) | Err(RecvError::Closed) => break,
+                                    _ => {}
+                                }
+                            }
+                        }
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_parse,
+    bench_fetch,
+    bench_interface_alloc,
+    bench_serialize,
+    bench_summary,
+    bench_shaping,
+    bench_throughput,
+    bench_live_fanout
+);
+criterion_main!(benches);
@@ -0,0 +1,231 @@
+//! Synthetic vnStat fixtures, a fake `vnstat` executable and an in-process
+//! executor for the benchmark suite.
+//!
+//! Fixtures are generated deterministically rather than checked in so the
+//! large dataset does not bloat the repository. The fake executable is a
+//! tiny shell script that prints a fixture to stdout, which lets the service
+//! layer run its real subprocess + parse path without vnStat installed.
+//! [`FixtureExecutor`] answers from memory instead, for benchmarks of the
+//! request path that should not measure process spawning.
+
+pub mod alloc;
+
+use serde_json::{Value, json};
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::time::Duration;
+use vnstat_rs_api::service::executor::{CommandFuture, CommandOutput, Program, VnstatExecutor};
+use vnstat_rs_api::task_handle::TaskSource;
+
+/// Number of records per period, roughly matching vnStat's default
+/// retention (`5MinuteHours 48`, `HourlyDays 4`, `DailyDays 62`, ...).
+const FIVEMINUTE_RECORDS: u32 = 576;
+const HOUR_RECORDS: u32 = 96;
+const DAY_RECORDS: u32 = 62;
+const MONTH_RECORDS: u32 = 25;
+const YEAR_RECORDS: u32 = 5;
+const TOP_RECORDS: u32 = 10;
+
+/// Day and month records of the long-history dataset: two years of each.
+const HISTORY_DAY_RECORDS: u32 = 730;
+const HISTORY_MONTH_RECORDS: u32 = 24;
+
+/// Base timestamp for generated records (2025-01-01 00:00:00 UTC).
+const BASE_TS: i64 = 1_735_689_600;
+
+/// A named dataset used across benchmark groups.
+pub struct Fixture {
+    /// Short label used as the benchmark parameter (`small`, `large`).
+    pub name: &'static str,
+    /// Number of interfaces in the dataset.
+    pub interfaces: usize,
+    /// The `vnstat --json` document.
+    pub json: String,
+}
+
+/// Returns the small (2-interface) and large (60-interface) datasets.
+pub fn fixtures() -> Vec<Fixture> {
+    vec![fixture("small", 2), fixture("large", 60)]
+}
+
+/// Returns a dataset of 50 interfaces with two years of day and month
+/// records each, as kept by a vnStat configured for long retention.
+pub fn history_fixture() -> Fixture {
+    build_fixture("history", 50, HISTORY_DAY_RECORDS, HISTORY_MONTH_RECORDS)
+}
+
+/// Builds a dataset with `interfaces` interfaces and the default
+/// retention.
+fn fixture(name: &'static str, interfaces: usize) -> Fixture {
+    build_fixture(name, interfaces, DAY_RECORDS, MONTH_RECORDS)
+}
+
+/// Builds a dataset with `interfaces` interfaces holding `days` day and
+/// `months` month records each.
+fn build_fixture(name: &'static str, interfaces: usize, days: u32, months: u32) -> Fixture {
+    let ifaces: Vec<Value> = (0..interfaces)
+        .map(|n| interface(n, days, months))
+        .collect();
+    let json = json!({
+        "vnstatversion": "2.12",
+        "jsonversion": "2",
+        "interfaces": ifaces,
+    })
+    .to_string();
+
+    Fixture {
+        name,
+        interfaces,
+        json,
+    }
+}
+
+/// Returns the name of the `n`th generated interface.
+pub fn interface_name(n: usize) -> String {
+    format!("eth{}", n)
+}
+
+fn interface(n: usize, days: u32, months: u32) -> Value {
+    let seed = n as u64 + 1;
+    json!({
+        "name": interface_name(n),
+        "alias": "",
+        "created": { "date": { "year": 2020, "month": 1, "day": 1 }, "timestamp": 1_577_836_800 },
+        "updated": {
+            "date": { "year": 2025, "month": 3, "day": 4 },
+            "time": { "hour": 12, "minute": 30 },
+            "timestamp": BASE_TS + 5_400_000
+        },
+        "traffic": {
+            "total": { "rx": seed * 1_000_000_000_000, "tx": seed * 250_000_000_000 },
+            "fiveminute": timed_records(FIVEMINUTE_RECORDS, 300, seed),
+            "hour": timed_records(HOUR_RECORDS, 3600, seed),
+            "day": dated_records(days, 86_400, seed),
+            "month": (0..months).map(|i| json!({
+                "id": i,
+                "date": { "year": 2023 + (i / 12) as i32, "month": i % 12 + 1 },
+                "timestamp": BASE_TS + i as i64 * 2_592_000,
+                "rx": seed * (i as u64 + 1) * 10_000_000_000,
+                "tx": seed * (i as u64 + 1) * 2_500_000_000,
+            })).collect::<Vec<_>>(),
+            "year": (0..YEAR_RECORDS).map(|i| json!({
+                "id": i,
+                "date": { "year": 2021 + i as i32 },
+                "timestamp": BASE_TS + i as i64 * 31_536_000,
+                "rx": seed * (i as u64 + 1) * 100_000_000_000,
+                "tx": seed * (i as u64 + 1) * 25_000_000_000,
+            })).collect::<Vec<_>>(),
+            "top": dated_records(TOP_RECORDS, 86_400, seed),
+        }
+    })
+}
+
+fn timed_records(count: u32, step: i64, seed: u64) -> Vec<Value> {
+    (0..count)
+        .map(|i| {
+            let ts = BASE_TS + i as i64 * step;
+            json!({
+                "id": i,
+                "date": { "year": 2025, "month": 1, "day": 1 + (ts - BASE_TS) / 86_400 % 28 },
+                "time": { "hour": (ts / 3600) % 24, "minute": (ts / 60) % 60 },
+                "timestamp": ts,
+                "rx": seed * (i as u64 % 97 + 1) * 1_048_576,
+                "tx": seed * (i as u64 % 89 + 1) * 262_144,
+            })
+        })
+        .collect()
+}
+
+fn dated_records(count: u32, step: i64, seed: u64) -> Vec<Value> {
+    (0..count)
+        .map(|i| {
+            let ts = BASE_TS + i as i64 * step;
+            json!({
+                "id": i,
+                "date": { "year": 2025, "month": 1 + (i / 28) % 12, "day": 1 + i % 28 },
+                "timestamp": ts,
+                "rx": seed * (i as u64 % 31 + 1) * 1_073_741_824,
+                "tx": seed * (i as u64 % 29 + 1) * 268_435_456,
+            })
+        })
+        .collect()
+}
+
+/// Returns `count` lines shaped like `vnstat --json -l` output.
+pub fn live_lines(count: usize) -> String {
+    (0..count)
+        .map(|i| {
+            json!({
+                "index": i,
+                "seconds": i,
+                "rx": { "ratestring": "1.23 Mbit/s", "bytespersecond": 153_750, "packetspersecond": 120, "bytes": 153_750, "packets": 120, "totalbytes": 153_750 * i, "totalpackets": 120 * i },
+                "tx": { "ratestring": "456 kbit/s", "bytespersecond": 57_000, "packetspersecond": 80, "bytes": 57_000, "packets": 80, "totalbytes": 57_000 * i, "totalpackets": 80 * i },
+            })
+            .to_string()
+                + "\n"
+        })
+        .collect()
+}
+
+/// Writes `contents` to a file in a per-process scratch directory and
+/// returns its path.
+pub fn write_scratch(name: &str, contents: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("vnstat-rs-api-bench-{}", std::process::id()));
+    fs::create_dir_all(&dir).expect("create bench scratch dir");
+    let path = dir.join(name);
+    fs::write(&path, contents).expect("write bench scratch file");
+    path
+}
+
+/// Creates a fake `vnstat` executable that prints `fixture` for any
+/// arguments and returns its path.
+pub fn fake_vnstat(fixture: &Fixture) -> PathBuf {
+    let data = write_scratch(&format!("{}.json", fixture.name), &fixture.json);
+    let script = write_scratch(
+        &format!("vnstat-{}", fixture.name),
+        &format!("#!/bin/sh\nexec cat '{}'\n", data.display()),
+    );
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).expect("chmod fake vnstat");
+    script
+}
+
+/// A [`VnstatExecutor`] printing a fixture for every `vnstat` run, without
+/// spawning a process.
+pub struct FixtureExecutor {
+    json: String,
+}
+
+impl FixtureExecutor {
+    /// Creates an executor printing `fixture`.
+    pub fn new(fixture: &Fixture) -> Self {
+        Self {
+            json: fixture.json.clone(),
+        }
+    }
+}
+
+impl VnstatExecutor for FixtureExecutor {
+    fn run<'a>(
+        &'a self,
+        _program: Program,
+        _args: &'a [&'a str],
+        _timeout: Duration,
+    ) -> CommandFuture<'a> {
+        Box::pin(async move {
+            Ok(CommandOutput {
+                code: Some(0),
+                stdout: self.json.clone().into_bytes(),
+                stderr: Vec::new(),
+            })
+        })
+    }
+
+    fn live(&self, _args: Vec<String>) -> TaskSource {
+        TaskSource::from(vec!["true".to_string()])
+    }
+
+    fn is_available(&self, program: Program) -> bool {
+        program == Program::Vnstat
+    }
+}
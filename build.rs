@@ -0,0 +1,34 @@
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Embeds the short git commit hash as `VNSTAT_RS_API_GIT_COMMIT`, or
+/// `unknown` when building outside a git checkout, and the build time as
+/// `VNSTAT_RS_API_BUILD_TIMESTAMP` (Unix seconds, taken from
+/// `SOURCE_DATE_EPOCH` when set for reproducible builds).
+fn main() {
+    let commit = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let built_at = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|s| s.trim().parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default()
+        });
+
+    println!("cargo:rustc-env=VNSTAT_RS_API_GIT_COMMIT={}", commit);
+    println!("cargo:rustc-env=VNSTAT_RS_API_BUILD_TIMESTAMP={}", built_at);
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+}
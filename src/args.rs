@@ -16,8 +16,12 @@ use clap::Parser;
     or automated scripts. Built for performance, safety, and extensibility in Rust."
 )]
 pub struct Args {
-    #[arg(short, long, default_value = "config.toml", help = "Config file")]
-    pub config: String,
+    #[arg(
+        short,
+        long,
+        help = "Config file (.toml, .yaml/.yml or .json; default: search ./, /etc/vnstat-rs-api/, $XDG_CONFIG_HOME/vnstat-rs-api/)"
+    )]
+    pub config: Option<String>,
 
     #[arg(short, long, default_value = "false", help = "Enable debug mode")]
     pub debug: bool,
@@ -0,0 +1,83 @@
+use super::error::ConfigErrors;
+use super::traits::ConfigEntity;
+use crate::utils::secret::secrets_match;
+use serde::Deserialize;
+
+/// Configuration for the administrative endpoints under `/admin`.
+///
+/// Admin endpoints are disabled by default. When enabled, every request must
+/// carry `Authorization: Bearer <token>`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AdminConfig {
+    /// Master switch for the admin endpoints.
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Bearer token required to access the admin endpoints.
+    #[serde(default)]
+    pub token: Option<String>,
+
+    /// Upper bounds (milliseconds, ascending) of the request latency
+    /// histograms reported by `GET /admin/stats`.
+    #[serde(default = "default_latency_buckets_ms")]
+    pub latency_buckets_ms: Vec<f64>,
+}
+
+impl Default for AdminConfig {
+    /// Returns a disabled `AdminConfig` without a token and with the
+    /// default latency buckets.
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            token: None,
+            latency_buckets_ms: default_latency_buckets_ms(),
+        }
+    }
+}
+
+impl AdminConfig {
+    /// Checks a presented bearer token against the configured one, in
+    /// constant time (see [`secrets_match`]).
+    ///
+    /// # Returns
+    ///
+    /// `true` if the admin endpoints are enabled and `token` matches.
+    pub fn authorize(&self, token: &str) -> bool {
+        self.token
+            .as_deref()
+            .filter(|_| self.enabled)
+            .is_some_and(|expected| secrets_match(expected, token))
+    }
+}
+
+impl ConfigEntity for AdminConfig {
+    /// Validates that a token is configured when the admin endpoints are
+    /// enabled, and the latency buckets.
+    ///
+    /// Reports `enabled` with a missing or empty `token`, a latency bucket
+    /// that is not a positive number, and buckets that are not strictly
+    /// ascending.
+    fn validate(&self, errors: &mut ConfigErrors) {
+        if self.enabled && self.token.as_deref().is_none_or(str::is_empty) {
+            errors.add("token", "must be set when the admin endpoints are enabled");
+        }
+
+        if self
+            .latency_buckets_ms
+            .iter()
+            .any(|&le| !le.is_finite() || le <= 0.0)
+        {
+            errors.add("latency_buckets_ms", "must only hold positive numbers");
+        } else if self.latency_buckets_ms.windows(2).any(|w| w[0] >= w[1]) {
+            errors.add("latency_buckets_ms", "must be strictly ascending");
+        }
+    }
+}
+
+/// Returns the default latency buckets (5 ms to 10 s).
+fn default_latency_buckets_ms() -> Vec<f64> {
+    vec![
+        5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0, 10000.0,
+    ]
+}
@@ -0,0 +1,73 @@
+use super::error::ConfigErrors;
+use super::traits::ConfigEntity;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Configuration of the vnStat data cache.
+///
+/// By default the cache is filled on demand: the first request after the
+/// cached snapshot expires runs vnStat. With a refresh interval, a
+/// background task refreshes the snapshot before it expires instead.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CacheConfig {
+    /// Seconds a fetched snapshot (and a rendered image) is served before
+    /// vnStat is run again.
+    #[serde(default = "default_ttl_seconds")]
+    pub ttl_seconds: u64,
+
+    /// Seconds between two background refreshes; `0` fetches on demand.
+    #[serde(default)]
+    pub refresh_interval_seconds: u64,
+}
+
+impl Default for CacheConfig {
+    /// Returns an on-demand `CacheConfig` with the default lifetime.
+    fn default() -> Self {
+        Self {
+            ttl_seconds: default_ttl_seconds(),
+            refresh_interval_seconds: 0,
+        }
+    }
+}
+
+impl CacheConfig {
+    /// Returns how long a fetched snapshot is served before a refresh is
+    /// attempted.
+    pub fn ttl(&self) -> Duration {
+        Duration::from_secs(self.ttl_seconds)
+    }
+
+    /// Returns the background refresh interval, or `None` if the cache is
+    /// filled on demand.
+    pub fn refresh_interval(&self) -> Option<Duration> {
+        (self.refresh_interval_seconds > 0)
+            .then(|| Duration::from_secs(self.refresh_interval_seconds))
+    }
+}
+
+impl ConfigEntity for CacheConfig {
+    /// Validates the cache lifetime, and that a refresh happens before the
+    /// cached snapshot expires.
+    ///
+    /// Reports a zero `ttl_seconds`, and a `refresh_interval_seconds` that
+    /// is not shorter than `ttl_seconds`.
+    fn validate(&self, errors: &mut ConfigErrors) {
+        if self.ttl_seconds == 0 {
+            errors.add("ttl_seconds", "must be at least 1");
+        } else if self.refresh_interval_seconds >= self.ttl_seconds {
+            errors.add(
+                "refresh_interval_seconds",
+                format!(
+                    "must be below the cache lifetime of {} seconds",
+                    self.ttl_seconds
+                ),
+            );
+        }
+    }
+}
+
+/// Returns the default cache lifetime (one minute).
+fn default_ttl_seconds() -> u64 {
+    60
+}
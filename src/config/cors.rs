@@ -0,0 +1,41 @@
+use super::traits::ConfigEntity;
+use serde::Deserialize;
+
+/// Configuration for Cross-Origin Resource Sharing (CORS).
+///
+/// CORS is disabled by default. When enabled, empty lists are interpreted as
+/// "allow any" for origins, methods and headers.
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CorsConfig {
+    /// Master switch for the CORS layer.
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Origins allowed to access the API. Empty means any origin.
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+
+    /// HTTP methods allowed in cross-origin requests. Empty means any method.
+    #[serde(default)]
+    pub allowed_methods: Vec<String>,
+
+    /// Request headers allowed in cross-origin requests. Empty means any
+    /// header.
+    #[serde(default)]
+    pub allowed_headers: Vec<String>,
+
+    /// Response headers exposed to browser scripts.
+    #[serde(default)]
+    pub expose_headers: Vec<String>,
+
+    /// Whether cookies and `Authorization` headers may be sent.
+    #[serde(default)]
+    pub allow_credentials: bool,
+
+    /// Max age (in seconds) for caching preflight responses.
+    #[serde(default)]
+    pub max_age: Option<u64>,
+}
+
+impl ConfigEntity for CorsConfig {}
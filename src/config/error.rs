@@ -0,0 +1,167 @@
+use super::traits::ConfigEntity;
+use std::fmt;
+
+/// A problem found while validating the configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    /// Dotted path of the offending setting as written in the configuration
+    /// file, e.g. `server.listen` or `server.listeners[1].tls`.
+    pub field: String,
+    /// What is wrong with it.
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// Collects the problems found by [`ConfigEntity::validate`], so that all of
+/// them are reported at once instead of one per start.
+///
+/// Fields are named relative to the section being validated; the section
+/// path is prepended by [`validate`](Self::validate) and
+/// [`section`](Self::section).
+#[derive(Debug, Default)]
+pub struct ConfigErrors {
+    /// Dotted path of the section being validated, empty at the top level.
+    path: String,
+    errors: Vec<ConfigError>,
+}
+
+impl ConfigErrors {
+    /// Records a problem with `field` of the current section; an empty
+    /// `field` names the section itself.
+    pub fn add(&mut self, field: &str, message: impl Into<String>) {
+        let field = match (self.path.is_empty(), field.is_empty()) {
+            (true, _) => field.to_string(),
+            (false, true) => self.path.clone(),
+            (false, false) => format!("{}.{}", self.path, field),
+        };
+
+        self.errors.push(ConfigError {
+            field,
+            message: message.into(),
+        });
+    }
+
+    /// Runs `f` with `name` appended to the section path.
+    pub fn section(&mut self, name: &str, f: impl FnOnce(&mut Self)) {
+        let parent_len = self.path.len();
+        if !self.path.is_empty() {
+            self.path.push('.');
+        }
+        self.path.push_str(name);
+
+        f(self);
+
+        self.path.truncate(parent_len);
+    }
+
+    /// Validates `entity` as the subsection `name` of the current section.
+    pub fn validate(&mut self, name: &str, entity: &impl ConfigEntity) {
+        self.section(name, |errors| entity.validate(errors));
+    }
+
+    /// Returns the problems recorded so far, in the order they were found.
+    pub fn errors(&self) -> &[ConfigError] {
+        &self.errors
+    }
+
+    /// Returns `true` if no problem was recorded.
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Returns `Ok` if no problem was recorded, otherwise the collected
+    /// problems as an error.
+    ///
+    /// # Errors
+    ///
+    /// Returns `self` if it holds at least one problem.
+    pub fn into_result(self) -> Result<(), Self> {
+        if self.is_empty() { Ok(()) } else { Err(self) }
+    }
+}
+
+impl fmt::Display for ConfigErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.errors.as_slice() {
+            [error] => write!(f, "Invalid configuration: {}", error),
+            errors => {
+                write!(f, "Invalid configuration ({} problems):", errors.len())?;
+                for error in errors {
+                    write!(f, "\n- {}", error)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigErrors {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+
+    #[test]
+    fn reports_every_problem_together() {
+        let mut config = AppConfig::default();
+        // Any executable will do; vnStat need not be installed.
+        config.vnstat.executable = "/bin/sh".to_string();
+        config.server.max_record_limit = 0;
+        config.server.worker_threads = Some(0);
+        config.cache.refresh_interval_seconds = 600;
+
+        let mut errors = ConfigErrors::default();
+        config.validate(&mut errors);
+
+        let fields: Vec<_> = errors.errors().iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            [
+                "server.max_record_limit",
+                "server.worker_threads",
+                "cache.refresh_interval_seconds",
+            ]
+        );
+        let message = errors.into_result().unwrap_err().to_string();
+        assert!(
+            message.starts_with("Invalid configuration (3 problems):"),
+            "{}",
+            message
+        );
+        assert!(
+            message.contains("\n- server.worker_threads: must be at least 1"),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn names_fields_by_their_section_path() {
+        let mut errors = ConfigErrors::default();
+        errors.add("top", "a");
+        errors.section("server", |errors| {
+            errors.section("listeners[1]", |errors| errors.add("tls", "b"));
+            errors.add("", "c");
+        });
+
+        let fields: Vec<_> = errors.errors().iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, ["top", "server.listeners[1].tls", "server"]);
+    }
+
+    #[test]
+    fn a_single_problem_is_reported_on_one_line() {
+        let mut errors = ConfigErrors::default();
+        errors.add("port", "must not be 0");
+
+        assert_eq!(
+            errors.to_string(),
+            "Invalid configuration: port: must not be 0"
+        );
+    }
+}
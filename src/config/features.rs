@@ -0,0 +1,54 @@
+use super::traits::ConfigEntity;
+use serde::Deserialize;
+
+/// Route groups served by the router (`[server.features]`).
+///
+/// A disabled group's routes are not registered at all, so its paths are
+/// answered like any unknown path (`404` with code `10014`) rather than
+/// revealing that the endpoint exists. Every group is enabled by default.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FeaturesConfig {
+    /// The live streams: `/live`, `/{if_name}/live` (SSE, WebSocket and long
+    /// polling) and `/{if_name}/updates`.
+    #[serde(default = "default_enabled")]
+    pub live_streams: bool,
+
+    /// The raw passthrough endpoints `/raw` and `/{if_name}/raw`; see also
+    /// `vnstat.expose_raw`.
+    #[serde(default = "default_enabled")]
+    pub raw_endpoints: bool,
+
+    /// The endpoints adding and removing interfaces and setting their
+    /// aliases; see also `vnstat.allow_management`.
+    #[serde(default = "default_enabled")]
+    pub management: bool,
+
+    /// The `/admin` endpoints; see also `admin.enabled`.
+    #[serde(default = "default_enabled")]
+    pub admin: bool,
+
+    /// The request metrics at `/admin/stats`.
+    #[serde(default = "default_enabled")]
+    pub metrics: bool,
+}
+
+impl Default for FeaturesConfig {
+    /// Returns a `FeaturesConfig` enabling every route group.
+    fn default() -> Self {
+        FeaturesConfig {
+            live_streams: true,
+            raw_endpoints: true,
+            management: true,
+            admin: true,
+            metrics: true,
+        }
+    }
+}
+
+impl ConfigEntity for FeaturesConfig {}
+
+/// Returns whether a route group is enabled by default (`true`).
+fn default_enabled() -> bool {
+    true
+}
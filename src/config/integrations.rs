@@ -0,0 +1,40 @@
+use super::error::ConfigErrors;
+use super::mqtt::MqttConfig;
+use super::traits::ConfigEntity;
+use serde::Deserialize;
+
+/// Endpoints and publishers speaking the protocols of third-party tools
+/// (`[integrations]`).
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IntegrationsConfig {
+    /// The Grafana JSON datasource endpoints under `/grafana`.
+    #[serde(default)]
+    pub grafana: GrafanaConfig,
+
+    /// Publishing to an MQTT broker; disabled unless the section is
+    /// present.
+    #[serde(default)]
+    pub mqtt: Option<MqttConfig>,
+}
+
+impl ConfigEntity for IntegrationsConfig {
+    /// Validates the MQTT section, if present.
+    fn validate(&self, errors: &mut ConfigErrors) {
+        if let Some(mqtt) = &self.mqtt {
+            errors.validate("mqtt", mqtt);
+        }
+    }
+}
+
+/// Configuration of the Grafana JSON datasource endpoints
+/// (`[integrations.grafana]`).
+///
+/// Disabled by default; while disabled the endpoints answer `404`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GrafanaConfig {
+    /// Master switch for the Grafana endpoints.
+    #[serde(default)]
+    pub enabled: bool,
+}
@@ -0,0 +1,237 @@
+use super::error::ConfigErrors;
+use super::traits::ConfigEntity;
+use crate::utils::cidr::Cidr;
+use crate::utils::secret::secrets_match;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::path::PathBuf;
+
+/// Port bound when neither `port` nor `listen` names one (`3000`).
+pub const DEFAULT_PORT: u16 = 3000;
+
+/// One address the server listens on (`[[server.listeners]]`), with the
+/// protection of the requests arriving there.
+///
+/// All listeners serve the same API and state; they only differ in how
+/// they are reached and who may use them.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ListenerConfig {
+    /// IP address or host name to bind, optionally with a port
+    /// (`0.0.0.0:8080`, `[::1]:8080`, `myhost.internal:8080`) that takes
+    /// precedence over `port`; the aliases of
+    /// [`ServerConfig::listen`](super::server::ServerConfig::listen) are
+    /// accepted.
+    #[serde(default = "default_listen")]
+    pub listen: String,
+    /// Port to bind; required unless `listen` names one.
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// Which of the addresses a host name in `listen` resolves to is bound.
+    #[serde(default)]
+    pub address_family: AddressFamily,
+    /// Serve HTTPS with this certificate instead of plain HTTP.
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+    /// Keys of which requests must carry one in the `X-API-Key` header;
+    /// empty lets every request in.
+    #[serde(default)]
+    pub api_keys: Vec<String>,
+    /// Networks of the clients allowed on this listener; `server.allowed_ips`
+    /// if unset, and every client if that is empty too.
+    #[serde(default)]
+    pub allowed_ips: Option<Vec<Cidr>>,
+}
+
+/// Certificate and private key of an HTTPS listener.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    /// PEM file with the certificate chain, leaf first.
+    pub cert: PathBuf,
+    /// PEM file with the private key (PKCS#8, PKCS#1 or SEC1).
+    pub key: PathBuf,
+}
+
+/// The IP version a listen address is restricted to.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AddressFamily {
+    /// The first address the host name resolves to, of either version.
+    #[default]
+    Any,
+    /// The first IPv4 address.
+    Ipv4,
+    /// The first IPv6 address.
+    Ipv6,
+}
+
+impl AddressFamily {
+    /// Returns `true` if `addr` belongs to this family.
+    fn matches(self, addr: &SocketAddr) -> bool {
+        match self {
+            AddressFamily::Any => true,
+            AddressFamily::Ipv4 => addr.is_ipv4(),
+            AddressFamily::Ipv6 => addr.is_ipv6(),
+        }
+    }
+}
+
+impl fmt::Display for AddressFamily {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AddressFamily::Any => "IP",
+            AddressFamily::Ipv4 => "IPv4",
+            AddressFamily::Ipv6 => "IPv6",
+        })
+    }
+}
+
+impl ListenerConfig {
+    /// Resolves the listen address and port into the `SocketAddr` to bind.
+    ///
+    /// A host name is looked up with the system resolver, blocking until it
+    /// answers, and the first address of `address_family` is used. The port
+    /// is the one named in `listen`, else `port`, else [`DEFAULT_PORT`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the host if the port in `listen` is invalid,
+    /// the host name does not resolve, or it has no address of
+    /// `address_family`.
+    pub fn to_socket_addr(&self) -> Result<SocketAddr> {
+        let (host, embedded_port) = split_listen(&self.listen)?;
+        let port = embedded_port.or(self.port).unwrap_or(DEFAULT_PORT);
+
+        let mut addrs = (host, port)
+            .to_socket_addrs()
+            .with_context(|| format!("Failed to resolve listen address `{}`", host))?;
+        addrs
+            .find(|addr| self.address_family.matches(addr))
+            .with_context(|| {
+                format!(
+                    "Listen address `{}` has no {} address",
+                    host, self.address_family
+                )
+            })
+    }
+
+    /// Returns `port` if `listen` names a port as well, which takes
+    /// precedence.
+    pub fn overridden_port(&self) -> Option<u16> {
+        let (_, embedded_port) = split_listen(&self.listen).ok()?;
+        embedded_port.and(self.port)
+    }
+
+    /// Checks a presented API key against the configured ones, in constant
+    /// time (see [`secrets_match`]).
+    ///
+    /// # Returns
+    ///
+    /// `true` if `key` is one of `api_keys`.
+    pub fn authorize(&self, key: &str) -> bool {
+        // Compare against every key, so that timing does not reveal which
+        // one matched.
+        self.api_keys.iter().fold(false, |found, expected| {
+            found | secrets_match(expected, key)
+        })
+    }
+}
+
+impl ConfigEntity for ListenerConfig {
+    /// Resolves the hostname aliases of `listen` (see
+    /// [`resolve_listen_alias`]).
+    fn finalize(&mut self) -> Result<()> {
+        resolve_listen_alias(&mut self.listen, self.address_family);
+
+        Ok(())
+    }
+
+    /// Reports a `listen` that does not resolve (see
+    /// [`to_socket_addr`](Self::to_socket_addr)), a missing port, an empty
+    /// API key, and a `tls` section naming an empty path.
+    fn validate(&self, errors: &mut ConfigErrors) {
+        match split_listen(&self.listen) {
+            Ok((_, None)) if self.port.is_none() => {
+                errors.add("port", "must be set unless `listen` names a port");
+            }
+            Ok(_) => {
+                if let Err(e) = self.to_socket_addr() {
+                    errors.add("listen", format!("{:#}", e));
+                }
+            }
+            Err(e) => errors.add("listen", format!("{:#}", e)),
+        }
+
+        if self.api_keys.iter().any(String::is_empty) {
+            errors.add("api_keys", "must not contain empty keys");
+        }
+
+        if let Some(tls) = &self.tls
+            && (tls.cert.as_os_str().is_empty() || tls.key.as_os_str().is_empty())
+        {
+            errors.add("tls", "`cert` and `key` must both be set");
+        }
+    }
+}
+
+/// Replaces the hostname aliases `localhost` (`127.0.0.1`, or `::1` for
+/// [`AddressFamily::Ipv6`]), `localhost6` and `ip6-localhost` (`::1`) with
+/// their addresses.
+pub(super) fn resolve_listen_alias(listen: &mut String, family: AddressFamily) {
+    if listen.eq_ignore_ascii_case("localhost") {
+        *listen = match family {
+            AddressFamily::Ipv6 => "::1",
+            _ => "127.0.0.1",
+        }
+        .to_string();
+    } else if listen.eq_ignore_ascii_case("localhost6")
+        || listen.eq_ignore_ascii_case("ip6-localhost")
+    {
+        *listen = "::1".to_string();
+    }
+}
+
+/// Splits `listen` into the host and the port it names, if any.
+///
+/// IPv6 addresses with a port are written in brackets (`[::1]:8080`); an
+/// unbracketed IPv6 address is taken as a whole.
+fn split_listen(listen: &str) -> Result<(&str, Option<u16>)> {
+    let parse_port = |port: &str| {
+        port.parse::<u16>()
+            .with_context(|| format!("Invalid port `{}` in listen address `{}`", port, listen))
+    };
+
+    if listen.parse::<IpAddr>().is_ok() {
+        return Ok((listen, None));
+    }
+
+    if let Some(rest) = listen.strip_prefix('[') {
+        let (host, port) = rest
+            .split_once(']')
+            .with_context(|| format!("Missing `]` in listen address `{}`", listen))?;
+        return match port {
+            "" => Ok((host, None)),
+            port => match port.strip_prefix(':') {
+                Some(port) => Ok((host, Some(parse_port(port)?))),
+                None => Err(anyhow::anyhow!(
+                    "Unexpected `{}` after `]` in listen address `{}`",
+                    port,
+                    listen
+                )),
+            },
+        };
+    }
+
+    match listen.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') => Ok((host, Some(parse_port(port)?))),
+        _ => Ok((listen, None)),
+    }
+}
+
+/// Returns the default listen address (`0.0.0.0`).
+pub(super) fn default_listen() -> String {
+    "0.0.0.0".to_string()
+}
@@ -0,0 +1,161 @@
+use super::error::ConfigErrors;
+use super::traits::ConfigEntity;
+use anyhow::Context;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tracing::Level;
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::filter::Directive;
+
+/// Configuration for application and access logging.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LoggingConfig {
+    /// Default log level (`trace`, `debug`, `info`, `warn` or `error`).
+    /// `--debug` raises it to `debug`; `RUST_LOG` overrides both.
+    #[serde(default = "default_level")]
+    pub level: String,
+
+    /// Per-module levels (e.g. `"vnstat_rs_api::task_handle" = "warn"`)
+    /// overriding the default level for that target and its children.
+    /// `off` silences a module entirely.
+    #[serde(default)]
+    pub filters: BTreeMap<String, String>,
+
+    /// Output format of log lines.
+    #[serde(default)]
+    pub format: LogFormat,
+
+    /// Also write logs to this file, rotating it by size. Console output is
+    /// kept either way.
+    #[serde(default)]
+    pub file: Option<PathBuf>,
+
+    /// Size in MiB at which the log file is rotated.
+    #[serde(default = "default_max_size_mb")]
+    pub max_size_mb: u64,
+
+    /// Number of rotated files (`<file>.1` … `<file>.N`) to keep.
+    #[serde(default = "default_max_files")]
+    pub max_files: usize,
+
+    /// Log one line per HTTP request (and per SSE connection open/close).
+    #[serde(default = "default_access_log")]
+    pub access_log: bool,
+
+    /// Generate an `X-Request-Id` for requests that arrive without one.
+    /// Disable when an upstream proxy already assigns IDs.
+    #[serde(default = "default_generate_request_id")]
+    pub generate_request_id: bool,
+}
+
+/// Output format of log lines.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable lines with a local timestamp.
+    #[default]
+    Text,
+    /// One JSON object per line (`timestamp`, `level`, `target`, `message`
+    /// and `request_id` when available).
+    Json,
+}
+
+impl LoggingConfig {
+    /// Returns the configured default level.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `level` is not a valid level name.
+    pub fn level(&self) -> anyhow::Result<Level> {
+        self.level
+            .parse()
+            .with_context(|| format!("Invalid log level `{}`", self.level))
+    }
+
+    /// Returns the per-module `filters` as filter directives.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the module if its level is not a valid level
+    /// name (or `off`), or if the module name cannot be used as a target.
+    pub fn directives(&self) -> anyhow::Result<Vec<Directive>> {
+        self.filters
+            .iter()
+            .map(|(module, level)| {
+                let level: LevelFilter = level.parse().with_context(|| {
+                    format!("Invalid log level `{}` for module `{}`", level, module)
+                })?;
+
+                format!("{}={}", module, level)
+                    .parse()
+                    .with_context(|| format!("Invalid log filter module `{}`", module))
+            })
+            .collect()
+    }
+}
+
+impl ConfigEntity for LoggingConfig {
+    /// Validates the log level and rotation settings.
+    ///
+    /// Reports:
+    /// - A `level` that is not a valid level name.
+    /// - A `filters` entry with an invalid level or module name.
+    /// - A zero `max_size_mb` while `file` is set.
+    fn validate(&self, errors: &mut ConfigErrors) {
+        if let Err(e) = self.level() {
+            errors.add("level", format!("{:#}", e));
+        }
+
+        if let Err(e) = self.directives() {
+            errors.add("filters", format!("{:#}", e));
+        }
+
+        if self.file.is_some() && self.max_size_mb == 0 {
+            errors.add("max_size_mb", "must be greater than zero");
+        }
+    }
+}
+
+impl Default for LoggingConfig {
+    /// Returns a `LoggingConfig` logging text at `info` to the console only,
+    /// with access logging and request-ID generation enabled.
+    fn default() -> Self {
+        LoggingConfig {
+            level: default_level(),
+            filters: BTreeMap::new(),
+            format: LogFormat::default(),
+            file: None,
+            max_size_mb: default_max_size_mb(),
+            max_files: default_max_files(),
+            access_log: default_access_log(),
+            generate_request_id: default_generate_request_id(),
+        }
+    }
+}
+
+/// Returns the default log level (`info`).
+fn default_level() -> String {
+    "info".to_string()
+}
+
+/// Returns the default log file rotation size (`10` MiB).
+fn default_max_size_mb() -> u64 {
+    10
+}
+
+/// Returns the default number of rotated log files kept (`5`).
+fn default_max_files() -> usize {
+    5
+}
+
+/// Returns whether access logging is enabled by default (`true`).
+fn default_access_log() -> bool {
+    true
+}
+
+/// Returns whether missing request IDs are generated by default (`true`).
+fn default_generate_request_id() -> bool {
+    true
+}
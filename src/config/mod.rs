@@ -1,25 +1,54 @@
+use self::error::ConfigErrors;
 use self::traits::ConfigEntity;
+use crate::config::admin::AdminConfig;
+use crate::config::cache::CacheConfig;
 use crate::config::cors::CorsConfig;
+use crate::config::integrations::IntegrationsConfig;
+use crate::config::logging::LoggingConfig;
+use crate::config::quota::QuotaConfig;
 use crate::config::server::ServerConfig;
 use crate::config::vnstat::VnstatConfig;
-use anyhow::{Result, bail};
+use crate::utils::interface_filter::InterfaceFilter;
+use anyhow::{Context, Result, bail};
 use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+pub mod admin;
+pub mod cache;
 pub mod cors;
+pub mod error;
+pub mod features;
+pub mod integrations;
+pub mod listener;
+pub mod logging;
+pub mod mqtt;
+pub mod quota;
 pub mod server;
+pub mod sse;
 pub mod traits;
 pub mod vnstat;
+pub mod webhook;
 
-/// Top-level application configuration deserialized from a TOML file.
+/// Top-level application configuration deserialized from a TOML, YAML or
+/// JSON file.
 ///
-/// Contains three subsections:
-/// - `server`: required, controls the HTTP listener settings.
-/// - `vnstat`: optional with defaults, configures the vnStat binary path.
-/// - `cors`:  optional with defaults, configures CORS behaviour.
-#[derive(Debug, Deserialize)]
+/// Contains eight subsections, all optional with defaults:
+/// - `server`: controls the HTTP listener settings.
+/// - `vnstat`: configures the vnStat binary path.
+/// - `cors`:  configures CORS behaviour.
+/// - `logging`: configures log level, log file and access logging.
+/// - `admin`: enables and protects the administrative endpoints.
+/// - `quota`: bandwidth limits per interface.
+/// - `cache`: background refreshing of the vnStat data cache.
+/// - `integrations`: endpoints and publishers for third-party tools such as
+///   Grafana and MQTT.
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct AppConfig {
+    #[serde(default)]
     pub server: ServerConfig,
 
     #[serde(default)]
@@ -27,6 +56,26 @@ pub struct AppConfig {
 
     #[serde(default)]
     pub cors: CorsConfig,
+
+    #[serde(default)]
+    pub logging: LoggingConfig,
+
+    #[serde(default)]
+    pub admin: AdminConfig,
+
+    #[serde(default)]
+    pub quota: QuotaConfig,
+
+    #[serde(default)]
+    pub cache: CacheConfig,
+
+    #[serde(default)]
+    pub integrations: IntegrationsConfig,
+
+    /// The file this configuration was loaded from, or `None` for the
+    /// built-in defaults.
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
 }
 
 impl ConfigEntity for AppConfig {
@@ -42,32 +91,218 @@ impl ConfigEntity for AppConfig {
     fn finalize(&mut self) -> Result<()> {
         self.server.finalize()?;
         self.vnstat.finalize()?;
+        self.logging.finalize()?;
+        self.admin.finalize()?;
 
         Ok(())
     }
 
     /// Validates all sub-configurations after finalization.
     ///
-    /// Delegates to each child's `validate()` in turn.
+    /// Delegates to each child's `validate()` in turn, so that the problems
+    /// of every section are reported together, named by their dotted path
+    /// (e.g. `server.listen`).
+    fn validate(&self, errors: &mut ConfigErrors) {
+        errors.validate("server", &self.server);
+        errors.validate("vnstat", &self.vnstat);
+        errors.validate("logging", &self.logging);
+        errors.validate("admin", &self.admin);
+        errors.validate("quota", &self.quota);
+        errors.validate("cache", &self.cache);
+        errors.validate("integrations", &self.integrations);
+    }
+}
+
+impl AppConfig {
+    /// Returns the sections of `new` that differ from `self` in settings
+    /// only read at startup, i.e. that a reload cannot apply.
+    ///
+    /// Sections are named as in the configuration file; `vnstat`, `logging`
+    /// and `cache` leave out what
+    /// [`AppState::reload`](crate::router::AppState::reload) applies, and
+    /// of `quota` only the webhooks are compared.
+    pub fn restart_required(&self, new: &AppConfig) -> Vec<&'static str> {
+        let startup_vnstat = |config: &VnstatConfig| VnstatConfig {
+            include_interfaces: Vec::new(),
+            exclude_interfaces: Vec::new(),
+            aliases: BTreeMap::new(),
+            interface_filter: InterfaceFilter::default(),
+            max_stale_seconds: 0,
+            extra_args: Vec::new(),
+            live_extra_args: Vec::new(),
+            ..config.clone()
+        };
+        let startup_logging = |config: &LoggingConfig| LoggingConfig {
+            level: String::new(),
+            ..config.clone()
+        };
+        let startup_cache = |config: &CacheConfig| CacheConfig {
+            ttl_seconds: 0,
+            ..config.clone()
+        };
+
+        [
+            ("server", self.server != new.server),
+            (
+                "vnstat",
+                startup_vnstat(&self.vnstat) != startup_vnstat(&new.vnstat),
+            ),
+            ("cors", self.cors != new.cors),
+            (
+                "logging",
+                startup_logging(&self.logging) != startup_logging(&new.logging),
+            ),
+            ("admin", self.admin != new.admin),
+            ("quota.webhooks", self.quota.webhooks != new.quota.webhooks),
+            (
+                "cache",
+                startup_cache(&self.cache) != startup_cache(&new.cache),
+            ),
+            ("integrations", self.integrations != new.integrations),
+        ]
+        .into_iter()
+        .filter_map(|(section, differs)| differs.then_some(section))
+        .collect()
+    }
+}
+
+/// Deserializes an [`AppConfig`] from `deserializer`, naming the key an
+/// error occurred at.
+fn with_key_path<'de, D>(deserializer: D) -> Result<AppConfig>
+where
+    D: serde::Deserializer<'de>,
+    D::Error: std::error::Error + Send + Sync + 'static,
+{
+    serde_path_to_error::deserialize(deserializer).map_err(|e| {
+        let path = e.path().to_string();
+        let error = anyhow::Error::new(e.into_inner());
+        if path == "." {
+            error
+        } else {
+            error.context(format!("Invalid setting `{}`", path))
+        }
+    })
+}
+
+/// Base name (without extension) of the configuration file looked up in the
+/// standard search paths.
+const CONFIG_FILE_STEM: &str = "config";
+
+/// Application directory name used under `/etc` and `$XDG_CONFIG_HOME`.
+const CONFIG_DIR_NAME: &str = "vnstat-rs-api";
+
+/// Supported configuration file formats, selected by file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    /// `.toml`
+    Toml,
+    /// `.yaml` or `.yml`
+    Yaml,
+    /// `.json`
+    Json,
+}
+
+impl ConfigFormat {
+    /// File extensions tried, in order, for each search directory.
+    const SEARCH_EXTENSIONS: [&'static str; 4] = ["toml", "yaml", "yml", "json"];
+
+    /// Detects the format from the extension of `path` (case-insensitive).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error listing the supported formats if the extension is
+    /// missing or unknown.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+
+        match ext.as_deref() {
+            Some("toml") => Ok(ConfigFormat::Toml),
+            Some("yaml") | Some("yml") => Ok(ConfigFormat::Yaml),
+            Some("json") => Ok(ConfigFormat::Json),
+            _ => bail!(
+                "Unsupported config file format `{}`; supported formats: .toml, .yaml, .yml, .json",
+                path.display()
+            ),
+        }
+    }
+
+    /// Deserializes `content` in this format.
     ///
     /// # Errors
     ///
-    /// Returns the first validation error encountered from any child
-    /// configuration.
-    fn validate(&self) -> Result<()> {
-        self.server.validate()?;
-        self.vnstat.validate()?;
+    /// Returns the underlying parser error, which includes the line and
+    /// column of the problem, in the context of the dotted path of the
+    /// offending key (e.g. `server.port`). Unknown keys are errors that list
+    /// the keys valid at that place.
+    fn parse(self, content: &str) -> Result<AppConfig> {
+        match self {
+            ConfigFormat::Toml => with_key_path(toml::Deserializer::parse(content)?),
+            // serde_norway already names the key path in its errors.
+            ConfigFormat::Yaml => Ok(serde_norway::from_str(content)?),
+            ConfigFormat::Json => {
+                let mut deserializer = serde_json::Deserializer::from_str(content);
+                let config = with_key_path(&mut deserializer)?;
+                deserializer.end()?;
+                Ok(config)
+            }
+        }
+    }
+}
 
-        Ok(())
+/// Returns the configuration file locations tried, in order, when no
+/// `--config` argument is given.
+///
+/// Directories are tried in this order:
+///
+/// 1. `.` (the working directory)
+/// 2. `/etc/vnstat-rs-api`
+/// 3. `$XDG_CONFIG_HOME/vnstat-rs-api` (only when the variable is set and
+///    non-empty)
+///
+/// Within each directory `config.toml`, `config.yaml`, `config.yml` and
+/// `config.json` are tried in that order.
+pub fn search_paths() -> Vec<PathBuf> {
+    search_paths_in(
+        Path::new("."),
+        Path::new("/etc"),
+        std::env::var_os("XDG_CONFIG_HOME"),
+    )
+}
+
+/// Returns the [`search_paths`] for the given working directory, `/etc`
+/// and `$XDG_CONFIG_HOME`.
+fn search_paths_in(work_dir: &Path, etc: &Path, xdg: Option<OsString>) -> Vec<PathBuf> {
+    let mut dirs = vec![work_dir.to_path_buf(), etc.join(CONFIG_DIR_NAME)];
+
+    if let Some(xdg) = xdg.filter(|v| !v.is_empty()) {
+        dirs.push(PathBuf::from(xdg).join(CONFIG_DIR_NAME));
     }
+
+    dirs.iter()
+        .flat_map(|dir| {
+            ConfigFormat::SEARCH_EXTENSIONS
+                .iter()
+                .map(move |ext| dir.join(format!("{}.{}", CONFIG_FILE_STEM, ext)))
+        })
+        .collect()
 }
 
-/// Loads, finalizes, and validates the application configuration from a TOML
-/// file.
+/// Loads the application configuration.
+///
+/// When `file_path` is given, that file is loaded and must exist. Otherwise
+/// the [`search_paths`] are tried in order and the first existing file is
+/// used; if none exists, the built-in defaults are used instead. In every
+/// case the result goes through `finalize()` and `validate()`, and
+/// [`AppConfig::source`] records which file was used.
+///
+/// Nothing is logged here, since logging is configured from the result.
 ///
 /// # Arguments
 ///
-/// * `file_path` — Path to the TOML configuration file.
+/// * `file_path` — Explicit path passed via `--config`, if any.
 ///
 /// # Returns
 ///
@@ -76,22 +311,285 @@ impl ConfigEntity for AppConfig {
 /// # Errors
 ///
 /// Returns an error if:
-/// - The file does not exist.
-/// - The file cannot be read.
-/// - The file content is not valid TOML.
+/// - An explicitly given file does not exist.
+/// - The selected file cannot be read or parsed.
 /// - Any `finalize()` or `validate()` step fails.
-pub fn load_config(file_path: &str) -> Result<AppConfig> {
-    if !Path::new(file_path).exists() {
-        bail!("File `{}` does not exist.", file_path);
+pub fn load_config(file_path: Option<&str>) -> Result<AppConfig> {
+    load_config_from(file_path, search_paths())
+}
+
+/// Loads the configuration like [`load_config`], trying `candidates`
+/// instead of the [`search_paths`].
+fn load_config_from(file_path: Option<&str>, candidates: Vec<PathBuf>) -> Result<AppConfig> {
+    let path = match file_path {
+        Some(path) => {
+            if !Path::new(path).exists() {
+                bail!("File `{}` does not exist.", path);
+            }
+            Some(PathBuf::from(path))
+        }
+        None => candidates.into_iter().find(|p| p.exists()),
+    };
+
+    match path {
+        Some(path) => {
+            let mut config = load_config_file(&path)?;
+            config.source = Some(path);
+            Ok(config)
+        }
+        None => finish(AppConfig::default()),
     }
+}
+
+/// Loads, finalizes, and validates the application configuration from a
+/// TOML, YAML or JSON file, chosen by the file extension.
+///
+/// # Arguments
+///
+/// * `file_path` — Path to the configuration file.
+///
+/// # Returns
+///
+/// A fully finalized and validated `AppConfig` instance.
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - The file extension is not a supported format.
+/// - The file cannot be read.
+/// - The file content is not valid for its format.
+/// - Any `finalize()` or `validate()` step fails.
+fn load_config_file(file_path: &Path) -> Result<AppConfig> {
+    let format = ConfigFormat::from_path(file_path)?;
 
     let config_content = fs::read_to_string(file_path)?;
 
-    let mut config: AppConfig = toml::from_str(&config_content)?;
+    let config = format
+        .parse(&config_content)
+        .with_context(|| format!("Failed to parse `{}`", file_path.display()))?;
 
+    finish(config)
+}
+
+/// Runs `finalize()` followed by `validate()` on a freshly built config.
+///
+/// # Errors
+///
+/// Propagates any error from `finalize()`, or returns the [`ConfigErrors`]
+/// holding every problem found by `validate()`.
+fn finish(mut config: AppConfig) -> Result<AppConfig> {
     config.finalize()?;
 
-    config.validate()?;
+    let mut errors = ConfigErrors::default();
+    config.validate(&mut errors);
+    errors.into_result()?;
 
     Ok(config)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the full error of parsing `content` as `format`.
+    fn parse_error(format: ConfigFormat, content: &str) -> String {
+        format!("{:#}", format.parse(content).unwrap_err())
+    }
+
+    #[test]
+    fn toml_typos_name_the_table_and_line() {
+        let message = parse_error(ConfigFormat::Toml, "[vnstat]\n\n[server]\nprot = 3000\n");
+
+        assert!(message.contains("`server.prot`"), "{}", message);
+        assert!(message.contains("line 4"), "{}", message);
+        assert!(
+            message.contains("`port`"),
+            "lists the valid keys: {}",
+            message
+        );
+    }
+
+    #[test]
+    fn yaml_typos_name_the_table_and_line() {
+        let message = parse_error(ConfigFormat::Yaml, "vnstat: {}\nserver:\n  prot: 3000\n");
+        assert!(message.starts_with("server: "), "{}", message);
+        assert!(message.contains("line 3"), "{}", message);
+        assert!(
+            message.contains("`port`"),
+            "lists the valid keys: {}",
+            message
+        );
+    }
+
+    #[test]
+    fn every_format_reads_the_same_configuration() {
+        let parse = |format: ConfigFormat, content: &str| {
+            format
+                .parse(content)
+                .unwrap_or_else(|e| panic!("{:?}: {:#}", format, e))
+        };
+        let toml = parse(
+            ConfigFormat::Toml,
+            include_str!("../../tests/fixtures/config.toml"),
+        );
+        let yaml = parse(
+            ConfigFormat::Yaml,
+            include_str!("../../tests/fixtures/config.yaml"),
+        );
+        let json = parse(
+            ConfigFormat::Json,
+            include_str!("../../tests/fixtures/config.json"),
+        );
+
+        assert_eq!(yaml, toml);
+        assert_eq!(json, toml);
+        assert_eq!(toml.server.port, Some(8080));
+        assert_eq!(toml.vnstat.aliases["eth0"], "uplink");
+        assert_eq!(toml.quota.limits["wg0"].0, 500 << 30);
+        assert_eq!(toml.cache.refresh_interval_seconds, 30);
+    }
+
+    #[test]
+    fn only_settings_read_at_startup_need_a_restart() {
+        let running = AppConfig::default();
+
+        let mut reloadable = AppConfig::default();
+        reloadable.vnstat.max_stale_seconds += 1;
+        reloadable.logging.level = "debug".to_string();
+        reloadable.cache.ttl_seconds += 1;
+        assert!(running.restart_required(&reloadable).is_empty());
+
+        let mut startup = AppConfig::default();
+        startup.server.port = Some(8080);
+        startup.cache.refresh_interval_seconds = 30;
+        assert_eq!(running.restart_required(&startup), ["server", "cache"]);
+    }
+
+    /// Returns an empty scratch directory for the test `name`, holding a
+    /// working directory, an `etc` and an XDG configuration home.
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("config-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for sub in ["work", "etc", "xdg"] {
+            fs::create_dir_all(dir.join(sub)).expect("create scratch directory");
+        }
+        dir
+    }
+
+    /// Writes a valid configuration listening on `port` to `path`, in the
+    /// format of its extension, creating its directory.
+    fn write_config(path: &Path, port: u16) {
+        let executable = std::env::current_exe().expect("test executable");
+        let executable = executable.to_str().expect("UTF-8 path");
+        let content = match ConfigFormat::from_path(path).expect("known format") {
+            ConfigFormat::Toml => format!(
+                "[vnstat]\nexecutable = '{}'\n\n[server]\nport = {}\n",
+                executable, port
+            ),
+            ConfigFormat::Yaml => format!(
+                "vnstat:\n  executable: '{}'\nserver:\n  port: {}\n",
+                executable, port
+            ),
+            ConfigFormat::Json => serde_json::json!({
+                "vnstat": { "executable": executable },
+                "server": { "port": port },
+            })
+            .to_string(),
+        };
+        fs::create_dir_all(path.parent().expect("a directory")).expect("create directory");
+        fs::write(path, content).expect("write configuration");
+    }
+
+    /// Loads the configuration of `file_path`, searching the scratch
+    /// directories of `dir` with `xdg` as `$XDG_CONFIG_HOME`.
+    fn load_in(dir: &Path, file_path: Option<&Path>, xdg: Option<&Path>) -> Result<AppConfig> {
+        let candidates = search_paths_in(
+            &dir.join("work"),
+            &dir.join("etc"),
+            xdg.map(|xdg| xdg.as_os_str().to_os_string()),
+        );
+        load_config_from(
+            file_path.map(|p| p.to_str().expect("UTF-8 path")),
+            candidates,
+        )
+    }
+
+    #[test]
+    fn searches_the_working_directory_then_etc_then_xdg() {
+        let dir = scratch("search-order");
+        let xdg = dir.join("xdg");
+        let in_work = dir.join("work/config.json");
+        let in_etc = dir.join("etc/vnstat-rs-api/config.yaml");
+        let in_xdg = dir.join("xdg/vnstat-rs-api/config.toml");
+
+        // The defaults only validate where vnStat is installed.
+        let uses_defaults = |xdg: Option<&Path>| {
+            load_in(&dir, None, xdg).map_or(true, |config| config.source.is_none())
+        };
+        assert!(uses_defaults(Some(&xdg)));
+
+        write_config(&in_xdg, 3001);
+        let config = load_in(&dir, None, Some(&xdg)).expect("XDG file");
+        assert_eq!(config.source.as_deref(), Some(in_xdg.as_path()));
+        assert_eq!(config.server.port, Some(3001));
+        assert!(uses_defaults(None));
+        assert!(
+            uses_defaults(Some(Path::new(""))),
+            "an empty XDG_CONFIG_HOME is ignored"
+        );
+
+        write_config(&in_etc, 3002);
+        let config = load_in(&dir, None, Some(&xdg)).expect("/etc file");
+        assert_eq!(config.source.as_deref(), Some(in_etc.as_path()));
+        assert_eq!(config.server.port, Some(3002));
+
+        write_config(&in_work, 3003);
+        let config = load_in(&dir, None, Some(&xdg)).expect("working directory file");
+        assert_eq!(config.source.as_deref(), Some(in_work.as_path()));
+        assert_eq!(config.server.port, Some(3003));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn prefers_toml_then_yaml_then_json_within_a_directory() {
+        let dir = scratch("search-formats");
+        for (ext, port) in [
+            ("json", 3001),
+            ("yml", 3002),
+            ("yaml", 3003),
+            ("toml", 3004),
+        ] {
+            let path = dir
+                .join("etc/vnstat-rs-api")
+                .join(format!("config.{}", ext));
+            write_config(&path, port);
+
+            let config = load_in(&dir, None, None).expect("valid configuration");
+            assert_eq!(config.source.as_deref(), Some(path.as_path()), "{}", ext);
+            assert_eq!(config.server.port, Some(port), "{}", ext);
+        }
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn an_explicit_path_overrides_the_search() {
+        let dir = scratch("explicit");
+        let xdg = dir.join("xdg");
+        write_config(&dir.join("work/config.toml"), 3001);
+        write_config(&dir.join("xdg/vnstat-rs-api/config.toml"), 3002);
+        let explicit = dir.join("elsewhere.yml");
+        write_config(&explicit, 3003);
+
+        let config = load_in(&dir, Some(&explicit), Some(&xdg)).expect("explicit file");
+        assert_eq!(config.source.as_deref(), Some(explicit.as_path()));
+        assert_eq!(config.server.port, Some(3003));
+
+        let missing = dir.join("missing.toml");
+        let error = load_in(&dir, Some(&missing), Some(&xdg)).unwrap_err();
+        assert!(error.to_string().contains("does not exist"), "{}", error);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,152 @@
+use super::error::ConfigErrors;
+use super::traits::ConfigEntity;
+use anyhow::{Context, bail};
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Port used when the broker URL does not name one.
+const DEFAULT_PORT: u16 = 1883;
+
+/// Publishing of interface summaries to an MQTT broker
+/// (`[integrations.mqtt]`).
+///
+/// Publishing starts when the section is present.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MqttConfig {
+    /// The broker, as `mqtt://host[:port]` (or `tcp://`); the port
+    /// defaults to 1883.
+    pub broker: String,
+
+    /// Client ID presented to the broker; must be unique per broker.
+    #[serde(default = "default_client_id")]
+    pub client_id: String,
+
+    /// User name to authenticate with, if the broker requires it.
+    #[serde(default)]
+    pub username: Option<String>,
+
+    /// Password to authenticate with, used together with `username`.
+    #[serde(default)]
+    pub password: Option<String>,
+
+    /// First level of every published topic, e.g. `vnstat` for
+    /// `vnstat/eth0/summary`.
+    #[serde(default = "default_topic_prefix")]
+    pub topic_prefix: String,
+
+    /// Seconds between two publications of the summaries.
+    #[serde(default = "default_publish_interval_seconds")]
+    pub publish_interval_seconds: u64,
+
+    /// Quality of service of the published messages (0, 1 or 2).
+    #[serde(default)]
+    pub qos: u8,
+
+    /// Publish Home Assistant MQTT discovery messages, so that the
+    /// interfaces' counters appear as sensors.
+    #[serde(default)]
+    pub home_assistant_discovery: bool,
+
+    /// Topic prefix Home Assistant watches for discovery messages.
+    #[serde(default = "default_discovery_prefix")]
+    pub discovery_prefix: String,
+}
+
+impl MqttConfig {
+    /// Returns the host and port of the broker.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `broker` is not an `mqtt://` or `tcp://` URL
+    /// with a host.
+    pub fn broker_address(&self) -> anyhow::Result<(String, u16)> {
+        let url = reqwest::Url::parse(&self.broker)
+            .with_context(|| format!("Invalid MQTT broker URL `{}`", self.broker))?;
+
+        if !matches!(url.scheme(), "mqtt" | "tcp") {
+            bail!(
+                "MQTT broker URL `{}` must start with mqtt:// or tcp://",
+                self.broker
+            );
+        }
+
+        let Some(host) = url.host_str().filter(|host| !host.is_empty()) else {
+            bail!("MQTT broker URL `{}` has no host", self.broker);
+        };
+
+        Ok((host.to_string(), url.port().unwrap_or(DEFAULT_PORT)))
+    }
+
+    /// Returns the interval between two publications.
+    pub fn publish_interval(&self) -> Duration {
+        Duration::from_secs(self.publish_interval_seconds)
+    }
+}
+
+impl ConfigEntity for MqttConfig {
+    /// Validates the broker, topics and publishing settings.
+    ///
+    /// Reports:
+    /// - A `broker` that is not a valid broker URL (see
+    ///   [`broker_address`](Self::broker_address)).
+    /// - An empty `client_id`.
+    /// - A `password` set without `username`.
+    /// - A `topic_prefix` or `discovery_prefix` that is empty, contains a
+    ///   `+` or `#` wildcard, or starts or ends with `/`.
+    /// - A zero `publish_interval_seconds`.
+    /// - A `qos` above 2.
+    fn validate(&self, errors: &mut ConfigErrors) {
+        if let Err(e) = self.broker_address() {
+            errors.add("broker", format!("{:#}", e));
+        }
+
+        if self.client_id.is_empty() {
+            errors.add("client_id", "must not be empty");
+        }
+
+        if self.password.is_some() && self.username.is_none() {
+            errors.add("password", "requires `username`");
+        }
+
+        validate_topic_prefix(errors, "topic_prefix", &self.topic_prefix);
+        validate_topic_prefix(errors, "discovery_prefix", &self.discovery_prefix);
+
+        if self.publish_interval_seconds == 0 {
+            errors.add("publish_interval_seconds", "must be greater than zero");
+        }
+
+        if self.qos > 2 {
+            errors.add("qos", "must be 0, 1 or 2");
+        }
+    }
+}
+
+/// Reports `field` if `prefix` cannot start a topic name.
+fn validate_topic_prefix(errors: &mut ConfigErrors, field: &str, prefix: &str) {
+    if prefix.is_empty() || prefix.starts_with('/') || prefix.ends_with('/') {
+        errors.add(field, "must not be empty or start or end with `/`");
+    } else if prefix.contains(['+', '#']) {
+        errors.add(field, "must not contain the wildcards `+` or `#`");
+    }
+}
+
+/// Returns the default client ID (`vnstat-rs-api`).
+fn default_client_id() -> String {
+    "vnstat-rs-api".to_string()
+}
+
+/// Returns the default topic prefix (`vnstat`).
+fn default_topic_prefix() -> String {
+    "vnstat".to_string()
+}
+
+/// Returns the default publish interval (`60` seconds).
+fn default_publish_interval_seconds() -> u64 {
+    60
+}
+
+/// Returns Home Assistant's default discovery prefix (`homeassistant`).
+fn default_discovery_prefix() -> String {
+    "homeassistant".to_string()
+}
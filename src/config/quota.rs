@@ -0,0 +1,100 @@
+use super::error::ConfigErrors;
+use super::traits::ConfigEntity;
+use super::webhook::WebhookConfig;
+use crate::utils::byte_size::ByteSize;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Bandwidth quotas per interface (`[quota]`).
+///
+/// Every key other than the settings below is a real interface name mapped
+/// to its limit per billing period, e.g. `eth0 = "2TB"`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct QuotaConfig {
+    /// Day of the month on which a billing period starts (1–28).
+    #[serde(default = "default_period_start_day")]
+    pub period_start_day: u8,
+
+    /// Percentage of the limit from which the status is `warning`.
+    #[serde(default = "default_warning_percent")]
+    pub warning_percent: f64,
+
+    /// Percentage of the limit from which the status is `exceeded`.
+    #[serde(default = "default_exceeded_percent")]
+    pub exceeded_percent: f64,
+
+    /// Notifications sent when a threshold is crossed.
+    #[serde(default)]
+    pub webhooks: WebhookConfig,
+
+    /// Limits keyed by real interface name.
+    #[serde(flatten)]
+    pub limits: BTreeMap<String, ByteSize>,
+}
+
+impl QuotaConfig {
+    /// Returns the limit configured for the interface `name`, if any.
+    pub fn limit(&self, name: &str) -> Option<u64> {
+        self.limits.get(name).map(ByteSize::bytes)
+    }
+}
+
+impl ConfigEntity for QuotaConfig {
+    /// Validates the billing period, thresholds, limits and webhooks.
+    ///
+    /// Reports:
+    /// - A `period_start_day` that is not between 1 and 28, so that every
+    ///   month has the day.
+    /// - A `warning_percent` that is not positive or exceeds
+    ///   `exceeded_percent`.
+    /// - Every zero limit, under the interface name.
+    /// - The problems of the webhook configuration.
+    fn validate(&self, errors: &mut ConfigErrors) {
+        if !(1..=28).contains(&self.period_start_day) {
+            errors.add("period_start_day", "must be between 1 and 28");
+        }
+
+        if !(self.warning_percent > 0.0 && self.warning_percent <= self.exceeded_percent) {
+            errors.add(
+                "warning_percent",
+                "must be positive and not above `exceeded_percent`",
+            );
+        }
+
+        for (name, _) in self.limits.iter().filter(|(_, limit)| limit.bytes() == 0) {
+            errors.add(name, "must be greater than zero");
+        }
+
+        errors.validate("webhooks", &self.webhooks);
+    }
+}
+
+impl Default for QuotaConfig {
+    /// Returns a `QuotaConfig` without limits, billing periods starting on
+    /// the 1st, a warning from 80 % and `exceeded` from 100 %, and no
+    /// webhooks.
+    fn default() -> Self {
+        QuotaConfig {
+            period_start_day: default_period_start_day(),
+            warning_percent: default_warning_percent(),
+            exceeded_percent: default_exceeded_percent(),
+            webhooks: WebhookConfig::default(),
+            limits: BTreeMap::new(),
+        }
+    }
+}
+
+/// Returns the default first day of a billing period (`1`).
+fn default_period_start_day() -> u8 {
+    1
+}
+
+/// Returns the default warning threshold (`80` percent).
+fn default_warning_percent() -> f64 {
+    80.0
+}
+
+/// Returns the default exceeded threshold (`100` percent).
+fn default_exceeded_percent() -> f64 {
+    100.0
+}
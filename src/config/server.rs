@@ -1,38 +1,144 @@
+use super::error::ConfigErrors;
+use super::features::FeaturesConfig;
+use super::listener::{AddressFamily, ListenerConfig, default_listen, resolve_listen_alias};
+use super::sse::SseConfig;
 use super::traits::ConfigEntity;
-use anyhow::{Context, Result, bail};
+use crate::utils::cidr::Cidr;
+use anyhow::Result;
 use serde::Deserialize;
-use std::net::{IpAddr, SocketAddr};
-use std::str::FromStr;
+use std::net::SocketAddr;
 
 /// Configuration for the HTTP server listener.
 ///
-/// Controls the IP address and port the server binds to. The `listen` field
+/// Controls the address and port the server binds to. The `listen` field
 /// supports common hostname aliases (`localhost`, `localhost6`, `ip6-localhost`)
-/// which are resolved to their canonical IP addresses during finalization.
-#[derive(Debug, Deserialize)]
+/// which are resolved to their canonical IP addresses during finalization;
+/// other host names are resolved when the address is bound, and a port may
+/// be given with the address (`0.0.0.0:8080`).
+/// With `[[server.listeners]]`, the server binds those instead (see
+/// [`listeners`](Self::listeners)).
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct ServerConfig {
     #[serde(default = "default_listen")]
     pub listen: String,
-    #[serde(default = "default_port")]
-    pub port: u16,
+    /// Port to bind, [`DEFAULT_PORT`](super::listener::DEFAULT_PORT) if
+    /// neither this nor `listen` names one.
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// Which of the addresses a host name in `listen` resolves to is bound.
+    #[serde(default)]
+    pub address_family: AddressFamily,
+    /// Addresses to listen on, each with its own TLS and client
+    /// restrictions, replacing `listen` and `port`.
+    #[serde(default)]
+    pub listeners: Vec<ListenerConfig>,
+    /// Maximum number of concurrent SSE clients; `0` means unlimited.
+    #[serde(default)]
+    pub max_sse_clients: usize,
+    /// Maximum number of data requests (everything but live streams)
+    /// handled at once; `0` means unlimited. Further requests wait, or are
+    /// rejected with `load_shed`.
+    #[serde(default)]
+    pub max_in_flight: usize,
+    /// Reject data requests with HTTP 503 while `max_in_flight` requests
+    /// are in flight, instead of queueing them.
+    #[serde(default)]
+    pub load_shed: bool,
+    /// Seconds after which a request still being handled is answered with
+    /// HTTP 504; `0` disables the timeout. Live streams are exempt.
+    #[serde(default = "default_request_timeout_seconds")]
+    pub request_timeout_seconds: u64,
+    /// Largest `?limit=` honoured on record lists; larger limits are
+    /// clamped to it. Also caps the points of one InfluxDB export.
+    #[serde(default = "default_max_record_limit")]
+    pub max_record_limit: usize,
+    /// Keep-alive and heartbeat settings of SSE streams (`[server.sse]`).
+    #[serde(default)]
+    pub sse: SseConfig,
+    /// The route groups served (`[server.features]`).
+    #[serde(default)]
+    pub features: FeaturesConfig,
+    /// Path prefix a reverse proxy strips before forwarding requests (e.g.
+    /// `/netstats`), prepended to the redirects the server issues. Empty
+    /// when the API is reached directly.
+    #[serde(default)]
+    pub base_path: String,
+    /// Trust the `X-Forwarded-*` headers set by a reverse proxy. Only enable
+    /// this when every request passes through a proxy that sets or strips
+    /// them, since clients can send them too.
+    #[serde(default)]
+    pub trust_proxy_headers: bool,
+    /// Networks of the reverse proxies whose `X-Forwarded-For` and
+    /// `X-Real-IP` headers are believed when determining the client address.
+    #[serde(default)]
+    pub trusted_proxies: Vec<Cidr>,
+    /// Networks of the clients allowed to use the API; others get HTTP 403.
+    /// Empty allows every client.
+    #[serde(default)]
+    pub allowed_ips: Vec<Cidr>,
+    /// Which Tokio runtime serves the API.
+    #[serde(default)]
+    pub runtime: RuntimeFlavor,
+    /// Worker threads of the multi-threaded runtime; unset uses one per
+    /// CPU (see [`worker_threads`](Self::worker_threads)).
+    #[serde(default)]
+    pub worker_threads: Option<usize>,
+    /// Most threads the runtime starts for blocking work; unset keeps
+    /// Tokio's default of 512.
+    #[serde(default)]
+    pub max_blocking_threads: Option<usize>,
+}
+
+/// The Tokio runtimes the server can run on.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuntimeFlavor {
+    /// Tasks run on `worker_threads` threads.
+    #[default]
+    MultiThread,
+    /// Every task runs on the main thread, for hosts with little memory.
+    CurrentThread,
 }
 
 impl Default for ServerConfig {
     /// Returns a `ServerConfig` with the default listen address (`0.0.0.0`)
-    /// and port (`3000`).
+    /// and port (`3000`) of any address family and no further listeners,
+    /// no limit on SSE clients or in-flight requests, no load shedding, a
+    /// 30-second request timeout, a record limit of at most 10000, default
+    /// SSE settings, every route group enabled, no base path, untrusted
+    /// proxy headers, no trusted proxies, no client restrictions, and a
+    /// multi-threaded runtime with one worker thread per CPU.
     fn default() -> Self {
         ServerConfig {
             listen: default_listen(),
-            port: default_port(),
+            port: None,
+            address_family: AddressFamily::Any,
+            listeners: Vec::new(),
+            max_sse_clients: 0,
+            max_in_flight: 0,
+            load_shed: false,
+            request_timeout_seconds: default_request_timeout_seconds(),
+            max_record_limit: default_max_record_limit(),
+            sse: SseConfig::default(),
+            features: FeaturesConfig::default(),
+            base_path: String::new(),
+            trust_proxy_headers: false,
+            trusted_proxies: Vec::new(),
+            allowed_ips: Vec::new(),
+            runtime: RuntimeFlavor::MultiThread,
+            worker_threads: None,
+            max_blocking_threads: None,
         }
     }
 }
 
 impl ConfigEntity for ServerConfig {
-    /// Finalizes the listen address by resolving common hostname aliases.
+    /// Finalizes the listen addresses by resolving common hostname aliases.
     ///
-    /// Performs the following normalizations:
-    /// - `"localhost"`        → `"127.0.0.1"`
+    /// Performs the following normalizations, on `listen` and on every
+    /// listener:
+    /// - `"localhost"`        → `"127.0.0.1"` (`"::1"` for IPv6)
     /// - `"localhost6"`       → `"::1"`
     /// - `"ip6-localhost"`    → `"::1"`
     ///
@@ -40,36 +146,111 @@ impl ConfigEntity for ServerConfig {
     ///
     /// This method currently never fails.
     fn finalize(&mut self) -> Result<()> {
-        if self.listen.eq_ignore_ascii_case("localhost") {
-            self.listen = "127.0.0.1".to_string();
-        } else if self.listen.eq_ignore_ascii_case("localhost6")
-            || self.listen.eq_ignore_ascii_case("ip6-localhost")
-        {
-            self.listen = "::1".to_string();
+        resolve_listen_alias(&mut self.listen, self.address_family);
+        for listener in &mut self.listeners {
+            listener.finalize()?;
         }
 
         Ok(())
     }
 
-    /// Validates that the listen address is a valid IP address.
-    ///
-    /// # Errors
+    /// Validates the listen address, the listeners, the base path, the
+    /// record limit and the SSE settings.
     ///
-    /// Returns an error if `listen` cannot be parsed as an `IpAddr`.
-    fn validate(&self) -> Result<()> {
-        if std::net::IpAddr::from_str(&self.listen).is_err() {
-            bail!("Invalid listen address: {}", self.listen);
+    /// Reports a `listen` that does not resolve unless listeners replace
+    /// it, the problems of every listener (as `listeners[N]`), listeners
+    /// binding the same address, a non-empty `base_path` not starting with
+    /// `/` or ending with one, a zero `max_record_limit`, `worker_threads`
+    /// or `max_blocking_threads`, and the problems of [`SseConfig`].
+    fn validate(&self, errors: &mut ConfigErrors) {
+        if self.listeners.is_empty()
+            && let Err(e) = self.to_socket_addr()
+        {
+            errors.add("listen", format!("{:#}", e));
         }
 
-        Ok(())
+        let mut bound = Vec::with_capacity(self.listeners.len());
+        for (index, listener) in self.listeners.iter().enumerate() {
+            let name = format!("listeners[{}]", index);
+            errors.validate(&name, listener);
+
+            let Ok(addr) = listener.to_socket_addr() else {
+                continue;
+            };
+            if bound.contains(&addr) {
+                errors.add(&name, format!("binds {} like an earlier listener", addr));
+            }
+            bound.push(addr);
+        }
+
+        if !self.base_path.is_empty()
+            && (!self.base_path.starts_with('/') || self.base_path.ends_with('/'))
+        {
+            errors.add(
+                "base_path",
+                format!(
+                    "`{}` must start with `/` and must not end with one",
+                    self.base_path
+                ),
+            );
+        }
+
+        if self.max_record_limit == 0 {
+            errors.add("max_record_limit", "must be at least 1");
+        }
+
+        if self.worker_threads == Some(0) {
+            errors.add("worker_threads", "must be at least 1");
+        }
+
+        if self.max_blocking_threads == Some(0) {
+            errors.add("max_blocking_threads", "must be at least 1");
+        }
+
+        errors.validate("sse", &self.sse);
     }
 }
 
 impl ServerConfig {
-    /// Converts the configuration into a `SocketAddr`.
-    ///
-    /// Parses the `listen` field as an `IpAddr` and pairs it with the
-    /// configured port.
+    /// Returns the listeners to bind: `listeners` if any are configured,
+    /// otherwise a single plain-HTTP listener on `listen` and `port`
+    /// restricted to `allowed_ips`.
+    pub fn listeners(&self) -> Vec<ListenerConfig> {
+        if !self.listeners.is_empty() {
+            return self.listeners.clone();
+        }
+
+        vec![self.default_listener()]
+    }
+
+    /// Returns the plain-HTTP listener on `listen` and `port`.
+    fn default_listener(&self) -> ListenerConfig {
+        ListenerConfig {
+            listen: self.listen.clone(),
+            port: self.port,
+            address_family: self.address_family,
+            tls: None,
+            api_keys: Vec::new(),
+            allowed_ips: None,
+        }
+    }
+
+    /// Returns the networks of the clients allowed on `listener`: its own
+    /// `allowed_ips`, or `allowed_ips` of the server if it has none.
+    pub fn allowed_ips<'a>(&'a self, listener: &'a ListenerConfig) -> &'a [Cidr] {
+        listener.allowed_ips.as_deref().unwrap_or(&self.allowed_ips)
+    }
+
+    /// Returns the worker threads of the multi-threaded runtime:
+    /// `worker_threads`, or the number of CPUs available to the server.
+    pub fn worker_threads(&self) -> usize {
+        self.worker_threads.unwrap_or_else(|| {
+            std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get)
+        })
+    }
+
+    /// Resolves `listen` and `port` into a `SocketAddr`, as
+    /// [`ListenerConfig::to_socket_addr`] does.
     ///
     /// # Returns
     ///
@@ -77,24 +258,19 @@ impl ServerConfig {
     ///
     /// # Errors
     ///
-    /// Returns an error if the `listen` field cannot be parsed as a valid
-    /// IP address.
+    /// Returns an error naming the host if the `listen` field does not
+    /// resolve to an address of `address_family`.
     pub fn to_socket_addr(&self) -> Result<SocketAddr> {
-        let ip: IpAddr = self
-            .listen
-            .parse()
-            .context("Failed to parse listen address")?;
-
-        Ok(SocketAddr::new(ip, self.port))
+        self.default_listener().to_socket_addr()
     }
 }
 
-/// Returns the default listen address (`0.0.0.0`).
-fn default_listen() -> String {
-    "0.0.0.0".to_string()
+/// Returns the default request timeout (`30` seconds).
+fn default_request_timeout_seconds() -> u64 {
+    30
 }
 
-/// Returns the default HTTP port (`3000`).
-fn default_port() -> u16 {
-    3000
+/// Returns the default largest record limit (`10000`).
+fn default_max_record_limit() -> usize {
+    10_000
 }
@@ -0,0 +1,86 @@
+use super::error::ConfigErrors;
+use super::traits::ConfigEntity;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Configuration for Server-Sent Event streams (`[server.sse]`).
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SseConfig {
+    /// Seconds without any event after which a keep-alive comment is sent.
+    #[serde(default = "default_keep_alive_seconds")]
+    pub keep_alive_seconds: u64,
+
+    /// Text of the keep-alive comment.
+    #[serde(default)]
+    pub keep_alive_text: String,
+
+    /// Seconds without output from vnStat after which a live stream sends a
+    /// `heartbeat <ms timestamp>` comment; `0` disables heartbeats.
+    #[serde(default = "default_heartbeat_seconds")]
+    pub heartbeat_seconds: u64,
+
+    /// Number of recent live events kept per interface for replay to
+    /// clients reconnecting with `Last-Event-ID`; `0` disables replay.
+    #[serde(default = "default_replay_buffer_size")]
+    pub replay_buffer_size: usize,
+}
+
+impl SseConfig {
+    /// Returns the keep-alive interval.
+    pub fn keep_alive_interval(&self) -> Duration {
+        Duration::from_secs(self.keep_alive_seconds)
+    }
+
+    /// Returns the heartbeat interval, or `None` if heartbeats are disabled.
+    pub fn heartbeat_interval(&self) -> Option<Duration> {
+        (self.heartbeat_seconds > 0).then(|| Duration::from_secs(self.heartbeat_seconds))
+    }
+}
+
+impl ConfigEntity for SseConfig {
+    /// Validates the keep-alive settings.
+    ///
+    /// Reports:
+    /// - A zero `keep_alive_seconds`.
+    /// - A `keep_alive_text` containing a line break, which would end the
+    ///   comment early.
+    fn validate(&self, errors: &mut ConfigErrors) {
+        if self.keep_alive_seconds == 0 {
+            errors.add("keep_alive_seconds", "must be greater than zero");
+        }
+
+        if self.keep_alive_text.contains(['\r', '\n']) {
+            errors.add("keep_alive_text", "must not contain line breaks");
+        }
+    }
+}
+
+impl Default for SseConfig {
+    /// Returns an `SseConfig` sending an empty keep-alive comment every 15
+    /// seconds and a heartbeat after 10 seconds without vnStat output, and
+    /// replaying up to 60 events.
+    fn default() -> Self {
+        SseConfig {
+            keep_alive_seconds: default_keep_alive_seconds(),
+            keep_alive_text: String::new(),
+            heartbeat_seconds: default_heartbeat_seconds(),
+            replay_buffer_size: default_replay_buffer_size(),
+        }
+    }
+}
+
+/// Returns the default keep-alive interval (`15` seconds).
+fn default_keep_alive_seconds() -> u64 {
+    15
+}
+
+/// Returns the default heartbeat interval (`10` seconds).
+fn default_heartbeat_seconds() -> u64 {
+    10
+}
+
+/// Returns the default replay buffer size (`60` events).
+fn default_replay_buffer_size() -> usize {
+    60
+}
@@ -1,3 +1,4 @@
+use super::error::ConfigErrors;
 use anyhow::Result;
 
 /// Common interface for configuration types that require post-deserialization
@@ -7,7 +8,8 @@ use anyhow::Result;
 /// - **Finalization** — normalizing or transforming raw parsed values (e.g.
 ///   resolving hostname aliases into IP addresses) before validation.
 /// - **Validation** — checking that the finalised values are semantically
-///   correct (e.g. confirming a file path exists).
+///   correct (e.g. confirming a file path exists), reporting every problem
+///   rather than the first one.
 ///
 /// Both methods have default no-op implementations so that implementors
 /// only need to override the steps they care about.
@@ -28,13 +30,8 @@ pub trait ConfigEntity {
     /// Validates the configuration after finalization.
     ///
     /// Checks that all values are semantically valid and internally
-    /// consistent.
-    ///
-    /// # Errors
-    ///
-    /// Return an error if the configuration is invalid, providing a
-    /// descriptive message about what failed.
-    fn validate(&self) -> Result<()> {
-        Ok(())
-    }
+    /// consistent, recording each problem in `errors` under the name of the
+    /// offending field, and validating subsections with
+    /// [`ConfigErrors::validate`].
+    fn validate(&self, _errors: &mut ConfigErrors) {}
 }
@@ -1,46 +1,562 @@
+use super::error::ConfigErrors;
 use super::traits::ConfigEntity;
-use anyhow::bail;
+use crate::task_handle::DEFAULT_BUFFER_SIZE;
+use crate::utils::interface_filter::InterfaceFilter;
+use crate::utils::process::IoClass;
+use crate::utils::version::Version;
 use serde::Deserialize;
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::env;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
 
 /// Configuration for the vnStat statistics backend.
 ///
 /// Controls the path to the vnStat binary that the application invokes to
-/// query network traffic statistics.
-#[derive(Debug, Deserialize)]
+/// query network traffic statistics, and how fetched data is normalised.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct VnstatConfig {
+    /// Path of the vnStat binary; a bare name (`vnstat`) is looked up on
+    /// `PATH` and replaced with the path found.
     #[serde(default = "default_executable")]
     pub executable: String,
+
+    /// Oldest vnStat version accepted at startup.
+    #[serde(default = "default_min_version")]
+    pub min_version: Version,
+
+    /// Refuse to start when the version check fails. When `false`, a
+    /// warning is logged and the health endpoint reports `degraded`.
+    #[serde(default = "default_enforce_min_version")]
+    pub enforce_min_version: bool,
+
+    /// Seconds a single vnStat invocation may take before it is killed.
+    /// Also bounds how long a live stream may take to produce its first
+    /// sample.
+    #[serde(default = "default_command_timeout_seconds")]
+    pub command_timeout_seconds: u64,
+
+    /// How many times a failed `vnstat --json` call is retried when vnStat
+    /// exits unsuccessfully without any output.
+    #[serde(default)]
+    pub command_retries: u32,
+
+    /// Seconds the last good data may keep being served, flagged as stale,
+    /// while vnStat fails. `0` disables serving stale data.
+    #[serde(default = "default_max_stale_seconds")]
+    pub max_stale_seconds: u64,
+
+    /// Consecutive failed vnStat runs after which vnStat is no longer run
+    /// for `breaker_cooldown_seconds`; requests get stale data or fail
+    /// with code `10027` meanwhile. `0` disables the circuit breaker.
+    #[serde(default = "default_breaker_threshold")]
+    pub breaker_threshold: u32,
+
+    /// Seconds the circuit breaker stays open before vnStat is probed
+    /// again.
+    #[serde(default = "default_breaker_cooldown_seconds")]
+    pub breaker_cooldown_seconds: u64,
+
+    /// Seconds since vnStat last updated an interface after which it is
+    /// reported as stale (e.g. vnstatd stopped tracking it after a rename).
+    /// `0` never reports interfaces as stale.
+    #[serde(default = "default_stale_after_seconds")]
+    pub stale_after_seconds: u64,
+
+    /// Day of the month (1–28) billing months start on, like vnStat's
+    /// `MonthRotate`, for the `/{if_name}/billing-month` endpoint.
+    #[serde(default = "default_month_rotate_day")]
+    pub month_rotate_day: u8,
+
+    /// What to do with records timestamped in the future (e.g. after a
+    /// clock correction).
+    #[serde(default)]
+    pub future_timestamps: FutureTimestampPolicy,
+
+    /// Seconds a record may lie in the future before the
+    /// `future_timestamps` policy applies.
+    #[serde(default = "default_future_tolerance")]
+    pub future_tolerance: u64,
+
+    /// Glob patterns of interfaces to expose; all others are hidden.
+    #[serde(default)]
+    pub include_interfaces: Vec<String>,
+
+    /// Glob patterns of interfaces to hide.
+    #[serde(default)]
+    pub exclude_interfaces: Vec<String>,
+
+    /// Whether the raw passthrough endpoints (`/raw`, `/{if_name}/raw`)
+    /// are served.
+    #[serde(default = "default_expose_raw")]
+    pub expose_raw: bool,
+
+    /// Maximum number of interfaces streamed live (i.e. `vnstat -l`
+    /// processes) at once; `0` means unlimited.
+    #[serde(default)]
+    pub max_live_processes: usize,
+
+    /// Seconds after which a live SSE connection is closed with a final
+    /// `timeout` event; `0` means no limit.
+    #[serde(default)]
+    pub live_max_duration_seconds: u64,
+
+    /// Messages buffered per live stream for clients that fall behind;
+    /// a client further behind loses messages (see the `lag` parameter of
+    /// the live endpoint).
+    #[serde(default = "default_live_buffer_size")]
+    pub live_buffer_size: usize,
+
+    /// How many times in a row a `vnstat -l` process that dies while
+    /// clients are connected is restarted; `0` ends their streams instead.
+    #[serde(default = "default_live_restart_attempts")]
+    pub live_restart_attempts: u32,
+
+    /// Seconds before the first restart of a dead `vnstat -l` process; the
+    /// n-th restart in a row waits n times as long.
+    #[serde(default = "default_live_restart_backoff_seconds")]
+    pub live_restart_backoff_seconds: u64,
+
+    /// Seconds a `vnstat -l` process being stopped may take to exit after
+    /// `SIGTERM` before it is killed; `0` kills it right away.
+    #[serde(default = "default_live_stop_grace_seconds")]
+    pub live_stop_grace_seconds: u64,
+
+    /// Seconds a `vnstat -l` process may keep running while no client
+    /// receives its output, e.g. because a client vanished without its
+    /// stream being closed; `0` lets it run until the last client leaves.
+    #[serde(default = "default_orphan_timeout_seconds")]
+    pub orphan_timeout_seconds: u64,
+
+    /// Longest wait, in seconds, a client of the long-polling live endpoint
+    /// may ask for with `timeout`; longer waits are shortened to it.
+    #[serde(default = "default_live_poll_max_timeout_seconds")]
+    pub live_poll_max_timeout_seconds: u64,
+
+    /// Seconds between two checks for new data by the watcher behind the
+    /// `/{if_name}/updates` streams.
+    #[serde(default = "default_updates_poll_seconds")]
+    pub updates_poll_seconds: u64,
+
+    /// Whether the endpoints adding and removing interfaces and setting
+    /// their alias in the vnStat database are served.
+    #[serde(default)]
+    pub allow_management: bool,
+
+    /// Path to the `vnstati` executable serving PNG graphs on
+    /// `/{if_name}/image`; the endpoint is disabled while unset.
+    #[serde(default)]
+    pub vnstati_executable: Option<String>,
+
+    /// vnStat configuration file passed as `--config` to every `vnstat` and
+    /// `vnstati` run, instead of the system-wide `vnstat.conf`.
+    #[serde(default)]
+    pub config_file: Option<PathBuf>,
+
+    /// Database directory passed as `--dbdir` to every `vnstat` and
+    /// `vnstati` run, overriding `DatabaseDir` of the vnStat configuration.
+    #[serde(default)]
+    pub db_dir: Option<PathBuf>,
+
+    /// Extra arguments for every `vnstat --json` run fetching data, e.g.
+    /// `["--locale", "en_US"]`. Arguments changing the output format or the
+    /// database are rejected, see [`FORBIDDEN_EXTRA_ARGS`].
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+
+    /// Extra arguments for the `vnstat -l` processes of live streams, which
+    /// do not get `extra_args`; checked like them.
+    #[serde(default)]
+    pub live_extra_args: Vec<String>,
+
+    /// Whether `vnstat` and `vnstati` inherit the server's whole
+    /// environment. By default they only get `PATH`, `LC_ALL=C` and, with
+    /// `keep_tz`, `TZ`, so that the locale cannot change their output and
+    /// secrets of the service are not passed on.
+    #[serde(default)]
+    pub inherit_env: bool,
+
+    /// Whether the server's `TZ` is passed on to `vnstat` and `vnstati`
+    /// when `inherit_env` is not set.
+    #[serde(default = "default_keep_tz")]
+    pub keep_tz: bool,
+
+    /// Niceness (-20 to 19) of the `vnstat` and `vnstati` processes, so
+    /// that e.g. the periodic data fetch yields to other work; unset keeps
+    /// the server's. Negative values require `CAP_SYS_NICE`. Unix only.
+    #[serde(default)]
+    pub nice: Option<i32>,
+
+    /// I/O scheduling class (`realtime`, `best-effort` or `idle`) of the
+    /// `vnstat` and `vnstati` processes, as with `ionice -c`; unset keeps
+    /// the server's. Linux only.
+    #[serde(default)]
+    pub ionice_class: Option<IoClass>,
+
+    /// Interface aliases keyed by real interface name (`[vnstat.aliases]`).
+    /// These override the alias reported by vnStat, and interfaces can be
+    /// looked up by them.
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+
+    /// Filter compiled from `include_interfaces` / `exclude_interfaces`
+    /// during finalization.
+    #[serde(skip)]
+    pub interface_filter: InterfaceFilter,
+}
+
+/// Policy for records whose timestamp lies in the future.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FutureTimestampPolicy {
+    /// Move the record into the current period, date included, adding its
+    /// traffic to the record of that period if there is one.
+    #[default]
+    Clamp,
+    /// Drop the record entirely.
+    Exclude,
+    /// Leave the record untouched.
+    Keep,
 }
 
 impl ConfigEntity for VnstatConfig {
-    /// Validates that the vnStat executable path is non-empty and points to an
-    /// existing file on disk.
+    /// Resolves bare executable names on `PATH` and compiles the interface
+    /// include/exclude patterns into
+    /// [`interface_filter`](Self::interface_filter).
+    ///
+    /// A bare name that is not found is kept, for
+    /// [`validate`](Self::validate) to report.
     ///
     /// # Errors
     ///
-    /// Returns an error if:
-    /// - The `executable` path is empty.
-    /// - The `executable` path does not exist on the filesystem.
-    fn validate(&self) -> anyhow::Result<()> {
-        if self.executable.is_empty() {
-            bail!("Vnstat executable is empty");
+    /// Returns an error if any pattern is not a valid glob.
+    fn finalize(&mut self) -> anyhow::Result<()> {
+        resolve_in_path(&mut self.executable);
+        if let Some(vnstati) = &mut self.vnstati_executable {
+            resolve_in_path(vnstati);
         }
 
-        if !Path::new(&self.executable).exists() {
-            bail!("Vnstat executable does not exist");
-        }
+        self.interface_filter =
+            InterfaceFilter::new(&self.include_interfaces, &self.exclude_interfaces)?;
 
         Ok(())
     }
+
+    /// Validates that the vnStat executables can be run, and the other
+    /// settings.
+    ///
+    /// Reports:
+    /// - An `executable` (and a `vnstati_executable`, if set) that is
+    ///   empty, a bare name not found on `PATH`, missing, a directory, not a
+    ///   regular file, or not executable by the current user.
+    /// - A `config_file` that is not an existing file, or a `db_dir` that is
+    ///   not an existing directory.
+    /// - Every entry of `extra_args` and `live_extra_args` that is one of
+    ///   [`FORBIDDEN_EXTRA_ARGS`].
+    /// - A `nice` outside -20 to 19, and a `nice` or `ionice_class` on a
+    ///   platform that does not support it.
+    /// - A zero `command_timeout_seconds`, `updates_poll_seconds`,
+    ///   `live_buffer_size` or `live_poll_max_timeout_seconds`.
+    /// - A zero `breaker_cooldown_seconds` while the circuit breaker is
+    ///   enabled.
+    /// - A `month_rotate_day` that is not between 1 and 28, so that every
+    ///   month has the day.
+    /// - Both `include_interfaces` and `exclude_interfaces` being set.
+    /// - Every alias that is empty, is used twice, or equals the real name
+    ///   of another configured interface, under `aliases.<interface>`.
+    fn validate(&self, errors: &mut ConfigErrors) {
+        validate_executable(errors, "executable", &self.executable);
+        if let Some(vnstati) = &self.vnstati_executable {
+            validate_executable(errors, "vnstati_executable", vnstati);
+        }
+
+        if let Some(path) = &self.config_file {
+            validate_path(errors, "config_file", path, false);
+        }
+        if let Some(path) = &self.db_dir {
+            validate_path(errors, "db_dir", path, true);
+        }
+
+        validate_extra_args(errors, "extra_args", &self.extra_args);
+        validate_extra_args(errors, "live_extra_args", &self.live_extra_args);
+
+        if let Some(nice) = self.nice {
+            if !(-20..=19).contains(&nice) {
+                errors.add("nice", "must be between -20 and 19");
+            } else if cfg!(not(unix)) {
+                errors.add("nice", "is only supported on Unix");
+            }
+        }
+        if self.ionice_class.is_some() && cfg!(not(target_os = "linux")) {
+            errors.add("ionice_class", "is only supported on Linux");
+        }
+
+        if self.command_timeout_seconds == 0 {
+            errors.add("command_timeout_seconds", "must be greater than zero");
+        }
+
+        if self.breaker_threshold > 0 && self.breaker_cooldown_seconds == 0 {
+            errors.add(
+                "breaker_cooldown_seconds",
+                "must be greater than zero unless breaker_threshold is 0",
+            );
+        }
+
+        if self.updates_poll_seconds == 0 {
+            errors.add("updates_poll_seconds", "must be greater than zero");
+        }
+
+        if self.live_buffer_size == 0 {
+            errors.add("live_buffer_size", "must be at least 1");
+        }
+
+        if self.live_poll_max_timeout_seconds == 0 {
+            errors.add("live_poll_max_timeout_seconds", "must be greater than zero");
+        }
+
+        if !(1..=28).contains(&self.month_rotate_day) {
+            errors.add("month_rotate_day", "must be between 1 and 28");
+        }
+
+        if !self.include_interfaces.is_empty() && !self.exclude_interfaces.is_empty() {
+            errors.add(
+                "exclude_interfaces",
+                "must not be set together with `include_interfaces`",
+            );
+        }
+
+        errors.section("aliases", |errors| {
+            let mut seen = BTreeMap::new();
+            for (name, alias) in &self.aliases {
+                if alias.is_empty() {
+                    errors.add(name, "must not be empty");
+                    continue;
+                }
+
+                if alias != name && self.aliases.contains_key(alias) {
+                    errors.add(
+                        name,
+                        format!(
+                            "alias `{}` collides with the real interface name `{}`",
+                            alias, alias
+                        ),
+                    );
+                }
+
+                if let Some(other) = seen.insert(alias, name) {
+                    errors.add(
+                        name,
+                        format!("alias `{}` is already used by `{}`", alias, other),
+                    );
+                }
+            }
+        });
+    }
+}
+
+/// vnStat arguments not allowed in `extra_args` and `live_extra_args`,
+/// because they change the output the server parses, start an interactive
+/// mode, or modify the database.
+pub const FORBIDDEN_EXTRA_ARGS: &[&str] = &[
+    "--json",
+    "--xml",
+    "--oneline",
+    "-l",
+    "--live",
+    "-tr",
+    "--traffic",
+    "--add",
+    "--remove",
+    "--rename",
+    "--setalias",
+];
+
+impl VnstatConfig {
+    /// Returns the arguments every `vnstat` and `vnstati` run starts with:
+    /// `--config` and `--dbdir` for the configured `config_file` and
+    /// `db_dir`.
+    pub fn base_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(path) = &self.config_file {
+            args.push("--config".to_string());
+            args.push(path.display().to_string());
+        }
+        if let Some(path) = &self.db_dir {
+            args.push("--dbdir".to_string());
+            args.push(path.display().to_string());
+        }
+        args
+    }
+}
+
+/// Replaces the bare program name `name` with the first executable of
+/// that name in the directories of `PATH`; paths and names not found are
+/// left alone.
+fn resolve_in_path(name: &mut String) {
+    let found = env::var_os("PATH").and_then(|paths| find_in_paths(name, &paths));
+    if let Some(path) = found {
+        *name = path.display().to_string();
+    }
+}
+
+/// Returns the first executable regular file named `name` in the
+/// directories of `paths` (a `PATH` value), or `None` if there is none or
+/// `name` is empty or a path.
+fn find_in_paths(name: &str, paths: &OsStr) -> Option<PathBuf> {
+    if name.is_empty() || name.contains('/') {
+        return None;
+    }
+
+    env::split_paths(paths)
+        .map(|dir| dir.join(name))
+        .find(|candidate| candidate.is_file() && is_executable(candidate))
+}
+
+/// Reports `field` if `path` cannot be run: it is empty, a bare name that
+/// [`resolve_in_path`] did not find, missing, not a regular file, or not
+/// executable by the current user.
+fn validate_executable(errors: &mut ConfigErrors, field: &str, path: &str) {
+    if path.is_empty() {
+        errors.add(field, "must not be empty");
+        return;
+    }
+
+    if !path.contains('/') {
+        errors.add(field, format!("`{}` was not found on PATH", path));
+        return;
+    }
+
+    match Path::new(path).metadata() {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            errors.add(field, format!("`{}` does not exist", path));
+        }
+        Err(e) => errors.add(field, format!("`{}` cannot be accessed: {}", path, e)),
+        Ok(metadata) if metadata.is_dir() => {
+            errors.add(field, format!("`{}` is a directory", path));
+        }
+        Ok(metadata) if !metadata.is_file() => {
+            errors.add(field, format!("`{}` is not a regular file", path));
+        }
+        Ok(_) if !is_executable(Path::new(path)) => {
+            errors.add(
+                field,
+                format!("`{}` is not executable by the current user", path),
+            );
+        }
+        Ok(_) => {}
+    }
+}
+
+/// Reports `field` if `path` does not exist, cannot be accessed, or is not
+/// a directory (`dir`) or a regular file (otherwise).
+fn validate_path(errors: &mut ConfigErrors, field: &str, path: &Path, dir: bool) {
+    match path.metadata() {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            errors.add(field, format!("`{}` does not exist", path.display()));
+        }
+        Err(e) => errors.add(
+            field,
+            format!("`{}` cannot be accessed: {}", path.display(), e),
+        ),
+        Ok(metadata) if dir && !metadata.is_dir() => {
+            errors.add(field, format!("`{}` is not a directory", path.display()));
+        }
+        Ok(metadata) if !dir && !metadata.is_file() => {
+            errors.add(field, format!("`{}` is not a regular file", path.display()));
+        }
+        Ok(_) => {}
+    }
+}
+
+/// Reports every entry of `args` that is one of [`FORBIDDEN_EXTRA_ARGS`],
+/// as `field[index]`.
+fn validate_extra_args(errors: &mut ConfigErrors, field: &str, args: &[String]) {
+    for (i, arg) in args.iter().enumerate() {
+        if FORBIDDEN_EXTRA_ARGS.contains(&arg.as_str()) {
+            errors.add(
+                &format!("{}[{}]", field, i),
+                format!(
+                    "`{}` is not allowed, as it changes the output format or the database",
+                    arg
+                ),
+            );
+        }
+    }
+}
+
+/// Returns `true` if the current user may execute `path`. Always `true`
+/// outside Unix.
+fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+
+        let Ok(path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+            return false;
+        };
+        // SAFETY: `path` is a valid NUL-terminated string that outlives the
+        // call; access(2) only reads it.
+        unsafe { libc::access(path.as_ptr(), libc::X_OK) == 0 }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        true
+    }
 }
 
 impl Default for VnstatConfig {
     /// Returns a `VnstatConfig` with the default executable path
-    /// (`/usr/bin/vnstat`).
+    /// (`/usr/bin/vnstat`), an enforced minimum version of `1.13`, a
+    /// 10-second command timeout without retries, one hour of allowed
+    /// staleness, a circuit breaker pausing vnStat for 30 seconds after
+    /// five failures in a row, interfaces stale after 15 minutes, billing
+    /// months starting on the 1st, the `clamp` future-timestamp policy, a
+    /// 300-second tolerance, a 60-second update poll interval, a
+    /// 100-message live buffer, up to three restarts of a dead live
+    /// process, the first after two seconds, three seconds
+    /// for a live process to exit after `SIGTERM`, live processes stopped
+    /// after a minute without receivers, long polls of at most 30
+    /// seconds, and a minimal environment for vnStat keeping `TZ`.
     fn default() -> Self {
         VnstatConfig {
             executable: default_executable(),
+            min_version: default_min_version(),
+            enforce_min_version: default_enforce_min_version(),
+            command_timeout_seconds: default_command_timeout_seconds(),
+            command_retries: 0,
+            max_stale_seconds: default_max_stale_seconds(),
+            breaker_threshold: default_breaker_threshold(),
+            breaker_cooldown_seconds: default_breaker_cooldown_seconds(),
+            stale_after_seconds: default_stale_after_seconds(),
+            month_rotate_day: default_month_rotate_day(),
+            future_timestamps: FutureTimestampPolicy::default(),
+            future_tolerance: default_future_tolerance(),
+            include_interfaces: Vec::new(),
+            exclude_interfaces: Vec::new(),
+            expose_raw: default_expose_raw(),
+            max_live_processes: 0,
+            live_max_duration_seconds: 0,
+            live_buffer_size: default_live_buffer_size(),
+            live_restart_attempts: default_live_restart_attempts(),
+            live_restart_backoff_seconds: default_live_restart_backoff_seconds(),
+            live_stop_grace_seconds: default_live_stop_grace_seconds(),
+            orphan_timeout_seconds: default_orphan_timeout_seconds(),
+            live_poll_max_timeout_seconds: default_live_poll_max_timeout_seconds(),
+            updates_poll_seconds: default_updates_poll_seconds(),
+            allow_management: false,
+            vnstati_executable: None,
+            config_file: None,
+            db_dir: None,
+            extra_args: Vec::new(),
+            live_extra_args: Vec::new(),
+            inherit_env: false,
+            keep_tz: default_keep_tz(),
+            nice: None,
+            ionice_class: None,
+            aliases: BTreeMap::new(),
+            interface_filter: InterfaceFilter::default(),
         }
     }
 }
@@ -49,3 +565,201 @@ impl Default for VnstatConfig {
 fn default_executable() -> String {
     "/usr/bin/vnstat".to_string()
 }
+
+/// Returns the default minimum vnStat version (`1.13`, the first release
+/// with `--json` output).
+fn default_min_version() -> Version {
+    Version::new(1, 13, 0)
+}
+
+/// Returns whether a failed version check prevents startup by default
+/// (`true`).
+fn default_enforce_min_version() -> bool {
+    true
+}
+
+/// Returns the default vnStat command timeout (`10` seconds).
+fn default_command_timeout_seconds() -> u64 {
+    10
+}
+
+/// Returns the default maximum age of stale data (`3600` seconds).
+fn default_max_stale_seconds() -> u64 {
+    3600
+}
+
+/// Returns the default number of failures opening the circuit breaker
+/// (`5`).
+fn default_breaker_threshold() -> u32 {
+    5
+}
+
+/// Returns the default circuit breaker cooldown (`30` seconds).
+fn default_breaker_cooldown_seconds() -> u64 {
+    30
+}
+
+/// Returns the default age after which an interface is stale (`900`
+/// seconds, three vnstatd save intervals).
+fn default_stale_after_seconds() -> u64 {
+    900
+}
+
+/// Returns the default first day of a billing month (`1`, vnStat's default
+/// `MonthRotate`).
+fn default_month_rotate_day() -> u8 {
+    1
+}
+
+/// Returns the default future-timestamp tolerance (`300` seconds).
+fn default_future_tolerance() -> u64 {
+    300
+}
+
+/// Returns the default update poll interval (`60` seconds, the lifetime of
+/// a cached vnStat snapshot).
+fn default_updates_poll_seconds() -> u64 {
+    60
+}
+
+/// Returns the default live stream buffer size (`100` messages).
+fn default_live_buffer_size() -> usize {
+    DEFAULT_BUFFER_SIZE
+}
+
+/// Returns the default number of consecutive live process restarts (`3`).
+fn default_live_restart_attempts() -> u32 {
+    3
+}
+
+/// Returns the default wait before restarting a live process (`2` seconds).
+fn default_live_restart_backoff_seconds() -> u64 {
+    2
+}
+
+/// Returns the default grace period of a live process being stopped (`3`
+/// seconds).
+fn default_live_stop_grace_seconds() -> u64 {
+    3
+}
+
+/// Returns the default time a live process may run without receivers (`60`
+/// seconds).
+fn default_orphan_timeout_seconds() -> u64 {
+    60
+}
+
+/// Returns the default longest wait of a live long poll (`30` seconds).
+fn default_live_poll_max_timeout_seconds() -> u64 {
+    30
+}
+
+/// Returns whether the raw passthrough endpoints are enabled by default
+/// (`true`).
+fn default_expose_raw() -> bool {
+    true
+}
+
+/// Returns whether `TZ` is passed on to vnStat by default (`true`).
+fn default_keep_tz() -> bool {
+    true
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    /// Returns an empty scratch directory for the test `name`.
+    fn scratch(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("vnstat-exec-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("create scratch directory");
+        dir
+    }
+
+    /// Creates the file `dir/name` with the permission bits `mode`.
+    fn file(dir: &Path, name: &str, mode: u32) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, "#!/bin/sh\n").expect("write file");
+        fs::set_permissions(&path, fs::Permissions::from_mode(mode)).expect("set mode");
+        path
+    }
+
+    /// Returns the messages [`validate_executable`] reports for `path`.
+    fn problems(path: &str) -> Vec<String> {
+        let mut errors = ConfigErrors::default();
+        validate_executable(&mut errors, "executable", path);
+        errors.errors().iter().map(|e| e.message.clone()).collect()
+    }
+
+    #[test]
+    fn only_executable_regular_files_are_accepted() {
+        let dir = scratch("validate");
+        let executable = file(&dir, "vnstat", 0o755);
+        let plain = file(&dir, "plain", 0o644);
+        let subdir = dir.join("subdir");
+        fs::create_dir(&subdir).expect("create directory");
+        let path = |p: &Path| p.to_str().expect("UTF-8 path").to_string();
+
+        assert!(problems(&path(&executable)).is_empty());
+        assert_eq!(
+            problems(&path(&plain)),
+            [format!(
+                "`{}` is not executable by the current user",
+                plain.display()
+            )]
+        );
+        assert_eq!(
+            problems(&path(&subdir)),
+            [format!("`{}` is a directory", subdir.display())]
+        );
+        let missing = dir.join("missing");
+        assert_eq!(
+            problems(&path(&missing)),
+            [format!("`{}` does not exist", missing.display())]
+        );
+        assert_eq!(problems(""), ["must not be empty"]);
+        assert_eq!(problems("vnstat"), ["`vnstat` was not found on PATH"]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn path_lookup_skips_what_cannot_be_run() {
+        let dir = scratch("lookup");
+        let [as_dir, as_plain, as_exec] = ["a", "b", "c"].map(|sub| {
+            let sub = dir.join(sub);
+            fs::create_dir(&sub).expect("create directory");
+            sub
+        });
+        fs::create_dir(as_dir.join("vnstat")).expect("create directory");
+        file(&as_plain, "vnstat", 0o644);
+        let executable = file(&as_exec, "vnstat", 0o755);
+        let paths = env::join_paths([&as_dir, &as_plain, &as_exec]).expect("valid PATH");
+
+        assert_eq!(find_in_paths("vnstat", &paths), Some(executable));
+        assert_eq!(find_in_paths("vnstati", &paths), None);
+        assert_eq!(find_in_paths("", &paths), None);
+        let only_unusable = env::join_paths([&as_dir, &as_plain]).expect("valid PATH");
+        assert_eq!(find_in_paths("vnstat", &only_unusable), None);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn bare_names_are_looked_up_on_path_and_paths_are_not() {
+        let mut name = "./vnstat".to_string();
+        resolve_in_path(&mut name);
+        assert_eq!(name, "./vnstat");
+
+        let mut name = "sh".to_string();
+        resolve_in_path(&mut name);
+        assert!(
+            name.ends_with("/sh") && is_executable(Path::new(&name)),
+            "{}",
+            name
+        );
+    }
+}
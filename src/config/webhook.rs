@@ -0,0 +1,102 @@
+use super::error::ConfigErrors;
+use super::traits::ConfigEntity;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Webhooks notified when an interface crosses a quota threshold
+/// (`[quota.webhooks]`).
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookConfig {
+    /// URLs receiving a JSON `POST` per crossed threshold. Empty disables
+    /// notifications.
+    #[serde(default)]
+    pub urls: Vec<String>,
+
+    /// Seconds between two evaluations of the quotas.
+    #[serde(default = "default_check_interval_seconds")]
+    pub check_interval_seconds: u64,
+
+    /// Seconds a single webhook request may take.
+    #[serde(default = "default_timeout_seconds")]
+    pub timeout_seconds: u64,
+
+    /// How many times a failed delivery is retried, with exponential
+    /// backoff.
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+}
+
+impl WebhookConfig {
+    /// Returns whether any webhook is configured.
+    pub fn enabled(&self) -> bool {
+        !self.urls.is_empty()
+    }
+
+    /// Returns the interval between two quota evaluations.
+    pub fn check_interval(&self) -> Duration {
+        Duration::from_secs(self.check_interval_seconds)
+    }
+
+    /// Returns the timeout of a single webhook request.
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_seconds)
+    }
+}
+
+impl ConfigEntity for WebhookConfig {
+    /// Validates the webhook URLs and intervals.
+    ///
+    /// Reports:
+    /// - Every URL not starting with `http://` or `https://`.
+    /// - A zero `check_interval_seconds` or `timeout_seconds`.
+    fn validate(&self, errors: &mut ConfigErrors) {
+        for url in self
+            .urls
+            .iter()
+            .filter(|url| !url.starts_with("http://") && !url.starts_with("https://"))
+        {
+            errors.add(
+                "urls",
+                format!("`{}` must start with http:// or https://", url),
+            );
+        }
+
+        if self.check_interval_seconds == 0 {
+            errors.add("check_interval_seconds", "must be greater than zero");
+        }
+
+        if self.timeout_seconds == 0 {
+            errors.add("timeout_seconds", "must be greater than zero");
+        }
+    }
+}
+
+impl Default for WebhookConfig {
+    /// Returns a `WebhookConfig` without URLs, checking every 5 minutes
+    /// with a 10-second request timeout and 3 retries.
+    fn default() -> Self {
+        WebhookConfig {
+            urls: Vec::new(),
+            check_interval_seconds: default_check_interval_seconds(),
+            timeout_seconds: default_timeout_seconds(),
+            retries: default_retries(),
+        }
+    }
+}
+
+/// Returns the default quota check interval (`300` seconds, vnStat's
+/// default database write interval).
+fn default_check_interval_seconds() -> u64 {
+    300
+}
+
+/// Returns the default webhook request timeout (`10` seconds).
+fn default_timeout_seconds() -> u64 {
+    10
+}
+
+/// Returns the default number of delivery retries (`3`).
+fn default_retries() -> u32 {
+    3
+}
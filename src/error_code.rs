@@ -1,3 +1,6 @@
+use crate::service::error::{AmbiguousInterface, NoInterfaces, VnstatError, VnstatUnavailable};
+use crate::task_manager::SubscribeError;
+use axum::http::StatusCode;
 use serde::{Serialize, Serializer};
 
 /// Well-known error codes returned by the vnstat-rs-api.
@@ -14,26 +17,173 @@ pub enum ErrorCode {
     GetDataFailed,
     /// The requested network interface does not exist.
     NoSuchInterface,
+    /// The requested endpoint is disabled by configuration.
+    EndpointDisabled,
+    /// vnStat produced a JSON schema version this API does not support.
+    UnsupportedJsonVersion,
+    /// vnStat did not respond within the configured timeout.
+    VnstatTimeout,
+    /// The request succeeded, but vnStat could not be refreshed and the
+    /// data is older than usual.
+    StaleData,
+    /// The vnStat executable could not be run or exited unsuccessfully.
+    VnstatExecutionFailed,
+    /// vnStat's output could not be decoded or parsed.
+    VnstatOutputParseError,
+    /// A query parameter is missing, malformed or out of range.
+    InvalidQueryParameter,
+    /// The request lacks valid credentials.
+    Unauthorized,
+    /// The client sent too many requests.
+    RateLimited,
+    /// The requested interface exists but is disabled.
+    InterfaceDisabled,
+    /// A path parameter could not be extracted or parsed.
+    InvalidPathParameter,
+    /// The route exists but does not support the request method.
+    MethodNotAllowed,
+    /// No route matches the request path.
+    RouteNotFound,
+    /// The request body is missing, malformed or holds invalid values.
+    InvalidRequestBody,
+    /// No live-stream task is registered under the requested key.
+    NoSuchTask,
+    /// A live-stream limit (SSE clients or vnStat processes) is reached.
+    TooManyStreams,
+    /// The request to a WebSocket endpoint is not a valid WebSocket upgrade.
+    WebSocketUpgradeFailed,
+    /// No bandwidth quota is configured for the requested interface.
+    NoQuotaConfigured,
+    /// The client address is not allowed to use the API.
+    Forbidden,
+    /// The limit on in-flight data requests is reached and load shedding is
+    /// enabled.
+    Overloaded,
+    /// The request was not answered within `server.request_timeout_seconds`.
+    RequestTimeout,
+    /// The request succeeded, but vnStat has not updated the interface for
+    /// longer than `vnstat.stale_after_seconds`.
+    StaleInterface,
+    /// The requested interface name is an alias shared by several
+    /// interfaces.
+    AmbiguousInterface,
+    /// The client does not accept the only media type the endpoint serves
+    /// (e.g. a browser tab opening a Server-Sent Events stream).
+    NotAcceptable,
+    /// vnStat monitors no interface yet, so there is none to list or look
+    /// up.
+    NoInterfacesConfigured,
+    /// vnStat failed repeatedly and is not run until
+    /// `vnstat.breaker_cooldown_seconds` have passed.
+    VnstatUnavailable,
 
     /// An unexpected or otherwise unclassified error occurred.
-    #[allow(dead_code)]
     UnknownError,
 }
 
 impl ErrorCode {
+    /// Every variant, in numeric order.
+    pub const ALL: [ErrorCode; 30] = [
+        ErrorCode::NoError,
+        ErrorCode::GetDataFailed,
+        ErrorCode::NoSuchInterface,
+        ErrorCode::EndpointDisabled,
+        ErrorCode::UnsupportedJsonVersion,
+        ErrorCode::VnstatTimeout,
+        ErrorCode::StaleData,
+        ErrorCode::VnstatExecutionFailed,
+        ErrorCode::VnstatOutputParseError,
+        ErrorCode::InvalidQueryParameter,
+        ErrorCode::Unauthorized,
+        ErrorCode::RateLimited,
+        ErrorCode::InterfaceDisabled,
+        ErrorCode::InvalidPathParameter,
+        ErrorCode::MethodNotAllowed,
+        ErrorCode::RouteNotFound,
+        ErrorCode::InvalidRequestBody,
+        ErrorCode::NoSuchTask,
+        ErrorCode::TooManyStreams,
+        ErrorCode::WebSocketUpgradeFailed,
+        ErrorCode::NoQuotaConfigured,
+        ErrorCode::Forbidden,
+        ErrorCode::Overloaded,
+        ErrorCode::RequestTimeout,
+        ErrorCode::StaleInterface,
+        ErrorCode::AmbiguousInterface,
+        ErrorCode::NotAcceptable,
+        ErrorCode::NoInterfacesConfigured,
+        ErrorCode::VnstatUnavailable,
+        ErrorCode::UnknownError,
+    ];
+
     /// Returns the numeric error code for this variant.
     ///
+    /// Codes are stable: existing values never change, new variants get new
+    /// values.
+    ///
     /// # Returns
     ///
     /// * `0`     — [`NoError`](ErrorCode::NoError)
     /// * `10000` — [`GetDataFailed`](ErrorCode::GetDataFailed)
     /// * `10001` — [`NoSuchInterface`](ErrorCode::NoSuchInterface)
+    /// * `10002` — [`EndpointDisabled`](ErrorCode::EndpointDisabled)
+    /// * `10003` — [`UnsupportedJsonVersion`](ErrorCode::UnsupportedJsonVersion)
+    /// * `10004` — [`VnstatTimeout`](ErrorCode::VnstatTimeout)
+    /// * `10005` — [`StaleData`](ErrorCode::StaleData)
+    /// * `10006` — [`VnstatExecutionFailed`](ErrorCode::VnstatExecutionFailed)
+    /// * `10007` — [`VnstatOutputParseError`](ErrorCode::VnstatOutputParseError)
+    /// * `10008` — [`InvalidQueryParameter`](ErrorCode::InvalidQueryParameter)
+    /// * `10009` — [`Unauthorized`](ErrorCode::Unauthorized)
+    /// * `10010` — [`RateLimited`](ErrorCode::RateLimited)
+    /// * `10011` — [`InterfaceDisabled`](ErrorCode::InterfaceDisabled)
+    /// * `10012` — [`InvalidPathParameter`](ErrorCode::InvalidPathParameter)
+    /// * `10013` — [`MethodNotAllowed`](ErrorCode::MethodNotAllowed)
+    /// * `10014` — [`RouteNotFound`](ErrorCode::RouteNotFound)
+    /// * `10015` — [`InvalidRequestBody`](ErrorCode::InvalidRequestBody)
+    /// * `10016` — [`NoSuchTask`](ErrorCode::NoSuchTask)
+    /// * `10017` — [`TooManyStreams`](ErrorCode::TooManyStreams)
+    /// * `10018` — [`WebSocketUpgradeFailed`](ErrorCode::WebSocketUpgradeFailed)
+    /// * `10019` — [`NoQuotaConfigured`](ErrorCode::NoQuotaConfigured)
+    /// * `10020` — [`Forbidden`](ErrorCode::Forbidden)
+    /// * `10021` — [`Overloaded`](ErrorCode::Overloaded)
+    /// * `10022` — [`RequestTimeout`](ErrorCode::RequestTimeout)
+    /// * `10023` — [`StaleInterface`](ErrorCode::StaleInterface)
+    /// * `10024` — [`AmbiguousInterface`](ErrorCode::AmbiguousInterface)
+    /// * `10025` — [`NotAcceptable`](ErrorCode::NotAcceptable)
+    /// * `10026` — [`NoInterfacesConfigured`](ErrorCode::NoInterfacesConfigured)
+    /// * `10027` — [`VnstatUnavailable`](ErrorCode::VnstatUnavailable)
     /// * `99999` — [`UnknownError`](ErrorCode::UnknownError)
-    pub fn code(&self) -> i32 {
+    pub const fn code(&self) -> i32 {
         match self {
             ErrorCode::NoError => 0,
             ErrorCode::GetDataFailed => 10000,
             ErrorCode::NoSuchInterface => 10001,
+            ErrorCode::EndpointDisabled => 10002,
+            ErrorCode::UnsupportedJsonVersion => 10003,
+            ErrorCode::VnstatTimeout => 10004,
+            ErrorCode::StaleData => 10005,
+            ErrorCode::VnstatExecutionFailed => 10006,
+            ErrorCode::VnstatOutputParseError => 10007,
+            ErrorCode::InvalidQueryParameter => 10008,
+            ErrorCode::Unauthorized => 10009,
+            ErrorCode::RateLimited => 10010,
+            ErrorCode::InterfaceDisabled => 10011,
+            ErrorCode::InvalidPathParameter => 10012,
+            ErrorCode::MethodNotAllowed => 10013,
+            ErrorCode::RouteNotFound => 10014,
+            ErrorCode::InvalidRequestBody => 10015,
+            ErrorCode::NoSuchTask => 10016,
+            ErrorCode::TooManyStreams => 10017,
+            ErrorCode::WebSocketUpgradeFailed => 10018,
+            ErrorCode::NoQuotaConfigured => 10019,
+            ErrorCode::Forbidden => 10020,
+            ErrorCode::Overloaded => 10021,
+            ErrorCode::RequestTimeout => 10022,
+            ErrorCode::StaleInterface => 10023,
+            ErrorCode::AmbiguousInterface => 10024,
+            ErrorCode::NotAcceptable => 10025,
+            ErrorCode::NoInterfacesConfigured => 10026,
+            ErrorCode::VnstatUnavailable => 10027,
             ErrorCode::UnknownError => 99999,
         }
     }
@@ -49,10 +199,120 @@ impl ErrorCode {
             ErrorCode::GetDataFailed => "Get data failed",
             ErrorCode::UnknownError => "Unknown error",
             ErrorCode::NoSuchInterface => "No such interface",
+            ErrorCode::EndpointDisabled => "Endpoint disabled",
+            ErrorCode::UnsupportedJsonVersion => "Unsupported vnStat JSON version",
+            ErrorCode::VnstatTimeout => "vnStat timed out",
+            ErrorCode::StaleData => "Data is stale",
+            ErrorCode::VnstatExecutionFailed => "vnStat execution failed",
+            ErrorCode::VnstatOutputParseError => "Failed to parse vnStat output",
+            ErrorCode::InvalidQueryParameter => "Invalid query parameter",
+            ErrorCode::Unauthorized => "Unauthorized",
+            ErrorCode::RateLimited => "Too many requests",
+            ErrorCode::InterfaceDisabled => "Interface disabled",
+            ErrorCode::InvalidPathParameter => "Invalid path parameter",
+            ErrorCode::MethodNotAllowed => "Method not allowed",
+            ErrorCode::RouteNotFound => "Not found",
+            ErrorCode::InvalidRequestBody => "Invalid request body",
+            ErrorCode::NoSuchTask => "No such task",
+            ErrorCode::TooManyStreams => "Too many live streams",
+            ErrorCode::WebSocketUpgradeFailed => "WebSocket upgrade failed",
+            ErrorCode::NoQuotaConfigured => "No quota configured",
+            ErrorCode::Forbidden => "Forbidden",
+            ErrorCode::Overloaded => "Server overloaded",
+            ErrorCode::RequestTimeout => "Request timed out",
+            ErrorCode::StaleInterface => "Interface is not being updated",
+            ErrorCode::AmbiguousInterface => "Interface alias is ambiguous",
+            ErrorCode::NotAcceptable => "Not acceptable",
+            ErrorCode::NoInterfacesConfigured => "No interfaces configured in vnStat",
+            ErrorCode::VnstatUnavailable => "vnStat temporarily unavailable",
+        }
+    }
+
+    /// Returns the canonical HTTP status for a response carrying this code.
+    ///
+    /// Codes that predate this mapping keep the API's historical
+    /// `400 Bad Request` (or `404` for disabled endpoints) so existing
+    /// clients see no change.
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            ErrorCode::NoError | ErrorCode::StaleData | ErrorCode::StaleInterface => StatusCode::OK,
+            ErrorCode::GetDataFailed
+            | ErrorCode::UnsupportedJsonVersion
+            | ErrorCode::InvalidQueryParameter
+            | ErrorCode::InvalidPathParameter
+            | ErrorCode::InvalidRequestBody
+            | ErrorCode::WebSocketUpgradeFailed => StatusCode::BAD_REQUEST,
+            ErrorCode::NoSuchInterface
+            | ErrorCode::EndpointDisabled
+            | ErrorCode::RouteNotFound
+            | ErrorCode::NoSuchTask
+            | ErrorCode::NoQuotaConfigured
+            | ErrorCode::NoInterfacesConfigured => StatusCode::NOT_FOUND,
+            ErrorCode::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+            ErrorCode::VnstatTimeout | ErrorCode::RequestTimeout => StatusCode::GATEWAY_TIMEOUT,
+            ErrorCode::VnstatExecutionFailed | ErrorCode::VnstatOutputParseError => {
+                StatusCode::BAD_GATEWAY
+            }
+            ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorCode::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::TooManyStreams | ErrorCode::Overloaded | ErrorCode::VnstatUnavailable => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            ErrorCode::InterfaceDisabled | ErrorCode::Forbidden => StatusCode::FORBIDDEN,
+            ErrorCode::AmbiguousInterface => StatusCode::CONFLICT,
+            ErrorCode::NotAcceptable => StatusCode::NOT_ACCEPTABLE,
+            ErrorCode::UnknownError => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Picks the error code describing `error`.
+    ///
+    /// Typed backend failures ([`VnstatError`], [`SubscribeError`],
+    /// [`AmbiguousInterface`], [`NoInterfaces`], [`VnstatUnavailable`]) map to their dedicated
+    /// code; anything else is reported as `fallback`.
+    pub fn from_error(error: &anyhow::Error, fallback: ErrorCode) -> ErrorCode {
+        if error.is::<SubscribeError>() {
+            return ErrorCode::TooManyStreams;
+        }
+
+        if error.is::<AmbiguousInterface>() {
+            return ErrorCode::AmbiguousInterface;
+        }
+
+        if error.is::<NoInterfaces>() {
+            return ErrorCode::NoInterfacesConfigured;
+        }
+
+        if error.is::<VnstatUnavailable>() {
+            return ErrorCode::VnstatUnavailable;
+        }
+
+        match error.downcast_ref::<VnstatError>() {
+            Some(VnstatError::UnsupportedJsonVersion(_)) => ErrorCode::UnsupportedJsonVersion,
+            Some(VnstatError::Timeout(_)) => ErrorCode::VnstatTimeout,
+            Some(VnstatError::SpawnFailed(_)) | Some(VnstatError::CommandFailed { .. }) => {
+                ErrorCode::VnstatExecutionFailed
+            }
+            Some(VnstatError::InvalidOutput(_)) => ErrorCode::VnstatOutputParseError,
+            None => fallback,
         }
     }
 }
 
+/// Compile-time check that no two variants share a numeric code.
+const _: () = {
+    let all = ErrorCode::ALL;
+    let mut i = 0;
+    while i < all.len() {
+        let mut j = i + 1;
+        while j < all.len() {
+            assert!(all[i].code() != all[j].code(), "duplicate error code");
+            j += 1;
+        }
+        i += 1;
+    }
+};
+
 impl Serialize for ErrorCode {
     /// Serialises this error code as its underlying `i32` value.
     ///
@@ -70,3 +330,28 @@ impl Serialize for ErrorCode {
         serializer.serialize_i32(self.code())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn codes_are_unique() {
+        let codes: HashSet<i32> = ErrorCode::ALL.iter().map(ErrorCode::code).collect();
+        assert_eq!(codes.len(), ErrorCode::ALL.len());
+    }
+
+    #[test]
+    fn all_is_in_numeric_order() {
+        assert!(ErrorCode::ALL.windows(2).all(|w| w[0].code() < w[1].code()));
+    }
+
+    #[test]
+    fn serializes_as_the_numeric_code() {
+        for code in ErrorCode::ALL {
+            let json = serde_json::to_string(&code).expect("serializable");
+            assert_eq!(json, code.code().to_string());
+        }
+    }
+}
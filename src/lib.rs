@@ -0,0 +1,21 @@
+//! Library half of vnstat-rs-api.
+//!
+//! The binary in `main.rs` is a thin wrapper around these modules; they are
+//! exposed as a library so the benchmark suite can drive the same code paths
+//! the server uses, and so the API can be mounted inside another axum
+//! application with [`get_router`].
+
+pub mod config;
+pub mod error_code;
+pub mod logging;
+pub mod middleware;
+pub mod model;
+pub mod router;
+pub mod service;
+pub mod task_handle;
+pub mod task_manager;
+pub mod utils;
+
+pub use model::jsend::JsendResponse;
+pub use model::vnstat::{Interface, VnstatData};
+pub use router::{AppState, get_router, router_with_state};
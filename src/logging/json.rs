@@ -0,0 +1,126 @@
+use crate::utils::request_id::RequestId;
+use serde_json::{Map, Value};
+use std::fmt;
+use time::format_description::well_known::Rfc3339;
+use time::{OffsetDateTime, UtcOffset};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::Layer;
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+
+/// Event formatter writing one JSON object per line:
+///
+/// ```json
+/// {"timestamp":"2024-06-17T10:30:00.123+02:00","level":"INFO","target":"vnstat_rs_api::access","message":"...","request_id":"..."}
+/// ```
+///
+/// Additional event fields are included under their own names;
+/// `request_id` is present for events emitted while handling a request.
+pub struct JsonFormat {
+    /// Offset applied to timestamps (the local offset at startup).
+    pub offset: UtcOffset,
+}
+
+impl<S, N> FormatEvent<S, N> for JsonFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let metadata = event.metadata();
+        let timestamp = OffsetDateTime::now_utc()
+            .to_offset(self.offset)
+            .format(&Rfc3339)
+            .map_err(|_| fmt::Error)?;
+
+        let mut object = Map::new();
+        object.insert("timestamp".into(), timestamp.into());
+        object.insert("level".into(), metadata.level().as_str().into());
+        object.insert("target".into(), metadata.target().into());
+        event.record(&mut JsonVisitor(&mut object));
+
+        let request_id = ctx.event_scope().and_then(|scope| {
+            scope
+                .from_root()
+                .find_map(|span| span.extensions().get::<RequestId>().cloned())
+        });
+        if let Some(RequestId(id)) = request_id {
+            object.insert("request_id".into(), id.into());
+        }
+
+        writeln!(writer, "{}", Value::Object(object))
+    }
+}
+
+/// Collects event fields into a JSON object.
+struct JsonVisitor<'a>(&'a mut Map<String, Value>);
+
+impl Visit for JsonVisitor<'_> {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().into(), format!("{:?}", value).into());
+    }
+}
+
+/// Layer remembering the `id` field of `request` spans (created by the
+/// request-ID middleware) as a [`RequestId`] in the span's extensions, so
+/// [`JsonFormat`] can emit it as a structured field.
+pub struct RequestIdLayer;
+
+impl<S> Layer<S> for RequestIdLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if attrs.metadata().name() != "request" {
+            return;
+        }
+
+        let mut visitor = IdVisitor(None);
+        attrs.record(&mut visitor);
+
+        if let (Some(request_id), Some(span)) = (visitor.0, ctx.span(id)) {
+            span.extensions_mut().insert(RequestId(request_id));
+        }
+    }
+}
+
+/// Extracts the `id` field of a span.
+struct IdVisitor(Option<String>);
+
+impl Visit for IdVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "id" {
+            self.0 = Some(format!("{:?}", value));
+        }
+    }
+}
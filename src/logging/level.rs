@@ -0,0 +1,69 @@
+use anyhow::{Context, Result};
+use std::sync::{Arc, Mutex, PoisonError};
+use tracing::Level;
+use tracing_subscriber::filter::Directive;
+use tracing_subscriber::{EnvFilter, Registry, reload};
+
+/// Handle for changing the default log level of the installed subscriber at
+/// runtime.
+///
+/// Cloning the handle is cheap; all clones control the same subscriber.
+#[derive(Clone)]
+pub struct LogLevelHandle {
+    /// Reload handle of the global filter layer.
+    reload: reload::Handle<EnvFilter, Registry>,
+
+    /// Per-module directives from `[logging.filters]`, re-applied on every
+    /// change so they keep overriding the default level.
+    directives: Arc<Vec<Directive>>,
+
+    /// The current default level.
+    level: Arc<Mutex<Level>>,
+}
+
+impl LogLevelHandle {
+    /// Creates a handle for `reload`, whose filter was built from `level`
+    /// and `directives`.
+    pub(super) fn new(
+        reload: reload::Handle<EnvFilter, Registry>,
+        level: Level,
+        directives: Vec<Directive>,
+    ) -> Self {
+        LogLevelHandle {
+            reload,
+            directives: Arc::new(directives),
+            level: Arc::new(Mutex::new(level)),
+        }
+    }
+
+    /// Returns the current default log level.
+    pub fn level(&self) -> Level {
+        *self.level.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Replaces the default log level.
+    ///
+    /// The filter is rebuilt from `level` and the configured per-module
+    /// filters; a filter taken from `RUST_LOG` at startup is replaced as
+    /// well. The change applies to every event recorded afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the subscriber the handle belongs to no longer
+    /// exists.
+    pub fn set_level(&self, level: Level) -> Result<()> {
+        let mut current = self.level.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let filter = self.directives.iter().cloned().fold(
+            EnvFilter::default().add_directive(level.into()),
+            EnvFilter::add_directive,
+        );
+        self.reload
+            .reload(filter)
+            .context("Failed to reload the log filter")?;
+
+        *current = level;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,152 @@
+use self::json::{JsonFormat, RequestIdLayer};
+use self::rotating::{PlainFields, RotatingFile};
+use crate::config::logging::{LogFormat, LoggingConfig};
+use anyhow::{Context, Result};
+use std::backtrace::Backtrace;
+use std::panic::PanicHookInfo;
+use time::UtcOffset;
+use tracing_subscriber::layer::{Layered, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer, Registry, fmt, reload};
+
+pub use self::level::LogLevelHandle;
+
+pub mod json;
+mod level;
+pub mod rotating;
+
+/// The registry with the reloadable global filter applied, on which the
+/// output layers are stacked.
+type Filtered = Layered<reload::Layer<EnvFilter, Registry>, Registry>;
+
+/// A type-erased output layer.
+type BoxedLayer = Box<dyn Layer<Filtered> + Send + Sync>;
+
+/// Initialises the global [`tracing`] subscriber with opinionated defaults.
+///
+/// In the default `text` format the subscriber writes human-readable log
+/// lines to the console and includes a local-timestamp, the target module,
+/// the log level, and ANSI colouring. In the `json` format every line is a
+/// JSON object instead (see [`JsonFormat`]). When `config.file` is set, the
+/// same lines (without colours) are also written to that file, which is
+/// rotated by size (see [`RotatingFile`]).
+///
+/// # Arguments
+///
+/// * `debug` — If `true`, the log level defaults to [`tracing::Level::DEBUG`];
+///   otherwise it defaults to `config.level`. `config.filters` then
+///   override the default for individual modules. In either case the
+///   effective filter can still be overridden via the `RUST_LOG` environment
+///   variable.
+/// * `config` — The `[logging]` configuration section.
+///
+/// # Returns
+///
+/// A [`LogLevelHandle`] for changing the default level at runtime.
+///
+/// # Errors
+///
+/// Returns an error if:
+/// * The timestamp format string cannot be parsed by the `time` crate.
+/// * The current local UTC offset cannot be determined (e.g. on systems where
+///   local time information is unavailable).
+/// * The configured level or a per-module filter is invalid.
+/// * The log file or its parent directory cannot be created.
+///
+/// Once the subscriber is installed this function cannot be called again
+/// (a subsequent call will panic).
+///
+/// Panics are logged at `error` with a backtrace from then on (see
+/// [`log_panic`]), instead of being printed to stderr.
+pub fn init(debug: bool, config: &LoggingConfig) -> Result<LogLevelHandle> {
+    let level = if debug {
+        tracing::Level::DEBUG
+    } else {
+        config.level()?
+    };
+    let directives = config.directives()?;
+    let mut filter = EnvFilter::builder()
+        .with_default_directive(level.into())
+        .from_env_lossy();
+    if std::env::var_os(EnvFilter::DEFAULT_ENV).is_none() {
+        for directive in directives.iter().cloned() {
+            filter = filter.add_directive(directive);
+        }
+    }
+    let (filter, reload) = reload::Layer::new(filter);
+
+    let offset = UtcOffset::current_local_offset().context("Failed to get current local offset")?;
+
+    let file = match &config.file {
+        Some(path) => Some(RotatingFile::open(
+            path,
+            config.max_size_mb.saturating_mul(1024 * 1024),
+            config.max_files,
+        )?),
+        None => None,
+    };
+
+    let mut layers: Vec<BoxedLayer> = Vec::new();
+
+    match config.format {
+        LogFormat::Text => {
+            #[allow(deprecated)]
+            let format = time::format_description::parse(
+                "[year]-[month]-[day] [hour]:[minute]:[second].[subsecond digits:3]",
+            )
+            .context("Failed to parse time format")?;
+            let timer = fmt::time::OffsetTime::new(offset, format);
+
+            layers.push(
+                fmt::layer()
+                    .with_timer(timer.clone())
+                    .with_target(true)
+                    .with_level(true)
+                    .with_ansi(true)
+                    .boxed(),
+            );
+
+            if let Some(file) = file {
+                layers.push(
+                    fmt::layer()
+                        .with_timer(timer)
+                        .with_target(true)
+                        .with_level(true)
+                        .with_ansi(false)
+                        .fmt_fields(PlainFields::default())
+                        .with_writer(file)
+                        .boxed(),
+                );
+            }
+        }
+        LogFormat::Json => {
+            layers.push(RequestIdLayer.boxed());
+            layers.push(fmt::layer().event_format(JsonFormat { offset }).boxed());
+
+            if let Some(file) = file {
+                layers.push(
+                    fmt::layer()
+                        .event_format(JsonFormat { offset })
+                        .with_writer(file)
+                        .boxed(),
+                );
+            }
+        }
+    }
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(layers)
+        .init();
+    std::panic::set_hook(Box::new(log_panic));
+
+    Ok(LogLevelHandle::new(reload, level, directives))
+}
+
+/// Panic hook logging the panic, where it happened and a backtrace, so
+/// that a handler panic answered with HTTP 500 still leaves a trace in the
+/// log (with the request's ID).
+fn log_panic(info: &PanicHookInfo<'_>) {
+    let backtrace = Backtrace::force_capture();
+    tracing::error!("{}\n{}", info, backtrace);
+}
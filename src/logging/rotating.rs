@@ -0,0 +1,147 @@
+use anyhow::{Context, Result};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use tracing_subscriber::field::RecordFields;
+use tracing_subscriber::fmt;
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FormatFields, MakeWriter};
+
+/// Field formatter for file output.
+///
+/// tracing-subscriber caches formatted span fields per formatter type; using
+/// a type distinct from the console's keeps the console's ANSI-coloured
+/// span fields out of the file.
+#[derive(Default)]
+pub struct PlainFields(fmt::format::DefaultFields);
+
+impl<'w> FormatFields<'w> for PlainFields {
+    fn format_fields<R: RecordFields>(&self, writer: Writer<'w>, fields: R) -> std::fmt::Result {
+        self.0.format_fields(writer, fields)
+    }
+}
+
+/// A log file that is rotated once it reaches a size limit.
+///
+/// On rotation `<file>.N-1` is renamed to `<file>.N` (down to `<file>` →
+/// `<file>.1`), files beyond `max_files` are deleted, and a fresh `<file>`
+/// is opened. Each log line is written while holding a mutex, so lines from
+/// concurrent tasks are never interleaved or lost across a rotation.
+pub struct RotatingFile {
+    path: PathBuf,
+    max_size: u64,
+    max_files: usize,
+    state: Mutex<RotatingState>,
+}
+
+/// The currently open file and its size.
+struct RotatingState {
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    /// Opens (appending to) the log file at `path`, creating it and its
+    /// parent directory if necessary.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` — Path of the active log file.
+    /// * `max_size` — Size in bytes at which the file is rotated.
+    /// * `max_files` — Number of rotated files to keep; `0` keeps none.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory or file cannot be created.
+    pub fn open(path: &Path, max_size: u64, max_files: usize) -> Result<Self> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create log directory `{}`", parent.display())
+            })?;
+        }
+
+        let file = open_append(path)
+            .with_context(|| format!("Failed to open log file `{}`", path.display()))?;
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            max_size,
+            max_files,
+            state: Mutex::new(RotatingState { file, size }),
+        })
+    }
+
+    /// Returns the path of the `index`-th rotated file (`<file>.<index>`).
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+
+    /// Shifts the rotated files and reopens an empty active file.
+    fn rotate(&self, state: &mut RotatingState) -> io::Result<()> {
+        state.file.flush()?;
+
+        if self.max_files == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            let _ = fs::remove_file(self.rotated_path(self.max_files));
+            for index in (1..self.max_files).rev() {
+                let from = self.rotated_path(index);
+                if from.exists() {
+                    fs::rename(&from, self.rotated_path(index + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rotated_path(1))?;
+        }
+
+        state.file = open_append(&self.path)?;
+        state.size = 0;
+
+        Ok(())
+    }
+}
+
+/// Opens `path` for appending, creating it if needed.
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// Writer for a single log line, holding the [`RotatingFile`] lock.
+pub struct RotatingWriter<'a> {
+    owner: &'a RotatingFile,
+    state: MutexGuard<'a, RotatingState>,
+}
+
+impl Write for RotatingWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.state.size > 0 && self.state.size + buf.len() as u64 > self.owner.max_size {
+            // A failed rotation must not drop the line; keep appending to
+            // the current file instead.
+            if let Err(e) = self.owner.rotate(&mut self.state) {
+                eprintln!("Failed to rotate log file: {}", e);
+            }
+        }
+
+        let written = self.state.file.write(buf)?;
+        self.state.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.state.file.flush()
+    }
+}
+
+impl<'a> MakeWriter<'a> for RotatingFile {
+    type Writer = RotatingWriter<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RotatingWriter {
+            owner: self,
+            state: self.state.lock().unwrap_or_else(|e| e.into_inner()),
+        }
+    }
+}
@@ -1,34 +1,46 @@
 use crate::args::Args;
-use crate::router::AppState;
 use anyhow::Context;
 use axum::Router;
 use clap::Parser;
+use futures_util::future::try_join_all;
+use std::future::Future;
+use std::io::ErrorKind;
 use std::net::SocketAddr;
+use std::pin::Pin;
 use std::sync::Arc;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
 use tokio::signal;
+use tokio_util::sync::CancellationToken;
 use tower_http::cors::{
     AllowCredentials, AllowHeaders, AllowMethods, AllowOrigin, CorsLayer, ExposeHeaders,
 };
 use tower_http::trace::TraceLayer;
-use tracing::info;
+use tracing::{error, info, warn};
+use vnstat_rs_api::config::AppConfig;
+use vnstat_rs_api::config::listener::ListenerConfig;
+use vnstat_rs_api::config::server::{RuntimeFlavor, ServerConfig};
+use vnstat_rs_api::logging::LogLevelHandle;
+use vnstat_rs_api::router::AppState;
+use vnstat_rs_api::service::cache_refresher::CacheRefresher;
+use vnstat_rs_api::service::mqtt_publisher::MqttPublisher;
+use vnstat_rs_api::service::quota_notifier::QuotaNotifier;
+use vnstat_rs_api::service::vnstat_service::VnstatService;
+use vnstat_rs_api::utils::build_info;
+use vnstat_rs_api::{config, logging, middleware, router};
 
 mod args;
-mod config;
-mod error_code;
-mod logging;
-mod model;
-mod router;
-mod service;
-mod task_handle;
-mod task_manager;
-mod utils;
+mod tls;
+
+/// Most threads Tokio starts for blocking work unless
+/// `server.max_blocking_threads` says otherwise (Tokio's own default).
+const DEFAULT_MAX_BLOCKING_THREADS: usize = 512;
 
 /// Entry point for the vnstat-rs API server.
 ///
-/// Parses command-line arguments, initialises logging, loads configuration,
-/// and starts the Axum HTTP server with graceful shutdown support.  The
-/// server binds to the address and port specified in the configuration file
-/// and exposes all routes under `/api/v1`.
+/// Parses command-line arguments, loads configuration and initialises
+/// logging, then builds the Tokio runtime sized by `[server]` and runs the
+/// server on it (see [`run`]).
 ///
 /// # Returns
 ///
@@ -36,34 +48,305 @@ mod utils;
 ///
 /// # Errors
 ///
-/// * Returns an error if logging initialisation fails.
 /// * Returns an error if the configuration file cannot be loaded or parsed.
-/// * Returns an error if the server socket cannot be bound.
-/// * Returns an error if the server encounters a fatal runtime failure.
-#[tokio::main(flavor = "multi_thread")]
-async fn main() -> anyhow::Result<()> {
+/// * Returns an error if logging initialisation fails.
+/// * Returns an error if the runtime cannot be built.
+/// * Returns the errors of [`run`].
+fn main() -> anyhow::Result<()> {
     let args = Args::parse();
 
-    logging::init(args.debug)?;
+    let config =
+        config::load_config(args.config.as_deref()).context("Failed to load configuration")?;
+
+    let log_level = logging::init(args.debug, &config.logging)?;
     tracing::debug!("debug mode enabled");
 
-    let config = config::load_config(&args.config).context("Failed to load configuration")?;
-    info!("Configuration loaded successfully");
+    match &config.source {
+        Some(path) => info!("Configuration loaded from `{}`", path.display()),
+        None => warn!("No configuration file found, using built-in defaults"),
+    }
 
-    let vnstat = Arc::new(service::vnstat_service::VnstatService::new(
-        config.vnstat.executable,
-    ));
-    let task_manager = Arc::new(task_manager::TaskManager::new());
+    build_runtime(&config.server)?.block_on(run(args, config, log_level))
+}
+
+/// Builds the Tokio runtime chosen by `runtime`, `worker_threads` and
+/// `max_blocking_threads` of `server`, and logs its size.
+///
+/// # Errors
+///
+/// Returns an error if the runtime cannot be created.
+fn build_runtime(server: &ServerConfig) -> anyhow::Result<tokio::runtime::Runtime> {
+    let max_blocking_threads = server
+        .max_blocking_threads
+        .unwrap_or(DEFAULT_MAX_BLOCKING_THREADS);
 
-    let app_state = AppState {
-        vnstat,
-        task_manager,
+    let mut builder = match server.runtime {
+        RuntimeFlavor::MultiThread => {
+            let worker_threads = server.worker_threads();
+            info!(
+                "Starting multi-threaded runtime (worker threads: {}, max blocking threads: {})",
+                worker_threads, max_blocking_threads
+            );
+            let mut builder = tokio::runtime::Builder::new_multi_thread();
+            builder.worker_threads(worker_threads);
+            builder
+        }
+        RuntimeFlavor::CurrentThread => {
+            if let Some(worker_threads) = server.worker_threads {
+                warn!(
+                    "Ignoring `worker_threads = {}` with `runtime = \"current_thread\"`",
+                    worker_threads
+                );
+            }
+            info!(
+                "Starting current-thread runtime (max blocking threads: {})",
+                max_blocking_threads
+            );
+            tokio::runtime::Builder::new_current_thread()
+        }
     };
 
-    let app = Router::new()
-        .nest("/api/v1", router::get_router())
+    builder
+        .max_blocking_threads(max_blocking_threads)
+        .enable_all()
+        .build()
+        .context("Failed to build the Tokio runtime")
+}
+
+/// Starts the Axum HTTP server with graceful shutdown support. The server
+/// binds to the addresses specified in the configuration file and exposes
+/// all routes under `/api/v1`.
+///
+/// # Errors
+///
+/// * Returns an error if the vnStat version check fails.
+/// * Returns an error if the server socket cannot be bound.
+/// * Returns an error if the server encounters a fatal runtime failure.
+async fn run(args: Args, config: AppConfig, log_level: LogLevelHandle) -> anyhow::Result<()> {
+    let app_state = AppState::from_config(&config)
+        .with_debug(args.debug)
+        .with_log_level(log_level);
+    let vnstat = app_state.vnstat.clone();
+    let quota = app_state.quota.clone();
+    #[cfg(unix)]
+    let reload_state = app_state.clone();
+    vnstat
+        .check_version()
+        .await
+        .context("vnStat version check failed")?;
+
+    let api = Router::new()
+        .nest("/api/v1", router::router_with_state(app_state))
         .layer(TraceLayer::new_for_http());
 
+    // Every listener is bound before any of them serves, so that a taken
+    // port fails the startup as a whole.
+    let stop = CancellationToken::new();
+    let mut servers: Vec<Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send>>> = Vec::new();
+    let mut bound = Vec::new();
+    for listener in config.server.listeners() {
+        let app = listener_app(api.clone(), &config, &listener);
+        if let Some(port) = listener.overridden_port() {
+            warn!(
+                "Listen address `{}` names its port, ignoring `port = {}`",
+                listener.listen, port
+            );
+        }
+        let addr = listener.to_socket_addr()?;
+        let tcp = bind(addr).await?;
+        let local_addr = tcp.local_addr()?;
+        let stop = stop.clone();
+
+        match &listener.tls {
+            Some(tls_config) => {
+                let acceptor = tls::acceptor(tls_config)?;
+                bound.push(format!("https://{}", local_addr));
+                servers.push(Box::pin(tls::serve(tcp, acceptor, app, stop)));
+            }
+            None => {
+                bound.push(format!("http://{}", local_addr));
+                servers.push(Box::pin(async move {
+                    axum::serve(tcp, app.into_make_service_with_connect_info::<SocketAddr>())
+                        .with_graceful_shutdown(stop.cancelled_owned())
+                        .await
+                        .with_context(|| format!("Server on {} failed", local_addr))
+                }));
+            }
+        }
+    }
+
+    log_banner(&bound, &vnstat);
+
+    let shutdown = CancellationToken::new();
+    let refresher = config.cache.refresh_interval().map(|interval| {
+        let refresher = CacheRefresher::new(vnstat.clone(), interval);
+        tokio::spawn(refresher.run(shutdown.clone()))
+    });
+    let notifier = if quota.load().webhooks.enabled() {
+        let notifier = QuotaNotifier::new(vnstat.clone(), quota)
+            .context("Failed to initialise quota webhooks")?;
+        Some(tokio::spawn(notifier.run(shutdown.clone())))
+    } else {
+        None
+    };
+    let mqtt = match &config.integrations.mqtt {
+        Some(mqtt) => {
+            let publisher = MqttPublisher::new(vnstat, mqtt.clone())
+                .context("Failed to initialise MQTT publishing")?;
+            Some(tokio::spawn(publisher.run(shutdown.clone())))
+        }
+        None => None,
+    };
+
+    #[cfg(unix)]
+    tokio::spawn(reload_on_hangup(reload_state, args.config, config));
+
+    tokio::spawn({
+        let stop = stop.clone();
+        async move {
+            shutdown_signal().await;
+            stop.cancel();
+        }
+    });
+    // A listener that fails stops the others, and the server with them.
+    let served = try_join_all(servers).await;
+    stop.cancel();
+
+    shutdown.cancel();
+    if let Some(refresher) = refresher {
+        let _ = refresher.await;
+    }
+    if let Some(notifier) = notifier {
+        let _ = notifier.await;
+    }
+    if let Some(mqtt) = mqtt {
+        let _ = mqtt.await;
+    }
+
+    served?;
+    info!("Server shut down gracefully");
+
+    Ok(())
+}
+
+/// Reloads the configuration on every `SIGHUP` and applies what can change
+/// without a restart (see [`AppState::reload`]).
+///
+/// A configuration that fails to load is reported and the current one is
+/// kept. Changes to settings only read at startup are compared with
+/// `running`, the configuration the server started with, and reported as
+/// needing a restart by the reload that loads them; later reloads only
+/// report what changed again.
+#[cfg(unix)]
+async fn reload_on_hangup(state: AppState, path: Option<String>, running: AppConfig) {
+    let mut hangup = match signal::unix::signal(signal::unix::SignalKind::hangup()) {
+        Ok(sig) => sig,
+        Err(e) => {
+            warn!("Failed to install SIGHUP handler: {}", e);
+            return;
+        }
+    };
+
+    let mut reloaded: Option<AppConfig> = None;
+    while hangup.recv().await.is_some() {
+        info!("SIGHUP received, reloading the configuration");
+
+        let config = match config::load_config(path.as_deref()) {
+            Ok(config) => config,
+            Err(e) => {
+                error!(
+                    "Configuration reload failed, keeping the current configuration: {:#}",
+                    e
+                );
+                continue;
+            }
+        };
+        if let Err(e) = state.reload(&config).await {
+            error!("Configuration reload failed: {:#}", e);
+            continue;
+        }
+
+        let mut pending = running.restart_required(&config);
+        if let Some(reloaded) = &reloaded {
+            let changed = reloaded.restart_required(&config);
+            pending.retain(|section| changed.contains(section));
+        }
+        reloaded = Some(config);
+
+        if pending.is_empty() {
+            info!("Configuration reloaded");
+        } else {
+            warn!(
+                "Configuration reloaded, but changes to {} need a restart to take effect",
+                pending.join(", ")
+            );
+        }
+    }
+}
+
+/// Binds a TCP listener on `addr`, logging the address first.
+///
+/// Port `0` binds a port chosen by the system, reported by the startup
+/// banner.
+///
+/// # Errors
+///
+/// Returns an error naming `addr` if it cannot be bound, with a hint on
+/// how to fix a port in use, a privileged port or an address that does not
+/// belong to the machine.
+async fn bind(addr: SocketAddr) -> anyhow::Result<tokio::net::TcpListener> {
+    info!("Binding {}", addr);
+
+    tokio::net::TcpListener::bind(addr).await.map_err(|e| {
+        let hint = match e.kind() {
+            ErrorKind::AddrInUse => Some(format!(
+                "another process already listens on port {}; stop it (`ss -ltnp 'sport = :{}'` shows which) or choose another port",
+                addr.port(),
+                addr.port()
+            )),
+            ErrorKind::PermissionDenied if addr.port() < 1024 => Some(
+                "ports below 1024 need root or the CAP_NET_BIND_SERVICE capability (e.g. `AmbientCapabilities=CAP_NET_BIND_SERVICE` in the systemd unit); or choose a port from 1024 up"
+                    .to_string(),
+            ),
+            ErrorKind::AddrNotAvailable => Some(format!(
+                "{} is not an address of this machine; listen on one of its addresses or on 0.0.0.0",
+                addr.ip()
+            )),
+            _ => None,
+        };
+
+        let error = anyhow::Error::new(e);
+        match hint {
+            Some(hint) => error.context(format!("Failed to bind socket {}: {}", addr, hint)),
+            None => error.context(format!("Failed to bind socket {}", addr)),
+        }
+    })
+}
+
+/// Wraps the API in the middleware of `listener`: its API keys and client
+/// allowlist, then CORS, access logging, client-IP resolution and request
+/// IDs, which are the same on every listener.
+fn listener_app(api: Router, config: &AppConfig, listener: &ListenerConfig) -> Router {
+    let app = if listener.api_keys.is_empty() {
+        api
+    } else {
+        api.layer(axum::middleware::from_fn_with_state(
+            Arc::new(listener.clone()),
+            middleware::api_key::api_key,
+        ))
+    };
+
+    // Inside CORS, so that browsers can read the rejection.
+    let allowed_ips = config.server.allowed_ips(listener);
+    let app = if allowed_ips.is_empty() {
+        app
+    } else {
+        app.layer(axum::middleware::from_fn_with_state(
+            Arc::from(allowed_ips),
+            middleware::ip_allowlist::ip_allowlist,
+        ))
+    };
+
     // Apply CORS layer based on configuration.
     let app = if config.cors.enabled {
         let cors = build_cors_layer(&config.cors);
@@ -72,25 +355,48 @@ async fn main() -> anyhow::Result<()> {
         app
     };
 
-    let app = app.with_state(app_state);
-
-    let listener = tokio::net::TcpListener::bind(config.server.to_socket_addr()?)
-        .await
-        .context("Failed to bind socket")?;
+    // Outermost, so that CORS preflights and rejections are logged too.
+    let app = if config.logging.access_log {
+        app.layer(axum::middleware::from_fn(
+            middleware::access_log::access_log,
+        ))
+    } else {
+        app
+    };
 
-    info!("Server listening on {}", listener.local_addr().unwrap());
+    // Outside the access log, so that its lines show the client behind a
+    // trusted proxy.
+    let app = app.layer(axum::middleware::from_fn_with_state(
+        Arc::from(config.server.trusted_proxies.as_slice()),
+        middleware::client_ip::client_ip,
+    ));
 
-    axum::serve(
-        listener,
-        app.into_make_service_with_connect_info::<SocketAddr>(),
-    )
-    .with_graceful_shutdown(shutdown_signal())
-    .await
-    .context("server failed to start")?;
+    // Outside the access log, so that its lines carry the request ID.
+    app.layer(axum::middleware::from_fn_with_state(
+        config.logging.generate_request_id,
+        middleware::request_id::request_id,
+    ))
+}
 
-    info!("Server shut down gracefully");
+/// Logs the startup banner: the build, the detected vnStat version and the
+/// URLs the server listens on, as also reported by `GET /api/v1/info`.
+fn log_banner(urls: &[String], vnstat: &VnstatService) {
+    let built_at = OffsetDateTime::from_unix_timestamp(build_info::build_timestamp())
+        .ok()
+        .and_then(|t| t.format(&Rfc3339).ok())
+        .unwrap_or_else(|| "unknown".to_string());
+    let vnstat_version = vnstat
+        .version()
+        .map_or_else(|| "unknown".to_string(), |v| v.to_string());
 
-    Ok(())
+    info!(
+        "vnstat-rs-api {} (commit {}, built {}) with vnStat {}, listening on {}",
+        build_info::VERSION,
+        build_info::GIT_COMMIT,
+        built_at,
+        vnstat_version,
+        urls.join(", ")
+    );
 }
 
 /// Build a [`CorsLayer`] from the application's CORS configuration.
@@ -219,3 +525,43 @@ async fn shutdown_signal() {
 
     info!("Shutdown signal received, gracefully stopping...");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn a_port_in_use_names_the_port_and_how_to_find_its_owner() {
+        let taken = std::net::TcpListener::bind("127.0.0.1:0").expect("bind a free port");
+        let addr = taken.local_addr().expect("local address");
+
+        let message = format!("{:#}", bind(addr).await.unwrap_err());
+
+        let port = addr.port();
+        assert!(
+            message.starts_with(&format!("Failed to bind socket 127.0.0.1:{}: ", port)),
+            "{}",
+            message
+        );
+        assert!(
+            message.contains(&format!(
+                "another process already listens on port {};",
+                port
+            )),
+            "{}",
+            message
+        );
+        assert!(
+            message.contains(&format!("`ss -ltnp 'sport = :{}'`", port)),
+            "{}",
+            message
+        );
+    }
+
+    #[tokio::test]
+    async fn a_free_port_binds() {
+        let listener = bind("127.0.0.1:0".parse().unwrap()).await.expect("bind");
+
+        assert_ne!(listener.local_addr().unwrap().port(), 0);
+    }
+}
@@ -0,0 +1,149 @@
+use crate::utils::client_ip::ClientIp;
+use axum::body::{Body, HttpBody};
+use axum::extract::{ConnectInfo, Request};
+use axum::http::{Method, Uri, header};
+use axum::middleware::Next;
+use axum::response::Response;
+use futures_util::StreamExt;
+use std::net::SocketAddr;
+use std::time::Instant;
+use tracing::{Span, info, warn};
+
+/// Target used for all access-log lines, so they can be filtered or routed
+/// separately from application logs.
+const TARGET: &str = "vnstat_rs_api::access";
+
+/// Middleware logging one line per request.
+///
+/// The line has the form
+///
+/// ```text
+/// 192.0.2.1 "GET /api/v1/vnstat/eth0" 200 1834 3ms
+/// ```
+///
+/// (client IP, method and URI, status, response size or `-` if unknown,
+/// elapsed time) and is logged at `info` for 1xx–3xx and `warn` for 4xx/5xx
+/// responses.
+///
+/// Server-Sent Event responses are logged when the stream is established
+/// (with `sse=open` instead of a size) and again when the client
+/// disconnects, with the stream duration and the number of events sent
+/// (keep-alive comments excluded).
+///
+/// The client IP is the [`ClientIp`] resolved through trusted proxies,
+/// falling back to [`ConnectInfo`]; `-` is logged when the server was not
+/// started with connect info.
+pub async fn access_log(request: Request, next: Next) -> Response {
+    let start = Instant::now();
+    let line = RequestLine {
+        client: request
+            .extensions()
+            .get::<ClientIp>()
+            .map(|ClientIp(ip)| ip.to_string())
+            .or_else(|| {
+                request
+                    .extensions()
+                    .get::<ConnectInfo<SocketAddr>>()
+                    .map(|ConnectInfo(addr)| addr.ip().to_string())
+            })
+            .unwrap_or_else(|| "-".to_string()),
+        method: request.method().clone(),
+        uri: request.uri().clone(),
+    };
+
+    let response = next.run(request).await;
+    let status = response.status();
+    let elapsed = start.elapsed().as_millis();
+
+    if is_event_stream(&response) {
+        info!(target: TARGET, "{} {} - {}ms sse=open", line, status.as_u16(), elapsed);
+        return track_event_stream(response, line);
+    }
+
+    let size = response
+        .body()
+        .size_hint()
+        .exact()
+        .map(|n| n.to_string())
+        .unwrap_or_else(|| "-".to_string());
+
+    if status.is_client_error() || status.is_server_error() {
+        warn!(target: TARGET, "{} {} {} {}ms", line, status.as_u16(), size, elapsed);
+    } else {
+        info!(target: TARGET, "{} {} {} {}ms", line, status.as_u16(), size, elapsed);
+    }
+
+    response
+}
+
+/// The request-identifying prefix of an access-log line.
+struct RequestLine {
+    client: String,
+    method: Method,
+    uri: Uri,
+}
+
+impl std::fmt::Display for RequestLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} \"{} {}\"", self.client, self.method, self.uri)
+    }
+}
+
+/// Returns `true` if `response` is a Server-Sent Events stream.
+fn is_event_stream(response: &Response) -> bool {
+    response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/event-stream"))
+}
+
+/// Logs the end of an SSE stream when dropped.
+struct StreamLog {
+    line: RequestLine,
+    opened: Instant,
+    events: u64,
+    /// The request's span (carrying its request ID), re-entered for the
+    /// close line since the body outlives the request handling.
+    span: Span,
+}
+
+impl Drop for StreamLog {
+    fn drop(&mut self) {
+        let _entered = self.span.enter();
+        info!(
+            target: TARGET,
+            "{} sse=closed duration={}s events={}",
+            self.line,
+            self.opened.elapsed().as_secs(),
+            self.events
+        );
+    }
+}
+
+/// Wraps the body of an SSE response so that events are counted and the
+/// disconnection is logged once the body is dropped.
+fn track_event_stream(response: Response, line: RequestLine) -> Response {
+    let (parts, body) = response.into_parts();
+    let mut log = StreamLog {
+        line,
+        opened: Instant::now(),
+        events: 0,
+        span: Span::current(),
+    };
+
+    let stream = body.into_data_stream().map(move |chunk| {
+        // Borrow the whole guard so the closure owns it (not just `events`)
+        // and the close line is logged when the stream is dropped.
+        let log = &mut log;
+        // Keep-alives are bare `:` comment frames.
+        if let Ok(bytes) = &chunk
+            && !bytes.starts_with(b":")
+        {
+            log.events += 1;
+        }
+        chunk
+    });
+
+    Response::from_parts(parts, Body::from_stream(stream))
+}
@@ -0,0 +1,38 @@
+use crate::config::listener::ListenerConfig;
+use crate::error_code::ErrorCode;
+use crate::model::jsend::JsendResponse;
+use axum::Json;
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::sync::Arc;
+
+/// Header carrying the API key of a request.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Middleware rejecting requests without one of the listener's `api_keys`
+/// in the `X-API-Key` header with HTTP 401 and [`ErrorCode::Unauthorized`].
+///
+/// A header of its own rather than `Authorization`, which carries the admin
+/// token of the `/admin` endpoints.
+pub async fn api_key(
+    State(listener): State<Arc<ListenerConfig>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let key = request
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|v| v.to_str().ok());
+
+    if key.is_some_and(|key| listener.authorize(key.trim())) {
+        return next.run(request).await;
+    }
+
+    let code = ErrorCode::Unauthorized;
+    (
+        code.status_code(),
+        Json(JsendResponse::<String>::fail(code)),
+    )
+        .into_response()
+}
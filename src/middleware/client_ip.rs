@@ -0,0 +1,32 @@
+use crate::utils::cidr::Cidr;
+use crate::utils::client_ip::{self, ClientIp};
+use axum::extract::{ConnectInfo, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// Middleware determining the client address of every request.
+///
+/// The address is resolved from the peer address and, when the peer is one
+/// of `trusted` (the `server.trusted_proxies` setting), the forwarding
+/// headers (see [`client_ip::resolve`]). It is stored in the request
+/// extensions as a [`ClientIp`]. Requests without [`ConnectInfo`] get no
+/// `ClientIp`.
+pub async fn client_ip(
+    State(trusted): State<Arc<[Cidr]>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+
+    if let Some(peer) = peer {
+        let ip = client_ip::resolve(peer, request.headers(), &trusted);
+        request.extensions_mut().insert(ClientIp(ip));
+    }
+
+    next.run(request).await
+}
@@ -0,0 +1,100 @@
+use crate::error_code::ErrorCode;
+use crate::model::jsend::JsendResponse;
+use axum::error_handling::HandleErrorLayer;
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use axum::{BoxError, Json, Router};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tower::ServiceBuilder;
+use tower::limit::GlobalConcurrencyLimitLayer;
+use tower::load_shed::LoadShedLayer;
+
+/// `Retry-After` sent with shed requests, in seconds.
+const RETRY_AFTER_SECONDS: &str = "1";
+
+/// Limit on the data requests handled at once (`server.max_in_flight`).
+///
+/// The limit is a [`GlobalConcurrencyLimitLayer`]: one semaphore is shared
+/// by all routes it is [applied](Self::apply) to, and kept here so the
+/// number of requests in flight can be read back. Without a limit the semaphore holds as many
+/// permits as it can, which still counts requests but never makes one wait.
+///
+/// Cloning is cheap and yields a handle to the same semaphore.
+#[derive(Debug, Clone)]
+pub struct InFlightLimit {
+    semaphore: Arc<Semaphore>,
+    /// Permits the semaphore was created with.
+    permits: usize,
+    /// Configured limit; `0` means unlimited.
+    max: usize,
+    /// Whether requests over the limit are rejected instead of queued.
+    load_shed: bool,
+}
+
+impl InFlightLimit {
+    /// Creates a limit of `max` requests (`0` for unlimited). With
+    /// `load_shed`, requests over the limit get HTTP 503 and
+    /// [`ErrorCode::Overloaded`] instead of waiting.
+    pub fn new(max: usize, load_shed: bool) -> Self {
+        let permits = if max == 0 {
+            Semaphore::MAX_PERMITS
+        } else {
+            max
+        };
+        Self {
+            semaphore: Arc::new(Semaphore::new(permits)),
+            permits,
+            max,
+            load_shed,
+        }
+    }
+
+    /// Returns the configured limit; `0` means unlimited.
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    /// Returns `true` if requests over the limit are rejected.
+    pub fn load_shed(&self) -> bool {
+        self.load_shed
+    }
+
+    /// Returns the number of requests currently being handled, not
+    /// counting those waiting for a slot.
+    pub fn in_flight(&self) -> usize {
+        self.permits - self.semaphore.available_permits()
+    }
+
+    /// Applies the limit to every route of `router`.
+    ///
+    /// Only matched routes count; fallbacks and routes added afterwards are
+    /// not limited.
+    pub fn apply<S>(&self, router: Router<S>) -> Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        let limit = GlobalConcurrencyLimitLayer::with_semaphore(self.semaphore.clone());
+        if self.load_shed {
+            router.route_layer(
+                ServiceBuilder::new()
+                    .layer(HandleErrorLayer::new(overloaded))
+                    .layer(LoadShedLayer::new())
+                    .layer(limit),
+            )
+        } else {
+            router.route_layer(limit)
+        }
+    }
+}
+
+/// Answers a request shed by the [`LoadShedLayer`].
+async fn overloaded(_: BoxError) -> Response {
+    let code = ErrorCode::Overloaded;
+    (
+        code.status_code(),
+        [(header::RETRY_AFTER, RETRY_AFTER_SECONDS)],
+        Json(JsendResponse::<String>::fail(code)),
+    )
+        .into_response()
+}
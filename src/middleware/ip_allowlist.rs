@@ -0,0 +1,86 @@
+use crate::error_code::ErrorCode;
+use crate::model::jsend::JsendResponse;
+use crate::utils::cidr::Cidr;
+use crate::utils::client_ip::ClientIp;
+use axum::Json;
+use axum::extract::{ConnectInfo, Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// Minimum time between two rejection warnings, so that a scanner cannot
+/// flood the log.
+const WARN_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Unix time in seconds of the last rejection warning.
+static LAST_WARNING: AtomicU64 = AtomicU64::new(0);
+
+/// Rejections not logged since the last warning.
+static SUPPRESSED: AtomicU64 = AtomicU64::new(0);
+
+/// Middleware rejecting clients outside `allowed` (the `server.allowed_ips`
+/// setting) with HTTP 403 and [`ErrorCode::Forbidden`].
+///
+/// The client address is the [`ClientIp`] resolved through trusted
+/// proxies, falling back to [`ConnectInfo`]. Requests whose address is
+/// unknown are rejected. Rejections are logged at `warn`, at most once
+/// every ten seconds, with the number of rejections left unlogged since.
+pub async fn ip_allowlist(
+    State(allowed): State<Arc<[Cidr]>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let ip = request
+        .extensions()
+        .get::<ClientIp>()
+        .map(|ClientIp(ip)| *ip)
+        .or_else(|| {
+            request
+                .extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip())
+        });
+
+    if ip.is_some_and(|ip| allowed.iter().any(|cidr| cidr.contains(ip))) {
+        return next.run(request).await;
+    }
+
+    warn_rejected(ip);
+    let code = ErrorCode::Forbidden;
+    (
+        code.status_code(),
+        Json(JsendResponse::<String>::fail(code)),
+    )
+        .into_response()
+}
+
+/// Logs the rejection of `ip`, unless another was logged less than
+/// [`WARN_INTERVAL`] ago.
+fn warn_rejected(ip: Option<IpAddr>) {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let last = LAST_WARNING.load(Ordering::Relaxed);
+    let due = now.saturating_sub(last) >= WARN_INTERVAL.as_secs()
+        && LAST_WARNING
+            .compare_exchange(last, now, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok();
+    if !due {
+        SUPPRESSED.fetch_add(1, Ordering::Relaxed);
+        return;
+    }
+
+    let ip = ip.map_or_else(|| "unknown address".to_string(), |ip| ip.to_string());
+    match SUPPRESSED.swap(0, Ordering::Relaxed) {
+        0 => warn!("Rejected request from {}: not in allowed_ips", ip),
+        n => warn!(
+            "Rejected request from {}: not in allowed_ips ({} more rejections not logged)",
+            ip, n
+        ),
+    }
+}
@@ -0,0 +1,152 @@
+use crate::model::admin::{LatencyBucket, LatencyHistogram, RouteStats};
+use axum::Router;
+use axum::extract::{MatchedPath, Request, State};
+use axum::middleware::{self, Next};
+use axum::response::Response;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Request counters and latency histograms per route.
+///
+/// Routes are keyed by their pattern (e.g. `/vnstat/{if_name}`), so the
+/// number of entries is bounded by the number of routes. Each router state
+/// owns its own instance, [applied](Self::apply) to its routes.
+///
+/// Cloning is cheap and yields a handle to the same counters.
+#[derive(Debug, Clone)]
+pub struct RequestMetrics {
+    /// Upper bounds of the latency buckets in milliseconds, ascending.
+    buckets: Arc<[f64]>,
+    routes: Arc<Mutex<HashMap<String, RouteCounters>>>,
+}
+
+/// Raw counters of one route.
+#[derive(Debug)]
+struct RouteCounters {
+    requests: u64,
+    /// Responses per status class, `1xx` to `5xx`.
+    statuses: [u64; 5],
+    /// Requests per latency bucket; the last entry counts those above the
+    /// largest bound.
+    latencies: Vec<u64>,
+    latency_sum_ms: f64,
+}
+
+/// Names of the status classes, indexed by the first digit minus one.
+const STATUS_CLASSES: [&str; 5] = ["1xx", "2xx", "3xx", "4xx", "5xx"];
+
+impl RequestMetrics {
+    /// Creates empty metrics with latency buckets bounded by `buckets`
+    /// (milliseconds, ascending).
+    pub fn new(buckets: &[f64]) -> Self {
+        Self {
+            buckets: Arc::from(buckets),
+            routes: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Records every request to a route of `router`.
+    ///
+    /// Only matched routes are recorded; requests answered by a fallback
+    /// (unknown paths and methods) and routes added afterwards are not.
+    pub fn apply<S>(&self, router: Router<S>) -> Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        router.route_layer(middleware::from_fn_with_state(self.clone(), record))
+    }
+
+    /// Returns the counters of every route requested so far, sorted by
+    /// route.
+    pub fn snapshot(&self) -> Vec<RouteStats> {
+        let routes = self.routes.lock().unwrap_or_else(|e| e.into_inner());
+        let mut stats: Vec<RouteStats> = routes
+            .iter()
+            .map(|(route, counters)| self.route_stats(route, counters))
+            .collect();
+        stats.sort_by(|a, b| a.route.cmp(&b.route));
+        stats
+    }
+
+    /// Returns the upper bounds of the latency buckets in milliseconds.
+    pub fn buckets(&self) -> &[f64] {
+        &self.buckets
+    }
+
+    /// Counts a request to `route` answered with `status` after
+    /// `latency_ms`.
+    fn observe(&self, route: &str, status: u16, latency_ms: f64) {
+        let bucket = self.buckets.partition_point(|&le| le < latency_ms);
+        let class = usize::from(status / 100).clamp(1, 5) - 1;
+
+        let mut routes = self.routes.lock().unwrap_or_else(|e| e.into_inner());
+        let counters = match routes.get_mut(route) {
+            Some(counters) => counters,
+            None => routes.entry(route.to_string()).or_insert(RouteCounters {
+                requests: 0,
+                statuses: [0; 5],
+                latencies: vec![0; self.buckets.len() + 1],
+                latency_sum_ms: 0.0,
+            }),
+        };
+        counters.requests += 1;
+        counters.statuses[class] += 1;
+        counters.latencies[bucket] += 1;
+        counters.latency_sum_ms += latency_ms;
+    }
+
+    fn route_stats(&self, route: &str, counters: &RouteCounters) -> RouteStats {
+        let mut cumulative = 0;
+        let buckets = self
+            .buckets
+            .iter()
+            .zip(&counters.latencies)
+            .map(|(&le, &count)| {
+                cumulative += count;
+                LatencyBucket {
+                    le,
+                    count: cumulative,
+                }
+            })
+            .collect();
+
+        RouteStats {
+            route: route.to_string(),
+            requests: counters.requests,
+            statuses: STATUS_CLASSES
+                .iter()
+                .zip(counters.statuses)
+                .filter(|&(_, count)| count > 0)
+                .map(|(&class, count)| (class, count))
+                .collect(),
+            latency: LatencyHistogram {
+                buckets,
+                count: counters.requests,
+                sum_ms: counters.latency_sum_ms,
+            },
+        }
+    }
+}
+
+/// Middleware timing a request until its response headers are ready and
+/// recording it under its [`MatchedPath`].
+///
+/// For streamed responses (live streams, exports) this is the time to the
+/// first byte, not the duration of the stream.
+async fn record(State(metrics): State<RequestMetrics>, request: Request, next: Next) -> Response {
+    let Some(route) = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+    else {
+        return next.run(request).await;
+    };
+
+    let start = Instant::now();
+    let response = next.run(request).await;
+    let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+    metrics.observe(&route, response.status().as_u16(), latency_ms);
+
+    response
+}
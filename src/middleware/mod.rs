@@ -0,0 +1,10 @@
+//! Tower/axum middleware applied around the API router.
+
+pub mod access_log;
+pub mod api_key;
+pub mod client_ip;
+pub mod in_flight;
+pub mod ip_allowlist;
+pub mod metrics;
+pub mod request_id;
+pub mod request_timeout;
@@ -0,0 +1,47 @@
+use crate::utils::request_id::RequestId;
+use axum::extract::{Request, State};
+use axum::http::HeaderValue;
+use axum::middleware::Next;
+use axum::response::Response;
+use tracing::Instrument;
+
+/// Name of the request/response header carrying the request ID.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Middleware assigning every request an ID.
+///
+/// A valid incoming `X-Request-Id` header is reused; otherwise a UUIDv4 is
+/// generated, unless `generate` (the `[logging] generate_request_id` setting)
+/// is `false`, in which case requests without a header get no ID.
+///
+/// The ID is stored in the request extensions as a [`RequestId`], made
+/// available through [`crate::utils::request_id::current`] (used to fill
+/// `requestId` in JSend error bodies), attached to a `request` tracing span
+/// so every log line emitted while handling the request carries it, and
+/// echoed in the `X-Request-Id` response header.
+pub async fn request_id(
+    State(generate): State<bool>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let incoming = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(RequestId::parse);
+
+    let Some(id) = incoming.or_else(|| generate.then(RequestId::generate)) else {
+        return next.run(request).await;
+    };
+
+    request.extensions_mut().insert(id.clone());
+
+    let span = tracing::info_span!("request", id = %id);
+    let mut response = id.clone().scope(next.run(request).instrument(span)).await;
+
+    if let Ok(value) = HeaderValue::from_str(&id.0) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+
+    response
+}
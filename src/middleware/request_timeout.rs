@@ -0,0 +1,40 @@
+use crate::error_code::ErrorCode;
+use crate::model::jsend::JsendResponse;
+use axum::error_handling::HandleErrorLayer;
+use axum::response::{IntoResponse, Response};
+use axum::{BoxError, Json, Router};
+use std::time::Duration;
+use tower::ServiceBuilder;
+use tower::timeout::TimeoutLayer;
+
+/// Applies `timeout` (`server.request_timeout_seconds`) to every route of
+/// `router`; `None` leaves it unchanged.
+///
+/// A request whose handler does not finish in time is answered with HTTP
+/// 504 and [`ErrorCode::RequestTimeout`], and the handler is dropped. The
+/// timeout covers producing the response, not streaming its body, and
+/// starts once the request is admitted by the
+/// [`InFlightLimit`](super::in_flight::InFlightLimit).
+pub fn apply<S>(router: Router<S>, timeout: Option<Duration>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    match timeout {
+        Some(timeout) => router.route_layer(
+            ServiceBuilder::new()
+                .layer(HandleErrorLayer::new(timed_out))
+                .layer(TimeoutLayer::new(timeout)),
+        ),
+        None => router,
+    }
+}
+
+/// Answers a request that hit the [`TimeoutLayer`].
+async fn timed_out(_: BoxError) -> Response {
+    let code = ErrorCode::RequestTimeout;
+    (
+        code.status_code(),
+        Json(JsendResponse::<String>::fail(code)),
+    )
+        .into_response()
+}
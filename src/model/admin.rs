@@ -0,0 +1,196 @@
+use crate::task_handle::TaskKind;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Body of `GET` and `PUT /admin/log-level`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogLevelBody {
+    /// Default log level: `trace`, `debug`, `info`, `warn` or `error`.
+    pub level: String,
+}
+
+/// One entry of `GET /admin/tasks`: a streaming task and its subscribers.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskInfo {
+    /// The task key (the interface name of a live stream, or
+    /// `updates:<interface>` for an update watcher).
+    pub key: String,
+    /// Whether the task runs a vnStat process or an update watcher.
+    pub kind: TaskKind,
+    /// Number of connected subscribers.
+    pub ref_count: usize,
+    /// Number of subscribers actually holding a receiver; lower than
+    /// `ref_count` if some are gone without having unsubscribed.
+    pub receivers: usize,
+    /// Whether the task's process or watcher is currently running.
+    pub running: bool,
+    /// Unix epoch timestamp (seconds) at which the running process was
+    /// started.
+    pub started_timestamp: Option<u64>,
+    /// Number of messages broadcast to subscribers so far.
+    pub messages: u64,
+}
+
+/// Body of `GET /admin/load`: current load against the configured limits.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadInfo {
+    /// Number of data requests being handled.
+    pub in_flight: usize,
+    /// `server.max_in_flight`; `0` means unlimited.
+    pub max_in_flight: usize,
+    /// Whether requests over `max_in_flight` are rejected (`server.load_shed`).
+    pub load_shed: bool,
+    /// Number of connected live-stream clients.
+    pub sse_clients: usize,
+    /// `server.max_sse_clients`; `0` means unlimited.
+    pub max_sse_clients: usize,
+}
+
+/// Result of `DELETE /admin/tasks/{key}`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskCancelled {
+    /// The task key.
+    pub key: String,
+    /// Whether a running process was killed; `false` if the task was idle.
+    pub cancelled: bool,
+}
+
+/// Body of `GET /admin/stats`: counters of the requests served and of the
+/// vnStat data cache since the server started.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatsInfo {
+    /// Hits, misses and failed refreshes of the vnStat data cache.
+    pub cache: CacheStats,
+    /// State of the circuit breaker guarding vnStat runs.
+    pub breaker: BreakerStatus,
+    /// Number of connected live-stream clients.
+    pub sse_clients: usize,
+    /// Upper bounds of the latency buckets in milliseconds
+    /// (`admin.latency_buckets_ms`).
+    pub latency_buckets_ms: Vec<f64>,
+    /// Counters of every route requested so far, sorted by route.
+    pub routes: Vec<RouteStats>,
+}
+
+/// Totals of the snapshot cache counters since the service was created.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    /// Reads answered from the cached snapshot without running vnStat.
+    pub hits: u64,
+    /// Reads that had to run vnStat because the snapshot was missing or
+    /// expired.
+    pub misses: u64,
+    /// Failed vnStat runs, on demand or by the background refresh.
+    pub refresh_failures: u64,
+}
+
+/// State of a [`CircuitBreaker`](crate::service::circuit_breaker::CircuitBreaker).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    /// vnStat is run as usual.
+    Closed,
+    /// vnStat failed repeatedly and is not run until the cooldown ends.
+    Open,
+    /// The cooldown ended; the next run decides whether the breaker closes
+    /// or opens again.
+    HalfOpen,
+}
+
+/// Circuit breaker status, as reported by `GET /admin/stats` and
+/// `GET /health`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BreakerStatus {
+    pub state: BreakerState,
+    /// vnStat runs that failed in a row.
+    pub consecutive_failures: u32,
+    /// The last failure, kept until a run succeeds.
+    pub last_error: Option<String>,
+    /// Seconds until vnStat is tried again, while the breaker is open.
+    pub retry_in_seconds: Option<u64>,
+}
+
+/// Body of `GET /admin/vnstat-status`: the state of the vnStat backend and
+/// its last runs.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VnstatStatusInfo {
+    /// State of the circuit breaker guarding vnStat runs.
+    pub breaker: BreakerStatus,
+    /// Hits, misses and failed refreshes of the vnStat data cache.
+    pub cache: CacheStats,
+    /// Age of the cached data in seconds; `None` until data was fetched.
+    pub data_age_seconds: Option<u64>,
+    /// The last vnStat runs, newest first.
+    pub history: Vec<FetchAttempt>,
+}
+
+/// What a recorded vnStat run was for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FetchKind {
+    /// A `vnstat --json` data fetch, on demand or by the background
+    /// refresh.
+    Data,
+    /// A `/health` check.
+    HealthCheck,
+}
+
+/// One vnStat run, as reported by `GET /admin/vnstat-status`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FetchAttempt {
+    /// Unix epoch timestamp (seconds) at which the run finished.
+    pub timestamp: i64,
+    pub kind: FetchKind,
+    /// Time the run took, retries included.
+    pub duration_ms: u64,
+    pub success: bool,
+    /// Why the run failed.
+    pub error: Option<String>,
+    /// Size of the JSON document vnStat printed, if the run succeeded.
+    pub output_bytes: Option<usize>,
+}
+
+/// Counters of one route, as reported by `GET /admin/stats`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RouteStats {
+    /// The route pattern, e.g. `/vnstat/{if_name}`.
+    pub route: String,
+    /// Requests handled.
+    pub requests: u64,
+    /// Responses per status class (`2xx`, `4xx`, ...); classes without any
+    /// response are left out.
+    pub statuses: BTreeMap<&'static str, u64>,
+    /// Time until the response headers were ready.
+    pub latency: LatencyHistogram,
+}
+
+/// A cumulative latency histogram.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencyHistogram {
+    /// Requests answered within each bound; requests above the largest
+    /// bound are only counted in [`count`](Self::count).
+    pub buckets: Vec<LatencyBucket>,
+    /// Number of requests measured.
+    pub count: u64,
+    /// Sum of all latencies in milliseconds.
+    pub sum_ms: f64,
+}
+
+/// One bucket of a [`LatencyHistogram`].
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencyBucket {
+    /// Upper bound in milliseconds (inclusive).
+    pub le: f64,
+    /// Requests answered within `le`.
+    pub count: u64,
+}
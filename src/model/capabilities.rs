@@ -0,0 +1,73 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Availability of an endpoint group at the time of the request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CapabilityStatus {
+    /// The endpoints work normally.
+    Available,
+    /// The endpoints respond, but with reduced fidelity (e.g. stale data).
+    Degraded,
+    /// The endpoints currently fail or are switched off.
+    Disabled,
+}
+
+/// Machine-readable reason attached to a non-available capability.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CapabilityReason {
+    /// Running `vnstat --json` failed, so no traffic data can be served.
+    VnstatUnavailable,
+    /// The configured vnStat executable does not exist on disk.
+    ExecutableMissing,
+    /// The endpoints are switched off in the configuration.
+    DisabledByConfig,
+    /// The installed vnStat is older than `min_version` or could not be
+    /// identified.
+    VnstatVersionUnsupported,
+    /// vnStat monitors no interface yet, so only `/vnstat/` has data.
+    NoInterfaces,
+    /// vnStat failed repeatedly and is not run until the circuit breaker's
+    /// cooldown ends.
+    CircuitBreakerOpen,
+}
+
+/// The reported state of one endpoint group.
+#[derive(Debug, Clone, Serialize)]
+pub struct Capability {
+    /// Current availability.
+    pub status: CapabilityStatus,
+    /// Why the group is not fully available. Absent when available.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<CapabilityReason>,
+}
+
+impl Capability {
+    /// A fully available capability.
+    pub fn available() -> Self {
+        Self {
+            status: CapabilityStatus::Available,
+            reason: None,
+        }
+    }
+
+    /// A degraded capability with the given reason.
+    pub fn degraded(reason: CapabilityReason) -> Self {
+        Self {
+            status: CapabilityStatus::Degraded,
+            reason: Some(reason),
+        }
+    }
+
+    /// A disabled capability with the given reason.
+    pub fn disabled(reason: CapabilityReason) -> Self {
+        Self {
+            status: CapabilityStatus::Disabled,
+            reason: Some(reason),
+        }
+    }
+}
+
+/// Capability matrix keyed by endpoint group name (e.g. `data`, `live`).
+pub type Capabilities = BTreeMap<&'static str, Capability>;
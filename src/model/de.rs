@@ -0,0 +1,85 @@
+//! Lenient deserializers for fields whose representation differs between
+//! vnStat releases.
+
+use serde::Deserialize;
+use serde::de::{self, Deserializer};
+
+use super::vnstat::Time;
+
+/// Deserializes a string that some vnStat versions emit as a number (e.g.
+/// `"jsonversion": 2` instead of `"2"`).
+pub fn string_or_number<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr {
+        String(String),
+        Integer(i64),
+        Float(f64),
+    }
+
+    Ok(match Repr::deserialize(deserializer)? {
+        Repr::String(s) => s,
+        Repr::Integer(n) => n.to_string(),
+        Repr::Float(n) => n.to_string(),
+    })
+}
+
+/// Deserializes an optional small integer that may be given as a number, a
+/// numeric string, or `null`.
+pub fn opt_u8_lenient<'de, D>(deserializer: D) -> Result<Option<u8>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr {
+        Integer(u8),
+        String(String),
+    }
+
+    match Option::<Repr>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(Repr::Integer(n)) => Ok(Some(n)),
+        Some(Repr::String(s)) if s.is_empty() => Ok(None),
+        Some(Repr::String(s)) => s.parse().map(Some).map_err(de::Error::custom),
+    }
+}
+
+/// Deserializes an optional [`Time`] given either as a `{hour, minute}`
+/// object or as an `"HH:MM"` string.
+pub fn opt_time_lenient<'de, D>(deserializer: D) -> Result<Option<Time>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr {
+        Object { hour: u8, minute: u8 },
+        String(String),
+    }
+
+    match Option::<Repr>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(Repr::Object { hour, minute }) => Ok(Some(Time { hour, minute })),
+        Some(Repr::String(s)) => {
+            let (hour, minute) = s
+                .split_once(':')
+                .ok_or_else(|| de::Error::custom(format!("invalid time `{}`", s)))?;
+            Ok(Some(Time {
+                hour: hour.trim().parse().map_err(de::Error::custom)?,
+                minute: minute.trim().parse().map_err(de::Error::custom)?,
+            }))
+        }
+    }
+}
+
+/// Deserializes a required [`Time`] leniently; see [`opt_time_lenient`].
+pub fn time_lenient<'de, D>(deserializer: D) -> Result<Time, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    opt_time_lenient(deserializer)?.ok_or_else(|| de::Error::custom("missing time"))
+}
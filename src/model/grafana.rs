@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+
+/// Body of `POST /grafana/search`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct GrafanaSearch {
+    /// Text the returned series names must contain; all series if empty.
+    #[serde(default)]
+    pub target: String,
+}
+
+/// Body of `POST /grafana/query`, as sent by Grafana's JSON datasources.
+///
+/// Fields this API does not use (e.g. `interval`, `scopedVars`) are
+/// ignored.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrafanaQuery {
+    /// The time range of the panel.
+    pub range: GrafanaRange,
+    /// The series to return.
+    pub targets: Vec<GrafanaTarget>,
+    /// Most data points the panel can show, per series.
+    pub max_data_points: Option<usize>,
+}
+
+/// Time range of a [`GrafanaQuery`], as RFC 3339 timestamps.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GrafanaRange {
+    /// Start of the range, e.g. `2026-10-17T06:00:00.000Z`.
+    pub from: String,
+    /// End of the range.
+    pub to: String,
+}
+
+/// One series requested by a [`GrafanaQuery`].
+#[derive(Debug, Clone, Deserialize)]
+pub struct GrafanaTarget {
+    /// The series name, as returned by `/grafana/search`; targets without
+    /// one are skipped.
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Whether the target is hidden in the panel; hidden targets are
+    /// skipped.
+    #[serde(default)]
+    pub hide: bool,
+}
+
+/// One series of a `/grafana/query` response.
+#[derive(Debug, Clone, Serialize)]
+pub struct GrafanaSeries {
+    /// The series name.
+    pub target: String,
+    /// `[bytes, timestamp in milliseconds]` per record, oldest first.
+    pub datapoints: Vec<(u64, i64)>,
+}
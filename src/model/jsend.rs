@@ -1,4 +1,5 @@
 use crate::error_code::ErrorCode;
+use crate::utils::request_id;
 use serde::Serialize;
 
 /// A standardized JSON response following the [JSend specification](https://github.com/omniti-labs/jsend).
@@ -31,6 +32,73 @@ where
     /// carries meaningful data.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<T>,
+
+    /// Optional diagnostic details about a failure, only populated in debug
+    /// mode.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+
+    /// ID of the request that failed, present on fail responses produced
+    /// while handling a request with an `X-Request-Id`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+
+    /// Notes on how the request was served, e.g. query parameters that
+    /// were adjusted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<ResponseMeta>,
+}
+
+/// Notes on how a request was served, sent as the `meta` field of a
+/// [`JsendResponse`].
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResponseMeta {
+    /// Unix time (seconds) at which vnStat was invoked for the served data.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generated_at: Option<i64>,
+    /// Seconds since the served data was fetched from vnStat.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_age_seconds: Option<u64>,
+    /// Where the served data was read from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<DataSource>,
+    /// Whether the data is stale: the last refresh failed and an older
+    /// snapshot is served (see [`ErrorCode::StaleData`]).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stale: Option<bool>,
+    /// Whether vnStat has stopped updating the requested interface (see
+    /// [`ErrorCode::StaleInterface`]).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interface_stale: Option<bool>,
+    /// Set when `?limit=` was out of bounds and clamped.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<ClampedLimit>,
+    /// Number of records vnStat dated in the future, set when there are
+    /// any. They were handled according to `vnstat.future_timestamps`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub future_records: Option<usize>,
+    /// Set on a live poll when lines after `since` were no longer buffered,
+    /// so some are missing before the returned ones.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gap: Option<bool>,
+}
+
+/// Where served traffic data was read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataSource {
+    /// The output of the `vnstat` command-line tool.
+    Cli,
+}
+
+/// A `?limit=` query parameter that was clamped to the allowed bounds.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ClampedLimit {
+    /// The limit sent by the client.
+    pub requested: usize,
+    /// The limit that was applied.
+    pub applied: usize,
 }
 
 impl<T> JsendResponse<T>
@@ -41,13 +109,15 @@ where
     ///
     /// The returned response has a `"success"` status, [`ErrorCode::NoError`], and
     /// both `message` and `data` set to `None`.
-    #[allow(dead_code)]
     pub fn success() -> Self {
         Self {
             status: "success".to_string(),
             code: ErrorCode::NoError,
             message: None,
             data: None,
+            details: None,
+            request_id: None,
+            meta: None,
         }
     }
 
@@ -65,6 +135,31 @@ where
             code: ErrorCode::NoError,
             message: None,
             data: Some(data),
+            details: None,
+            request_id: None,
+            meta: None,
+        }
+    }
+
+    /// Creates a success response containing the given data and a
+    /// non-zero code qualifying it (e.g. [`ErrorCode::StaleData`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `data` — The payload to include in the response.
+    /// * `code` — The warning code; its default message is used as
+    ///   `message`.
+    ///
+    /// The returned response has a `"success"` status.
+    pub fn success_with_data_and_code(data: T, code: ErrorCode) -> Self {
+        Self {
+            status: "success".to_string(),
+            message: Some(code.message().to_string()),
+            code,
+            data: Some(data),
+            details: None,
+            request_id: None,
+            meta: None,
         }
     }
 
@@ -82,6 +177,48 @@ where
             message: Some(code.message().to_string()),
             code,
             data: None,
+            details: None,
+            request_id: request_id::current().map(|id| id.0),
+            meta: None,
         }
     }
+
+    /// Creates a fail response with the given error code and a specific
+    /// message instead of the code's default one.
+    ///
+    /// # Arguments
+    ///
+    /// * `code` — The application-specific error code describing the failure.
+    /// * `message` — Human-readable explanation of this particular failure.
+    pub fn fail_with_message(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            status: "fail".to_string(),
+            message: Some(message.into()),
+            code,
+            data: None,
+            details: None,
+            request_id: request_id::current().map(|id| id.0),
+            meta: None,
+        }
+    }
+
+    /// Attaches diagnostic details to the response.
+    ///
+    /// # Arguments
+    ///
+    /// * `details` — Free-form diagnostic text, e.g. an error chain.
+    pub fn with_details(mut self, details: impl Into<String>) -> Self {
+        self.details = Some(details.into());
+        self
+    }
+
+    /// Attaches notes on how the request was served.
+    ///
+    /// # Arguments
+    ///
+    /// * `meta` — The notes; `None` leaves the field out.
+    pub fn with_meta(mut self, meta: Option<ResponseMeta>) -> Self {
+        self.meta = meta;
+        self
+    }
 }
@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+/// Body of `POST /vnstat/interfaces`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewInterfaceBody {
+    /// Name of the interface to add to the vnStat database.
+    pub name: String,
+}
+
+/// Body of `PUT /vnstat/interfaces/{if_name}/alias`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AliasBody {
+    /// The new alias; empty to remove it.
+    pub alias: String,
+}
+
+/// Result of the interface management endpoints.
+#[derive(Debug, Clone, Serialize)]
+pub struct InterfaceChange {
+    /// The system-level interface name (e.g. `eth0`).
+    pub name: String,
+    /// The alias now stored by vnStat; only set by the alias endpoint.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+}
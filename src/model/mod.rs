@@ -1,2 +1,9 @@
+pub mod admin;
+pub mod capabilities;
+mod de;
+pub mod grafana;
 pub mod jsend;
+pub mod management;
+pub mod response;
 pub mod vnstat;
+pub mod vnstat_v1;
@@ -0,0 +1,614 @@
+use crate::error_code::ErrorCode;
+use crate::model::vnstat::{
+    Created, Date, DayRecord, FiveMinuteRecord, HourRecord, Interface, MonthDate, MonthRecord,
+    TopRecord, Total, Updated, YearRecord,
+};
+use crate::service::busiest::TrafficMetric;
+use crate::service::decimate::SeriesGranularity;
+use crate::service::stats::Granularity;
+use crate::service::week::WeekStart;
+use crate::utils::traffic_unit::Amount;
+use crate::utils::version::Version;
+use serde::Serialize;
+
+/// A single entry of the `/interfaces` listing.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InterfaceEntry {
+    /// The system-level interface name (e.g. `eth0`).
+    pub name: String,
+    /// The interface alias (configured alias if any, otherwise vnStat's).
+    pub alias: String,
+    /// Unix epoch timestamp (seconds) of the interface's last update.
+    pub updated_timestamp: i64,
+    /// Seconds since the interface's last update.
+    pub seconds_since_update: i64,
+    /// `true` if vnStat has not updated the interface for longer than
+    /// `stale_after_seconds`.
+    pub stale: bool,
+    /// Total bytes received since the interface was created.
+    pub total_rx: u64,
+    /// Total bytes transmitted since the interface was created.
+    pub total_tx: u64,
+}
+
+/// Payload of the `/interfaces` endpoint.
+///
+/// Serialised untagged, i.e. either as an array of names or as an array of
+/// [`InterfaceEntry`] objects.
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum InterfacesResponse {
+    /// Bare interface names (`?names_only=true`).
+    Names(Vec<String>),
+    /// Interface summary objects.
+    Entries(Vec<InterfaceEntry>),
+}
+
+/// Payload of the `/info` endpoint (also served as `/vnstat/info`).
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InfoResponse {
+    /// Version of this API (the crate version).
+    pub api_version: &'static str,
+    /// Git commit the API was built from, or `unknown`.
+    pub git_commit: &'static str,
+    /// Unix epoch timestamp (seconds) at which the API was built; `0` if
+    /// unknown.
+    pub build_timestamp: i64,
+    /// vnStat version detected at startup; absent if detection failed.
+    pub vnstat_version: Option<Version>,
+    /// Oldest vnStat version the API accepts.
+    pub min_vnstat_version: Version,
+    /// Whether the startup version check failed without being enforced.
+    pub degraded: bool,
+    /// Unix epoch timestamp (seconds) at which the server started.
+    pub started_at: i64,
+    /// Seconds since the server started.
+    pub uptime_seconds: i64,
+    /// Unix epoch timestamp (seconds) of the last successful configuration
+    /// reload (`SIGHUP`); absent if it was never reloaded.
+    pub reloaded_at: Option<i64>,
+    /// How the vnStat data is cached.
+    pub cache: CacheInfo,
+}
+
+/// The caching settings reported by `/info`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheInfo {
+    /// Seconds a fetched snapshot is served before vnStat is run again.
+    pub ttl_seconds: u64,
+    /// Seconds the last good data may be served, flagged as stale, while
+    /// vnStat fails.
+    pub max_stale_seconds: u64,
+    /// Seconds between two background refreshes; `None` if the cache is
+    /// filled on demand.
+    pub refresh_interval_seconds: Option<u64>,
+}
+
+/// Traffic of one day summed across all interfaces.
+#[derive(Debug, Clone, Serialize)]
+pub struct DayTotal {
+    /// The date of this record.
+    pub date: Date,
+    /// Unix epoch timestamp (seconds) of the start of the day.
+    pub timestamp: i64,
+    /// Bytes received on this day.
+    pub rx: u64,
+    /// Bytes transmitted on this day.
+    pub tx: u64,
+}
+
+/// Traffic of one month summed across all interfaces.
+#[derive(Debug, Clone, Serialize)]
+pub struct MonthTotal {
+    /// The month and year of this record.
+    pub date: MonthDate,
+    /// Unix epoch timestamp (seconds) of the start of the month.
+    pub timestamp: i64,
+    /// Bytes received in this month.
+    pub rx: u64,
+    /// Bytes transmitted in this month.
+    pub tx: u64,
+}
+
+/// Payload of the `/vnstat/total` endpoint.
+#[derive(Debug, Clone, Serialize)]
+pub struct TotalResponse {
+    /// Names of the interfaces that were summed.
+    pub interfaces: Vec<String>,
+    /// Lifetime traffic of all interfaces.
+    pub total: Total,
+    /// Per-day traffic, oldest first.
+    pub day: Vec<DayTotal>,
+    /// Per-month traffic, oldest first.
+    pub month: Vec<MonthTotal>,
+}
+
+/// One record of a compact series: `[timestamp, rx, tx]`.
+pub type SeriesPoint = (i64, Amount, Amount);
+
+/// Lifetime traffic in the unit of a compact series.
+#[derive(Debug, Clone, Serialize)]
+pub struct SeriesTotal {
+    /// Total received since creation.
+    pub rx: Amount,
+    /// Total transmitted since creation.
+    pub tx: Amount,
+}
+
+/// Record series of one interface, as compact `[timestamp, rx, tx]` arrays.
+#[derive(Debug, Clone, Serialize)]
+pub struct TrafficSeries {
+    /// Per-5-minute traffic, oldest first.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fiveminute: Option<Vec<SeriesPoint>>,
+    /// Per-hour traffic, oldest first.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hour: Option<Vec<SeriesPoint>>,
+    /// Per-day traffic, oldest first.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub day: Option<Vec<SeriesPoint>>,
+    /// Per-month traffic, oldest first.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub month: Option<Vec<SeriesPoint>>,
+    /// Per-year traffic, oldest first.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub year: Option<Vec<SeriesPoint>>,
+    /// Top traffic days, in vnStat's order.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top: Option<Vec<SeriesPoint>>,
+}
+
+/// Payload of the `/vnstat/{if_name}` endpoint with `?format=series`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InterfaceSeries {
+    /// The system-level interface name (e.g. `eth0`).
+    pub name: String,
+    /// The interface alias (configured alias if any, otherwise vnStat's).
+    pub alias: String,
+    /// Unit of the `rx` and `tx` values.
+    pub rx_unit: &'static str,
+    /// Cumulative total traffic since the interface was created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<SeriesTotal>,
+    /// The record series.
+    pub series: TrafficSeries,
+}
+
+/// The traffic data of an interface, restricted to the fields selected
+/// with `?include=` or `?exclude=`; unselected fields are omitted.
+#[derive(Debug, Clone, Serialize)]
+pub struct TrafficView {
+    /// Per-day traffic records.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub day: Option<Vec<DayRecord>>,
+    /// Per-5-minute traffic records.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fiveminute: Option<Vec<FiveMinuteRecord>>,
+    /// Per-hour traffic records.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hour: Option<Vec<HourRecord>>,
+    /// Per-month traffic records.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub month: Option<Vec<MonthRecord>>,
+    /// Top traffic day records.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top: Option<Vec<TopRecord>>,
+    /// Cumulative total traffic since the interface was created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<Total>,
+    /// Per-year traffic records.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub year: Option<Vec<YearRecord>>,
+}
+
+/// An [`Interface`] with only some of its traffic fields, see
+/// [`TrafficView`].
+#[derive(Debug, Clone, Serialize)]
+pub struct InterfaceView {
+    /// A human-readable alias or label for the interface.
+    pub alias: String,
+    /// The timestamp at which this interface was first created / observed.
+    pub created: Created,
+    /// The system-level name of the interface (e.g. `eth0`, `wlan0`).
+    pub name: String,
+    /// The selected traffic fields.
+    pub traffic: TrafficView,
+    /// The timestamp of the most recent update for this interface.
+    pub updated: Updated,
+}
+
+/// Summed series of all interfaces, as compact `[timestamp, rx, tx]`
+/// arrays.
+#[derive(Debug, Clone, Serialize)]
+pub struct TotalTrafficSeries {
+    /// Per-day traffic, oldest first.
+    pub day: Vec<SeriesPoint>,
+    /// Per-month traffic, oldest first.
+    pub month: Vec<SeriesPoint>,
+}
+
+/// Payload of the `/vnstat/total` endpoint with `?format=series`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TotalSeries {
+    /// Names of the interfaces that were summed.
+    pub interfaces: Vec<String>,
+    /// Unit of the `rx` and `tx` values.
+    pub rx_unit: &'static str,
+    /// Lifetime traffic of all interfaces.
+    pub total: SeriesTotal,
+    /// The summed series.
+    pub series: TotalTrafficSeries,
+}
+
+/// Projected traffic at the end of a period.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Estimate {
+    /// Unix epoch timestamp (seconds) at which the period ends.
+    pub period_end: i64,
+    /// Projected bytes received in the period.
+    pub rx: u64,
+    /// Projected bytes transmitted in the period.
+    pub tx: u64,
+    /// Projected bytes received and transmitted in the period.
+    pub total: u64,
+}
+
+/// Payload of the `/vnstat/{if_name}/estimate` endpoint.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EstimateResponse {
+    /// The system-level interface name (e.g. `eth0`).
+    pub name: String,
+    /// Unix epoch timestamp (seconds) of the interface's last update.
+    pub updated_timestamp: i64,
+    /// `false` if the interface was last updated more than an hour ago.
+    pub reliable: bool,
+    /// Projection for the current day; `null` with less than an hour of
+    /// data.
+    pub day: Option<Estimate>,
+    /// Projection for the current month; `null` with less than an hour of
+    /// data.
+    pub month: Option<Estimate>,
+}
+
+/// How close an interface is to its bandwidth quota.
+///
+/// Ordered by severity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuotaStatus {
+    /// Usage is below the warning threshold.
+    Ok,
+    /// Usage reached the warning threshold.
+    Warning,
+    /// Usage reached the exceeded threshold.
+    Exceeded,
+}
+
+/// Payload of the `/vnstat/{if_name}/quota` endpoint.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotaResponse {
+    /// The system-level interface name (e.g. `eth0`).
+    pub name: String,
+    /// Unix epoch timestamp (seconds) at which the billing period started.
+    pub period_start: i64,
+    /// Unix epoch timestamp (seconds) at which the billing period ends.
+    pub period_end: i64,
+    /// Bytes allowed per billing period.
+    pub limit: u64,
+    /// Bytes received and transmitted in the billing period so far.
+    pub used: u64,
+    /// Bytes left in the billing period; `0` once the limit is exceeded.
+    pub remaining: u64,
+    /// `used` as a percentage of `limit`.
+    pub percent: f64,
+    /// Threshold status of `percent`.
+    pub status: QuotaStatus,
+}
+
+/// A week's traffic, summed from the interface's day records.
+#[derive(Debug, Clone, Serialize)]
+pub struct WeekRecord {
+    /// The first day of the week.
+    pub date: Date,
+    /// Total bytes received in the week.
+    pub rx: u64,
+    /// Unix epoch timestamp (seconds) of the start of the week.
+    pub timestamp: i64,
+    /// Total bytes transmitted in the week.
+    pub tx: u64,
+    /// Number of days of the week with a day record.
+    pub days: u8,
+    /// `true` if the week has not ended yet or its first days are no longer
+    /// in the database.
+    pub partial: bool,
+}
+
+/// Payload of the `/vnstat/{if_name}/week` endpoint.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeekResponse {
+    /// The system-level interface name (e.g. `eth0`).
+    pub name: String,
+    /// The day the weeks start on.
+    pub week_start: WeekStart,
+    /// The weeks, oldest first.
+    pub week: Vec<WeekRecord>,
+}
+
+/// A billing month's traffic, summed from the interface's day records.
+#[derive(Debug, Clone, Serialize)]
+pub struct BillingMonthRecord {
+    /// The first day of the billing month.
+    pub start: Date,
+    /// The last day of the billing month.
+    pub end: Date,
+    /// Total bytes received in the billing month.
+    pub rx: u64,
+    /// Unix epoch timestamp (seconds) of the start of the billing month.
+    pub timestamp: i64,
+    /// Total bytes transmitted in the billing month.
+    pub tx: u64,
+    /// Number of days of the billing month with a day record.
+    pub days: u8,
+    /// `true` if the billing month has not ended yet or its first days are
+    /// no longer in the database.
+    pub partial: bool,
+}
+
+/// Payload of the `/vnstat/{if_name}/billing-month` endpoint.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BillingMonthResponse {
+    /// The system-level interface name (e.g. `eth0`).
+    pub name: String,
+    /// The day of the month billing months start on.
+    pub rotate_day: u8,
+    /// The billing months, oldest first.
+    pub billing_month: Vec<BillingMonthRecord>,
+}
+
+/// Statistics of the rates of one direction, in bits per second.
+///
+/// Every value is `null` when no record falls into the requested range.
+#[derive(Debug, Clone, Serialize)]
+pub struct RateStats {
+    /// The lowest rate.
+    pub min: Option<f64>,
+    /// The highest rate.
+    pub max: Option<f64>,
+    /// The average rate.
+    pub mean: Option<f64>,
+    /// The median rate.
+    pub median: Option<f64>,
+    /// The 95th percentile rate.
+    pub p95: Option<f64>,
+}
+
+/// Payload of the `/vnstat/{if_name}/stats` endpoint.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatsResponse {
+    /// The system-level interface name (e.g. `eth0`).
+    pub name: String,
+    /// The record series the statistics were computed from.
+    pub granularity: Granularity,
+    /// Length of one record's interval, in seconds.
+    pub interval_seconds: i64,
+    /// Number of records the statistics were computed from.
+    pub samples: usize,
+    /// Statistics of the receive rates.
+    pub rx: RateStats,
+    /// Statistics of the transmit rates.
+    pub tx: RateStats,
+}
+
+/// Why an interface requested together with others has no data.
+#[derive(Debug, Clone, Serialize)]
+pub struct InterfaceError {
+    /// Application-specific error code.
+    pub code: i32,
+    /// Human-readable description of the error.
+    pub message: String,
+}
+
+impl From<ErrorCode> for InterfaceError {
+    fn from(code: ErrorCode) -> Self {
+        InterfaceError {
+            code: code.code(),
+            message: code.message().to_string(),
+        }
+    }
+}
+
+/// One interface of a `/vnstat/compare` response.
+#[derive(Debug, Clone, Serialize)]
+pub struct ComparedInterface {
+    /// The name (or alias) as requested.
+    pub interface: String,
+    /// The system-level interface name; absent if it was not found.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Traffic of the interface in the compared range.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<Total>,
+    /// Why the interface could not be compared.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<InterfaceError>,
+}
+
+/// The traffic of the compared interfaces in one period.
+#[derive(Debug, Clone, Serialize)]
+pub struct CompareRecord {
+    /// Unix epoch timestamp (seconds) of the start of the period.
+    pub timestamp: i64,
+    /// Traffic of each compared interface, in the order requested; `null`
+    /// where an interface has no record for the period.
+    pub traffic: Vec<Option<Total>>,
+}
+
+/// Difference in bytes between two interfaces' traffic.
+#[derive(Debug, Clone, Serialize)]
+pub struct TrafficDelta {
+    /// Bytes received by the first interface minus the second's.
+    pub rx: i64,
+    /// Bytes transmitted by the first interface minus the second's.
+    pub tx: i64,
+}
+
+/// Ratio between two interfaces' traffic.
+#[derive(Debug, Clone, Serialize)]
+pub struct TrafficRatio {
+    /// Bytes received by the first interface divided by the second's;
+    /// `null` if the second received nothing.
+    pub rx: Option<f64>,
+    /// Bytes transmitted by the first interface divided by the second's;
+    /// `null` if the second transmitted nothing.
+    pub tx: Option<f64>,
+}
+
+/// The first two compared interfaces set against each other.
+#[derive(Debug, Clone, Serialize)]
+pub struct Comparison {
+    /// The first interface, as requested.
+    pub first: String,
+    /// The second interface, as requested.
+    pub second: String,
+    /// `first` minus `second`.
+    pub delta: TrafficDelta,
+    /// `first` divided by `second`.
+    pub ratio: TrafficRatio,
+}
+
+/// Payload of the `/vnstat/compare` endpoint.
+#[derive(Debug, Clone, Serialize)]
+pub struct CompareResponse {
+    /// The record series compared.
+    pub granularity: SeriesGranularity,
+    /// The compared interfaces, in the order requested.
+    pub interfaces: Vec<ComparedInterface>,
+    /// The aligned records, oldest first.
+    pub records: Vec<CompareRecord>,
+    /// The first two interfaces compared; `null` unless both were found.
+    pub comparison: Option<Comparison>,
+}
+
+/// One of the busiest periods of an interface.
+#[derive(Debug, Clone, Serialize)]
+pub struct BusyPeriod {
+    /// Unix epoch timestamp (seconds) of the start of the period.
+    pub timestamp: i64,
+    /// Bytes received in the period.
+    pub rx: u64,
+    /// Bytes transmitted in the period.
+    pub tx: u64,
+    /// Bytes received and transmitted in the period.
+    pub total: u64,
+}
+
+/// Payload of the `/vnstat/{if_name}/busiest` endpoint.
+#[derive(Debug, Clone, Serialize)]
+pub struct BusiestResponse {
+    /// The system-level interface name (e.g. `eth0`).
+    pub name: String,
+    /// The record series ranked.
+    pub granularity: SeriesGranularity,
+    /// The traffic the periods are ranked by.
+    pub by: TrafficMetric,
+    /// The busiest periods, busiest first.
+    pub periods: Vec<BusyPeriod>,
+}
+
+/// Payload of the `/vnstat/{if_name}/delta` endpoint.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeltaResponse {
+    /// The system-level interface name (e.g. `eth0`).
+    pub name: String,
+    /// Start of the requested window (Unix seconds).
+    pub from: i64,
+    /// End of the requested window (Unix seconds), exclusive.
+    pub to: i64,
+    /// The record series the traffic was summed from.
+    pub resolution: SeriesGranularity,
+    /// Bytes received.
+    pub rx: u64,
+    /// Bytes transmitted.
+    pub tx: u64,
+    /// Share of the window covered by records, from `0` to `1`.
+    pub coverage: f64,
+    /// Start of the first record counted, if any.
+    pub covered_from: Option<i64>,
+    /// End of the last record counted, if any.
+    pub covered_to: Option<i64>,
+}
+
+/// One interface of a `/vnstat/interfaces/batch` response: the requested
+/// parts, or the error that prevented reading them.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BatchEntry {
+    /// The `/vnstat/interfaces` entry of the interface.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<InterfaceEntry>,
+    /// Cumulative traffic since the interface was created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<Total>,
+    /// Five-minute records.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fiveminute: Option<Vec<FiveMinuteRecord>>,
+    /// Hour records.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hour: Option<Vec<HourRecord>>,
+    /// Day records.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub day: Option<Vec<DayRecord>>,
+    /// Month records.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub month: Option<Vec<MonthRecord>>,
+    /// Year records.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub year: Option<Vec<YearRecord>>,
+    /// Top days.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top: Option<Vec<TopRecord>>,
+    /// Why the interface could not be read.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<InterfaceError>,
+}
+
+impl BatchEntry {
+    /// Returns an entry carrying only the error `code`.
+    pub fn error(code: ErrorCode) -> Self {
+        BatchEntry {
+            error: Some(code.into()),
+            ..BatchEntry::default()
+        }
+    }
+}
+
+/// An event of the `/vnstat/{if_name}/updates` stream.
+#[derive(Debug, Clone, Serialize)]
+pub struct InterfaceUpdate<'a> {
+    /// The interface as returned by `/vnstat/{if_name}`.
+    pub interface: &'a Interface,
+    /// Traffic since the previous update; `null` for the snapshot sent on
+    /// connect.
+    pub delta: Option<Total>,
+}
+
+/// A data line returned by `/vnstat/{if_name}/live/poll`.
+#[derive(Debug, Clone, Serialize)]
+pub struct LivePollLine {
+    /// The line's sequence number, the `id` of the same line on the SSE
+    /// stream; pass the last one received as `since` to the next poll.
+    pub seq: u64,
+    /// The JSON line printed by `vnstat -l --json`, unparsed.
+    pub line: String,
+}
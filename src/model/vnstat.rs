@@ -1,23 +1,48 @@
+use super::de;
+use crate::utils::timestamp;
 use serde::{Deserialize, Serialize};
 
 /// Top-level vnstat data containing all monitored network interfaces and
 /// version information about the vnstat daemon and the JSON output format.
+///
+/// The model is lenient so that output from every vnStat 2.x release parses:
+/// unknown fields are ignored, traffic arrays and timestamps missing in older
+/// releases default to empty / `0` (timestamps are then synthesised from the
+/// date fields after parsing), and fields whose representation changed
+/// between releases accept each known form.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VnstatData {
     /// A list of network interfaces and their traffic statistics.
     pub interfaces: Vec<Interface>,
     /// The version of the JSON output format used by vnstat.
+    #[serde(deserialize_with = "de::string_or_number")]
     pub jsonversion: String,
     /// The version of the vnstat daemon that produced this data.
+    #[serde(deserialize_with = "de::string_or_number")]
     pub vnstatversion: String,
+    /// Number of future-dated records detected when this data was fetched.
+    /// Not part of vnstat's output.
+    #[serde(skip)]
+    pub future_records: usize,
+}
+
+/// Just the `jsonversion` field of a vnStat document, used to pick the
+/// matching schema before parsing the rest.
+#[derive(Debug, Deserialize)]
+pub struct JsonVersionProbe {
+    /// The version of the JSON output format used by vnstat.
+    #[serde(deserialize_with = "de::string_or_number")]
+    pub jsonversion: String,
 }
 
 /// Traffic statistics and metadata for a single network interface.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Interface {
     /// A human-readable alias or label for the interface.
+    #[serde(default)]
     pub alias: String,
     /// The timestamp at which this interface was first created / observed.
+    #[serde(default)]
     pub created: Created,
     /// The system-level name of the interface (e.g. `eth0`, `wlan0`).
     pub name: String,
@@ -28,11 +53,12 @@ pub struct Interface {
 }
 
 /// The creation timestamp of a network interface.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Created {
     /// The date on which the interface was created.
     pub date: Date,
     /// Unix epoch timestamp (seconds) of the creation time.
+    #[serde(default)]
     pub timestamp: i64,
 }
 
@@ -41,28 +67,38 @@ pub struct Created {
 pub struct Updated {
     /// The date of the most recent update.
     pub date: Date,
-    /// The time of day of the most recent update.
-    pub time: Time,
+    /// The time of day of the most recent update. Missing in some vnStat
+    /// releases.
+    #[serde(
+        default,
+        deserialize_with = "de::opt_time_lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub time: Option<Time>,
     /// Unix epoch timestamp (seconds) of the most recent update.
+    #[serde(default)]
     pub timestamp: i64,
 }
 
 /// A calendar date with optional day and month fields.
 ///
 /// The `day` and `month` fields may be `None` when the date represents a
-/// coarser granularity (e.g. a year-only date).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// coarser granularity (e.g. a year-only date). Both accept a number or a
+/// numeric string.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Date {
     /// Day of the month (1–31). `None` when not applicable.
+    #[serde(default, deserialize_with = "de::opt_u8_lenient")]
     pub day: Option<u8>,
     /// Month of the year (1–12). `None` when not applicable.
+    #[serde(default, deserialize_with = "de::opt_u8_lenient")]
     pub month: Option<u8>,
     /// Four-digit year (e.g. 2025).
     pub year: i32,
 }
 
 /// A time of day expressed in hours and minutes.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Time {
     /// Hour of the day (0–23).
     pub hour: u8,
@@ -74,18 +110,25 @@ pub struct Time {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Traffic {
     /// Per-day traffic records.
+    #[serde(default)]
     pub day: Vec<DayRecord>,
     /// Per-5-minute traffic records (high-resolution).
+    #[serde(default)]
     pub fiveminute: Vec<FiveMinuteRecord>,
     /// Per-hour traffic records.
+    #[serde(default)]
     pub hour: Vec<HourRecord>,
     /// Per-month traffic records.
+    #[serde(default)]
     pub month: Vec<MonthRecord>,
     /// Top traffic day records.
+    #[serde(default)]
     pub top: Vec<TopRecord>,
     /// Cumulative total traffic since the interface was created.
+    #[serde(default)]
     pub total: Total,
     /// Per-year traffic records.
+    #[serde(default)]
     pub year: Vec<YearRecord>,
 }
 
@@ -95,10 +138,12 @@ pub struct DayRecord {
     /// The date of this record.
     pub date: Date,
     /// A sequential identifier for this record.
+    #[serde(default)]
     pub id: u32,
     /// Total bytes received on this day.
     pub rx: u64,
     /// Unix epoch timestamp (seconds) for this record.
+    #[serde(default)]
     pub timestamp: i64,
     /// Total bytes transmitted on this day.
     pub tx: u64,
@@ -110,12 +155,15 @@ pub struct FiveMinuteRecord {
     /// The date of this record.
     pub date: Date,
     /// A sequential identifier for this record.
+    #[serde(default)]
     pub id: u32,
     /// Total bytes received in this 5-minute interval.
     pub rx: u64,
     /// The time at which this 5-minute interval started.
+    #[serde(deserialize_with = "de::time_lenient")]
     pub time: Time,
     /// Unix epoch timestamp (seconds) for this record.
+    #[serde(default)]
     pub timestamp: i64,
     /// Total bytes transmitted in this 5-minute interval.
     pub tx: u64,
@@ -127,12 +175,15 @@ pub struct HourRecord {
     /// The date of this record.
     pub date: Date,
     /// A sequential identifier for this record.
+    #[serde(default)]
     pub id: u32,
     /// Total bytes received in this hour.
     pub rx: u64,
     /// The starting time of this hour.
+    #[serde(deserialize_with = "de::time_lenient")]
     pub time: Time,
     /// Unix epoch timestamp (seconds) for this record.
+    #[serde(default)]
     pub timestamp: i64,
     /// Total bytes transmitted in this hour.
     pub tx: u64,
@@ -144,10 +195,12 @@ pub struct MonthRecord {
     /// The month and year of this record.
     pub date: MonthDate,
     /// A sequential identifier for this record.
+    #[serde(default)]
     pub id: u32,
     /// Total bytes received in this month.
     pub rx: u64,
     /// Unix epoch timestamp (seconds) for this record.
+    #[serde(default)]
     pub timestamp: i64,
     /// Total bytes transmitted in this month.
     pub tx: u64,
@@ -169,17 +222,19 @@ pub struct TopRecord {
     /// The date of this top record.
     pub date: Date,
     /// A sequential identifier for this record.
+    #[serde(default)]
     pub id: u32,
     /// Total bytes received on this day.
     pub rx: u64,
     /// Unix epoch timestamp (seconds) for this record.
+    #[serde(default)]
     pub timestamp: i64,
     /// Total bytes transmitted on this day.
     pub tx: u64,
 }
 
 /// Cumulative total traffic since the interface was created.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Total {
     /// Total bytes received since creation.
     pub rx: u64,
@@ -193,10 +248,12 @@ pub struct YearRecord {
     /// The year of this record.
     pub date: YearDate,
     /// A sequential identifier for this record.
+    #[serde(default)]
     pub id: u32,
     /// Total bytes received in this year.
     pub rx: u64,
     /// Unix epoch timestamp (seconds) for this record.
+    #[serde(default)]
     pub timestamp: i64,
     /// Total bytes transmitted in this year.
     pub tx: u64,
@@ -208,3 +265,112 @@ pub struct YearDate {
     /// Four-digit year (e.g. 2025).
     pub year: i32,
 }
+
+/// Access to the Unix timestamp carried by every traffic record type.
+///
+/// Lets normalisation passes treat the different record lists uniformly.
+pub trait Timestamped {
+    /// Returns the record's Unix epoch timestamp (seconds).
+    fn timestamp(&self) -> i64;
+
+    /// Overwrites the record's Unix epoch timestamp (seconds).
+    fn set_timestamp(&mut self, timestamp: i64);
+
+    /// Computes the timestamp of the start of the record's period from its
+    /// date (and time) fields. Used when vnStat did not emit a timestamp.
+    fn date_timestamp(&self) -> Option<i64>;
+
+    /// Sets the record's date (and time) fields to the period containing
+    /// the given local date and time.
+    fn set_period(&mut self, year: i32, month: u8, day: u8, hour: u8, minute: u8);
+
+    /// Moves the record into the period containing `timestamp`: its date
+    /// (and time) fields name that period and its timestamp becomes the
+    /// start of it.
+    fn move_to_period_of(&mut self, timestamp: i64) {
+        let Some((year, month, day, hour, minute)) = timestamp::to_local_datetime(timestamp) else {
+            self.set_timestamp(timestamp);
+            return;
+        };
+
+        self.set_period(year, month, day, hour, minute);
+        let start = self.date_timestamp().unwrap_or(timestamp);
+        self.set_timestamp(start);
+    }
+}
+
+impl Date {
+    /// Returns the full date `year-month-day`.
+    pub fn ymd(year: i32, month: u8, day: u8) -> Self {
+        Self {
+            day: Some(day),
+            month: Some(month),
+            year,
+        }
+    }
+}
+
+impl Date {
+    /// Returns the local timestamp of `hour:minute` on this date, treating a
+    /// missing day or month as the first one.
+    fn timestamp_at(&self, hour: u8, minute: u8) -> Option<i64> {
+        timestamp::from_local_date(
+            self.year,
+            self.month.unwrap_or(1),
+            self.day.unwrap_or(1),
+            hour,
+            minute,
+        )
+    }
+}
+
+macro_rules! impl_timestamped {
+    ($(
+        $ty:ty => |$r:ident| $date_ts:expr,
+            |$p:ident, $year:ident, $month:ident, $day:ident, $hour:ident, $minute:ident| $set:expr
+    );* $(;)?) => {
+        $(
+            impl Timestamped for $ty {
+                fn timestamp(&self) -> i64 {
+                    self.timestamp
+                }
+
+                fn set_timestamp(&mut self, timestamp: i64) {
+                    self.timestamp = timestamp;
+                }
+
+                fn date_timestamp(&self) -> Option<i64> {
+                    let $r = self;
+                    $date_ts
+                }
+
+                #[allow(unused_variables)]
+                fn set_period(&mut self, $year: i32, $month: u8, $day: u8, $hour: u8, $minute: u8) {
+                    let $p = self;
+                    $set
+                }
+            }
+        )*
+    };
+}
+
+impl_timestamped!(
+    DayRecord => |r| r.date.timestamp_at(0, 0),
+        |r, year, month, day, hour, minute| r.date = Date::ymd(year, month, day);
+    FiveMinuteRecord => |r| r.date.timestamp_at(r.time.hour, r.time.minute),
+        |r, year, month, day, hour, minute| {
+            r.date = Date::ymd(year, month, day);
+            r.time = Time { hour, minute: minute - minute % 5 };
+        };
+    HourRecord => |r| r.date.timestamp_at(r.time.hour, 0),
+        |r, year, month, day, hour, minute| {
+            r.date = Date::ymd(year, month, day);
+            r.time = Time { hour, minute: 0 };
+        };
+    MonthRecord => |r| timestamp::from_local_date(r.date.year, r.date.month, 1, 0, 0),
+        |r, year, month, day, hour, minute| r.date = MonthDate { month, year };
+    TopRecord => |r| r.date.timestamp_at(0, 0),
+        |r, year, month, day, hour, minute| r.date = Date::ymd(year, month, day);
+    YearRecord => |r| timestamp::from_local_date(r.date.year, 1, 1, 0, 0),
+        |r, year, month, day, hour, minute| r.date = YearDate { year };
+);
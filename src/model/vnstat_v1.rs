@@ -0,0 +1,244 @@
+//! vnStat 1.x `--json` schema (`jsonversion: "1"`).
+//!
+//! vnStat 1.x names interfaces by `id` (with an optional `nick`), reports
+//! traffic in KiB, uses `days` / `months` / `hours` / `tops` arrays and emits
+//! no timestamps. These types only exist to be converted into the version-2
+//! model in [`super::vnstat`], so the API surface is identical regardless of
+//! the installed vnStat version.
+
+use super::de;
+use super::vnstat::{
+    Created, Date, DayRecord, HourRecord, Interface, MonthDate, MonthRecord, Time, TopRecord,
+    Total, Traffic, Updated, VnstatData,
+};
+use serde::Deserialize;
+
+/// Bytes per KiB, the unit vnStat 1.x reports traffic in.
+const KIB: u64 = 1024;
+
+/// Top-level vnStat 1.x document.
+#[derive(Debug, Deserialize)]
+pub struct VnstatDataV1 {
+    interfaces: Vec<InterfaceV1>,
+    #[serde(deserialize_with = "de::string_or_number")]
+    jsonversion: String,
+    #[serde(deserialize_with = "de::string_or_number")]
+    vnstatversion: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct InterfaceV1 {
+    id: String,
+    #[serde(default)]
+    nick: String,
+    #[serde(default)]
+    created: CreatedV1,
+    updated: UpdatedV1,
+    traffic: TrafficV1,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct CreatedV1 {
+    #[serde(default)]
+    date: Date,
+}
+
+#[derive(Debug, Deserialize)]
+struct UpdatedV1 {
+    date: Date,
+    #[serde(default)]
+    time: Option<TimeV1>,
+}
+
+/// vnStat 1.x spells the minute field `minutes`.
+#[derive(Debug, Default, Deserialize)]
+struct TimeV1 {
+    hour: u8,
+    #[serde(alias = "minute")]
+    minutes: u8,
+}
+
+#[derive(Debug, Deserialize)]
+struct TrafficV1 {
+    #[serde(default)]
+    total: TotalV1,
+    #[serde(default)]
+    days: Vec<RecordV1<Date>>,
+    #[serde(default)]
+    months: Vec<RecordV1<MonthDate>>,
+    #[serde(default)]
+    hours: Vec<RecordV1<Date>>,
+    #[serde(default)]
+    tops: Vec<RecordV1<Date>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TotalV1 {
+    rx: u64,
+    tx: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordV1<D> {
+    #[serde(default)]
+    id: u32,
+    date: D,
+    rx: u64,
+    tx: u64,
+}
+
+impl From<TimeV1> for Time {
+    fn from(t: TimeV1) -> Self {
+        Time {
+            hour: t.hour,
+            minute: t.minutes,
+        }
+    }
+}
+
+impl From<VnstatDataV1> for VnstatData {
+    /// Converts a vnStat 1.x document into the version-2 model.
+    ///
+    /// Traffic is scaled from KiB to bytes. Timestamps are left at `0`; they
+    /// are synthesised from the date fields by the normalisation pass that
+    /// runs after every fetch.
+    fn from(v1: VnstatDataV1) -> Self {
+        VnstatData {
+            interfaces: v1.interfaces.into_iter().map(Interface::from).collect(),
+            jsonversion: v1.jsonversion,
+            vnstatversion: v1.vnstatversion,
+            future_records: 0,
+        }
+    }
+}
+
+impl From<InterfaceV1> for Interface {
+    fn from(v1: InterfaceV1) -> Self {
+        let t = v1.traffic;
+
+        Interface {
+            alias: v1.nick,
+            created: Created {
+                date: v1.created.date,
+                timestamp: 0,
+            },
+            name: v1.id,
+            traffic: Traffic {
+                day: t
+                    .days
+                    .into_iter()
+                    .map(|r| DayRecord {
+                        date: r.date,
+                        id: r.id,
+                        rx: r.rx * KIB,
+                        timestamp: 0,
+                        tx: r.tx * KIB,
+                    })
+                    .collect(),
+                fiveminute: Vec::new(),
+                // The id of a 1.x hour record is the hour of the day.
+                hour: t
+                    .hours
+                    .into_iter()
+                    .map(|r| HourRecord {
+                        date: r.date,
+                        id: r.id,
+                        rx: r.rx * KIB,
+                        time: Time {
+                            hour: r.id as u8,
+                            minute: 0,
+                        },
+                        timestamp: 0,
+                        tx: r.tx * KIB,
+                    })
+                    .collect(),
+                month: t
+                    .months
+                    .into_iter()
+                    .map(|r| MonthRecord {
+                        date: r.date,
+                        id: r.id,
+                        rx: r.rx * KIB,
+                        timestamp: 0,
+                        tx: r.tx * KIB,
+                    })
+                    .collect(),
+                top: t
+                    .tops
+                    .into_iter()
+                    .map(|r| TopRecord {
+                        date: r.date,
+                        id: r.id,
+                        rx: r.rx * KIB,
+                        timestamp: 0,
+                        tx: r.tx * KIB,
+                    })
+                    .collect(),
+                total: Total {
+                    rx: t.total.rx * KIB,
+                    tx: t.total.tx * KIB,
+                },
+                year: Vec::new(),
+            },
+            updated: Updated {
+                date: v1.updated.date,
+                time: v1.updated.time.map(Time::from),
+                timestamp: 0,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses the vnStat 1.18 dump in `tests/fixtures/vnstat1.json`.
+    fn fixture() -> VnstatData {
+        let json = include_str!("../../tests/fixtures/vnstat1.json");
+        let v1: VnstatDataV1 = serde_json::from_str(json).expect("valid 1.x document");
+        v1.into()
+    }
+
+    #[test]
+    fn maps_id_to_name_and_nick_to_alias() {
+        let data = fixture();
+
+        assert_eq!(data.jsonversion, "1");
+        assert_eq!(data.vnstatversion, "1.18");
+        let names: Vec<_> = data.interfaces.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names, ["eth0", "wlan0"]);
+        assert_eq!(data.interfaces[0].alias, "uplink");
+        assert_eq!(data.interfaces[1].alias, "");
+    }
+
+    #[test]
+    fn converts_kib_to_bytes() {
+        let traffic = &fixture().interfaces[0].traffic;
+
+        assert_eq!((traffic.total.rx, traffic.total.tx), (5 << 30, 1 << 30));
+        assert_eq!((traffic.day[0].rx, traffic.day[0].tx), (2 << 20, 512 << 10));
+        assert_eq!(
+            (traffic.month[0].rx, traffic.month[0].tx),
+            (6 << 20, 1536 << 10)
+        );
+        assert_eq!(
+            (traffic.top[0].rx, traffic.top[0].tx),
+            (100 << 20, 20 << 20)
+        );
+        assert_eq!(
+            (traffic.hour[1].rx, traffic.hour[1].tx),
+            (200 << 10, 20 << 10)
+        );
+    }
+
+    #[test]
+    fn takes_the_hour_from_the_record_id() {
+        let interface = &fixture().interfaces[0];
+
+        let hours: Vec<_> = interface.traffic.hour.iter().map(|r| r.time.hour).collect();
+        assert_eq!(hours, [20, 21]);
+        let updated = interface.updated.time.as_ref().expect("update time");
+        assert_eq!((updated.hour, updated.minute), (21, 15));
+    }
+}
@@ -0,0 +1,300 @@
+use crate::config::features::FeaturesConfig;
+use crate::error_code::ErrorCode;
+use crate::model::admin::{
+    LoadInfo, LogLevelBody, StatsInfo, TaskCancelled, TaskInfo, VnstatStatusInfo,
+};
+use crate::model::jsend::JsendResponse;
+use axum::extract::{FromRequestParts, State};
+use axum::http::header;
+use axum::http::request::Parts;
+use axum::routing::{delete, get};
+use axum::{Json, Router};
+use std::time::UNIX_EPOCH;
+use tracing::{Level, warn};
+
+use super::AppState;
+use super::extract::{JsendJson, JsendPath, Rejection};
+
+/// Builds and returns the Axum [`Router`] for all `/admin` endpoints.
+///
+/// Every handler requires [`AdminAuth`]; while `[admin]` is disabled the
+/// endpoints answer `404` with [`ErrorCode::EndpointDisabled`]. `/stats`
+/// is only registered with the `metrics` feature.
+///
+/// # Routes
+///
+/// | Method | Path             | Handler               |
+/// |--------|------------------|-----------------------|
+/// | GET    | `/log-level`     | [`get_log_level`]     |
+/// | PUT    | `/log-level`     | [`put_log_level`]     |
+/// | GET    | `/tasks`         | [`get_tasks`]         |
+/// | DELETE | `/tasks/{key}`   | [`delete_task`]       |
+/// | GET    | `/load`          | [`get_load`]          |
+/// | GET    | `/stats`         | [`get_stats`]         |
+/// | GET    | `/vnstat-status` | [`get_vnstat_status`] |
+pub fn router(features: &FeaturesConfig) -> Router<AppState> {
+    let router = Router::new()
+        .route("/log-level", get(get_log_level).put(put_log_level))
+        .route("/tasks", get(get_tasks))
+        .route("/tasks/{key}", delete(delete_task))
+        .route("/load", get(get_load))
+        .route("/vnstat-status", get(get_vnstat_status));
+
+    if features.metrics {
+        router.route("/stats", get(get_stats))
+    } else {
+        router
+    }
+}
+
+/// Proof that a request carries the configured admin bearer token.
+///
+/// Rejects the request with `404` ([`ErrorCode::EndpointDisabled`]) while
+/// the admin endpoints are disabled, and with `401`
+/// ([`ErrorCode::Unauthorized`]) when the `Authorization: Bearer` header is
+/// missing or holds the wrong token.
+pub struct AdminAuth;
+
+impl FromRequestParts<AppState> for AdminAuth {
+    type Rejection = Rejection;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        if !state.admin.enabled {
+            return Err(failure(ErrorCode::EndpointDisabled));
+        }
+
+        let token = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "));
+
+        match token {
+            Some(token) if state.admin.authorize(token.trim()) => Ok(AdminAuth),
+            _ => Err(failure(ErrorCode::Unauthorized)),
+        }
+    }
+}
+
+/// Handler for `GET /admin/log-level`.
+///
+/// # Returns
+///
+/// - `200 OK` with the current default log level (`{ "level": "info" }`)
+///   wrapped in a JSend envelope.
+/// - `404 NOT_FOUND` with code `10002` when the router is embedded without
+///   a [`LogLevelHandle`](crate::logging::LogLevelHandle).
+async fn get_log_level(
+    _: AdminAuth,
+    State(state): State<AppState>,
+) -> Result<Json<JsendResponse<LogLevelBody>>, Rejection> {
+    let log_level = state
+        .log_level
+        .as_ref()
+        .ok_or_else(|| failure(ErrorCode::EndpointDisabled))?;
+
+    Ok(Json(JsendResponse::success_with_data(LogLevelBody {
+        level: level_name(log_level.level()).to_string(),
+    })))
+}
+
+/// Handler for `PUT /admin/log-level`.
+///
+/// Accepts `{ "level": "debug" }` and replaces the default log level at
+/// runtime. Per-module `[logging.filters]` keep applying on top of it. The
+/// change is logged and takes effect immediately.
+///
+/// # Returns
+///
+/// - `200 OK` with the new level wrapped in a JSend envelope.
+/// - `400 BAD_REQUEST` with code `10015` when the body is malformed or the
+///   level is not one of `trace`, `debug`, `info`, `warn` or `error`.
+/// - `404 NOT_FOUND` with code `10002` when the router is embedded without
+///   a [`LogLevelHandle`](crate::logging::LogLevelHandle).
+/// - `500 INTERNAL_SERVER_ERROR` when the log filter cannot be replaced.
+async fn put_log_level(
+    _: AdminAuth,
+    State(state): State<AppState>,
+    JsendJson(body): JsendJson<LogLevelBody>,
+) -> Result<Json<JsendResponse<LogLevelBody>>, Rejection> {
+    let log_level = state
+        .log_level
+        .as_ref()
+        .ok_or_else(|| failure(ErrorCode::EndpointDisabled))?;
+
+    let Some(level) = parse_level(&body.level) else {
+        return Err((
+            ErrorCode::InvalidRequestBody.status_code(),
+            Json(JsendResponse::fail_with_message(
+                ErrorCode::InvalidRequestBody,
+                format!(
+                    "Invalid log level `{}`; expected one of trace, debug, info, warn, error",
+                    body.level
+                ),
+            )),
+        ));
+    };
+
+    let previous = log_level.level();
+    log_level.set_level(level).map_err(|e| {
+        warn!("Failed to change log level: {:#}", e);
+        failure(ErrorCode::UnknownError)
+    })?;
+
+    warn!(
+        "Log level changed from {} to {} via the admin API",
+        level_name(previous),
+        level_name(level)
+    );
+
+    Ok(Json(JsendResponse::success_with_data(LogLevelBody {
+        level: level_name(level).to_string(),
+    })))
+}
+
+/// Handler for `GET /admin/tasks`.
+///
+/// Lists the live-stream tasks known to the
+/// [`TaskManager`](crate::task_manager::TaskManager), sorted by key.
+///
+/// # Returns
+///
+/// - `200 OK` with an array of [`TaskInfo`] wrapped in a JSend envelope.
+async fn get_tasks(
+    _: AdminAuth,
+    State(state): State<AppState>,
+) -> Json<JsendResponse<Vec<TaskInfo>>> {
+    let tasks = state
+        .task_manager
+        .snapshot()
+        .into_iter()
+        .map(|(key, task)| TaskInfo {
+            key,
+            kind: task.kind,
+            ref_count: task.ref_count,
+            receivers: task.receivers,
+            running: task.running,
+            started_timestamp: task
+                .started_at
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs()),
+            messages: task.messages,
+        })
+        .collect();
+
+    Json(JsendResponse::success_with_data(tasks))
+}
+
+/// Handler for `DELETE /admin/tasks/{key}`.
+///
+/// Force-cancels a live-stream task: its vnStat process is killed and every
+/// subscriber's stream ends.
+///
+/// # Returns
+///
+/// - `200 OK` with a [`TaskCancelled`] wrapped in a JSend envelope;
+///   `cancelled` is `false` if no process was running.
+/// - `404 NOT_FOUND` with code `10016` when no task has that key.
+async fn delete_task(
+    _: AdminAuth,
+    State(state): State<AppState>,
+    JsendPath(key): JsendPath<String>,
+) -> Result<Json<JsendResponse<TaskCancelled>>, Rejection> {
+    let cancelled = state
+        .task_manager
+        .cancel(&key)
+        .ok_or_else(|| failure(ErrorCode::NoSuchTask))?;
+
+    Ok(Json(JsendResponse::success_with_data(TaskCancelled {
+        key,
+        cancelled,
+    })))
+}
+
+/// Handler for `GET /admin/load`.
+///
+/// Reports the requests and live streams currently handled, next to their
+/// limits, for tuning `server.max_in_flight` and `server.max_sse_clients`.
+///
+/// # Returns
+///
+/// - `200 OK` with a [`LoadInfo`] wrapped in a JSend envelope.
+async fn get_load(_: AdminAuth, State(state): State<AppState>) -> Json<JsendResponse<LoadInfo>> {
+    Json(JsendResponse::success_with_data(LoadInfo {
+        in_flight: state.in_flight.in_flight(),
+        max_in_flight: state.in_flight.max(),
+        load_shed: state.in_flight.load_shed(),
+        sse_clients: state.task_manager.subscriber_count(),
+        max_sse_clients: state.task_manager.max_subscribers(),
+    }))
+}
+
+/// Handler for `GET /admin/stats`.
+///
+/// Reports the requests served per route, with their status classes and a
+/// latency histogram, the hits, misses and failed refreshes of the vnStat
+/// data cache, and the connected live-stream clients. The counters start
+/// at zero when the server starts.
+///
+/// # Returns
+///
+/// - `200 OK` with a [`StatsInfo`] wrapped in a JSend envelope.
+async fn get_stats(_: AdminAuth, State(state): State<AppState>) -> Json<JsendResponse<StatsInfo>> {
+    Json(JsendResponse::success_with_data(StatsInfo {
+        cache: state.vnstat.cache_stats(),
+        breaker: state.vnstat.breaker_status(),
+        sse_clients: state.task_manager.subscriber_count(),
+        latency_buckets_ms: state.metrics.buckets().to_vec(),
+        routes: state.metrics.snapshot(),
+    }))
+}
+
+/// Handler for `GET /admin/vnstat-status`.
+///
+/// Reports the circuit breaker and data cache state and the last vnStat
+/// runs of data fetches and health checks with their outcome, so that
+/// failures can be investigated after the logs rotated.
+///
+/// # Returns
+///
+/// - `200 OK` with a [`VnstatStatusInfo`] wrapped in a JSend envelope.
+async fn get_vnstat_status(
+    _: AdminAuth,
+    State(state): State<AppState>,
+) -> Json<JsendResponse<VnstatStatusInfo>> {
+    Json(JsendResponse::success_with_data(
+        state.vnstat.status().await,
+    ))
+}
+
+/// Parses one of the five level names (case-insensitive).
+fn parse_level(name: &str) -> Option<Level> {
+    match name.to_ascii_lowercase().as_str() {
+        "trace" => Some(Level::TRACE),
+        "debug" => Some(Level::DEBUG),
+        "info" => Some(Level::INFO),
+        "warn" => Some(Level::WARN),
+        "error" => Some(Level::ERROR),
+        _ => None,
+    }
+}
+
+/// Returns the lowercase name of `level`, as accepted by [`parse_level`].
+fn level_name(level: Level) -> &'static str {
+    match level {
+        Level::TRACE => "trace",
+        Level::DEBUG => "debug",
+        Level::INFO => "info",
+        Level::WARN => "warn",
+        Level::ERROR => "error",
+    }
+}
+
+/// Builds a bare JSend failure response for `code` with its canonical
+/// status.
+fn failure(code: ErrorCode) -> Rejection {
+    (code.status_code(), Json(JsendResponse::fail(code)))
+}
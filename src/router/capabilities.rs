@@ -0,0 +1,101 @@
+use crate::model::admin::BreakerState;
+use crate::model::capabilities::{Capabilities, Capability, CapabilityReason};
+use crate::model::jsend::JsendResponse;
+use axum::Json;
+use axum::extract::State;
+
+use super::AppState;
+
+/// Handler for `GET /capabilities`.
+///
+/// Reports, per endpoint group, whether the group is currently available,
+/// degraded or disabled, assembled from the live application state:
+///
+/// | Group    | Endpoints                                        |
+/// |----------|--------------------------------------------------|
+/// | `data`   | `/vnstat/`, `/vnstat/version`, `/vnstat/interfaces`, `/vnstat/{if_name}` |
+/// | `live`   | `/vnstat/live`, `/vnstat/{if_name}/live`, `/vnstat/{if_name}/live/ws`, `/vnstat/{if_name}/live/poll` |
+/// | `raw`    | `/vnstat/raw`, `/vnstat/{if_name}/raw`           |
+/// | `health` | `/vnstat/health`                                 |
+/// | `grafana` | `/grafana/`, `/grafana/search`, `/grafana/query` |
+/// | `management` | `POST /vnstat/interfaces`, `DELETE /vnstat/interfaces/{if_name}`, `PUT /vnstat/interfaces/{if_name}/alias` |
+/// | `export` | `/export/influx`                                |
+/// | `admin`  | `/admin/...` but `/admin/stats`                  |
+/// | `metrics` | `/admin/stats`                                  |
+///
+/// The vnStat-backed groups report `circuit_breaker_open` while the
+/// circuit breaker keeps vnStat from being run.
+///
+/// # Returns
+///
+/// `200 OK` with the capability matrix wrapped in a JSend envelope.
+pub async fn get_capabilities(State(state): State<AppState>) -> Json<JsendResponse<Capabilities>> {
+    let mut capabilities = Capabilities::new();
+
+    let fetched = state.vnstat.fetch_vnstat_data().await;
+    let unavailable = match state.vnstat.breaker_status().state {
+        BreakerState::Closed => CapabilityReason::VnstatUnavailable,
+        BreakerState::Open | BreakerState::HalfOpen => CapabilityReason::CircuitBreakerOpen,
+    };
+    let data = match fetched {
+        Ok(_) if state.vnstat.is_degraded() => {
+            Capability::degraded(CapabilityReason::VnstatVersionUnsupported)
+        }
+        Ok(fetched) if fetched.stale => Capability::degraded(unavailable),
+        Ok(fetched) if fetched.value.interfaces.is_empty() => {
+            Capability::degraded(CapabilityReason::NoInterfaces)
+        }
+        Ok(_) => Capability::available(),
+        Err(_) => Capability::disabled(unavailable),
+    };
+
+    let raw = if state.vnstat.raw_enabled() && state.features.raw_endpoints {
+        data.clone()
+    } else {
+        Capability::disabled(CapabilityReason::DisabledByConfig)
+    };
+
+    let grafana = if state.grafana_enabled {
+        data.clone()
+    } else {
+        Capability::disabled(CapabilityReason::DisabledByConfig)
+    };
+
+    let management = if state.vnstat.management_enabled() && state.features.management {
+        data.clone()
+    } else {
+        Capability::disabled(CapabilityReason::DisabledByConfig)
+    };
+
+    capabilities.insert("data", data.clone());
+    capabilities.insert("raw", raw);
+    capabilities.insert("grafana", grafana);
+    capabilities.insert("management", management);
+    capabilities.insert("export", data);
+
+    let live = if !state.features.live_streams {
+        Capability::disabled(CapabilityReason::DisabledByConfig)
+    } else if state.vnstat.executable_exists() {
+        Capability::available()
+    } else {
+        Capability::disabled(CapabilityReason::ExecutableMissing)
+    };
+    capabilities.insert("live", live);
+
+    let admin = if state.admin.enabled && state.features.admin {
+        Capability::available()
+    } else {
+        Capability::disabled(CapabilityReason::DisabledByConfig)
+    };
+    let metrics = if state.features.metrics {
+        admin.clone()
+    } else {
+        Capability::disabled(CapabilityReason::DisabledByConfig)
+    };
+    capabilities.insert("admin", admin);
+    capabilities.insert("metrics", metrics);
+
+    capabilities.insert("health", Capability::available());
+
+    Json(JsendResponse::success_with_data(capabilities))
+}
@@ -0,0 +1,107 @@
+use crate::error_code::ErrorCode;
+use crate::service::decimate::SeriesGranularity;
+use crate::service::export::{influx_export, write_line};
+use async_stream::stream;
+use axum::Router;
+use axum::body::{Body, Bytes};
+use axum::extract::State;
+use axum::http::header;
+use axum::response::{AppendHeaders, IntoResponse, Response};
+use axum::routing::get;
+use serde::Deserialize;
+use std::convert::Infallible;
+
+use super::AppState;
+use super::extract::{JsendQuery, Rejection};
+use super::vnstat::{freshness_headers, vnstat_failure};
+
+/// Header carrying the `since` that resumes an export after its last point.
+const NEXT_SINCE_HEADER: &str = "x-next-since";
+
+/// Header telling whether an export left out newer records.
+const TRUNCATED_HEADER: &str = "x-truncated";
+
+/// Number of lines rendered into each chunk of a streamed export.
+const LINES_PER_CHUNK: usize = 512;
+
+/// Builds and returns the Axum [`Router`] for the `/export` endpoints,
+/// which serve the traffic data in the formats of other tools.
+///
+/// # Routes
+///
+/// | Method | Path      | Handler        |
+/// |--------|-----------|----------------|
+/// | GET    | `/influx` | [`get_influx`] |
+pub fn router() -> Router<AppState> {
+    Router::new().route("/influx", get(get_influx))
+}
+
+/// Query parameters accepted by `GET /export/influx`.
+#[derive(Debug, Default, Deserialize)]
+struct InfluxQuery {
+    /// The record series to export; five-minute records if unset.
+    granularity: Option<SeriesGranularity>,
+    /// Only export records starting after this Unix timestamp (seconds).
+    since: Option<i64>,
+}
+
+/// Handler for `GET /export/influx`.
+///
+/// Exports the records of `?granularity=` (default `fiveminute`) of every
+/// visible interface in InfluxDB line protocol, one point per record and
+/// interface, oldest first; see [`influx_export`]. Only records starting
+/// after `?since=` are exported, and at most `server.max_record_limit`
+/// points per request. The body is streamed in chunks as it is rendered.
+///
+/// # Returns
+///
+/// - `200 OK` with a `text/plain` body, an `X-Next-Since` header holding
+///   the timestamp to pass as `since` to resume after the last point (unless
+///   there is none), and `X-Truncated: true` if newer records remain.
+///   `X-Generated-At` and, for stale data, `X-Data-Age-Seconds` are set as
+///   for the JSON endpoints.
+/// - `400 BAD_REQUEST` with code `10008` when `granularity` is unknown or
+///   `since` is not an integer.
+/// - `502 BAD_GATEWAY` / `504 GATEWAY_TIMEOUT` when vnStat fails.
+async fn get_influx(
+    State(state): State<AppState>,
+    JsendQuery(query): JsendQuery<InfluxQuery>,
+) -> Result<Response, Rejection> {
+    let data = state
+        .vnstat
+        .fetch_vnstat_data()
+        .await
+        .map_err(|e| vnstat_failure(&state, &e, ErrorCode::GetDataFailed))?;
+
+    let granularity = query.granularity.unwrap_or(SeriesGranularity::FiveMinute);
+    let export = influx_export(
+        &data.value,
+        granularity,
+        query.since,
+        state.max_record_limit,
+    );
+
+    let mut headers = vec![(TRUNCATED_HEADER, export.truncated.to_string())];
+    if let Some(next_since) = export.next_since {
+        headers.push((NEXT_SINCE_HEADER, next_since.to_string()));
+    }
+
+    let points = export.points;
+    let body = Body::from_stream(stream! {
+        for chunk in points.chunks(LINES_PER_CHUNK) {
+            let mut lines = String::new();
+            for point in chunk {
+                write_line(&mut lines, point);
+            }
+            yield Ok::<_, Infallible>(Bytes::from(lines));
+        }
+    });
+
+    Ok((
+        freshness_headers(&data),
+        AppendHeaders(headers),
+        [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+        body,
+    )
+        .into_response())
+}
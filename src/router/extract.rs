@@ -0,0 +1,140 @@
+use crate::error_code::ErrorCode;
+use crate::model::jsend::JsendResponse;
+use axum::Json;
+use axum::extract::rejection::{JsonRejection, PathRejection, QueryRejection};
+use axum::extract::{FromRequest, FromRequestParts, Path, Query, Request};
+use axum::http::StatusCode;
+use axum::http::request::Parts;
+use serde::de::DeserializeOwned;
+
+/// The JSend failure response produced when an extractor rejects a request.
+pub type Rejection = (StatusCode, Json<JsendResponse<String>>);
+
+/// Drop-in replacement for axum's [`Path`] extractor that reports
+/// extraction failures as a JSend body with
+/// [`ErrorCode::InvalidPathParameter`] instead of plain text.
+///
+/// The status code and human-readable reason are taken from the underlying
+/// [`PathRejection`].
+pub struct JsendPath<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for JsendPath<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match Path::<T>::from_request_parts(parts, state).await {
+            Ok(Path(value)) => Ok(JsendPath(value)),
+            Err(rejection) => Err(path_rejection(rejection)),
+        }
+    }
+}
+
+/// Drop-in replacement for axum's [`Query`] extractor that reports
+/// extraction failures as a JSend body with
+/// [`ErrorCode::InvalidQueryParameter`] instead of plain text.
+///
+/// The status code and human-readable reason are taken from the underlying
+/// [`QueryRejection`].
+pub struct JsendQuery<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for JsendQuery<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match Query::<T>::from_request_parts(parts, state).await {
+            Ok(Query(value)) => Ok(JsendQuery(value)),
+            Err(rejection) => Err(query_rejection(rejection)),
+        }
+    }
+}
+
+/// Drop-in replacement for axum's [`Json`] extractor that reports
+/// extraction failures as a JSend body with
+/// [`ErrorCode::InvalidRequestBody`] instead of plain text.
+///
+/// The status code and human-readable reason are taken from the underlying
+/// [`JsonRejection`].
+pub struct JsendJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for JsendJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match Json::<T>::from_request(request, state).await {
+            Ok(Json(value)) => Ok(JsendJson(value)),
+            Err(rejection) => Err(json_rejection(rejection)),
+        }
+    }
+}
+
+/// Extracts the `{if_name}` path parameter, rejecting empty names (e.g.
+/// `/interfaces//traffic`) with [`ErrorCode::InvalidPathParameter`].
+pub struct InterfaceName(pub String);
+
+impl<S> FromRequestParts<S> for InterfaceName
+where
+    S: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let JsendPath(if_name) = JsendPath::<String>::from_request_parts(parts, state).await?;
+
+        if if_name.is_empty() {
+            return Err((
+                ErrorCode::InvalidPathParameter.status_code(),
+                Json(JsendResponse::fail_with_message(
+                    ErrorCode::InvalidPathParameter,
+                    "Interface name must not be empty",
+                )),
+            ));
+        }
+
+        Ok(InterfaceName(if_name))
+    }
+}
+
+/// Converts a [`PathRejection`] into a JSend failure response.
+fn path_rejection(rejection: PathRejection) -> Rejection {
+    (
+        rejection.status(),
+        Json(JsendResponse::fail_with_message(
+            ErrorCode::InvalidPathParameter,
+            rejection.body_text(),
+        )),
+    )
+}
+
+/// Converts a [`QueryRejection`] into a JSend failure response.
+fn query_rejection(rejection: QueryRejection) -> Rejection {
+    (
+        rejection.status(),
+        Json(JsendResponse::fail_with_message(
+            ErrorCode::InvalidQueryParameter,
+            rejection.body_text(),
+        )),
+    )
+}
+
+/// Converts a [`JsonRejection`] into a JSend failure response.
+fn json_rejection(rejection: JsonRejection) -> Rejection {
+    (
+        rejection.status(),
+        Json(JsendResponse::fail_with_message(
+            ErrorCode::InvalidRequestBody,
+            rejection.body_text(),
+        )),
+    )
+}
@@ -0,0 +1,197 @@
+use crate::error_code::ErrorCode;
+use crate::model::grafana::{GrafanaQuery, GrafanaSearch, GrafanaSeries};
+use crate::model::jsend::JsendResponse;
+use crate::service::aggregate::TimeRange;
+use crate::service::grafana::{
+    DEFAULT_MAX_DATA_POINTS, grafana_granularity, grafana_series, grafana_targets, parse_target,
+};
+use axum::extract::{FromRequestParts, State};
+use axum::http::request::Parts;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+
+use super::AppState;
+use super::extract::{JsendJson, Rejection};
+use super::vnstat::vnstat_failure;
+
+/// Builds and returns the Axum [`Router`] for the `/grafana` endpoints,
+/// which implement the protocol of Grafana's JSON datasources
+/// (SimpleJSON, JSON API).
+///
+/// The routes carry their full paths, to be merged rather than nested:
+/// Grafana probes the datasource URL with a trailing `/`, which a nested
+/// `/` would not match.
+///
+/// Every handler requires [`GrafanaEnabled`]. Apart from the health probe
+/// and failures, responses are the bare JSON Grafana expects rather than
+/// JSend envelopes.
+///
+/// # Routes
+///
+/// | Method | Path               | Handler          |
+/// |--------|--------------------|------------------|
+/// | GET    | `/grafana`         | [`get_health`]   |
+/// | GET    | `/grafana/`        | [`get_health`]   |
+/// | POST   | `/grafana/search`  | [`post_search`]  |
+/// | POST   | `/grafana/query`   | [`post_query`]   |
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/grafana", get(get_health))
+        .route("/grafana/", get(get_health))
+        .route("/grafana/search", post(post_search))
+        .route("/grafana/query", post(post_query))
+}
+
+/// Proof that the Grafana endpoints are enabled.
+///
+/// Rejects the request with `404` ([`ErrorCode::EndpointDisabled`]) while
+/// `[integrations.grafana]` is disabled.
+pub struct GrafanaEnabled;
+
+impl FromRequestParts<AppState> for GrafanaEnabled {
+    type Rejection = Rejection;
+
+    async fn from_request_parts(
+        _parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        if state.grafana_enabled {
+            Ok(GrafanaEnabled)
+        } else {
+            Err(failure(ErrorCode::EndpointDisabled))
+        }
+    }
+}
+
+/// Handler for `GET /grafana/`, the probe of Grafana's "Save & test".
+///
+/// # Returns
+///
+/// `200 OK` with an empty JSend success.
+async fn get_health(_: GrafanaEnabled) -> Json<JsendResponse<()>> {
+    Json(JsendResponse::success())
+}
+
+/// Handler for `POST /grafana/search`.
+///
+/// Accepts `{ "target": "eth" }` and lists the series whose name contains
+/// the text; see [`grafana_targets`].
+///
+/// # Returns
+///
+/// - `200 OK` with a JSON array of series names.
+/// - `400 BAD_REQUEST` with code `10015` when the body is malformed.
+/// - `502 BAD_GATEWAY` / `504 GATEWAY_TIMEOUT` when vnStat fails.
+async fn post_search(
+    _: GrafanaEnabled,
+    State(state): State<AppState>,
+    JsendJson(body): JsendJson<GrafanaSearch>,
+) -> Result<Json<Vec<String>>, Rejection> {
+    let data = state
+        .vnstat
+        .fetch_vnstat_data()
+        .await
+        .map_err(|e| vnstat_failure(&state, &e, ErrorCode::GetDataFailed))?;
+
+    Ok(Json(grafana_targets(&data.value, body.target.trim())))
+}
+
+/// Handler for `POST /grafana/query`.
+///
+/// Returns the requested series over the panel's time range, from the
+/// finest records that fit into its `maxDataPoints` (default 1000); see
+/// [`grafana_granularity`]. Each data point is a record's traffic in bytes
+/// with the start of its period. Hidden targets and targets without a name
+/// are skipped.
+///
+/// # Returns
+///
+/// - `200 OK` with a JSON array of `{ "target", "datapoints" }` objects.
+/// - `400 BAD_REQUEST` with code `10015` when the body is malformed, the
+///   range is not made of RFC 3339 timestamps, `from` is after `to`, or a
+///   target is not `<interface>.rx`, `.tx` or `.total`.
+/// - `404 NOT_FOUND` with code `10001` when a target's interface does not
+///   exist or is hidden by the interface filter.
+/// - `502 BAD_GATEWAY` / `504 GATEWAY_TIMEOUT` when vnStat fails.
+async fn post_query(
+    _: GrafanaEnabled,
+    State(state): State<AppState>,
+    JsendJson(body): JsendJson<GrafanaQuery>,
+) -> Result<Json<Vec<GrafanaSeries>>, Rejection> {
+    let from =
+        parse_time("range.from", &body.range.from).map_err(|reason| invalid_body(&reason))?;
+    let to = parse_time("range.to", &body.range.to).map_err(|reason| invalid_body(&reason))?;
+    if from > to {
+        return Err(invalid_body("`range.from` must not be after `range.to`"));
+    }
+
+    let max_points = body.max_data_points.unwrap_or(DEFAULT_MAX_DATA_POINTS);
+    let granularity = grafana_granularity(from, to, max_points);
+    let range = TimeRange {
+        from: Some(from),
+        to: Some(to),
+    };
+
+    let targets = body
+        .targets
+        .iter()
+        .filter(|target| !target.hide)
+        .filter_map(|target| target.target.as_deref())
+        .filter(|target| !target.is_empty());
+
+    let mut series = Vec::new();
+    for target in targets {
+        let Some((if_name, metric)) = parse_target(target) else {
+            return Err(invalid_body(&format!(
+                "Unknown series `{}`; expected `<interface>.rx`, `<interface>.tx` or `<interface>.total`",
+                target
+            )));
+        };
+        let interface = state
+            .vnstat
+            .fetch_interface_stats(if_name)
+            .await
+            .map_err(|e| vnstat_failure(&state, &e, ErrorCode::NoSuchInterface))?;
+        series.push(grafana_series(
+            target,
+            &interface.value,
+            metric,
+            granularity,
+            range,
+        ));
+    }
+
+    Ok(Json(series))
+}
+
+/// Parses the RFC 3339 timestamp `value` of the body field `field` into
+/// Unix seconds.
+///
+/// # Errors
+///
+/// Returns the reason to report with code `10015` when `value` is not an
+/// RFC 3339 timestamp.
+fn parse_time(field: &str, value: &str) -> Result<i64, String> {
+    OffsetDateTime::parse(value, &Rfc3339)
+        .map(OffsetDateTime::unix_timestamp)
+        .map_err(|_| format!("`{}` must be an RFC 3339 timestamp, got `{}`", field, value))
+}
+
+/// Builds the `400` failure response for a malformed request body.
+fn invalid_body(reason: &str) -> Rejection {
+    (
+        ErrorCode::InvalidRequestBody.status_code(),
+        Json(JsendResponse::fail_with_message(
+            ErrorCode::InvalidRequestBody,
+            reason,
+        )),
+    )
+}
+
+/// Builds a bare JSend failure response for `code` with its canonical
+/// status.
+fn failure(code: ErrorCode) -> Rejection {
+    (code.status_code(), Json(JsendResponse::fail(code)))
+}
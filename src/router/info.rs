@@ -0,0 +1,42 @@
+use crate::model::jsend::JsendResponse;
+use crate::model::response::{CacheInfo, InfoResponse};
+use crate::utils::build_info;
+use crate::utils::timestamp;
+use axum::Json;
+use axum::extract::State;
+use std::sync::atomic::Ordering;
+
+use super::AppState;
+
+/// Handler for `GET /info` (also served as `GET /vnstat/info`).
+///
+/// Reports which build is running and how: this API's version, git commit
+/// and build time, the vnStat version detected at startup, the caching
+/// settings, when the server started and when its configuration was last
+/// reloaded. Does not invoke vnStat.
+///
+/// # Returns
+///
+/// `200 OK` with an [`InfoResponse`] wrapped in a JSend envelope.
+pub(super) async fn get_info(State(state): State<AppState>) -> Json<JsendResponse<InfoResponse>> {
+    Json(JsendResponse::success_with_data(InfoResponse {
+        api_version: build_info::VERSION,
+        git_commit: build_info::GIT_COMMIT,
+        build_timestamp: build_info::build_timestamp(),
+        vnstat_version: state.vnstat.version(),
+        min_vnstat_version: state.vnstat.min_version(),
+        degraded: state.vnstat.is_degraded(),
+        started_at: state.started_at,
+        uptime_seconds: timestamp::get_in_secs()
+            .saturating_sub(state.started_at)
+            .max(0),
+        reloaded_at: Some(state.reloaded_at.load(Ordering::Relaxed)).filter(|&at| at > 0),
+        cache: CacheInfo {
+            ttl_seconds: state.vnstat.cache_ttl().as_secs(),
+            max_stale_seconds: state.vnstat.max_stale().as_secs(),
+            refresh_interval_seconds: state
+                .cache_refresh_interval
+                .map(|interval| interval.as_secs()),
+        },
+    }))
+}
@@ -1,8 +1,37 @@
+use crate::config::AppConfig;
+use crate::config::admin::AdminConfig;
+use crate::config::features::FeaturesConfig;
+use crate::config::quota::QuotaConfig;
+use crate::config::sse::SseConfig;
+use crate::error_code::ErrorCode;
+use crate::logging::LogLevelHandle;
+use crate::middleware::in_flight::InFlightLimit;
+use crate::middleware::metrics::RequestMetrics;
+use crate::middleware::request_timeout;
+use crate::model::jsend::JsendResponse;
 use crate::service::vnstat_service::VnstatService;
 use crate::task_manager::TaskManager;
-use axum::Router;
+use crate::utils::reloadable::Reloadable;
+use crate::utils::timestamp;
+use axum::http::HeaderMap;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use std::any::Any;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::Duration;
+use tower_http::catch_panic::CatchPanicLayer;
 
+/// Header carrying the path prefix stripped by a reverse proxy.
+const FORWARDED_PREFIX: &str = "x-forwarded-prefix";
+
+mod admin;
+mod capabilities;
+mod export;
+mod extract;
+mod grafana;
+mod info;
 mod vnstat;
 
 /// Application-wide shared state injected into every Axum handler via
@@ -19,12 +48,302 @@ pub struct AppState {
     /// Manager that controls the lifecycle of background tasks such as
     /// SSE keep-alive watchers.
     pub task_manager: Arc<TaskManager>,
+
+    /// Whether the server runs with `--debug`; enables error details in
+    /// responses.
+    pub debug: bool,
+
+    /// The `[admin]` configuration, checked by the admin endpoints.
+    pub admin: Arc<AdminConfig>,
+
+    /// Handle for changing the log level at runtime; the log-level
+    /// endpoints are disabled without one.
+    pub log_level: Option<LogLevelHandle>,
+
+    /// Keep-alive and heartbeat settings of SSE streams.
+    pub sse: Arc<SseConfig>,
+
+    /// The `[quota]` configuration, checked by the quota endpoint; replaced
+    /// by [`reload`](Self::reload).
+    pub quota: Reloadable<QuotaConfig>,
+
+    /// External path prefix stripped by a reverse proxy (`server.base_path`),
+    /// prepended to redirect targets.
+    pub base_path: Arc<str>,
+
+    /// Whether `X-Forwarded-*` headers are trusted
+    /// (`server.trust_proxy_headers`).
+    pub trust_proxy_headers: bool,
+
+    /// Limit on the data requests handled at once (`server.max_in_flight`).
+    pub in_flight: InFlightLimit,
+
+    /// Request counters and latency histograms of every route, reported by
+    /// `GET /admin/stats`.
+    pub metrics: RequestMetrics,
+
+    /// Time limit of every request but live streams
+    /// (`server.request_timeout_seconds`); `None` for no limit.
+    pub request_timeout: Option<Duration>,
+
+    /// Largest `?limit=` honoured on record lists
+    /// (`server.max_record_limit`).
+    pub max_record_limit: usize,
+
+    /// Whether the Grafana endpoints are served
+    /// (`integrations.grafana.enabled`).
+    pub grafana_enabled: bool,
+
+    /// The route groups registered by the router (`server.features`).
+    pub features: FeaturesConfig,
+
+    /// Interval of the background cache refresh
+    /// (`cache.refresh_interval_seconds`); `None` if the cache is filled on
+    /// demand.
+    pub cache_refresh_interval: Option<Duration>,
+
+    /// Unix epoch timestamp (seconds) at which the state was created, i.e.
+    /// when the server started.
+    pub started_at: i64,
+
+    /// Unix epoch timestamp (seconds) of the last successful
+    /// [`reload`](Self::reload); `0` if the configuration was never
+    /// reloaded.
+    pub reloaded_at: Arc<AtomicI64>,
+}
+
+impl AppState {
+    /// Creates the state for `config`, spawning the configured vnStat
+    /// executable.
+    ///
+    /// Builds a new [`VnstatService`] and a new [`TaskManager`] with the
+    /// configured live-stream limits, so routers built from different
+    /// states share no cache, no live processes and no request counters.
+    /// Debug mode is off and no log-level handle is set; see
+    /// [`with_debug`](Self::with_debug) and
+    /// [`with_log_level`](Self::with_log_level).
+    ///
+    /// The vnStat version is not checked; call
+    /// [`VnstatService::check_version`] on [`vnstat`](Self::vnstat) for that.
+    pub fn from_config(config: &AppConfig) -> Self {
+        let vnstat = Arc::new(VnstatService::new(config.vnstat.clone()));
+        Self::with_service(config, vnstat)
+    }
+
+    /// Like [`from_config`](Self::from_config), but serving the data of an
+    /// existing [`VnstatService`], e.g. one created with
+    /// [`VnstatService::with_executor`]. The service takes the cache
+    /// lifetime of `config`.
+    pub fn with_service(config: &AppConfig, vnstat: Arc<VnstatService>) -> Self {
+        vnstat.set_cache_ttl(config.cache.ttl());
+        let task_manager = Arc::new(
+            TaskManager::with_startup_timeout(vnstat.timeout())
+                .with_limits(
+                    config.server.max_sse_clients,
+                    config.vnstat.max_live_processes,
+                )
+                .with_replay_capacity(config.server.sse.replay_buffer_size)
+                .with_buffer_size(config.vnstat.live_buffer_size)
+                .with_restarts(
+                    config.vnstat.live_restart_attempts,
+                    Duration::from_secs(config.vnstat.live_restart_backoff_seconds),
+                )
+                .with_stop_grace(Duration::from_secs(config.vnstat.live_stop_grace_seconds))
+                .with_orphan_timeout(
+                    (config.vnstat.orphan_timeout_seconds > 0)
+                        .then(|| Duration::from_secs(config.vnstat.orphan_timeout_seconds)),
+                ),
+        );
+
+        Self {
+            vnstat,
+            task_manager,
+            debug: false,
+            admin: Arc::new(config.admin.clone()),
+            log_level: None,
+            sse: Arc::new(config.server.sse.clone()),
+            quota: Reloadable::new(config.quota.clone()),
+            base_path: Arc::from(config.server.base_path.as_str()),
+            trust_proxy_headers: config.server.trust_proxy_headers,
+            in_flight: InFlightLimit::new(config.server.max_in_flight, config.server.load_shed),
+            metrics: RequestMetrics::new(&config.admin.latency_buckets_ms),
+            request_timeout: Some(config.server.request_timeout_seconds)
+                .filter(|&seconds| seconds > 0)
+                .map(Duration::from_secs),
+            max_record_limit: config.server.max_record_limit,
+            grafana_enabled: config.integrations.grafana.enabled,
+            features: config.server.features,
+            cache_refresh_interval: config.cache.refresh_interval(),
+            started_at: timestamp::get_in_secs(),
+            reloaded_at: Arc::new(AtomicI64::new(0)),
+        }
+    }
+
+    /// Applies the settings of `config` that can change without a restart:
+    ///
+    /// - the interface filter, the aliases and `max_stale_seconds` of
+    ///   `[vnstat]` (see [`VnstatService::reload`]);
+    /// - `cache.ttl_seconds`;
+    /// - the limits and thresholds of `[quota]`; the webhook settings are
+    ///   kept;
+    /// - `logging.level`, unless the server runs with `--debug` or the
+    ///   level is unchanged.
+    ///
+    /// Every other setting keeps the value the state was created with; see
+    /// [`AppConfig::restart_required`] for those that differ.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error, before anything is applied, if the log level is
+    /// invalid; returns an error if the log filter cannot be replaced.
+    pub async fn reload(&self, config: &AppConfig) -> anyhow::Result<()> {
+        let level = config.logging.level()?;
+
+        self.vnstat.set_cache_ttl(config.cache.ttl());
+        self.vnstat.reload(&config.vnstat).await;
+
+        let quota = QuotaConfig {
+            webhooks: self.quota.load().webhooks.clone(),
+            ..config.quota.clone()
+        };
+        self.quota.store(quota);
+
+        let log_level = self.log_level.as_ref().filter(|_| !self.debug);
+        if let Some(log_level) = log_level.filter(|handle| handle.level() != level) {
+            log_level.set_level(level)?;
+        }
+
+        self.reloaded_at
+            .store(timestamp::get_in_secs(), Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    /// Returns the path prefix stripped by the reverse proxy in front of
+    /// the server, to prepend to self-referential URLs.
+    ///
+    /// With `trust_proxy_headers`, a well-formed `X-Forwarded-Prefix` (an
+    /// absolute path) takes precedence over `base_path`; its trailing `/` is
+    /// dropped. Otherwise `base_path` is returned.
+    pub fn external_base_path<'a>(&'a self, headers: &'a HeaderMap) -> &'a str {
+        let forwarded = headers
+            .get(FORWARDED_PREFIX)
+            .filter(|_| self.trust_proxy_headers)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.trim().trim_end_matches('/'))
+            .filter(|v| v.is_empty() || (v.starts_with('/') && !v.starts_with("//")));
+
+        forwarded.unwrap_or(&self.base_path)
+    }
+
+    /// Enables or disables error details in responses.
+    pub fn with_debug(self, debug: bool) -> Self {
+        Self { debug, ..self }
+    }
+
+    /// Enables the admin log-level endpoints, controlling `log_level`.
+    pub fn with_log_level(self, log_level: LogLevelHandle) -> Self {
+        Self {
+            log_level: Some(log_level),
+            ..self
+        }
+    }
+
+    /// Replaces the state's [`TaskManager`] with `task_manager`, e.g. one
+    /// shared with another state so that both routers serve the same live
+    /// processes. Its limits and live-stream settings are the ones it was
+    /// built with, not those of the configuration.
+    pub fn with_task_manager(self, task_manager: Arc<TaskManager>) -> Self {
+        Self {
+            task_manager,
+            ..self
+        }
+    }
+}
+
+/// Builds the API for `config`, ready to be served or nested into another
+/// application.
+///
+/// Every call creates its own state (see [`AppState::from_config`]): a new
+/// vnStat cache and a new [`TaskManager`], so two routers never share live
+/// processes. Use [`router_with_state`] to keep a handle on the state, or
+/// to share a task manager (see [`AppState::with_task_manager`]).
+///
+/// CORS, access logging, request IDs, client-IP resolution and the IP
+/// allowlist are applied by the server binary, not by this router.
+///
+/// ```no_run
+/// use axum::Router;
+/// use vnstat_rs_api::config::AppConfig;
+///
+/// let config = AppConfig::default();
+/// let app: Router = Router::new().nest("/dashboard/traffic", vnstat_rs_api::get_router(&config));
+/// ```
+pub fn get_router(config: &AppConfig) -> Router {
+    router_with_state(AppState::from_config(config))
+}
+
+/// Builds the API serving `state`.
+pub fn router_with_state(state: AppState) -> Router {
+    routes(&state).with_state(state)
 }
 
 /// Assembles the top-level Axum [`Router`] and returns it.
 ///
-/// All routes are prefixed under `/vnstat` and delegate to the sub-module
-/// [`vnstat::router`].
-pub fn get_router() -> Router<AppState> {
-    Router::new().nest("/vnstat", vnstat::router())
+/// vnStat routes are prefixed under `/vnstat` and delegate to the
+/// sub-module [`vnstat::router`]; `/capabilities` reports which of them are
+/// currently usable and `/info` which build is running (see
+/// [`info::get_info`]). Administrative endpoints live under `/admin` (see
+/// [`admin::router`]), the exports in other tools' formats under `/export`
+/// (see [`export::router`]) and the Grafana JSON datasource under
+/// `/grafana` (see [`grafana::router`]).
+///
+/// The data routes are subject to the state's [`InFlightLimit`], and every
+/// route except the live streams to its request timeout. Every route is
+/// counted in the state's [`RequestMetrics`].
+///
+/// Route groups disabled in the state's [`FeaturesConfig`] are left out, so
+/// their paths fall through to the fallback below.
+///
+/// Unknown paths and unsupported methods are answered with a JSend body
+/// ([`ErrorCode::RouteNotFound`] / [`ErrorCode::MethodNotAllowed`]) rather
+/// than axum's empty default responses.
+///
+/// A handler that panics is answered with `500` and a JSend body
+/// ([`ErrorCode::UnknownError`], see [`panic_response`]) instead of a
+/// dropped connection.
+fn routes(state: &AppState) -> Router<AppState> {
+    let mut timed = Router::new()
+        .route("/capabilities", get(capabilities::get_capabilities))
+        .route("/info", get(info::get_info))
+        .nest("/export", state.in_flight.apply(export::router()))
+        .merge(state.in_flight.apply(grafana::router()));
+    if state.features.admin {
+        timed = timed.nest("/admin", admin::router(&state.features));
+    }
+
+    let routes =
+        request_timeout::apply(timed, state.request_timeout).nest("/vnstat", vnstat::router(state));
+
+    // Inside the metrics, so that panics are counted as 500s.
+    let routes = routes.layer(CatchPanicLayer::custom(panic_response));
+
+    state
+        .metrics
+        .apply(routes)
+        .method_not_allowed_fallback(|| async { failure(ErrorCode::MethodNotAllowed) })
+        .fallback(|| async { failure(ErrorCode::RouteNotFound) })
+}
+
+/// Answers a request whose handler panicked with a JSend failure
+/// ([`ErrorCode::UnknownError`]). The panic itself is logged by the panic
+/// hook (see [`logging::init`](crate::logging::init)).
+fn panic_response(_panic: Box<dyn Any + Send + 'static>) -> Response {
+    failure(ErrorCode::UnknownError).into_response()
+}
+
+/// Builds a bare JSend failure response for `code` with its canonical
+/// status.
+fn failure(code: ErrorCode) -> (axum::http::StatusCode, Json<JsendResponse<String>>) {
+    (code.status_code(), Json(JsendResponse::fail(code)))
 }
@@ -1,22 +1,68 @@
+use crate::config::features::FeaturesConfig;
 use crate::error_code::ErrorCode;
-use crate::model::jsend::JsendResponse;
-use crate::model::vnstat::{Interface, VnstatData};
+use crate::middleware::request_timeout;
+use crate::model::jsend::{ClampedLimit, DataSource, JsendResponse, ResponseMeta};
+use crate::model::management::{AliasBody, InterfaceChange, NewInterfaceBody};
+use crate::model::response::{BillingMonthResponse, WeekResponse};
+use crate::service::aggregate::TimeRange;
+use crate::service::batch::{BatchPart, MAX_BATCH_BYTES, MAX_BATCH_INTERFACES, serialized_len};
+use crate::service::billing::billing_months;
+use crate::service::busiest::{TrafficMetric, busiest_periods};
+use crate::service::compare::MAX_COMPARED_INTERFACES;
+use crate::service::decimate::{SeriesGranularity, SeriesWindow, SortOrder, window_interface};
+use crate::service::delta::{delta_window, interface_delta};
+use crate::service::error::{AmbiguousInterface, NoInterfaces};
+use crate::service::fields::TrafficFields;
+use crate::service::image::{ImageKind, MAX_WIDTH, MIN_WIDTH};
+use crate::service::lag::LagPolicy;
+use crate::service::live::{LiveMode, LiveOptions, RateUnit};
+use crate::service::quota::quota_usage;
+use crate::service::series::{TrafficFormat, interface_series, total_series};
+use crate::service::stats::{Granularity, interface_stats};
+use crate::service::vnstat_service::{Fetched, JsonView};
+use crate::service::week::{WeekStart, weekly_traffic};
+use crate::utils::byte_size::humanize_json;
+use crate::utils::interface_name::{validate_alias, validate_interface_name};
+use crate::utils::request_id;
 use crate::utils::sse::sse_with_default_headers;
-use axum::extract::{Path, State};
-use axum::http::StatusCode;
+use crate::utils::timestamp;
+use crate::utils::traffic_unit::TrafficUnit;
+use crate::utils::ws::forward_task_messages;
+use axum::body::Bytes;
+use axum::extract::ws::WebSocketUpgrade;
+use axum::extract::ws::rejection::WebSocketUpgradeRejection;
+use axum::extract::{OriginalUri, State};
+use axum::http::{HeaderMap, StatusCode, Uri, header};
 use axum::response::sse::KeepAlive;
-use axum::response::{IntoResponse, Redirect, Response, Sse};
-use axum::routing::get;
+use axum::response::{AppendHeaders, IntoResponse, Redirect, Response, Sse};
+use axum::routing::{MethodRouter, delete, get, post, put};
 use axum::{Json, Router};
 use percent_encoding::{NON_ALPHANUMERIC, utf8_percent_encode};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use tracing::{info, trace};
 
 use super::AppState;
+use super::extract::{InterfaceName, JsendJson, JsendQuery};
+
+/// Header reporting how old the served data is, in seconds.
+const DATA_AGE_HEADER: &str = "x-data-age-seconds";
+
+/// Header carrying the Unix time (seconds) at which vnStat was invoked for
+/// the served data.
+const GENERATED_AT_HEADER: &str = "x-generated-at";
 
 /// Builds and returns the Axum [`Router`] for all `/vnstat` endpoints.
 ///
 /// Registers both the flat (canonical) routes and legacy prefixed routes
-/// that redirect to their flat counterparts.
+/// that redirect to their flat counterparts. The `in_flight` limit and the
+/// request timeout of `state` apply to every route except the live streams
+/// (SSE, WebSocket and long polls), which are long-lived and limited by the
+/// [`TaskManager`](crate::task_manager::TaskManager) instead.
+///
+/// The live streams (and their legacy redirects), the raw endpoints and the
+/// interface management endpoints are only registered while their group is
+/// enabled in `state.features`.
 ///
 /// # Routes
 ///
@@ -25,30 +71,136 @@ use super::AppState;
 /// | GET    | `/`                                     | [`get_data`]                |
 /// | GET    | `/health`                               | [`get_health`]              |
 /// | GET    | `/version`                              | [`get_version`]             |
+/// | GET    | `/info`                                 | [`super::info::get_info`]   |
+/// | GET    | `/raw`                                  | [`get_raw`]                 |
+/// | GET    | `/live`                                 | [`get_live_sse`]            |
+/// | GET    | `/total`                                | [`get_total`]               |
+/// | GET    | `/compare`                              | [`get_compare`]             |
+/// | GET    | `/stale`                                | [`get_stale_interfaces`]    |
 /// | GET    | `/{if_name}`                            | [`get_interface_data`]      |
 /// | GET    | `/{if_name}/live`                       | [`get_interface_live_sse`]  |
+/// | GET    | `/{if_name}/live/ws`                    | [`get_interface_live_ws`]   |
+/// | GET    | `/{if_name}/live/poll`                  | [`get_interface_live_poll`] |
+/// | GET    | `/{if_name}/raw`                        | [`get_interface_raw`]       |
+/// | GET    | `/{if_name}/estimate`                   | [`get_interface_estimate`]  |
+/// | GET    | `/{if_name}/quota`                      | [`get_interface_quota`]     |
+/// | GET    | `/{if_name}/delta`                      | [`get_interface_delta`]     |
+/// | GET    | `/{if_name}/week`                       | [`get_interface_weeks`]     |
+/// | GET    | `/{if_name}/billing-month`              | [`get_interface_billing_months`] |
+/// | GET    | `/{if_name}/stats`                      | [`get_interface_stats`]     |
+/// | GET    | `/{if_name}/busiest`                    | [`get_interface_busiest`]   |
+/// | GET    | `/{if_name}/updates`                    | [`get_interface_updates_sse`] |
+/// | GET    | `/{if_name}/image`                      | [`get_interface_image`]     |
 /// | GET    | `/interfaces`                           | [`get_interfaces`]          |
-/// | GET    | `/interfaces/{if_name}`                 | [`redir_interface_to_flat`] |
-/// | GET    | `/interfaces/{if_name}/traffic`         | [`redir_interface_traffic_to_flat`] |
-/// | GET    | `/interfaces/{if_name}/live`            | [`redir_interface_live_to_flat`] |
-pub fn router() -> Router<AppState> {
-    Router::new()
+/// | POST   | `/interfaces`                           | [`add_interface`]           |
+/// | GET    | `/interfaces/batch`                     | [`get_interfaces_batch`]    |
+/// | GET    | `/interfaces/{if_name}`                 | [`redirect_to`] |
+/// | DELETE | `/interfaces/{if_name}`                 | [`remove_interface`]        |
+/// | PUT    | `/interfaces/{if_name}/alias`           | [`set_interface_alias`]     |
+/// | GET    | `/interfaces/{if_name}/traffic`         | [`redirect_to`] |
+/// | GET    | `/interfaces/{if_name}/stats`           | [`redirect_to`] |
+/// | GET    | `/interfaces/{if_name}/traffic/busiest` | [`redirect_to`] |
+/// | GET    | `/interfaces/{if_name}/traffic/week`    | [`redirect_to`] |
+/// | GET    | `/interfaces/{if_name}/traffic/billing-month` | [`redirect_to`] |
+/// | GET    | `/interfaces/{if_name}/live`            | [`redirect_to`] |
+/// | GET    | `/interfaces/{if_name}/live/ws`         | [`get_interface_live_ws`]   |
+/// | GET    | `/interfaces/{if_name}/live/poll`       | [`get_interface_live_poll`] |
+/// | GET    | `/interfaces/{if_name}/raw`             | [`redirect_to`] |
+/// | GET    | `/interfaces/{if_name}/estimate`        | [`redirect_to`] |
+/// | GET    | `/interfaces/{if_name}/quota`           | [`redirect_to`] |
+/// | GET    | `/interfaces/{if_name}/delta`           | [`redirect_to`] |
+/// | GET    | `/interfaces/{if_name}/updates`         | [`redirect_to`] |
+/// | GET    | `/interfaces/{if_name}/image`           | [`get_interface_image`]     |
+pub fn router(state: &AppState) -> Router<AppState> {
+    let features = &state.features;
+    let data = state.in_flight.apply(data_routes(features));
+    let routes = request_timeout::apply(data, state.request_timeout);
+    if features.live_streams {
+        routes.merge(stream_routes())
+    } else {
+        routes
+    }
+}
+
+/// The routes answering with a single response, without the groups
+/// disabled in `features`.
+fn data_routes(features: &FeaturesConfig) -> Router<AppState> {
+    let mut routes = Router::new()
         .route("/", get(get_data))
         .route("/health", get(get_health))
         .route("/version", get(get_version))
+        .route("/info", get(super::info::get_info))
+        .route("/total", get(get_total))
+        .route("/compare", get(get_compare))
+        .route("/stale", get(get_stale_interfaces))
         // New flat routes
         .route("/{if_name}", get(get_interface_data))
-        .route("/{if_name}/live", get(get_interface_live_sse))
+        .route("/{if_name}/estimate", get(get_interface_estimate))
+        .route("/{if_name}/quota", get(get_interface_quota))
+        .route("/{if_name}/delta", get(get_interface_delta))
+        .route("/{if_name}/week", get(get_interface_weeks))
+        .route(
+            "/{if_name}/billing-month",
+            get(get_interface_billing_months),
+        )
+        .route("/{if_name}/stats", get(get_interface_stats))
+        .route("/{if_name}/busiest", get(get_interface_busiest))
+        .route("/{if_name}/image", get(get_interface_image))
         // Legacy redirects
         .route("/interfaces", get(get_interfaces))
-        .route("/interfaces/{if_name}", get(redir_interface_to_flat))
+        .route("/interfaces/batch", get(get_interfaces_batch))
+        .route("/interfaces/{if_name}", redirect_to(""))
+        .route("/interfaces/{if_name}/", redirect_to(""))
+        .route("/interfaces/{if_name}/traffic", redirect_to(""))
+        .route("/interfaces/{if_name}/stats", redirect_to("/stats"))
         .route(
-            "/interfaces/{if_name}/traffic",
-            get(redir_interface_traffic_to_flat),
+            "/interfaces/{if_name}/traffic/busiest",
+            redirect_to("/busiest"),
         )
+        .route("/interfaces/{if_name}/traffic/week", redirect_to("/week"))
         .route(
-            "/interfaces/{if_name}/live",
-            get(redir_interface_live_to_flat),
+            "/interfaces/{if_name}/traffic/billing-month",
+            redirect_to("/billing-month"),
+        )
+        .route("/interfaces/{if_name}/estimate", redirect_to("/estimate"))
+        .route("/interfaces/{if_name}/quota", redirect_to("/quota"))
+        .route("/interfaces/{if_name}/delta", redirect_to("/delta"))
+        .route("/interfaces/{if_name}/image", get(get_interface_image));
+
+    if features.raw_endpoints {
+        routes = routes
+            .route("/raw", get(get_raw))
+            .route("/{if_name}/raw", get(get_interface_raw))
+            .route("/interfaces/{if_name}/raw", redirect_to("/raw"));
+    }
+    if features.management {
+        routes = routes
+            .route("/interfaces", post(add_interface))
+            .route("/interfaces/{if_name}", delete(remove_interface))
+            .route("/interfaces/{if_name}/alias", put(set_interface_alias));
+    }
+    if features.live_streams {
+        // Legacy redirects to the live streams
+        routes = routes
+            .route("/interfaces/{if_name}/live", redirect_to("/live"))
+            .route("/interfaces/{if_name}/updates", redirect_to("/updates"));
+    }
+    routes
+}
+
+/// The live-stream routes, including the long polls.
+fn stream_routes() -> Router<AppState> {
+    Router::new()
+        .route("/live", get(get_live_sse))
+        .route("/{if_name}/live", get(get_interface_live_sse))
+        .route("/{if_name}/live/ws", get(get_interface_live_ws))
+        .route("/{if_name}/live/poll", get(get_interface_live_poll))
+        .route("/{if_name}/updates", get(get_interface_updates_sse))
+        // Served directly: WebSocket clients generally do not follow redirects
+        .route("/interfaces/{if_name}/live/ws", get(get_interface_live_ws))
+        .route(
+            "/interfaces/{if_name}/live/poll",
+            get(get_interface_live_poll),
         )
 }
 
@@ -59,21 +211,42 @@ pub fn router() -> Router<AppState> {
 /// # Returns
 ///
 /// - `200 OK` with a JSON body containing `"ok"` when the daemon is
-///   reachable.
-/// - `503 SERVICE_UNAVAILABLE` with an error JSON body when the health
-///   check fails.
-async fn get_health(
-    State(state): State<AppState>,
-) -> Result<Json<JsendResponse<String>>, (StatusCode, Json<serde_json::Value>)> {
+///   reachable, or `"degraded"` if the startup version check failed
+///   without being enforced or vnStat monitors no interface yet.
+/// - `503 SERVICE_UNAVAILABLE` with an error JSON body (including
+///   `dataAgeSeconds` and the circuit breaker state as `breaker`) when
+///   the health check fails or the circuit breaker is open.
+///
+/// Once data has been fetched, the age of the freshest cached snapshot is
+/// reported in the `X-Data-Age-Seconds` header.
+async fn get_health(State(state): State<AppState>) -> Response {
+    let data_age = state
+        .vnstat
+        .data_age()
+        .await
+        .map(|age| [(DATA_AGE_HEADER, age.as_secs().to_string())]);
+
     match state.vnstat.check_health().await {
-        Ok(_) => Ok(Json(JsendResponse::success_with_data("ok".to_string()))),
+        Ok(_) if state.vnstat.is_degraded() || !state.vnstat.has_interfaces().await => (
+            data_age,
+            Json(JsendResponse::success_with_data("degraded".to_string())),
+        )
+            .into_response(),
+        Ok(_) => (
+            data_age,
+            Json(JsendResponse::success_with_data("ok".to_string())),
+        )
+            .into_response(),
         Err(e) => {
             let err_json = serde_json::json!({
                 "status": "error",
-                "code": ErrorCode::GetDataFailed,
+                "code": ErrorCode::from_error(&e, ErrorCode::GetDataFailed),
                 "message": format!("vnstat health check failed: {}", e),
+                "dataAgeSeconds": state.vnstat.data_age().await.map(|age| age.as_secs()),
+                "breaker": state.vnstat.breaker_status(),
+                "requestId": request_id::current().map(|id| id.0),
             });
-            Err((StatusCode::SERVICE_UNAVAILABLE, Json(err_json)))
+            (StatusCode::SERVICE_UNAVAILABLE, data_age, Json(err_json)).into_response()
         }
     }
 }
@@ -86,148 +259,1889 @@ async fn get_health(
 /// # Returns
 ///
 /// - `200 OK` with the vnstat version string wrapped in a JSend envelope.
+/// - `200 OK` with code `10005` and an `X-Data-Age-Seconds` header when
+///   vnStat is failing and older cached data is served instead.
+/// - `502 BAD_GATEWAY` when vnStat fails to run or its output cannot be
+///   parsed.
+/// - `504 GATEWAY_TIMEOUT` when vnStat does not answer in time.
 /// - `400 BAD_REQUEST` when the data fetch fails.
 async fn get_version(
     State(state): State<AppState>,
-) -> Result<Json<JsendResponse<String>>, (StatusCode, Json<JsendResponse<String>>)> {
-    let data = state.vnstat.fetch_vnstat_data().await.map_err(|_| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(JsendResponse::fail(ErrorCode::GetDataFailed)),
-        )
-    })?;
+) -> Result<Response, (StatusCode, Json<JsendResponse<String>>)> {
+    let data = state
+        .vnstat
+        .fetch_vnstat_data()
+        .await
+        .map_err(|e| vnstat_failure(&state, &e, ErrorCode::GetDataFailed))?;
 
-    Ok(Json(JsendResponse::success_with_data(data.vnstatversion)))
+    Ok(fetched_json(data.map(|data| data.vnstatversion.clone())))
 }
 
 /// Handler for `GET /vnstat/`.
 ///
 /// Returns the complete vnstat data object (all interfaces and their
-/// traffic history).
+/// traffic history). The response body is serialised once per cached
+/// snapshot (see
+/// [`VnstatService::fetch_json`](crate::service::vnstat_service::VnstatService::fetch_json)).
 ///
 /// # Returns
 ///
-/// - `200 OK` with a [`VnstatData`] payload wrapped in a JSend envelope.
+/// - `200 OK` with a [`VnstatData`](crate::model::vnstat::VnstatData)
+///   payload wrapped in a JSend envelope.
+/// - `200 OK` with code `10005` and an `X-Data-Age-Seconds` header when
+///   vnStat is failing and older cached data is served instead.
+/// - `502 BAD_GATEWAY` when vnStat fails to run or its output cannot be
+///   parsed.
+/// - `504 GATEWAY_TIMEOUT` when vnStat does not answer in time.
 /// - `400 BAD_REQUEST` when the data fetch fails.
 async fn get_data(
     State(state): State<AppState>,
-) -> Result<Json<JsendResponse<VnstatData>>, (StatusCode, Json<JsendResponse<String>>)> {
-    let data = state.vnstat.fetch_vnstat_data().await.map_err(|e| {
-        info!("err: {}", e);
-        (
-            StatusCode::BAD_REQUEST,
-            Json(JsendResponse::fail(ErrorCode::GetDataFailed)),
-        )
-    })?;
+) -> Result<Response, (StatusCode, Json<JsendResponse<String>>)> {
+    let body = state
+        .vnstat
+        .fetch_json(JsonView::Data)
+        .await
+        .map_err(|e| vnstat_failure(&state, &e, ErrorCode::GetDataFailed))?;
+
+    Ok(cached_jsend(body))
+}
+
+/// Query parameters accepted by `GET /vnstat/interfaces`.
+#[derive(Debug, Default, Deserialize)]
+struct InterfacesQuery {
+    /// Return bare interface names (the legacy response shape).
+    #[serde(default)]
+    names_only: bool,
+    /// Unit of the traffic totals.
+    #[serde(default)]
+    unit: TrafficUnit,
+    /// Add readable forms of the traffic totals.
+    #[serde(default)]
+    human: bool,
+}
+
+/// Query parameters accepted by `GET /vnstat/total`.
+#[derive(Debug, Default, Deserialize)]
+struct TotalQuery {
+    /// Start of the time range (Unix seconds).
+    from: Option<i64>,
+    /// End of the time range (Unix seconds), inclusive.
+    to: Option<i64>,
+    /// Number of records to keep per series, see [`SeriesWindow::limit`].
+    limit: Option<usize>,
+    /// Order of the records.
+    order: Option<SortOrder>,
+    /// Maximum number of records per series, see [`SeriesWindow::points`].
+    points: Option<usize>,
+    /// How the series are serialised.
+    #[serde(default)]
+    format: TrafficFormat,
+    /// Unit of the traffic values.
+    #[serde(default)]
+    unit: TrafficUnit,
+    /// Add readable forms of the traffic values.
+    #[serde(default)]
+    human: bool,
+}
+
+/// Query parameters accepted by `GET /vnstat/{if_name}`.
+#[derive(Debug, Default, Deserialize)]
+struct InterfaceQuery {
+    /// Start of the time range (Unix seconds).
+    from: Option<i64>,
+    /// End of the time range (Unix seconds), inclusive.
+    to: Option<i64>,
+    /// Number of records to keep per series, see [`SeriesWindow::limit`].
+    limit: Option<usize>,
+    /// Order of the records.
+    order: Option<SortOrder>,
+    /// Maximum number of records per series, see [`SeriesWindow::points`].
+    points: Option<usize>,
+    /// How the series are serialised.
+    #[serde(default)]
+    format: TrafficFormat,
+    /// Unit of the traffic values.
+    #[serde(default)]
+    unit: TrafficUnit,
+    /// Comma-separated traffic fields to serialise, see [`TrafficFields`].
+    include: Option<String>,
+    /// Comma-separated traffic fields to leave out, see [`TrafficFields`].
+    exclude: Option<String>,
+}
+
+/// Validates the `from`, `to`, `limit`, `order` and `points` query
+/// parameters and combines them into a [`SeriesWindow`].
+///
+/// A `limit` of `0` or above `max_limit` (`server.max_record_limit`) is
+/// clamped into range rather than rejected; the returned [`ResponseMeta`]
+/// reports the adjustment.
+///
+/// # Errors
+///
+/// Returns the reason to report with code `10008` when `from` is after `to`
+/// or `points` is `0`.
+fn series_window(
+    from: Option<i64>,
+    to: Option<i64>,
+    limit: Option<usize>,
+    order: Option<SortOrder>,
+    points: Option<usize>,
+    max_limit: usize,
+) -> Result<(SeriesWindow, Option<ResponseMeta>), &'static str> {
+    match (from, to, points) {
+        (Some(from), Some(to), _) if from > to => return Err("`from` must not be after `to`"),
+        (_, _, Some(0)) => return Err("`points` must be at least 1"),
+        _ => {}
+    }
+
+    let (applied, meta) = match limit {
+        Some(limit) => {
+            let (applied, meta) = clamp_limit(limit, max_limit);
+            (Some(applied), meta)
+        }
+        None => (None, None),
+    };
+
+    let window = SeriesWindow {
+        range: TimeRange { from, to },
+        limit: applied,
+        order,
+        points,
+    };
+    Ok((window, meta))
+}
+
+/// Clamps a requested number of records into `1..=max_limit`.
+///
+/// Returns the number to apply, and a `meta` block reporting the clamping
+/// if it changed the number.
+fn clamp_limit(requested: usize, max_limit: usize) -> (usize, Option<ResponseMeta>) {
+    let applied = requested.clamp(1, max_limit);
+    let meta = (applied != requested).then(|| ResponseMeta {
+        limit: Some(ClampedLimit { requested, applied }),
+        ..ResponseMeta::default()
+    });
+    (applied, meta)
+}
+
+/// Builds the `400 BAD_REQUEST` response for an invalid query parameter.
+fn invalid_query(reason: &str) -> (StatusCode, Json<JsendResponse<String>>) {
+    (
+        ErrorCode::InvalidQueryParameter.status_code(),
+        Json(JsendResponse::fail_with_message(
+            ErrorCode::InvalidQueryParameter,
+            reason,
+        )),
+    )
+}
+
+/// Handler for `GET /vnstat/total`.
+///
+/// Returns the traffic of all visible interfaces summed together: the
+/// lifetime totals plus per-day and per-month series aligned by date. The
+/// series can be restricted with `?from=` and `?to=` (Unix timestamps);
+/// records whose day or month overlaps the range are returned. `?limit=`
+/// keeps the most recent records of those and `?order=asc|desc` orders
+/// them (see [`SeriesWindow`]). With `?points=`, each series is
+/// downsampled to at most that many records spread evenly over the range
+/// (see [`decimate`](crate::service::decimate::decimate)).
+/// `?format=series` returns the series as compact `[timestamp, rx, tx]`
+/// arrays instead of objects, and `?unit=` converts the traffic values
+/// (see [`TrafficUnit`]).
+/// `?human=true` adds `rxHuman`/`txHuman` strings next to the objects'
+/// values (see [`humanize_json`]).
+///
+/// # Returns
+///
+/// - `200 OK` with the summed traffic wrapped in a JSend envelope (code
+///   `10005` and `X-Data-Age-Seconds` when stale data is served), with a
+///   `meta` field when `limit` was clamped.
+/// - `400 BAD_REQUEST` with code `10008` when `from` is after `to`,
+///   `points` is `0`, or `format`, `order` or `unit` is unknown.
+/// - `502 BAD_GATEWAY` / `504 GATEWAY_TIMEOUT` when vnStat fails.
+async fn get_total(
+    State(state): State<AppState>,
+    JsendQuery(query): JsendQuery<TotalQuery>,
+) -> Result<Response, (StatusCode, Json<JsendResponse<String>>)> {
+    let (window, meta) = series_window(
+        query.from,
+        query.to,
+        query.limit,
+        query.order,
+        query.points,
+        state.max_record_limit,
+    )
+    .map_err(invalid_query)?;
+    let data = state
+        .vnstat
+        .aggregate_all_interfaces(window.range)
+        .await
+        .map_err(|e| vnstat_failure(&state, &e, ErrorCode::GetDataFailed))?;
+
+    let data = data.map(|mut total| {
+        total.day = window.apply(total.day);
+        total.month = window.apply(total.month);
+        total
+    });
+
+    Ok(match query.format {
+        TrafficFormat::Objects => traffic_json(&state, data, query.unit, query.human, meta),
+        TrafficFormat::Series => {
+            fetched_json_with_meta(data.map(|total| total_series(total, query.unit)), meta)
+        }
+    })
+}
+
+/// Handler for `GET /vnstat/raw`.
+///
+/// Returns vnStat's `--json` output as-is (minus interfaces hidden by the
+/// interface filter), without the JSend envelope.
+///
+/// # Returns
+///
+/// - `200 OK` with the raw JSON and `Content-Type: application/json`.
+/// - `404 NOT_FOUND` when raw endpoints are disabled by configuration.
+/// - `200 OK` with an `X-Data-Age-Seconds` header when vnStat is failing
+///   and older cached data is served instead.
+/// - `502 BAD_GATEWAY` when vnStat fails to run or its output cannot be
+///   parsed.
+/// - `504 GATEWAY_TIMEOUT` when vnStat does not answer in time.
+/// - `400 BAD_REQUEST` when the data fetch fails.
+async fn get_raw(State(state): State<AppState>) -> Response {
+    if !state.vnstat.raw_enabled() {
+        return raw_disabled();
+    }
+
+    match state.vnstat.fetch_vnstat_raw().await {
+        Ok(raw) => raw_json(raw),
+        Err(e) => vnstat_failure(&state, &e, ErrorCode::GetDataFailed).into_response(),
+    }
+}
+
+/// Handler for `GET /vnstat/{if_name}/raw`.
+///
+/// Returns the interface object from vnStat's `--json` output as-is,
+/// without the JSend envelope.
+///
+/// # Returns
+///
+/// - `200 OK` with the raw JSON and `Content-Type: application/json`.
+/// - `404 NOT_FOUND` when raw endpoints are disabled by configuration.
+/// - `200 OK` with an `X-Data-Age-Seconds` header when vnStat is failing
+///   and older cached data is served instead.
+/// - `502 BAD_GATEWAY` when vnStat fails to run or its output cannot be
+///   parsed.
+/// - `504 GATEWAY_TIMEOUT` when vnStat does not answer in time.
+/// - `404 NOT_FOUND` with code `10001` when the interface does not exist
+///   or is hidden by the interface filter.
+/// - `400 BAD_REQUEST` when the fetch fails otherwise.
+async fn get_interface_raw(
+    InterfaceName(if_name): InterfaceName,
+    State(state): State<AppState>,
+) -> Response {
+    if !state.vnstat.raw_enabled() {
+        return raw_disabled();
+    }
+
+    match state.vnstat.fetch_interface_raw(if_name).await {
+        Ok(raw) => raw_json(raw),
+        Err(e) => vnstat_failure(&state, &e, ErrorCode::NoSuchInterface).into_response(),
+    }
+}
+
+/// Builds the JSend failure response for an error from the vnStat service.
+///
+/// The error code is derived from `e` (falling back to `fallback`) and
+/// determines the status via [`ErrorCode::status_code`]. In debug mode the full error chain
+/// (including vnStat's exit code and stderr) is returned in `details`. An
+/// ambiguous alias is reported with the candidate interfaces in `message`,
+/// and an empty vnStat database with how to add an interface.
+pub(super) fn vnstat_failure(
+    state: &AppState,
+    e: &anyhow::Error,
+    fallback: ErrorCode,
+) -> (StatusCode, Json<JsendResponse<String>>) {
+    info!("err: {}", e);
+    let code = ErrorCode::from_error(e, fallback);
+    let mut response = if let Some(ambiguous) = e.downcast_ref::<AmbiguousInterface>() {
+        JsendResponse::fail_with_message(code, ambiguous.to_string())
+    } else if let Some(none) = e.downcast_ref::<NoInterfaces>() {
+        JsendResponse::fail_with_message(code, none.to_string())
+    } else {
+        JsendResponse::fail(code)
+    };
+    if state.debug {
+        response = response.with_details(format!("{:#}", e));
+    }
+    (code.status_code(), Json(response))
+}
+
+/// Wraps fetched data in a `200 OK` JSend success response.
+///
+/// A `meta` field and an `X-Generated-At` header report when and how the
+/// data was fetched. Stale data is flagged with [`ErrorCode::StaleData`]
+/// and an `X-Data-Age-Seconds` header.
+fn fetched_json<T: Serialize>(fetched: Fetched<T>) -> Response {
+    fetched_json_with_meta(fetched, None)
+}
+
+/// Like [`fetched_json`], with the notes in `meta` added to the `meta`
+/// field.
+///
+/// Data about an interface vnStat no longer updates (see
+/// [`interface_meta`]) is flagged with [`ErrorCode::StaleInterface`],
+/// unless the data itself is stale.
+fn fetched_json_with_meta<T: Serialize>(
+    fetched: Fetched<T>,
+    meta: Option<ResponseMeta>,
+) -> Response {
+    let headers = freshness_headers(&fetched);
+    let meta = freshness_meta(&fetched, meta);
+    let response = if fetched.stale {
+        JsendResponse::success_with_data_and_code(fetched.value, ErrorCode::StaleData)
+    } else if meta.interface_stale == Some(true) {
+        JsendResponse::success_with_data_and_code(fetched.value, ErrorCode::StaleInterface)
+    } else {
+        JsendResponse::success_with_data(fetched.value)
+    };
+
+    (headers, Json(response.with_meta(Some(meta)))).into_response()
+}
+
+/// Returns `meta` (or empty notes) noting whether vnStat still updates the
+/// interface it last updated at `updated_timestamp`.
+fn interface_meta(
+    state: &AppState,
+    updated_timestamp: i64,
+    meta: Option<ResponseMeta>,
+) -> Option<ResponseMeta> {
+    Some(ResponseMeta {
+        interface_stale: Some(state.vnstat.is_interface_stale(updated_timestamp)),
+        ..meta.unwrap_or_default()
+    })
+}
+
+/// Returns `meta` (or empty notes) completed with the freshness of
+/// `fetched`.
+fn freshness_meta<T>(fetched: &Fetched<T>, meta: Option<ResponseMeta>) -> ResponseMeta {
+    ResponseMeta {
+        generated_at: Some(fetched.generated_at),
+        cache_age_seconds: Some(fetched.age.as_secs()),
+        source: Some(DataSource::Cli),
+        stale: Some(fetched.stale),
+        future_records: (fetched.future_records > 0).then_some(fetched.future_records),
+        ..meta.unwrap_or_default()
+    }
+}
+
+/// Like [`fetched_json`], with readable forms of the byte counts added when
+/// `human` is set (see [`humanize_json`]) and the traffic values converted
+/// to `unit` (see [`TrafficUnit::convert_json`]). Without either, the data
+/// is served as is.
+fn traffic_json<T: Serialize>(
+    state: &AppState,
+    fetched: Fetched<T>,
+    unit: TrafficUnit,
+    human: bool,
+    meta: Option<ResponseMeta>,
+) -> Response {
+    if unit == TrafficUnit::Bytes && !human {
+        return fetched_json_with_meta(fetched, meta);
+    }
+
+    let converted = fetched.try_map(|value| {
+        let mut value = serde_json::to_value(value)?;
+        if human {
+            humanize_json(&mut value);
+        }
+        if unit != TrafficUnit::Bytes {
+            unit.convert_json(&mut value);
+        }
+        Ok(value)
+    });
+    match converted {
+        Ok(converted) => fetched_json_with_meta(converted, meta),
+        Err(e) => vnstat_failure(state, &e, ErrorCode::GetDataFailed).into_response(),
+    }
+}
 
-    Ok(Json(JsendResponse::success_with_data(data)))
+/// Wraps JSON bytes (vnStat's own output) in a `200 OK` response with the
+/// JSON content type, adding an `X-Generated-At` header, and an
+/// `X-Data-Age-Seconds` header when the data is stale.
+fn raw_json(raw: Fetched<Bytes>) -> Response {
+    (
+        freshness_headers(&raw),
+        [(header::CONTENT_TYPE, "application/json")],
+        raw.value,
+    )
+        .into_response()
+}
+
+/// Like [`raw_json`], for a JSend response serialised once per snapshot
+/// (see [`VnstatService::fetch_json`](crate::service::vnstat_service::VnstatService::fetch_json)).
+///
+/// The `meta` field changes with every request, so it is spliced in before
+/// the closing brace of the cached body instead of being serialised with
+/// it. `meta` is the last field of [`JsendResponse`], so the result is the
+/// same as serialising the whole response.
+fn cached_jsend(body: Fetched<Bytes>) -> Response {
+    let meta = serde_json::to_vec(&freshness_meta(&body, None));
+    let value = match (meta, body.value.strip_suffix(b"}")) {
+        (Ok(meta), Some(head)) => {
+            let mut spliced = Vec::with_capacity(body.value.len() + meta.len() + 9);
+            spliced.extend_from_slice(head);
+            spliced.extend_from_slice(b",\"meta\":");
+            spliced.extend_from_slice(&meta);
+            spliced.push(b'}');
+            Bytes::from(spliced)
+        }
+        _ => body.value.clone(),
+    };
+
+    raw_json(Fetched { value, ..body })
+}
+
+/// The `X-Generated-At` header for `fetched`, and the `X-Data-Age-Seconds`
+/// header if it is stale.
+pub(super) fn freshness_headers<T>(
+    fetched: &Fetched<T>,
+) -> AppendHeaders<Vec<(&'static str, String)>> {
+    let mut headers = vec![(GENERATED_AT_HEADER, fetched.generated_at.to_string())];
+    if fetched.stale {
+        headers.push((DATA_AGE_HEADER, fetched.age.as_secs().to_string()));
+    }
+    AppendHeaders(headers)
+}
+
+/// The `404 NOT_FOUND` JSend response returned when raw endpoints are
+/// disabled.
+fn raw_disabled() -> Response {
+    (
+        ErrorCode::EndpointDisabled.status_code(),
+        Json(JsendResponse::<String>::fail(ErrorCode::EndpointDisabled)),
+    )
+        .into_response()
 }
 
 /// Handler for `GET /vnstat/interfaces`.
 ///
-/// Returns a list of all network interface names known to the vnstat
-/// daemon.
+/// Returns a list of all network interfaces known to the vnstat daemon.
+/// The entries are built per request from the cached snapshot, so that
+/// `secondsSinceUpdate` and `stale` are current; the bare names of
+/// `?names_only=true` are serialised once per snapshot like [`get_data`].
+/// With `?unit=`, the totals are converted (see [`TrafficUnit`]) and each
+/// entry carries a `unit` field; with `?human=true`,
+/// `totalRxHuman`/`totalTxHuman` strings are added.
 ///
 /// # Returns
 ///
-/// - `200 OK` with a `Vec<String>` of interface names wrapped in a JSend
-///   envelope.
+/// - `200 OK` with an
+///   [`InterfacesResponse`](crate::model::response::InterfacesResponse)
+///   wrapped in a JSend envelope:
+///   `{ name, alias, updatedTimestamp, secondsSinceUpdate, stale, totalRx,
+///   totalTx }` objects, or bare names with `?names_only=true`.
+/// - `200 OK` with code `10005` and an `X-Data-Age-Seconds` header when
+///   vnStat is failing and older cached data is served instead.
+/// - `502 BAD_GATEWAY` when vnStat fails to run or its output cannot be
+///   parsed.
+/// - `504 GATEWAY_TIMEOUT` when vnStat does not answer in time.
 /// - `400 BAD_REQUEST` when the interface listing fails.
 async fn get_interfaces(
+    JsendQuery(query): JsendQuery<InterfacesQuery>,
     State(state): State<AppState>,
-) -> Result<Json<JsendResponse<Vec<String>>>, (StatusCode, Json<JsendResponse<String>>)> {
-    let interfaces = state.vnstat.list_vnstat_interfaces().await.map_err(|e| {
-        info!("err: {}", e);
-        (
-            StatusCode::BAD_REQUEST,
-            Json(JsendResponse::fail(ErrorCode::GetDataFailed)),
-        )
-    })?;
+) -> Result<Response, (StatusCode, Json<JsendResponse<String>>)> {
+    if !query.names_only {
+        let data = state
+            .vnstat
+            .list_vnstat_interfaces(false)
+            .await
+            .map_err(|e| vnstat_failure(&state, &e, ErrorCode::GetDataFailed))?;
+        return Ok(traffic_json(&state, data, query.unit, query.human, None));
+    }
+
+    let body = state
+        .vnstat
+        .fetch_json(JsonView::InterfaceNames)
+        .await
+        .map_err(|e| vnstat_failure(&state, &e, ErrorCode::GetDataFailed))?;
+
+    Ok(cached_jsend(body))
+}
+
+/// Query parameters accepted by `GET /vnstat/interfaces/batch`.
+#[derive(Debug, Deserialize)]
+struct BatchQuery {
+    /// Comma-separated interface names (or aliases).
+    names: String,
+    /// Comma-separated parts to return per interface.
+    include: Option<String>,
+}
+
+/// Handler for `GET /vnstat/interfaces/batch?names=eth0,wg0`.
+///
+/// Returns the parts listed in `?include=` (default `summary,total`; see
+/// [`BatchPart`]) of several interfaces at once, read from a single cached
+/// snapshot, as an object keyed by the requested names. A name that does
+/// not exist gets an entry with an `error` object instead of failing the
+/// batch.
+///
+/// # Returns
+///
+/// - `200 OK` with the entries wrapped in a JSend envelope (code `10005`
+///   and `X-Data-Age-Seconds` when stale data is served).
+/// - `400 BAD_REQUEST` with code `10008` when `names` is missing, names no
+///   interface or more than [`MAX_BATCH_INTERFACES`], when `include` holds
+///   an unknown part, or when the data would exceed [`MAX_BATCH_BYTES`].
+/// - `502 BAD_GATEWAY` / `504 GATEWAY_TIMEOUT` when vnStat fails.
+async fn get_interfaces_batch(
+    State(state): State<AppState>,
+    JsendQuery(query): JsendQuery<BatchQuery>,
+) -> Result<Response, (StatusCode, Json<JsendResponse<String>>)> {
+    let labels = interface_labels(&query.names);
+    if labels.is_empty() {
+        return Err(invalid_query("`names` must name at least one interface"));
+    }
+    if labels.len() > MAX_BATCH_INTERFACES {
+        return Err(invalid_query(&format!(
+            "`names` must name at most {} interfaces",
+            MAX_BATCH_INTERFACES
+        )));
+    }
+    let include = match &query.include {
+        Some(include) => BatchPart::parse_list(include).map_err(|name| {
+            let expected: Vec<_> = BatchPart::ALL.iter().map(|part| part.name()).collect();
+            invalid_query(&format!(
+                "unknown `include` part `{}`, expected any of `{}`",
+                name,
+                expected.join("`, `")
+            ))
+        })?,
+        None => vec![BatchPart::Summary, BatchPart::Total],
+    };
+
+    let batch = state
+        .vnstat
+        .batch_interfaces(&labels, &include)
+        .await
+        .map_err(|e| vnstat_failure(&state, &e, ErrorCode::GetDataFailed))?;
+
+    let len = serialized_len(&batch.value)
+        .map_err(|e| vnstat_failure(&state, &e.into(), ErrorCode::GetDataFailed))?;
+    if len > MAX_BATCH_BYTES {
+        return Err(invalid_query(&format!(
+            "the batch would exceed {} bytes; request fewer interfaces or parts",
+            MAX_BATCH_BYTES
+        )));
+    }
+
+    Ok(fetched_json(batch))
+}
+
+/// Handler for `GET /vnstat/stale`.
+///
+/// Lists the interfaces vnStat has not updated for longer than
+/// `stale_after_seconds`, e.g. because vnstatd stopped tracking them after
+/// a rename or driver reload, as `/vnstat/interfaces` entries.
+///
+/// # Returns
+///
+/// - `200 OK` with the (possibly empty) list wrapped in a JSend envelope
+///   (code `10005` and `X-Data-Age-Seconds` when stale data is served).
+/// - `502 BAD_GATEWAY` / `504 GATEWAY_TIMEOUT` when vnStat fails.
+async fn get_stale_interfaces(
+    State(state): State<AppState>,
+) -> Result<Response, (StatusCode, Json<JsendResponse<String>>)> {
+    let stale = state
+        .vnstat
+        .list_stale_interfaces()
+        .await
+        .map_err(|e| vnstat_failure(&state, &e, ErrorCode::GetDataFailed))?;
+
+    Ok(fetched_json(stale))
+}
+
+/// Query parameters accepted by `GET /vnstat/{if_name}/image`.
+#[derive(Debug, Default, Deserialize)]
+struct ImageQuery {
+    /// The graph to render.
+    #[serde(default, rename = "type")]
+    kind: ImageKind,
+    /// Approximate image width in pixels.
+    width: Option<u32>,
+}
+
+/// Handler for `GET /vnstat/{if_name}/image`, also served as
+/// `/vnstat/interfaces/{if_name}/image`.
+///
+/// Renders a PNG graph of the interface with vnstati: `?type=summary`
+/// (default), `hours`, `days` or `months`, optionally scaled to about
+/// `?width=` pixels. Images are cached as long as vnStat data. Only served
+/// when `vnstati_executable` is configured.
+///
+/// # Returns
+///
+/// - `200 OK` with the `image/png` body and a short `Cache-Control`.
+/// - `400 BAD_REQUEST` with code `10008` when `type` is unknown or `width`
+///   is out of range.
+/// - `404 NOT_FOUND` with code `10002` when vnstati is not configured, or
+///   with code `10001` when the interface does not exist or is hidden.
+/// - `502 BAD_GATEWAY` / `504 GATEWAY_TIMEOUT` when vnstati fails.
+async fn get_interface_image(
+    InterfaceName(if_name): InterfaceName,
+    State(state): State<AppState>,
+    JsendQuery(query): JsendQuery<ImageQuery>,
+) -> Result<Response, (StatusCode, Json<JsendResponse<String>>)> {
+    if !state.vnstat.images_enabled() {
+        return Err((
+            ErrorCode::EndpointDisabled.status_code(),
+            Json(JsendResponse::fail(ErrorCode::EndpointDisabled)),
+        ));
+    }
+
+    if let Some(width) = query.width
+        && !(MIN_WIDTH..=MAX_WIDTH).contains(&width)
+    {
+        return Err((
+            ErrorCode::InvalidQueryParameter.status_code(),
+            Json(JsendResponse::fail_with_message(
+                ErrorCode::InvalidQueryParameter,
+                format!("`width` must be between {} and {}", MIN_WIDTH, MAX_WIDTH),
+            )),
+        ));
+    }
+
+    let image = state
+        .vnstat
+        .render_interface_image(if_name, query.kind, query.width)
+        .await
+        .map_err(|e| vnstat_failure(&state, &e, ErrorCode::NoSuchInterface))?;
+
+    // Rendered images are cached for the data cache lifetime.
+    let cache_control = format!("public, max-age={}", state.vnstat.cache_ttl().as_secs());
+    Ok((
+        [
+            (header::CONTENT_TYPE, "image/png".to_string()),
+            (header::CACHE_CONTROL, cache_control),
+        ],
+        image,
+    )
+        .into_response())
+}
+
+/// Handler for `POST /vnstat/interfaces`.
+///
+/// Adds the interface named in `{ "name": "wg0" }` to the vnStat database
+/// with `vnstat --add`. Only served with `allow_management` enabled.
+///
+/// # Returns
+///
+/// - `201 CREATED` with `{ "name": "wg0" }` wrapped in a JSend envelope.
+/// - `400 BAD_REQUEST` with code `10015` when the body is malformed or the
+///   name is invalid.
+/// - `404 NOT_FOUND` with code `10002` when management is disabled.
+/// - `502 BAD_GATEWAY` / `504 GATEWAY_TIMEOUT` when vnStat fails, e.g.
+///   because the interface is already in the database.
+async fn add_interface(
+    State(state): State<AppState>,
+    JsendJson(body): JsendJson<NewInterfaceBody>,
+) -> Result<Response, (StatusCode, Json<JsendResponse<String>>)> {
+    if !state.vnstat.management_enabled() {
+        return Err(management_disabled());
+    }
+
+    validate_interface_name(&body.name)
+        .map_err(|e| invalid_argument(ErrorCode::InvalidRequestBody, &e))?;
+
+    state
+        .vnstat
+        .add_interface(&body.name)
+        .await
+        .map_err(|e| vnstat_failure(&state, &e, ErrorCode::GetDataFailed))?;
 
-    Ok(Json(JsendResponse::success_with_data(interfaces)))
+    let change = InterfaceChange {
+        name: body.name,
+        alias: None,
+    };
+    Ok((
+        StatusCode::CREATED,
+        Json(JsendResponse::success_with_data(change)),
+    )
+        .into_response())
+}
+
+/// Handler for `DELETE /vnstat/interfaces/{if_name}`.
+///
+/// Removes the interface, addressed by name or alias, and all its data from
+/// the vnStat database with `vnstat --remove --force`. Only served with
+/// `allow_management` enabled.
+///
+/// # Returns
+///
+/// - `200 OK` with `{ "name": "wg0" }` wrapped in a JSend envelope.
+/// - `404 NOT_FOUND` with code `10001` when the interface does not exist
+///   or is hidden by the interface filter, or with code `10002` when
+///   management is disabled.
+/// - `502 BAD_GATEWAY` / `504 GATEWAY_TIMEOUT` when vnStat fails.
+async fn remove_interface(
+    InterfaceName(if_name): InterfaceName,
+    State(state): State<AppState>,
+) -> Result<Response, (StatusCode, Json<JsendResponse<String>>)> {
+    if !state.vnstat.management_enabled() {
+        return Err(management_disabled());
+    }
+
+    let name = resolve_managed_interface(&state, if_name).await?;
+    state
+        .vnstat
+        .remove_interface(&name)
+        .await
+        .map_err(|e| vnstat_failure(&state, &e, ErrorCode::GetDataFailed))?;
+
+    let change = InterfaceChange { name, alias: None };
+    Ok(Json(JsendResponse::success_with_data(change)).into_response())
+}
+
+/// Handler for `PUT /vnstat/interfaces/{if_name}/alias`.
+///
+/// Sets the alias vnStat stores for the interface from
+/// `{ "alias": "VPN" }` with `vnstat --setalias`; an empty alias removes
+/// it. Aliases configured under `[vnstat.aliases]` still take precedence
+/// in responses. Only served with `allow_management` enabled.
+///
+/// # Returns
+///
+/// - `200 OK` with `{ "name": "wg0", "alias": "VPN" }` wrapped in a JSend
+///   envelope.
+/// - `400 BAD_REQUEST` with code `10015` when the body is malformed or the
+///   alias is invalid.
+/// - `404 NOT_FOUND` with code `10001` when the interface does not exist
+///   or is hidden by the interface filter, or with code `10002` when
+///   management is disabled.
+/// - `502 BAD_GATEWAY` / `504 GATEWAY_TIMEOUT` when vnStat fails.
+async fn set_interface_alias(
+    InterfaceName(if_name): InterfaceName,
+    State(state): State<AppState>,
+    JsendJson(body): JsendJson<AliasBody>,
+) -> Result<Response, (StatusCode, Json<JsendResponse<String>>)> {
+    if !state.vnstat.management_enabled() {
+        return Err(management_disabled());
+    }
+
+    validate_alias(&body.alias).map_err(|e| invalid_argument(ErrorCode::InvalidRequestBody, &e))?;
+
+    let name = resolve_managed_interface(&state, if_name).await?;
+    state
+        .vnstat
+        .set_interface_alias(&name, &body.alias)
+        .await
+        .map_err(|e| vnstat_failure(&state, &e, ErrorCode::GetDataFailed))?;
+
+    let change = InterfaceChange {
+        name,
+        alias: Some(body.alias),
+    };
+    Ok(Json(JsendResponse::success_with_data(change)).into_response())
+}
+
+/// Resolves an interface name or alias to the name vnStat knows it by, for
+/// the management endpoints.
+async fn resolve_managed_interface(
+    state: &AppState,
+    if_name: String,
+) -> Result<String, (StatusCode, Json<JsendResponse<String>>)> {
+    let name = state
+        .vnstat
+        .fetch_interface_stats(if_name)
+        .await
+        .map_err(|e| vnstat_failure(state, &e, ErrorCode::NoSuchInterface))?
+        .value
+        .name
+        .clone();
+
+    validate_interface_name(&name)
+        .map_err(|e| invalid_argument(ErrorCode::InvalidPathParameter, &e))?;
+
+    Ok(name)
+}
+
+/// The `404 NOT_FOUND` JSend response returned when the management
+/// endpoints are disabled.
+fn management_disabled() -> (StatusCode, Json<JsendResponse<String>>) {
+    (
+        ErrorCode::EndpointDisabled.status_code(),
+        Json(JsendResponse::fail(ErrorCode::EndpointDisabled)),
+    )
+}
+
+/// The JSend failure response for a rejected interface name or alias.
+fn invalid_argument(
+    code: ErrorCode,
+    e: &anyhow::Error,
+) -> (StatusCode, Json<JsendResponse<String>>) {
+    (
+        code.status_code(),
+        Json(JsendResponse::fail_with_message(code, e.to_string())),
+    )
 }
 
 // --- Legacy redirect handlers ---
 
-/// Redirect handler for the legacy route `GET /vnstat/interfaces/{if_name}`.
+/// Builds the redirect from a legacy `/interfaces/{if_name}...` request to
+/// the flat route `/{if_name}{suffix}`.
 ///
-/// Issues a permanent redirect (301) to the flat canonical route
-/// `/vnstat/{if_name}`.  The interface name is percent-encoded to
-/// preserve special characters.
-async fn redir_interface_to_flat(Path(if_name): Path<String>) -> impl IntoResponse {
-    let encoded = utf8_percent_encode(&if_name, NON_ALPHANUMERIC).to_string();
-    Redirect::permanent(&format!("/api/v1/vnstat/{}", encoded))
+/// The target is derived from the path the request actually arrived on,
+/// so it stays correct wherever the router is nested, and the prefix
+/// stripped by a reverse proxy is prepended (see
+/// [`AppState::external_base_path`]). The interface name
+/// is percent-encoded and the query string is kept. The redirect is
+/// temporary (307) so that clients do not cache a target that depends on
+/// the deployment.
+fn redirect_to_flat(
+    state: &AppState,
+    headers: &HeaderMap,
+    original: &Uri,
+    uri: &Uri,
+    if_name: &str,
+    suffix: &str,
+) -> Redirect {
+    // `uri` is relative to this router; whatever precedes it in the
+    // original path is where the router is mounted.
+    let mount = original.path().strip_suffix(uri.path()).unwrap_or_default();
+    let encoded = utf8_percent_encode(if_name, NON_ALPHANUMERIC);
+    let query = original
+        .query()
+        .map(|query| format!("?{}", query))
+        .unwrap_or_default();
+
+    Redirect::temporary(&format!(
+        "{}{}/{}{}{}",
+        state.external_base_path(headers),
+        mount,
+        encoded,
+        suffix,
+        query
+    ))
 }
 
-/// Redirect handler for the legacy route `GET /vnstat/interfaces/{if_name}/traffic`.
+/// Returns the `GET` handler of a legacy `/interfaces/{if_name}...` route,
+/// issuing a temporary redirect (307) to the flat route
+/// `/{if_name}{suffix}`, see [`redirect_to_flat`].
+fn redirect_to(suffix: &'static str) -> MethodRouter<AppState> {
+    get(
+        move |InterfaceName(if_name): InterfaceName,
+              State(state): State<AppState>,
+              OriginalUri(original): OriginalUri,
+              uri: Uri,
+              headers: HeaderMap| async move {
+            redirect_to_flat(&state, &headers, &original, &uri, &if_name, suffix)
+        },
+    )
+}
+
+/// Handler for `GET /vnstat/{if_name}/estimate`.
+///
+/// Projects the interface's traffic at the end of the current day and
+/// month from the traffic recorded so far, like the `estimated` line of
+/// vnStat's CLI. A projection is `null` while its period has less than an
+/// hour of data; `reliable` is `false` when vnStat last updated the
+/// interface more than an hour ago.
 ///
-/// Issues a permanent redirect (301) to the flat canonical route
-/// `/vnstat/{if_name}`.  The interface name is percent-encoded to
-/// preserve special characters.
-async fn redir_interface_traffic_to_flat(Path(if_name): Path<String>) -> impl IntoResponse {
-    let encoded = utf8_percent_encode(&if_name, NON_ALPHANUMERIC).to_string();
-    Redirect::permanent(&format!("/api/v1/vnstat/{}", encoded))
+/// # Returns
+///
+/// - `200 OK` with the estimate wrapped in a JSend envelope (code `10005`
+///   and `X-Data-Age-Seconds` when stale data is served).
+/// - `404 NOT_FOUND` with code `10001` when the interface does not exist
+///   or is hidden by the interface filter.
+/// - `502 BAD_GATEWAY` / `504 GATEWAY_TIMEOUT` when vnStat fails.
+async fn get_interface_estimate(
+    InterfaceName(if_name): InterfaceName,
+    State(state): State<AppState>,
+) -> Result<Response, (StatusCode, Json<JsendResponse<String>>)> {
+    let estimate = state
+        .vnstat
+        .estimate_interface_traffic(if_name)
+        .await
+        .map_err(|e| vnstat_failure(&state, &e, ErrorCode::NoSuchInterface))?;
+
+    let meta = interface_meta(&state, estimate.value.updated_timestamp, None);
+    Ok(fetched_json_with_meta(estimate, meta))
+}
+
+/// Handler for `GET /vnstat/{if_name}/updates`.
+///
+/// Opens a Server-Sent Events (SSE) stream that sends the interface's
+/// current data on connect and then pushes fresh data only when vnStat has
+/// written new data, instead of clients polling `/vnstat/{if_name}`. Clients
+/// of the same interface share one watcher polling every
+/// `updates_poll_seconds`.
+///
+/// # Returns
+///
+/// - An SSE response with `Cache-Control`, `Connection`, and
+///   `X-Accel-Buffering` headers set for optimal streaming behaviour.
+/// - `404 NOT_FOUND` with code `10001` when the interface does not exist
+///   or is hidden by the interface filter.
+/// - `502 BAD_GATEWAY` / `504 GATEWAY_TIMEOUT` when vnStat fails.
+/// - `503 SERVICE_UNAVAILABLE` with code `10017` when the SSE client limit
+///   is reached.
+async fn get_interface_updates_sse(
+    InterfaceName(if_name): InterfaceName,
+    State(state): State<AppState>,
+) -> Response {
+    trace!("Update stream for interface `{}` connected.", if_name);
+
+    let stream = match state
+        .vnstat
+        .stream_interface_updates(Arc::clone(&state.task_manager), if_name)
+        .await
+    {
+        Ok(stream) => stream,
+        Err(e) => return vnstat_failure(&state, &e, ErrorCode::NoSuchInterface).into_response(),
+    };
+    let keep_alive = KeepAlive::new()
+        .interval(state.sse.keep_alive_interval())
+        .text(state.sse.keep_alive_text.as_str());
+    let sse = Sse::new(stream).keep_alive(keep_alive);
+
+    sse_with_default_headers(sse)
+}
+
+/// Query parameters accepted by `GET /vnstat/{if_name}/quota`.
+#[derive(Debug, Default, Deserialize)]
+struct QuotaQuery {
+    /// Add readable forms of `limit`, `used` and `remaining`.
+    #[serde(default)]
+    human: bool,
+}
+
+/// Handler for `GET /vnstat/{if_name}/quota`.
+///
+/// Reports the interface's usage in the current billing period against the
+/// limit configured in `[quota]`, with an `ok`/`warning`/`exceeded` status.
+/// With `?human=true`, `limitHuman`, `usedHuman` and `remainingHuman`
+/// strings (e.g. `"1.24 GiB"`) are added.
+///
+/// # Returns
+///
+/// - `200 OK` with the usage wrapped in a JSend envelope (code `10005` and
+///   `X-Data-Age-Seconds` when stale data is served).
+/// - `404 NOT_FOUND` with code `10019` when no quota is configured for the
+///   interface.
+/// - `404 NOT_FOUND` with code `10001` when the interface does not exist
+///   or is hidden by the interface filter.
+/// - `502 BAD_GATEWAY` / `504 GATEWAY_TIMEOUT` when vnStat fails.
+async fn get_interface_quota(
+    InterfaceName(if_name): InterfaceName,
+    State(state): State<AppState>,
+    JsendQuery(query): JsendQuery<QuotaQuery>,
+) -> Result<Response, (StatusCode, Json<JsendResponse<String>>)> {
+    let data = state
+        .vnstat
+        .fetch_interface_stats(if_name)
+        .await
+        .map_err(|e| vnstat_failure(&state, &e, ErrorCode::NoSuchInterface))?;
+
+    let quota = state.quota.load();
+    let Some(limit) = quota.limit(&data.value.name) else {
+        return Err((
+            ErrorCode::NoQuotaConfigured.status_code(),
+            Json(JsendResponse::fail(ErrorCode::NoQuotaConfigured)),
+        ));
+    };
+
+    let now = timestamp::get_in_secs();
+    let meta = interface_meta(&state, data.value.updated.timestamp, None);
+    let usage = data.map(|interface| quota_usage(&interface, limit, &quota, now));
+    Ok(traffic_json(
+        &state,
+        usage,
+        TrafficUnit::Bytes,
+        query.human,
+        meta,
+    ))
+}
+
+/// Query parameters accepted by `GET /vnstat/compare`.
+#[derive(Debug, Deserialize)]
+struct CompareQuery {
+    /// Comma-separated interface names (or aliases).
+    interfaces: String,
+    /// The record series to compare.
+    #[serde(default)]
+    granularity: SeriesGranularity,
+    /// Start of the time range (Unix seconds).
+    from: Option<i64>,
+    /// End of the time range (Unix seconds), inclusive.
+    to: Option<i64>,
+}
+
+/// Handler for `GET /vnstat/compare?interfaces=eth0,wg0`.
+///
+/// Returns the records of `?granularity=` (default `day`) of several
+/// interfaces aligned on their timestamps, with each interface's total over
+/// `?from=`/`?to=` and the first two interfaces set against each other;
+/// see [`compare_interfaces`](crate::service::compare::compare_interfaces).
+/// A name that does not exist gets an error entry in the payload while the
+/// others are still compared.
+///
+/// # Returns
+///
+/// - `200 OK` with the comparison wrapped in a JSend envelope (code `10005`
+///   and `X-Data-Age-Seconds` when stale data is served).
+/// - `400 BAD_REQUEST` with code `10008` when `interfaces` is missing,
+///   names no interface or more than [`MAX_COMPARED_INTERFACES`], or when
+///   `from` is after `to`.
+/// - `502 BAD_GATEWAY` / `504 GATEWAY_TIMEOUT` when vnStat fails.
+async fn get_compare(
+    State(state): State<AppState>,
+    JsendQuery(query): JsendQuery<CompareQuery>,
+) -> Result<Response, (StatusCode, Json<JsendResponse<String>>)> {
+    let labels = interface_labels(&query.interfaces);
+    if labels.is_empty() {
+        return Err(invalid_query(
+            "`interfaces` must name at least one interface",
+        ));
+    }
+    if labels.len() > MAX_COMPARED_INTERFACES {
+        return Err(invalid_query(&format!(
+            "`interfaces` must name at most {} interfaces",
+            MAX_COMPARED_INTERFACES
+        )));
+    }
+    if let (Some(from), Some(to)) = (query.from, query.to)
+        && from > to
+    {
+        return Err(invalid_query("`from` must not be after `to`"));
+    }
+
+    let range = TimeRange {
+        from: query.from,
+        to: query.to,
+    };
+    let comparison = state
+        .vnstat
+        .compare_interface_traffic(&labels, query.granularity, range)
+        .await
+        .map_err(|e| vnstat_failure(&state, &e, ErrorCode::GetDataFailed))?;
+
+    Ok(fetched_json(comparison))
+}
+
+/// Query parameters accepted by `GET /vnstat/{if_name}/week`.
+#[derive(Debug, Default, Deserialize)]
+struct WeekQuery {
+    /// Number of calendar weeks, counting back from the current one.
+    weeks: Option<usize>,
+    /// The day weeks start on.
+    #[serde(default)]
+    week_start: WeekStart,
+    /// Unit of the traffic values.
+    #[serde(default)]
+    unit: TrafficUnit,
+    /// Add readable forms of the traffic values.
+    #[serde(default)]
+    human: bool,
+}
+
+/// Number of weeks returned by `GET /vnstat/{if_name}/week` by default.
+const DEFAULT_WEEKS: usize = 8;
+
+/// Handler for `GET /vnstat/{if_name}/week`.
+///
+/// Sums the interface's day records per week, since vnStat keeps no weekly
+/// series; see [`weekly_traffic`]. `?weeks=` (default 8) sets how many
+/// calendar weeks to return and `?week_start=` the day they start on
+/// (default `monday`, as in ISO 8601). `?unit=` and `?human=true` apply as
+/// for [`get_total`].
+///
+/// # Returns
+///
+/// - `200 OK` with the weeks wrapped in a JSend envelope (code `10005` and
+///   `X-Data-Age-Seconds` when stale data is served).
+/// - `400 BAD_REQUEST` with code `10008` when `weeks` is `0`.
+/// - `404 NOT_FOUND` with code `10001` when the interface does not exist
+///   or is hidden by the interface filter.
+/// - `502 BAD_GATEWAY` / `504 GATEWAY_TIMEOUT` when vnStat fails.
+async fn get_interface_weeks(
+    InterfaceName(if_name): InterfaceName,
+    State(state): State<AppState>,
+    JsendQuery(query): JsendQuery<WeekQuery>,
+) -> Result<Response, (StatusCode, Json<JsendResponse<String>>)> {
+    let weeks = query.weeks.unwrap_or(DEFAULT_WEEKS);
+    if weeks == 0 {
+        return Err(invalid_query("`weeks` must be at least 1"));
+    }
+
+    let data = state
+        .vnstat
+        .fetch_interface_stats(if_name)
+        .await
+        .map_err(|e| vnstat_failure(&state, &e, ErrorCode::NoSuchInterface))?;
+
+    let now = timestamp::get_in_secs();
+    let meta = interface_meta(&state, data.value.updated.timestamp, None);
+    let response = data.map(|interface| WeekResponse {
+        name: interface.name.clone(),
+        week_start: query.week_start,
+        week: weekly_traffic(&interface.traffic.day, query.week_start, weeks, now),
+    });
+    Ok(traffic_json(
+        &state,
+        response,
+        query.unit,
+        query.human,
+        meta,
+    ))
+}
+
+/// Query parameters accepted by `GET /vnstat/{if_name}/delta`.
+#[derive(Debug, Default, Deserialize)]
+struct DeltaQuery {
+    /// Start of the window (Unix seconds).
+    from: Option<i64>,
+    /// End of the window (Unix seconds), exclusive.
+    to: Option<i64>,
+    /// Unit of the traffic values.
+    #[serde(default)]
+    unit: TrafficUnit,
+    /// Add readable forms of the traffic values.
+    #[serde(default)]
+    human: bool,
+}
+
+/// Handler for `GET /vnstat/{if_name}/delta`.
+///
+/// Sums the interface's traffic between `?from=` and `?to=`, from the
+/// finest record series still reaching back to `from`; see
+/// [`interface_delta`]. `?unit=` and `?human=true` apply as for
+/// [`get_total`].
+///
+/// # Returns
+///
+/// - `200 OK` with the sums wrapped in a JSend envelope (code `10005` and
+///   `X-Data-Age-Seconds` when stale data is served).
+/// - `400 BAD_REQUEST` with code `10008` when `from` or `to` is missing or
+///   `from` is not before `to`.
+/// - `404 NOT_FOUND` with code `10001` when the interface does not exist
+///   or is hidden by the interface filter.
+/// - `502 BAD_GATEWAY` / `504 GATEWAY_TIMEOUT` when vnStat fails.
+async fn get_interface_delta(
+    InterfaceName(if_name): InterfaceName,
+    State(state): State<AppState>,
+    JsendQuery(query): JsendQuery<DeltaQuery>,
+) -> Result<Response, (StatusCode, Json<JsendResponse<String>>)> {
+    let (from, to) = delta_window(query.from, query.to).map_err(invalid_query)?;
+
+    let data = state
+        .vnstat
+        .fetch_interface_stats(if_name)
+        .await
+        .map_err(|e| vnstat_failure(&state, &e, ErrorCode::NoSuchInterface))?;
+
+    let meta = interface_meta(&state, data.value.updated.timestamp, None);
+    let delta = data.map(|interface| interface_delta(&interface, from, to));
+    Ok(traffic_json(&state, delta, query.unit, query.human, meta))
+}
+
+/// Query parameters accepted by `GET /vnstat/{if_name}/billing-month`.
+#[derive(Debug, Default, Deserialize)]
+struct BillingMonthQuery {
+    /// Number of billing months, counting back from the current one.
+    months: Option<usize>,
+    /// Unit of the traffic values.
+    #[serde(default)]
+    unit: TrafficUnit,
+    /// Add readable forms of the traffic values.
+    #[serde(default)]
+    human: bool,
+}
+
+/// Number of billing months returned by
+/// `GET /vnstat/{if_name}/billing-month` by default.
+const DEFAULT_BILLING_MONTHS: usize = 12;
+
+/// Handler for `GET /vnstat/{if_name}/billing-month`.
+///
+/// Sums the interface's day records per billing month starting on
+/// `vnstat.month_rotate_day`, for hosts whose vnStat does not set
+/// `MonthRotate` itself; see [`billing_months`]. `?months=` (default 12)
+/// sets how many billing months to return. `?unit=` and `?human=true` apply
+/// as for [`get_total`].
+///
+/// # Returns
+///
+/// - `200 OK` with the billing months wrapped in a JSend envelope (code
+///   `10005` and `X-Data-Age-Seconds` when stale data is served).
+/// - `400 BAD_REQUEST` with code `10008` when `months` is `0`.
+/// - `404 NOT_FOUND` with code `10001` when the interface does not exist
+///   or is hidden by the interface filter.
+/// - `502 BAD_GATEWAY` / `504 GATEWAY_TIMEOUT` when vnStat fails.
+async fn get_interface_billing_months(
+    InterfaceName(if_name): InterfaceName,
+    State(state): State<AppState>,
+    JsendQuery(query): JsendQuery<BillingMonthQuery>,
+) -> Result<Response, (StatusCode, Json<JsendResponse<String>>)> {
+    let months = query.months.unwrap_or(DEFAULT_BILLING_MONTHS);
+    if months == 0 {
+        return Err(invalid_query("`months` must be at least 1"));
+    }
+
+    let data = state
+        .vnstat
+        .fetch_interface_stats(if_name)
+        .await
+        .map_err(|e| vnstat_failure(&state, &e, ErrorCode::NoSuchInterface))?;
+
+    let now = timestamp::get_in_secs();
+    let rotate_day = state.vnstat.month_rotate_day();
+    let meta = interface_meta(&state, data.value.updated.timestamp, None);
+    let response = data.map(|interface| BillingMonthResponse {
+        name: interface.name.clone(),
+        rotate_day,
+        billing_month: billing_months(&interface.traffic.day, rotate_day, months, now),
+    });
+    Ok(traffic_json(
+        &state,
+        response,
+        query.unit,
+        query.human,
+        meta,
+    ))
+}
+
+/// Query parameters accepted by `GET /vnstat/{if_name}/stats`.
+#[derive(Debug, Default, Deserialize)]
+struct StatsQuery {
+    /// The record series to compute the statistics from.
+    #[serde(default)]
+    granularity: Granularity,
+    /// Start of the time range (Unix seconds).
+    from: Option<i64>,
+    /// End of the time range (Unix seconds), inclusive.
+    to: Option<i64>,
+}
+
+/// Handler for `GET /vnstat/{if_name}/stats`.
+///
+/// Reports the minimum, maximum, mean, median and 95th percentile of the
+/// interface's rx and tx rates (bits per second) over the five-minute or
+/// hour records (`?granularity=`) overlapping `?from=`/`?to=`; see
+/// [`interface_stats`].
+///
+/// # Returns
+///
+/// - `200 OK` with the statistics wrapped in a JSend envelope (code `10005`
+///   and `X-Data-Age-Seconds` when stale data is served); the values are
+///   `null` when no record is in the range.
+/// - `400 BAD_REQUEST` with code `10008` when `from` is after `to`.
+/// - `404 NOT_FOUND` with code `10001` when the interface does not exist
+///   or is hidden by the interface filter.
+/// - `502 BAD_GATEWAY` / `504 GATEWAY_TIMEOUT` when vnStat fails.
+async fn get_interface_stats(
+    InterfaceName(if_name): InterfaceName,
+    State(state): State<AppState>,
+    JsendQuery(query): JsendQuery<StatsQuery>,
+) -> Result<Response, (StatusCode, Json<JsendResponse<String>>)> {
+    if let (Some(from), Some(to)) = (query.from, query.to)
+        && from > to
+    {
+        return Err(invalid_query("`from` must not be after `to`"));
+    }
+
+    let data = state
+        .vnstat
+        .fetch_interface_stats(if_name)
+        .await
+        .map_err(|e| vnstat_failure(&state, &e, ErrorCode::NoSuchInterface))?;
+
+    let range = TimeRange {
+        from: query.from,
+        to: query.to,
+    };
+    let meta = interface_meta(&state, data.value.updated.timestamp, None);
+    let stats = data.map(|interface| interface_stats(&interface, query.granularity, range));
+    Ok(fetched_json_with_meta(stats, meta))
 }
 
-/// Redirect handler for the legacy route `GET /vnstat/interfaces/{if_name}/live`.
+/// Query parameters accepted by `GET /vnstat/{if_name}/busiest`.
+#[derive(Debug, Default, Deserialize)]
+struct BusiestQuery {
+    /// The record series to rank.
+    #[serde(default)]
+    granularity: SeriesGranularity,
+    /// The traffic to rank by.
+    #[serde(default)]
+    by: TrafficMetric,
+    /// Number of periods to return.
+    n: Option<usize>,
+    /// Start of the time range (Unix seconds).
+    from: Option<i64>,
+    /// End of the time range (Unix seconds), inclusive.
+    to: Option<i64>,
+}
+
+/// Number of periods returned by `GET /vnstat/{if_name}/busiest` by
+/// default.
+const DEFAULT_BUSIEST: usize = 10;
+
+/// Handler for `GET /vnstat/{if_name}/busiest`.
+///
+/// Returns the `?n=` (default 10) busiest records of `?granularity=`
+/// (default `day`) overlapping `?from=`/`?to=`, ranked by `?by=` (`total`,
+/// `rx` or `tx`); see [`busiest_periods`]. Like `?limit=`, `n` is clamped
+/// into `1..=max_record_limit`, which `meta.limit` then reports.
 ///
-/// Issues a permanent redirect (301) to the flat canonical route
-/// `/vnstat/{if_name}/live`.  The interface name is percent-encoded to
-/// preserve special characters.
-async fn redir_interface_live_to_flat(Path(if_name): Path<String>) -> impl IntoResponse {
-    let encoded = utf8_percent_encode(&if_name, NON_ALPHANUMERIC).to_string();
-    Redirect::permanent(&format!("/api/v1/vnstat/{}/live", encoded))
+/// # Returns
+///
+/// - `200 OK` with the periods wrapped in a JSend envelope (code `10005`
+///   and `X-Data-Age-Seconds` when stale data is served).
+/// - `400 BAD_REQUEST` with code `10008` when `from` is after `to`.
+/// - `404 NOT_FOUND` with code `10001` when the interface does not exist
+///   or is hidden by the interface filter.
+/// - `502 BAD_GATEWAY` / `504 GATEWAY_TIMEOUT` when vnStat fails.
+async fn get_interface_busiest(
+    InterfaceName(if_name): InterfaceName,
+    State(state): State<AppState>,
+    JsendQuery(query): JsendQuery<BusiestQuery>,
+) -> Result<Response, (StatusCode, Json<JsendResponse<String>>)> {
+    if let (Some(from), Some(to)) = (query.from, query.to)
+        && from > to
+    {
+        return Err(invalid_query("`from` must not be after `to`"));
+    }
+    let (n, meta) = clamp_limit(query.n.unwrap_or(DEFAULT_BUSIEST), state.max_record_limit);
+
+    let data = state
+        .vnstat
+        .fetch_interface_stats(if_name)
+        .await
+        .map_err(|e| vnstat_failure(&state, &e, ErrorCode::NoSuchInterface))?;
+
+    let range = TimeRange {
+        from: query.from,
+        to: query.to,
+    };
+    let meta = interface_meta(&state, data.value.updated.timestamp, meta);
+    let busiest =
+        data.map(|interface| busiest_periods(&interface, query.granularity, query.by, n, range));
+    Ok(fetched_json_with_meta(busiest, meta))
 }
 
 /// Handler for `GET /vnstat/{if_name}`.
 ///
-/// Fetches traffic statistics for a single network interface by name.
+/// Fetches traffic statistics for a single network interface by name or
+/// alias (case-insensitive).
+///
+/// The record series can be restricted with `?from=` and `?to=` (Unix
+/// timestamps), limited and ordered with `?limit=` and `?order=`,
+/// downsampled with `?points=`, returned as compact arrays with
+/// `?format=series` and converted with `?unit=`, as for [`get_total`]; see
+/// [`window_interface`]. `?include=` or `?exclude=` selects which traffic
+/// fields are returned, see [`TrafficFields::from_query`].
 ///
 /// # Returns
 ///
-/// - `200 OK` with an [`Interface`] payload wrapped in a JSend envelope.
-/// - `400 BAD_REQUEST` when the interface does not exist or the fetch
-///   fails.
+/// - `200 OK` with an [`Interface`](crate::model::vnstat::Interface)
+///   payload wrapped in a JSend envelope (an
+///   [`InterfaceView`](crate::model::response::InterfaceView) when fields
+///   are selected), or an
+///   [`InterfaceSeries`](crate::model::response::InterfaceSeries) with
+///   `?format=series`; with a `meta` field when `limit` was clamped.
+/// - `400 BAD_REQUEST` with code `10008` when `from` is after `to`,
+///   `points` is `0`, `format`, `order` or `unit` is unknown, `include` or
+///   `exclude` names an unknown field, or both are given.
+/// - `200 OK` with code `10005` and an `X-Data-Age-Seconds` header when
+///   vnStat is failing and older cached data is served instead.
+/// - `502 BAD_GATEWAY` when vnStat fails to run or its output cannot be
+///   parsed.
+/// - `504 GATEWAY_TIMEOUT` when vnStat does not answer in time.
+/// - `404 NOT_FOUND` with code `10001` when the interface does not exist
+///   or is hidden by the interface filter.
+/// - `400 BAD_REQUEST` when the fetch fails otherwise.
 async fn get_interface_data(
-    Path(if_name): Path<String>,
+    InterfaceName(if_name): InterfaceName,
     State(state): State<AppState>,
-) -> Result<Json<JsendResponse<Interface>>, (StatusCode, Json<JsendResponse<String>>)> {
+    JsendQuery(query): JsendQuery<InterfaceQuery>,
+) -> Result<Response, (StatusCode, Json<JsendResponse<String>>)> {
+    let (window, meta) = series_window(
+        query.from,
+        query.to,
+        query.limit,
+        query.order,
+        query.points,
+        state.max_record_limit,
+    )
+    .map_err(invalid_query)?;
+    let fields = TrafficFields::from_query(query.include.as_deref(), query.exclude.as_deref())
+        .map_err(|reason| invalid_query(&reason))?;
     let data = state
         .vnstat
         .fetch_interface_stats(if_name)
         .await
-        .map_err(|e| {
-            info!("err: {}", e);
-            (
-                StatusCode::BAD_REQUEST,
-                Json(JsendResponse::fail(ErrorCode::NoSuchInterface)),
-            )
-        })?;
+        .map_err(|e| vnstat_failure(&state, &e, ErrorCode::NoSuchInterface))?;
+    let meta = interface_meta(&state, data.value.updated.timestamp, meta);
 
-    Ok(Json(JsendResponse::success_with_data(data)))
+    Ok(match (query.format, window.is_whole(), fields) {
+        (TrafficFormat::Objects, true, None) => traffic_json(&state, data, query.unit, false, meta),
+        (TrafficFormat::Objects, true, Some(fields)) => traffic_json(
+            &state,
+            data.map(|interface| fields.view((*interface).clone())),
+            query.unit,
+            false,
+            meta,
+        ),
+        (TrafficFormat::Objects, false, fields) => traffic_json(
+            &state,
+            data.map(|interface| {
+                fields
+                    .unwrap_or_default()
+                    .view(window_interface(&interface, &window))
+            }),
+            query.unit,
+            false,
+            meta,
+        ),
+        (TrafficFormat::Series, true, fields) => fetched_json_with_meta(
+            data.map(|interface| {
+                interface_series(&interface, query.unit, fields.unwrap_or_default())
+            }),
+            meta,
+        ),
+        (TrafficFormat::Series, false, fields) => fetched_json_with_meta(
+            data.map(|interface| {
+                interface_series(
+                    &window_interface(&interface, &window),
+                    query.unit,
+                    fields.unwrap_or_default(),
+                )
+            }),
+            meta,
+        ),
+    })
+}
+
+/// Parses the `Last-Event-ID` header of a reconnecting SSE client.
+///
+/// IDs that are not sequence numbers (e.g. the millisecond timestamps sent
+/// by older versions) are ignored.
+fn last_event_id(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse().ok())
+}
+
+/// Whether the `Accept` header allows a Server-Sent Events response.
+///
+/// It does when the header is missing, lists `text/event-stream`, or lists
+/// `*/*` without `text/html`: browser tabs ask for both `text/html` and
+/// `*/*`, while `curl` and most HTTP libraries only send `*/*`. Media
+/// ranges with `q=0` are ignored.
+fn accepts_event_stream(headers: &HeaderMap) -> bool {
+    let mut values = headers.get_all(header::ACCEPT).iter().peekable();
+    if values.peek().is_none() {
+        return true;
+    }
+
+    let (mut any, mut html) = (false, false);
+    for range in values
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+    {
+        let mut parts = range.split(';');
+        let media_type = parts.next().unwrap_or_default().trim();
+        let refused = parts.any(|param| {
+            param
+                .trim()
+                .strip_prefix("q=")
+                .and_then(|q| q.trim().parse::<f32>().ok())
+                == Some(0.0)
+        });
+        if refused {
+            continue;
+        }
+
+        if media_type.eq_ignore_ascii_case("text/event-stream") {
+            return true;
+        }
+        any |= media_type == "*/*";
+        html |= media_type.eq_ignore_ascii_case("text/html");
+    }
+
+    any && !html
+}
+
+/// Splits a comma-separated `interfaces` query parameter into its names,
+/// dropping blanks and duplicates.
+fn interface_labels(interfaces: &str) -> Vec<String> {
+    let mut labels: Vec<String> = Vec::new();
+    for name in interfaces.split(',').map(str::trim) {
+        if !name.is_empty() && !labels.iter().any(|label| label == name) {
+            labels.push(name.to_string());
+        }
+    }
+    labels
+}
+
+/// Query parameters accepted by `GET /vnstat/live`.
+#[derive(Debug, Deserialize)]
+struct LiveQuery {
+    /// Comma-separated interface names (or aliases).
+    interfaces: String,
+}
+
+/// Handler for `GET /vnstat/live?interfaces=eth0,wg0`.
+///
+/// Opens one Server-Sent Events (SSE) stream carrying the live traffic of
+/// several interfaces. Each event is a JSON object tagged with its
+/// interface, `{"interface": "eth0", "data": {...}}`; an interface that
+/// does not exist (or cannot be streamed) gets a single
+/// `{"interface": "wg0", "error": {"code": ..., "message": "..."}}` event
+/// while the others keep streaming. Keep-alive and heartbeats work as for
+/// [`get_interface_live_sse`]; `Last-Event-ID` replay is not supported.
+///
+/// # Returns
+///
+/// - An SSE response with the default streaming headers.
+/// - `400 BAD_REQUEST` with code `10008` when `interfaces` is missing or
+///   names no interface.
+/// - The error of the first interface (e.g. `404` with code `10001`) when
+///   none of them can be streamed.
+async fn get_live_sse(
+    State(state): State<AppState>,
+    JsendQuery(query): JsendQuery<LiveQuery>,
+) -> Response {
+    let labels = interface_labels(&query.interfaces);
+    if labels.is_empty() {
+        return (
+            ErrorCode::InvalidQueryParameter.status_code(),
+            Json(JsendResponse::<String>::fail_with_message(
+                ErrorCode::InvalidQueryParameter,
+                "`interfaces` must name at least one interface",
+            )),
+        )
+            .into_response();
+    }
+
+    // Resolve aliases to the real names that `vnstat -i` expects.
+    let mut interfaces = Vec::with_capacity(labels.len());
+    for label in labels {
+        let if_name = state
+            .vnstat
+            .fetch_interface_stats(&label)
+            .await
+            .map(|interface| interface.value.name.clone());
+        interfaces.push((label, if_name));
+    }
+
+    trace!(
+        "Multiplexed SSE stream for {} interfaces connected.",
+        interfaces.len()
+    );
+
+    let stream = match state
+        .vnstat
+        .stream_multi_interface_live_stats(
+            Arc::clone(&state.task_manager),
+            interfaces,
+            state.sse.heartbeat_interval(),
+        )
+        .await
+    {
+        Ok(stream) => stream,
+        Err(e) => return vnstat_failure(&state, &e, ErrorCode::NoSuchInterface).into_response(),
+    };
+    let keep_alive = KeepAlive::new()
+        .interval(state.sse.keep_alive_interval())
+        .text(state.sse.keep_alive_text.as_str());
+    let sse = Sse::new(stream).keep_alive(keep_alive);
+
+    sse_with_default_headers(sse)
+}
+
+/// Query parameters accepted by `GET /vnstat/{if_name}/live`.
+#[derive(Debug, Default, Deserialize)]
+pub struct InterfaceLiveQuery {
+    /// What to do when the client falls behind; comment and go on if
+    /// unset.
+    pub lag: Option<LagPolicy>,
+    /// Stream even if the `Accept` header does not ask for
+    /// `text/event-stream`, for debugging.
+    #[serde(default)]
+    pub force: bool,
+    /// What vnStat reports; vnStat's default if unset.
+    pub mode: Option<LiveMode>,
+    /// The unit of the rates; vnStat's configured unit if unset.
+    pub rateunit: Option<RateUnit>,
 }
 
 /// Handler for `GET /vnstat/{if_name}/live`.
 ///
 /// Opens a Server-Sent Events (SSE) stream that pushes live traffic
 /// updates for a single network interface.  The stream is kept alive
-/// with periodic keep-alive pings.
+/// with keep-alive comments and heartbeats configured under
+/// `[server.sse]`. A new client first gets the current sample as an
+/// `event: snapshot` event, if vnStat has printed one; a client
+/// reconnecting with `Last-Event-ID` instead gets the buffered events it
+/// missed. `?lag=` selects the [`LagPolicy`] applied
+/// when the client falls behind; `?mode=` and `?rateunit=` select what
+/// vnStat reports ([`LiveOptions`]), with one vnStat process per
+/// combination.
+///
+/// Failures of the vnStat process after the response has started are sent
+/// as `event: error` events with a `{"code": 10006, "message": "..."}`
+/// payload, which `EventSource` clients can listen for. The stream ends
+/// right after a fatal one (the process could not be started, printed
+/// nothing in time, or kept dying); otherwise it goes on, e.g. with a
+/// restarted process.
 ///
 /// # Returns
 ///
-/// An SSE response with `Cache-Control`, `Connection`, and
-/// `X-Accel-Buffering` headers set for optimal streaming behaviour.
+/// - An SSE response with `Cache-Control`, `Connection`, and
+///   `X-Accel-Buffering` headers set for optimal streaming behaviour.
+/// - `502 BAD_GATEWAY` when vnStat fails to run or its output cannot be
+///   parsed.
+/// - `504 GATEWAY_TIMEOUT` when vnStat does not answer in time.
+/// - `404 NOT_FOUND` with code `10001` when the interface does not exist
+///   or is hidden by the interface filter.
+/// - `503 SERVICE_UNAVAILABLE` with code `10017` when the SSE client or
+///   live process limit is reached.
+/// - `400 BAD_REQUEST` with code `10008` when `lag` is not `skip`,
+///   `disconnect` or `latest`, `mode` is not `traffic` or `packets`, or
+///   `rateunit` is not `bits` or `bytes`.
+/// - `406 NOT_ACCEPTABLE` with code `10025` when the `Accept` header does
+///   not allow an event stream (e.g. a browser tab, which asks for
+///   `text/html`), unless `force=true`. Nothing is started in that case.
 pub async fn get_interface_live_sse(
-    Path(if_name): Path<String>,
+    InterfaceName(if_name): InterfaceName,
     State(state): State<AppState>,
+    JsendQuery(query): JsendQuery<InterfaceLiveQuery>,
+    OriginalUri(original): OriginalUri,
+    headers: HeaderMap,
 ) -> Response {
+    if !query.force && !accepts_event_stream(&headers) {
+        let path = original.path();
+        let data_path = path.strip_suffix("/live").unwrap_or(path);
+        return (
+            ErrorCode::NotAcceptable.status_code(),
+            Json(JsendResponse::<String>::fail_with_message(
+                ErrorCode::NotAcceptable,
+                format!(
+                    "`{}` only serves Server-Sent Events: request it with `Accept: text/event-stream`, \
+                     use `{}` for the current data, or add `force=true` to the query",
+                    path, data_path
+                ),
+            )),
+        )
+            .into_response();
+    }
+
+    // Resolve aliases to the real name that `vnstat -i` expects.
+    let if_name = match state.vnstat.fetch_interface_stats(&if_name).await {
+        Ok(interface) => interface.value.name.clone(),
+        Err(e) => {
+            return vnstat_failure(&state, &e, ErrorCode::NoSuchInterface).into_response();
+        }
+    };
+
     trace!("SSE stream for interface `{}` connected.", if_name);
 
-    let stream = state
+    let stream = match state
         .vnstat
-        .stream_interface_live_stats(state.task_manager, if_name)
-        .await;
-    let sse = Sse::new(stream).keep_alive(KeepAlive::default());
+        .stream_interface_live_stats(
+            Arc::clone(&state.task_manager),
+            if_name,
+            last_event_id(&headers),
+            state.sse.heartbeat_interval(),
+            query.lag.unwrap_or_default(),
+            LiveOptions {
+                mode: query.mode,
+                rate_unit: query.rateunit,
+            },
+        )
+        .await
+    {
+        Ok(stream) => stream,
+        Err(e) => return vnstat_failure(&state, &e, ErrorCode::UnknownError).into_response(),
+    };
+    let keep_alive = KeepAlive::new()
+        .interval(state.sse.keep_alive_interval())
+        .text(state.sse.keep_alive_text.as_str());
+    let sse = Sse::new(stream).keep_alive(keep_alive);
 
     sse_with_default_headers(sse)
 }
+
+/// Query parameters accepted by `GET /vnstat/{if_name}/live/poll`.
+#[derive(Debug, Deserialize)]
+struct LivePollQuery {
+    /// The `seq` of the last line the client has; unset for a first poll.
+    since: Option<u64>,
+    /// Seconds to wait for a new line, at most (and by default)
+    /// `live_poll_max_timeout_seconds`.
+    timeout: Option<u64>,
+}
+
+/// Handler for `GET /vnstat/{if_name}/live/poll?since=<seq>&timeout=25`
+/// (also served as `/vnstat/interfaces/{if_name}/live/poll`).
+///
+/// Long-polling fallback of [`get_interface_live_sse`] for networks whose
+/// proxies buffer or cut event streams. Answers with the live-traffic
+/// lines after `since` as soon as there is at least one, or with an empty
+/// array once `timeout` seconds have passed without one. A first poll
+/// (without `since`) gets the latest line right away if vnStat has printed
+/// one. Polls share the interface's `vnstat -l` process with the streaming
+/// clients.
+///
+/// A client is expected to loop:
+///
+/// 1. poll without `since`;
+/// 2. handle the returned lines and poll again right away with `since` set
+///    to the `seq` of the last one (or the previous `since` after an empty
+///    answer);
+/// 3. on an error, wait a few seconds before polling again.
+///
+/// # Returns
+///
+/// - `200 OK` with an array of `{"seq": ..., "line": "..."}` objects, oldest
+///   first, wrapped in a JSend envelope, with `"meta": {"gap": true}` when
+///   lines after `since` are no longer buffered and were missed.
+/// - `400 BAD_REQUEST` with code `10008` when `since` or `timeout` is not
+///   a non-negative integer.
+/// - `404 NOT_FOUND` with code `10001` when the interface does not exist
+///   or is hidden by the interface filter.
+/// - `502 BAD_GATEWAY` with code `10006` when vnStat fails for good while
+///   the poll waits.
+/// - `503 SERVICE_UNAVAILABLE` with code `10017` when the SSE client or
+///   live process limit is reached.
+async fn get_interface_live_poll(
+    InterfaceName(if_name): InterfaceName,
+    State(state): State<AppState>,
+    JsendQuery(query): JsendQuery<LivePollQuery>,
+) -> Response {
+    // Resolve aliases to the real name that `vnstat -i` expects.
+    let if_name = match state.vnstat.fetch_interface_stats(&if_name).await {
+        Ok(interface) => interface.value.name.clone(),
+        Err(e) => {
+            return vnstat_failure(&state, &e, ErrorCode::NoSuchInterface).into_response();
+        }
+    };
+
+    let timeout = state
+        .vnstat
+        .live_poll_timeout(query.timeout.unwrap_or(u64::MAX));
+    match state
+        .vnstat
+        .poll_interface_live(&state.task_manager, if_name, query.since, timeout)
+        .await
+    {
+        Ok(poll) => {
+            let meta = poll.gap.then(|| ResponseMeta {
+                gap: Some(true),
+                ..ResponseMeta::default()
+            });
+            Json(JsendResponse::success_with_data(poll.lines).with_meta(meta)).into_response()
+        }
+        Err(e) => vnstat_failure(&state, &e, ErrorCode::UnknownError).into_response(),
+    }
+}
+
+/// Handler for `GET /vnstat/{if_name}/live/ws` (also served as
+/// `/vnstat/interfaces/{if_name}/live/ws`).
+///
+/// Upgrades the connection to a WebSocket that pushes live traffic updates
+/// for a single network interface, for clients that handle WebSockets
+/// better than `EventSource`. Each JSON line from `vnstat -l --json` is
+/// sent as a text frame; WebSocket and SSE clients of the same interface
+/// share one vnStat process.
+///
+/// The socket is closed with code `1000` when the stream ends, and with
+/// code `1011` and a `{"error": "..."}` reason when vnStat fails for good;
+/// errors followed by a process restart are not reported.
+///
+/// # Returns
+///
+/// - `101 SWITCHING_PROTOCOLS` when the upgrade succeeds.
+/// - The status reported by axum (e.g. `400`, `426`) with code `10018` when
+///   the request is not a valid WebSocket upgrade.
+/// - `404 NOT_FOUND` with code `10001` when the interface does not exist
+///   or is hidden by the interface filter.
+/// - `503 SERVICE_UNAVAILABLE` with code `10017` when the live process limit
+///   is reached.
+pub async fn get_interface_live_ws(
+    InterfaceName(if_name): InterfaceName,
+    State(state): State<AppState>,
+    ws: Result<WebSocketUpgrade, WebSocketUpgradeRejection>,
+) -> Response {
+    let ws = match ws {
+        Ok(ws) => ws,
+        Err(rejection) => {
+            let body = JsendResponse::<String>::fail_with_message(
+                ErrorCode::WebSocketUpgradeFailed,
+                rejection.body_text(),
+            );
+            return (rejection.status(), Json(body)).into_response();
+        }
+    };
+
+    // Resolve aliases to the real name that `vnstat -i` expects.
+    let if_name = match state.vnstat.fetch_interface_stats(&if_name).await {
+        Ok(interface) => interface.value.name.clone(),
+        Err(e) => {
+            return vnstat_failure(&state, &e, ErrorCode::NoSuchInterface).into_response();
+        }
+    };
+
+    // Subscribe before upgrading so a refused subscription is still
+    // answered with an error status.
+    let (receiver, guard) = match state
+        .vnstat
+        .subscribe_interface_live(&state.task_manager, if_name.clone())
+        .await
+    {
+        Ok(subscription) => subscription,
+        Err(e) => return vnstat_failure(&state, &e, ErrorCode::UnknownError).into_response(),
+    };
+
+    trace!("WebSocket for interface `{}` connected.", if_name);
+
+    ws.on_upgrade(move |socket| forward_task_messages(socket, receiver, guard, if_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    /// Returns headers with one `Accept` header per value.
+    fn accept(values: &[&'static str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append(header::ACCEPT, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn event_stream_clients_get_events() {
+        assert!(accepts_event_stream(&accept(&["text/event-stream"])));
+        assert!(accepts_event_stream(&accept(&[
+            "text/html, text/event-stream;q=0.9"
+        ])));
+    }
+
+    #[test]
+    fn generic_clients_get_events() {
+        assert!(accepts_event_stream(&accept(&[])));
+        assert!(accepts_event_stream(&accept(&["*/*"])));
+        assert!(accepts_event_stream(&accept(&["application/json", "*/*"])));
+    }
+
+    #[test]
+    fn browser_tabs_do_not_get_events() {
+        let browser = "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8";
+        assert!(!accepts_event_stream(&accept(&[browser])));
+        assert!(!accepts_event_stream(&accept(&["application/json"])));
+        assert!(!accepts_event_stream(&accept(&["text/event-stream;q=0"])));
+    }
+}
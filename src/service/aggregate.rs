@@ -0,0 +1,115 @@
+use crate::model::response::{DayTotal, MonthTotal, TotalResponse};
+use crate::model::vnstat::{Date, MonthDate, Timestamped, Total, VnstatData};
+use crate::utils::timestamp;
+use std::collections::BTreeMap;
+
+/// Length of a day record's period, ignoring DST transitions.
+pub(crate) const SECONDS_PER_DAY: i64 = 86_400;
+
+/// An inclusive range of Unix timestamps (seconds); `None` leaves that end
+/// open.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TimeRange {
+    /// Earliest timestamp to include.
+    pub from: Option<i64>,
+    /// Latest timestamp to include.
+    pub to: Option<i64>,
+}
+
+impl TimeRange {
+    /// Returns `true` if the period `[start, end)` overlaps the range.
+    pub fn overlaps(&self, start: i64, end: i64) -> bool {
+        self.from.is_none_or(|from| end > from) && self.to.is_none_or(|to| start <= to)
+    }
+}
+
+/// Sums the traffic of every interface in `data`.
+///
+/// Day and month records are aligned by their calendar date rather than
+/// their raw timestamp, so records of interfaces created mid-period still
+/// line up; a date present on only some interfaces carries the partial sum.
+/// Each aligned record's timestamp is the start of its period. Sums
+/// saturate at `u64::MAX` instead of overflowing.
+///
+/// # Arguments
+///
+/// * `data` - The (already filtered) vnStat data.
+/// * `range` - Only day and month records whose period overlaps this range
+///   are included. `total` always covers the whole lifetime of the
+///   interfaces.
+///
+/// # Returns
+///
+/// The summed totals and the day and month series in chronological order.
+pub fn aggregate_interfaces(data: &VnstatData, range: TimeRange) -> TotalResponse {
+    let mut total = Total::default();
+    let mut days: BTreeMap<(i32, u8, u8), DayTotal> = BTreeMap::new();
+    let mut months: BTreeMap<(i32, u8), MonthTotal> = BTreeMap::new();
+
+    for interface in &data.interfaces {
+        let traffic = &interface.traffic;
+        total.rx = total.rx.saturating_add(traffic.total.rx);
+        total.tx = total.tx.saturating_add(traffic.total.tx);
+
+        for record in &traffic.day {
+            let (month, day) = (record.date.month.unwrap_or(1), record.date.day.unwrap_or(1));
+            let start = record.date_timestamp().unwrap_or(record.timestamp);
+            if !range.overlaps(start, start + SECONDS_PER_DAY) {
+                continue;
+            }
+
+            let entry = days
+                .entry((record.date.year, month, day))
+                .or_insert_with(|| DayTotal {
+                    date: Date {
+                        day: Some(day),
+                        month: Some(month),
+                        year: record.date.year,
+                    },
+                    timestamp: start,
+                    rx: 0,
+                    tx: 0,
+                });
+            entry.rx = entry.rx.saturating_add(record.rx);
+            entry.tx = entry.tx.saturating_add(record.tx);
+        }
+
+        for record in &traffic.month {
+            let start = record.date_timestamp().unwrap_or(record.timestamp);
+            if !range.overlaps(start, month_end(&record.date).unwrap_or(i64::MAX)) {
+                continue;
+            }
+
+            let entry = months
+                .entry((record.date.year, record.date.month))
+                .or_insert_with(|| MonthTotal {
+                    date: MonthDate {
+                        month: record.date.month,
+                        year: record.date.year,
+                    },
+                    timestamp: start,
+                    rx: 0,
+                    tx: 0,
+                });
+            entry.rx = entry.rx.saturating_add(record.rx);
+            entry.tx = entry.tx.saturating_add(record.tx);
+        }
+    }
+
+    TotalResponse {
+        interfaces: data.interfaces.iter().map(|i| i.name.clone()).collect(),
+        total,
+        day: days.into_values().collect(),
+        month: months.into_values().collect(),
+    }
+}
+
+/// Returns the local timestamp of the start of the month after `date`.
+pub(crate) fn month_end(date: &MonthDate) -> Option<i64> {
+    let (year, month) = match date.month {
+        12 => (date.year + 1, 1),
+        month => (date.year, month + 1),
+    };
+
+    timestamp::from_local_date(year, month, 1, 0, 0)
+}
@@ -0,0 +1,134 @@
+use crate::model::response::{BatchEntry, InterfaceEntry};
+use crate::model::vnstat::Interface;
+use serde::Serialize;
+use std::io;
+
+/// Maximum number of interfaces in one batch.
+pub const MAX_BATCH_INTERFACES: usize = 16;
+
+/// Maximum size, in bytes, of the data of a batch response.
+pub const MAX_BATCH_BYTES: usize = 8 * 1024 * 1024;
+
+/// A part of an interface returned by `/vnstat/interfaces/batch`
+/// (`?include=`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchPart {
+    /// The `/vnstat/interfaces` entry.
+    Summary,
+    /// The totals since the interface was created.
+    Total,
+    /// The five-minute records.
+    FiveMinute,
+    /// The hour records.
+    Hour,
+    /// The day records.
+    Day,
+    /// The month records.
+    Month,
+    /// The year records.
+    Year,
+    /// The top days.
+    Top,
+}
+
+impl BatchPart {
+    /// Every part, in the order accepted by `?include=`.
+    pub const ALL: [BatchPart; 8] = [
+        BatchPart::Summary,
+        BatchPart::Total,
+        BatchPart::FiveMinute,
+        BatchPart::Hour,
+        BatchPart::Day,
+        BatchPart::Month,
+        BatchPart::Year,
+        BatchPart::Top,
+    ];
+
+    /// Returns the name of the part, as accepted by `?include=`.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Summary => "summary",
+            Self::Total => "total",
+            Self::FiveMinute => "fiveminute",
+            Self::Hour => "hour",
+            Self::Day => "day",
+            Self::Month => "month",
+            Self::Year => "year",
+            Self::Top => "top",
+        }
+    }
+
+    /// Parses a comma-separated `?include=` list, dropping blanks and
+    /// duplicates.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first name that is not a part.
+    pub fn parse_list(list: &str) -> Result<Vec<BatchPart>, String> {
+        let mut parts = Vec::new();
+        for name in list
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            let part = Self::ALL
+                .into_iter()
+                .find(|part| part.name() == name)
+                .ok_or_else(|| name.to_string())?;
+            if !parts.contains(&part) {
+                parts.push(part);
+            }
+        }
+        Ok(parts)
+    }
+}
+
+/// Builds the batch entry of `interface` with the parts in `include`.
+///
+/// `summary` is the interface's `/vnstat/interfaces` entry, built by the
+/// caller since it depends on the staleness settings.
+pub fn batch_entry(
+    interface: &Interface,
+    include: &[BatchPart],
+    summary: Option<InterfaceEntry>,
+) -> BatchEntry {
+    let traffic = &interface.traffic;
+    let part = |part: BatchPart| include.contains(&part);
+
+    BatchEntry {
+        summary,
+        total: part(BatchPart::Total).then(|| traffic.total.clone()),
+        fiveminute: part(BatchPart::FiveMinute).then(|| traffic.fiveminute.clone()),
+        hour: part(BatchPart::Hour).then(|| traffic.hour.clone()),
+        day: part(BatchPart::Day).then(|| traffic.day.clone()),
+        month: part(BatchPart::Month).then(|| traffic.month.clone()),
+        year: part(BatchPart::Year).then(|| traffic.year.clone()),
+        top: part(BatchPart::Top).then(|| traffic.top.clone()),
+        error: None,
+    }
+}
+
+/// Returns the length of `value` serialised as JSON, without buffering it.
+///
+/// # Errors
+///
+/// Propagates any serialisation error.
+pub fn serialized_len<T: Serialize>(value: &T) -> serde_json::Result<usize> {
+    let mut counter = ByteCounter(0);
+    serde_json::to_writer(&mut counter, value)?;
+    Ok(counter.0)
+}
+
+/// An [`io::Write`] sink counting the bytes written to it.
+struct ByteCounter(usize);
+
+impl io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
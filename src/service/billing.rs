@@ -0,0 +1,118 @@
+use crate::model::response::BillingMonthRecord;
+use crate::model::vnstat::{Date, DayRecord};
+use crate::service::week::{calendar_date, calendar_date_of};
+use crate::utils::timestamp;
+use std::collections::BTreeMap;
+use time::Duration;
+
+/// Returns the first day of the billing month containing `date`, for billing
+/// months starting on `rotate_day` (1–28).
+fn period_of(date: time::Date, rotate_day: u8) -> Option<time::Date> {
+    let start = calendar_date_of(date.year(), date.month() as u8, rotate_day)?;
+    if date >= start {
+        Some(start)
+    } else {
+        shift_months(start, -1)
+    }
+}
+
+/// Moves `start` by `months` calendar months, keeping its day.
+///
+/// Only used with days up to the 28th, which every month has.
+fn shift_months(start: time::Date, months: i64) -> Option<time::Date> {
+    let index = i64::from(start.year()) * 12 + i64::from(start.month() as u8 - 1) + months;
+    let year = i32::try_from(index.div_euclid(12)).ok()?;
+    calendar_date_of(year, index.rem_euclid(12) as u8 + 1, start.day())
+}
+
+/// Groups day records into billing months starting on `rotate_day`, like
+/// vnStat's `MonthRotate` setting.
+///
+/// Days are assigned to billing months by their calendar date, as in
+/// [`weekly_traffic`](crate::service::week::weekly_traffic). A billing
+/// month's traffic is the (saturating) sum of its days, and `days` tells how
+/// many were recorded.
+///
+/// # Arguments
+///
+/// * `days` - The interface's day records, in any order. Records without a
+///   valid date are ignored.
+/// * `rotate_day` - The day of the month billing months start on (1–28).
+/// * `months` - How many billing months to return, counting back from the
+///   current one. Billing months without any record produce no entry.
+/// * `now` - The current Unix time in seconds.
+///
+/// # Returns
+///
+/// The billing months in chronological order. A billing month is `partial`
+/// if it has not ended yet, or if it starts before the oldest day record,
+/// i.e. its first days were already dropped by vnStat's retention.
+pub fn billing_months(
+    days: &[DayRecord],
+    rotate_day: u8,
+    months: usize,
+    now: i64,
+) -> Vec<BillingMonthRecord> {
+    let mut totals: BTreeMap<time::Date, (u64, u64, u8)> = BTreeMap::new();
+    let mut oldest: Option<time::Date> = None;
+    for record in days {
+        let Some(date) = calendar_date(&record.date) else {
+            continue;
+        };
+        let Some(period) = period_of(date, rotate_day) else {
+            continue;
+        };
+        oldest = Some(oldest.map_or(date, |oldest| oldest.min(date)));
+
+        let (rx, tx, count) = totals.entry(period).or_default();
+        *rx = rx.saturating_add(record.rx);
+        *tx = tx.saturating_add(record.tx);
+        *count = count.saturating_add(1);
+    }
+
+    let today = timestamp::to_local_date(now)
+        .and_then(|(year, month, day)| calendar_date_of(year, month, day))
+        .or_else(|| totals.keys().next_back().copied());
+    let Some(current) = today.and_then(|today| period_of(today, rotate_day)) else {
+        return Vec::new();
+    };
+    // Capped so that the month index cannot overflow; far beyond any date.
+    let span = months.saturating_sub(1).min(u32::MAX as usize) as i64;
+    let first = shift_months(current, -span).unwrap_or(time::Date::MIN);
+
+    totals
+        .range(first..)
+        .map(|(&start, &(rx, tx, days))| {
+            let end = shift_months(start, 1).unwrap_or(time::Date::MAX);
+            let last = end.checked_sub(Duration::days(1)).unwrap_or(start);
+            let truncated = oldest.is_some_and(|oldest| start < oldest);
+            let ongoing = today.is_some_and(|today| end > today);
+
+            BillingMonthRecord {
+                start: date_of(start),
+                end: date_of(last),
+                rx,
+                timestamp: timestamp::from_local_date(
+                    start.year(),
+                    start.month() as u8,
+                    start.day(),
+                    0,
+                    0,
+                )
+                .unwrap_or_default(),
+                tx,
+                days,
+                partial: truncated || ongoing,
+            }
+        })
+        .collect()
+}
+
+/// Converts a calendar date into a vnStat day date.
+fn date_of(date: time::Date) -> Date {
+    Date {
+        day: Some(date.day()),
+        month: Some(date.month() as u8),
+        year: date.year(),
+    }
+}
@@ -0,0 +1,84 @@
+use crate::model::response::{BusiestResponse, BusyPeriod};
+use crate::model::vnstat::{Interface, Total};
+use crate::service::aggregate::TimeRange;
+use crate::service::decimate::{SeriesGranularity, SeriesWindow, window_interface};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+
+/// The traffic periods are ranked by (`?by=`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrafficMetric {
+    /// Bytes received and transmitted.
+    #[default]
+    Total,
+    /// Bytes received.
+    Rx,
+    /// Bytes transmitted.
+    Tx,
+}
+
+impl TrafficMetric {
+    /// Every metric.
+    pub const ALL: [TrafficMetric; 3] =
+        [TrafficMetric::Rx, TrafficMetric::Tx, TrafficMetric::Total];
+
+    /// Returns the name of the metric, as serialised.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Total => "total",
+            Self::Rx => "rx",
+            Self::Tx => "tx",
+        }
+    }
+
+    /// Returns the bytes of `traffic` this metric counts.
+    pub fn of(self, traffic: &Total) -> u64 {
+        match self {
+            Self::Total => traffic.rx.saturating_add(traffic.tx),
+            Self::Rx => traffic.rx,
+            Self::Tx => traffic.tx,
+        }
+    }
+}
+
+/// Ranks the records of `granularity` of `interface` by traffic.
+///
+/// Only records whose period overlaps `range` are ranked (see
+/// [`window_interface`]). They are sorted by `by`, busiest first, with ties
+/// broken by the start of the period, latest first, so that the ranking is
+/// deterministic.
+///
+/// # Returns
+///
+/// The `n` busiest periods.
+pub fn busiest_periods(
+    interface: &Interface,
+    granularity: SeriesGranularity,
+    by: TrafficMetric,
+    n: usize,
+    range: TimeRange,
+) -> BusiestResponse {
+    let window = SeriesWindow {
+        range,
+        ..SeriesWindow::default()
+    };
+    let mut samples = granularity.samples(&window_interface(interface, &window).traffic);
+    samples.sort_by_key(|(start, traffic)| Reverse((by.of(traffic), *start)));
+    samples.truncate(n);
+
+    BusiestResponse {
+        name: interface.name.clone(),
+        granularity,
+        by,
+        periods: samples
+            .into_iter()
+            .map(|(timestamp, traffic)| BusyPeriod {
+                timestamp,
+                rx: traffic.rx,
+                tx: traffic.tx,
+                total: traffic.rx.saturating_add(traffic.tx),
+            })
+            .collect(),
+    }
+}
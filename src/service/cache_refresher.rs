@@ -0,0 +1,92 @@
+use crate::service::vnstat_service::VnstatService;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+/// Longest delay between two attempts while vnStat keeps failing.
+const MAX_BACKOFF: Duration = Duration::from_secs(600);
+
+/// Background task keeping the vnStat data cache warm
+/// (`cache.refresh_interval_seconds`).
+///
+/// Refreshes the snapshot every interval, so requests are served from the
+/// cache without running vnStat. While vnStat fails, the delay doubles
+/// after each failure, up to ten minutes, and requests fall back to the
+/// usual on-demand refresh once the cached snapshot expires. Only changes
+/// between healthy and failing are logged above `debug`.
+pub struct CacheRefresher {
+    vnstat: Arc<VnstatService>,
+    interval: Duration,
+    /// Number of consecutive failed refreshes.
+    failures: u32,
+}
+
+impl CacheRefresher {
+    /// Creates a refresher running every `interval`.
+    pub fn new(vnstat: Arc<VnstatService>, interval: Duration) -> Self {
+        Self {
+            vnstat,
+            interval,
+            failures: 0,
+        }
+    }
+
+    /// Refreshes the cache until `shutdown` is cancelled, starting
+    /// immediately. A refresh in progress is abandoned on shutdown.
+    pub async fn run(mut self, shutdown: CancellationToken) {
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = self.refresh() => {}
+            }
+
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = tokio::time::sleep(self.delay()) => {}
+            }
+        }
+
+        debug!("Cache refresher stopped");
+    }
+
+    /// Refreshes the cache once and records the outcome.
+    async fn refresh(&mut self) {
+        match self.vnstat.refresh().await {
+            Ok(()) if self.failures > 0 => {
+                info!(
+                    "vnStat cache refresh recovered after {} failed attempt(s)",
+                    self.failures
+                );
+                self.failures = 0;
+            }
+            Ok(()) => debug!("vnStat cache refreshed"),
+            Err(e) => {
+                self.failures += 1;
+                if self.failures == 1 {
+                    warn!(
+                        "vnStat cache refresh failing, retrying in {}s: {:#}",
+                        self.delay().as_secs(),
+                        e
+                    );
+                } else {
+                    debug!(
+                        "vnStat cache refresh failed {} times, retrying in {}s: {:#}",
+                        self.failures,
+                        self.delay().as_secs(),
+                        e
+                    );
+                }
+            }
+        }
+    }
+
+    /// Returns the wait before the next refresh: the interval, doubled per
+    /// consecutive failure up to [`MAX_BACKOFF`].
+    fn delay(&self) -> Duration {
+        let factor = 1u32 << self.failures.min(16);
+        self.interval
+            .saturating_mul(factor)
+            .min(MAX_BACKOFF.max(self.interval))
+    }
+}
@@ -0,0 +1,241 @@
+use crate::model::admin::{BreakerState, BreakerStatus};
+use crate::service::error::VnstatUnavailable;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Stops running vnStat after repeated failures
+/// (`vnstat.breaker_threshold`), e.g. while its database is corrupt, so
+/// that requests do not each spawn a doomed process.
+///
+/// Closed, every call is allowed. After `threshold` consecutive failures
+/// the breaker opens and refuses calls for `cooldown`; then it is half
+/// open and lets a single call probe vnStat again, refusing the others
+/// until the probe's outcome is recorded. A success closes it, a failure
+/// opens it for another `cooldown`. A `threshold` of zero never opens it.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    inner: Mutex<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    /// When the breaker opened, while it is open or half open.
+    opened_at: Option<Instant>,
+    /// Whether a half-open probe is running.
+    probe_in_flight: bool,
+    consecutive_failures: u32,
+    last_error: Option<String>,
+}
+
+impl CircuitBreaker {
+    /// Creates a closed breaker opening after `threshold` consecutive
+    /// failures for `cooldown`.
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold,
+            cooldown,
+            inner: Mutex::default(),
+        }
+    }
+
+    /// Returns whether vnStat may be run now.
+    ///
+    /// Once the cooldown has elapsed, the first caller gets the probe
+    /// permit; the outcome of its run must be passed back with
+    /// [`record_success`](Self::record_success) or
+    /// [`record_failure`](Self::record_failure). A permit dropped without
+    /// an outcome (e.g. a cancelled request) lets the next caller probe.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`VnstatUnavailable`] while the breaker is open, and while
+    /// another caller is probing.
+    pub fn allow(&self) -> Result<Permit<'_>, VnstatUnavailable> {
+        let mut inner = self.lock();
+        let Some(opened_at) = inner.opened_at else {
+            return Ok(Permit::new(self, false));
+        };
+
+        let elapsed = opened_at.elapsed();
+        if elapsed < self.cooldown || inner.probe_in_flight {
+            return Err(VnstatUnavailable {
+                failures: inner.consecutive_failures,
+                retry_in: self.cooldown.saturating_sub(elapsed),
+                last_error: inner.last_error.clone().unwrap_or_default(),
+            });
+        }
+
+        info!("Circuit breaker half open, probing vnStat");
+        inner.probe_in_flight = true;
+        Ok(Permit::new(self, true))
+    }
+
+    /// Records a successful vnStat run, closing the breaker.
+    pub fn record_success(&self, permit: Permit<'_>) {
+        permit.disarm();
+        let mut inner = self.lock();
+        if inner.opened_at.is_some() {
+            info!(
+                "vnStat recovered after {} failures, closing the circuit breaker",
+                inner.consecutive_failures
+            );
+        }
+        *inner = Inner::default();
+    }
+
+    /// Records a failed vnStat run, opening the breaker once `threshold`
+    /// runs in a row failed, or again if this was a half-open probe.
+    pub fn record_failure(&self, permit: Permit<'_>, error: &anyhow::Error) {
+        permit.disarm();
+        let mut inner = self.lock();
+        inner.consecutive_failures = inner.consecutive_failures.saturating_add(1);
+        inner.last_error = Some(format!("{:#}", error));
+
+        if self.threshold == 0 {
+            return;
+        }
+
+        if inner.opened_at.is_some() {
+            warn!(
+                "vnStat probe failed, keeping the circuit breaker open for {}s",
+                self.cooldown.as_secs()
+            );
+            inner.opened_at = Some(Instant::now());
+            inner.probe_in_flight = false;
+        } else if inner.consecutive_failures >= self.threshold {
+            warn!(
+                "vnStat failed {} times in a row, opening the circuit breaker for {}s: {:#}",
+                inner.consecutive_failures,
+                self.cooldown.as_secs(),
+                error
+            );
+            inner.opened_at = Some(Instant::now());
+        }
+    }
+
+    /// Returns the current state, for `/admin/stats` and `/health`.
+    pub fn status(&self) -> BreakerStatus {
+        let inner = self.lock();
+        let (state, retry_in) = match inner.opened_at {
+            None => (BreakerState::Closed, None),
+            Some(opened_at) if opened_at.elapsed() < self.cooldown => (
+                BreakerState::Open,
+                Some((self.cooldown - opened_at.elapsed()).as_secs()),
+            ),
+            Some(_) => (BreakerState::HalfOpen, None),
+        };
+
+        BreakerStatus {
+            state,
+            consecutive_failures: inner.consecutive_failures,
+            last_error: inner.last_error.clone(),
+            retry_in_seconds: retry_in,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Permission to run vnStat, from [`CircuitBreaker::allow`].
+///
+/// A half-open probe's permit releases the probe when dropped without an
+/// outcome, so that an abandoned probe does not keep the breaker shut.
+#[must_use = "pass the permit back with the outcome of the run"]
+#[derive(Debug)]
+pub struct Permit<'a> {
+    breaker: &'a CircuitBreaker,
+    probe: bool,
+}
+
+impl<'a> Permit<'a> {
+    fn new(breaker: &'a CircuitBreaker, probe: bool) -> Self {
+        Self { breaker, probe }
+    }
+
+    /// Consumes the permit without releasing the probe, whose outcome is
+    /// being recorded.
+    fn disarm(mut self) {
+        self.probe = false;
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if self.probe {
+            self.breaker.lock().probe_in_flight = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fail(breaker: &CircuitBreaker, times: u32) {
+        for _ in 0..times {
+            let permit = breaker.allow().expect("allowed");
+            breaker.record_failure(permit, &anyhow::anyhow!("database is corrupt"));
+        }
+    }
+
+    #[test]
+    fn opens_after_threshold_failures() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(60));
+
+        fail(&breaker, 3);
+
+        let refused = breaker.allow().unwrap_err();
+        assert_eq!(refused.failures, 3);
+        assert_eq!(refused.last_error, "database is corrupt");
+        assert_eq!(breaker.status().state, BreakerState::Open);
+    }
+
+    #[test]
+    fn lets_a_single_probe_through_once_half_open() {
+        let breaker = CircuitBreaker::new(1, Duration::ZERO);
+        fail(&breaker, 1);
+
+        let probe = breaker.allow().expect("probe allowed");
+        assert!(breaker.allow().is_err());
+        assert!(breaker.allow().is_err());
+
+        breaker.record_success(probe);
+        assert_eq!(breaker.status().state, BreakerState::Closed);
+        let first = breaker.allow().expect("closed");
+        let second = breaker.allow().expect("closed");
+        breaker.record_success(first);
+        breaker.record_success(second);
+    }
+
+    #[test]
+    fn failed_probe_reopens_the_breaker() {
+        let breaker = CircuitBreaker::new(1, Duration::from_millis(50));
+        fail(&breaker, 1);
+        std::thread::sleep(Duration::from_millis(60));
+
+        let probe = breaker.allow().expect("probe allowed");
+        breaker.record_failure(probe, &anyhow::anyhow!("still corrupt"));
+
+        assert_eq!(breaker.status().state, BreakerState::Open);
+        assert_eq!(breaker.allow().unwrap_err().last_error, "still corrupt");
+    }
+
+    #[test]
+    fn abandoned_probe_lets_the_next_caller_probe() {
+        let breaker = CircuitBreaker::new(1, Duration::ZERO);
+        fail(&breaker, 1);
+
+        let probe = breaker.allow().expect("probe allowed");
+        assert!(breaker.allow().is_err());
+        drop(probe);
+
+        let probe = breaker.allow().expect("probe released");
+        breaker.record_success(probe);
+        assert_eq!(breaker.status().state, BreakerState::Closed);
+    }
+}
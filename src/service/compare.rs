@@ -0,0 +1,121 @@
+use crate::error_code::ErrorCode;
+use crate::model::response::{
+    CompareRecord, CompareResponse, ComparedInterface, Comparison, TrafficDelta, TrafficRatio,
+};
+use crate::model::vnstat::{Interface, Total};
+use crate::service::aggregate::TimeRange;
+use crate::service::decimate::{SeriesGranularity, SeriesWindow, window_interface};
+use std::collections::BTreeMap;
+
+/// Maximum number of interfaces compared in one request.
+pub const MAX_COMPARED_INTERFACES: usize = 8;
+
+/// Compares the traffic of several interfaces side by side.
+///
+/// The records of `granularity` whose period overlaps `range` (see
+/// [`window_interface`]) are aligned on the start of their period (see
+/// [`SeriesGranularity::samples`]). Each interface's total is the
+/// (saturating) sum of those records.
+///
+/// # Arguments
+///
+/// * `interfaces` - The requested names (or aliases), with the interface
+///   each resolved to, or `None` if it does not exist.
+/// * `granularity` - The record series to compare.
+/// * `range` - The time range to compare.
+///
+/// # Returns
+///
+/// One entry per requested name, carrying either its total or an error,
+/// and the aligned records in chronological order. A record's `traffic`
+/// has one element per requested name, in the same order, which is `null`
+/// where that interface has no record (or was not found). `comparison`
+/// sets the first two names against each other, if both were found.
+pub fn compare_interfaces(
+    interfaces: &[(&str, Option<&Interface>)],
+    granularity: SeriesGranularity,
+    range: TimeRange,
+) -> CompareResponse {
+    let window = SeriesWindow {
+        range,
+        ..SeriesWindow::default()
+    };
+
+    let mut compared = Vec::with_capacity(interfaces.len());
+    let mut totals = Vec::with_capacity(interfaces.len());
+    let mut records: BTreeMap<i64, Vec<Option<Total>>> = BTreeMap::new();
+
+    for (index, (label, interface)) in interfaces.iter().enumerate() {
+        let Some(interface) = interface else {
+            compared.push(ComparedInterface {
+                interface: label.to_string(),
+                name: None,
+                total: None,
+                error: Some(ErrorCode::NoSuchInterface.into()),
+            });
+            totals.push(None);
+            continue;
+        };
+
+        let traffic = window_interface(interface, &window).traffic;
+        let samples = granularity.samples(&traffic);
+
+        let mut total = Total::default();
+        for (start, traffic) in samples {
+            total.rx = total.rx.saturating_add(traffic.rx);
+            total.tx = total.tx.saturating_add(traffic.tx);
+
+            let slot = &mut records
+                .entry(start)
+                .or_insert_with(|| vec![None; interfaces.len()])[index];
+            let sum = slot.get_or_insert_with(Total::default);
+            sum.rx = sum.rx.saturating_add(traffic.rx);
+            sum.tx = sum.tx.saturating_add(traffic.tx);
+        }
+
+        compared.push(ComparedInterface {
+            interface: label.to_string(),
+            name: Some(interface.name.clone()),
+            total: Some(total.clone()),
+            error: None,
+        });
+        totals.push(Some(total));
+    }
+
+    let comparison = match (totals.first(), totals.get(1)) {
+        (Some(Some(first)), Some(Some(second))) => Some(Comparison {
+            first: interfaces[0].0.to_string(),
+            second: interfaces[1].0.to_string(),
+            delta: TrafficDelta {
+                rx: difference(first.rx, second.rx),
+                tx: difference(first.tx, second.tx),
+            },
+            ratio: TrafficRatio {
+                rx: ratio(first.rx, second.rx),
+                tx: ratio(first.tx, second.tx),
+            },
+        }),
+        _ => None,
+    };
+
+    CompareResponse {
+        granularity,
+        interfaces: compared,
+        records: records
+            .into_iter()
+            .map(|(timestamp, traffic)| CompareRecord { timestamp, traffic })
+            .collect(),
+        comparison,
+    }
+}
+
+/// Returns `first - second`, saturating at the bounds of `i64`.
+fn difference(first: u64, second: u64) -> i64 {
+    let difference = i128::from(first) - i128::from(second);
+    difference.clamp(i128::from(i64::MIN), i128::from(i64::MAX)) as i64
+}
+
+/// Returns `first / second`, or `None` if `second` is `0`.
+fn ratio(first: u64, second: u64) -> Option<f64> {
+    (second != 0).then(|| first as f64 / second as f64)
+}
@@ -0,0 +1,386 @@
+use crate::model::response::{DayTotal, MonthTotal};
+use crate::model::vnstat::{
+    DayRecord, FiveMinuteRecord, HourRecord, Interface, MonthRecord, Timestamped, TopRecord, Total,
+    Traffic, YearRecord,
+};
+use crate::service::aggregate::{SECONDS_PER_DAY, TimeRange, month_end};
+use crate::utils::timestamp;
+use serde::{Deserialize, Serialize};
+
+/// A traffic record that can be merged into a bucket: a start timestamp and
+/// received/transmitted byte counts.
+pub trait TrafficSample: Clone {
+    /// Returns the Unix timestamp (seconds) of the start of the record's
+    /// period.
+    fn start(&self) -> i64;
+
+    /// Overwrites the start of the record's period.
+    fn set_start(&mut self, start: i64);
+
+    /// Returns the bytes received and transmitted, in that order.
+    fn traffic(&self) -> (u64, u64);
+
+    /// Overwrites the bytes received and transmitted.
+    fn set_traffic(&mut self, rx: u64, tx: u64);
+}
+
+macro_rules! impl_traffic_sample {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl TrafficSample for $ty {
+                fn start(&self) -> i64 {
+                    self.timestamp
+                }
+
+                fn set_start(&mut self, start: i64) {
+                    self.timestamp = start;
+                }
+
+                fn traffic(&self) -> (u64, u64) {
+                    (self.rx, self.tx)
+                }
+
+                fn set_traffic(&mut self, rx: u64, tx: u64) {
+                    self.rx = rx;
+                    self.tx = tx;
+                }
+            }
+        )*
+    };
+}
+
+impl_traffic_sample!(
+    DayRecord,
+    FiveMinuteRecord,
+    HourRecord,
+    MonthRecord,
+    TopRecord,
+    YearRecord,
+    DayTotal,
+    MonthTotal,
+);
+
+/// Order of the records in a series (`?order=`), by timestamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    /// Oldest first.
+    Asc,
+    /// Newest first.
+    Desc,
+}
+
+/// One of the record series of an interface (`?granularity=`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SeriesGranularity {
+    /// The five-minute records.
+    FiveMinute,
+    /// The hour records.
+    Hour,
+    /// The day records.
+    #[default]
+    Day,
+    /// The month records.
+    Month,
+    /// The year records.
+    Year,
+}
+
+impl SeriesGranularity {
+    /// Returns the start of the period and the traffic of each record of
+    /// this series in `traffic`.
+    ///
+    /// The start is computed from the record's date, so that records of
+    /// interfaces created mid-period line up, and falls back to its
+    /// timestamp.
+    pub fn samples(self, traffic: &Traffic) -> Vec<(i64, Total)> {
+        match self {
+            Self::FiveMinute => samples(&traffic.fiveminute),
+            Self::Hour => samples(&traffic.hour),
+            Self::Day => samples(&traffic.day),
+            Self::Month => samples(&traffic.month),
+            Self::Year => samples(&traffic.year),
+        }
+    }
+}
+
+fn samples<T: TrafficSample + Timestamped>(records: &[T]) -> Vec<(i64, Total)> {
+    records
+        .iter()
+        .map(|record| {
+            let (rx, tx) = record.traffic();
+            let start = record.date_timestamp().unwrap_or(record.start());
+            (start, Total { rx, tx })
+        })
+        .collect()
+}
+
+/// The part of a record series to return: its time range, how many
+/// records, in which order and how far downsampled.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SeriesWindow {
+    /// Only records whose period overlaps this range.
+    pub range: TimeRange,
+    /// The most recent records (the oldest with [`SortOrder::Asc`]) to
+    /// keep of those in the range.
+    pub limit: Option<usize>,
+    /// Order of the returned records; oldest first if `None`.
+    pub order: Option<SortOrder>,
+    /// Maximum number of records after downsampling, see [`decimate`].
+    pub points: Option<usize>,
+}
+
+impl SeriesWindow {
+    /// Returns `true` if the window leaves every series untouched.
+    pub fn is_whole(&self) -> bool {
+        self.range.from.is_none()
+            && self.range.to.is_none()
+            && self.limit.is_none()
+            && self.order.is_none()
+            && self.points.is_none()
+    }
+
+    /// Applies the limit, downsampling and order to `records`, which are
+    /// already restricted to the range.
+    ///
+    /// The records are sorted by timestamp, and `limit` keeps the most
+    /// recent ones, or the oldest ones with [`SortOrder::Asc`]. The rest
+    /// are then [decimated](decimate) and, with [`SortOrder::Desc`],
+    /// reversed.
+    pub fn apply<T: TrafficSample>(&self, mut records: Vec<T>) -> Vec<T> {
+        records.sort_by_key(T::start);
+        if let Some(limit) = self.limit {
+            if self.order == Some(SortOrder::Asc) {
+                records.truncate(limit);
+            } else {
+                records.drain(..records.len().saturating_sub(limit));
+            }
+        }
+
+        let mut records = match self.points {
+            Some(points) => decimate(records, points, self.range),
+            None => records,
+        };
+        if self.order == Some(SortOrder::Desc) {
+            records.reverse();
+        }
+        records
+    }
+}
+
+/// Downsamples `records` to at most `points` records for charting.
+///
+/// The time range is split into `points` buckets of equal length, and the
+/// records falling into a bucket are merged into one: its traffic is the
+/// (saturating) sum of theirs and its timestamp is the start of the bucket.
+/// The other fields, such as the date, are those of the bucket's earliest
+/// record. Empty buckets produce no record.
+///
+/// # Arguments
+///
+/// * `records` - The records to downsample, in any order.
+/// * `points` - The maximum number of records to return.
+/// * `range` - The time range to split. An open end is taken from the
+///   earliest or latest record; records starting before `from` (whose
+///   period still overlaps the range) count towards the first bucket.
+///
+/// # Returns
+///
+/// The merged records in chronological order, or `records` unchanged when
+/// there are no more than `points` of them (or `points` is `0`).
+pub fn decimate<T: TrafficSample>(mut records: Vec<T>, points: usize, range: TimeRange) -> Vec<T> {
+    if points == 0 || records.len() <= points {
+        return records;
+    }
+    records.sort_by_key(T::start);
+
+    let first = range.from.unwrap_or_else(|| records[0].start());
+    let last = range
+        .to
+        .unwrap_or_else(|| records[records.len() - 1].start());
+    let span = (i128::from(last) - i128::from(first) + 1).max(1);
+    let points = points as i128;
+
+    let bucket_of = |start: i64| {
+        let offset = (i128::from(start) - i128::from(first)).max(0);
+        (offset * points / span).min(points - 1)
+    };
+    // The smallest offset mapped to bucket `index` by `bucket_of`.
+    let bucket_start = |index: i128| first + ((index * span + points - 1) / points) as i64;
+
+    let mut buckets: Vec<(i128, T)> = Vec::with_capacity(points as usize);
+    for record in records {
+        let index = bucket_of(record.start());
+        match buckets.last_mut() {
+            Some((last_index, bucket)) if *last_index == index => {
+                let (rx, tx) = bucket.traffic();
+                let (record_rx, record_tx) = record.traffic();
+                bucket.set_traffic(rx.saturating_add(record_rx), tx.saturating_add(record_tx));
+            }
+            _ => {
+                let mut bucket = record;
+                bucket.set_start(bucket_start(index));
+                buckets.push((index, bucket));
+            }
+        }
+    }
+
+    buckets.into_iter().map(|(_, bucket)| bucket).collect()
+}
+
+/// Restricts the record series of `interface` to `window`.
+///
+/// Records whose period overlaps the window's range are kept, as for
+/// [`aggregate_interfaces`](crate::service::aggregate::aggregate_interfaces).
+/// The five-minute, hour, day, month and year series are then limited,
+/// [decimated](decimate) and ordered (see [`SeriesWindow::apply`]); the
+/// `top` days are only filtered, since they are a ranking rather than a
+/// series. `total` is left as is.
+pub fn window_interface(interface: &Interface, window: &SeriesWindow) -> Interface {
+    let range = window.range;
+    let mut interface = interface.clone();
+    let traffic = &mut interface.traffic;
+
+    retain_overlapping(&mut traffic.fiveminute, range, |r| r.timestamp + 300);
+    retain_overlapping(&mut traffic.hour, range, |r| r.timestamp + 3_600);
+    retain_overlapping(&mut traffic.day, range, |r| r.timestamp + SECONDS_PER_DAY);
+    retain_overlapping(&mut traffic.top, range, |r| r.timestamp + SECONDS_PER_DAY);
+    retain_overlapping(&mut traffic.month, range, |r| {
+        month_end(&r.date).unwrap_or(i64::MAX)
+    });
+    retain_overlapping(&mut traffic.year, range, |r| {
+        timestamp::from_local_date(r.date.year + 1, 1, 1, 0, 0).unwrap_or(i64::MAX)
+    });
+
+    traffic.fiveminute = window.apply(std::mem::take(&mut traffic.fiveminute));
+    traffic.hour = window.apply(std::mem::take(&mut traffic.hour));
+    traffic.day = window.apply(std::mem::take(&mut traffic.day));
+    traffic.month = window.apply(std::mem::take(&mut traffic.month));
+    traffic.year = window.apply(std::mem::take(&mut traffic.year));
+
+    interface
+}
+
+/// Drops the records whose period, from their timestamp to `end`, does not
+/// overlap `range`.
+fn retain_overlapping<T: TrafficSample>(
+    records: &mut Vec<T>,
+    range: TimeRange,
+    end: impl Fn(&T) -> i64,
+) {
+    if range.from.is_some() || range.to.is_some() {
+        records.retain(|record| range.overlaps(record.start(), end(record)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A bare record: a start timestamp and byte counts.
+    #[derive(Debug, Clone, PartialEq)]
+    struct Sample {
+        start: i64,
+        rx: u64,
+        tx: u64,
+    }
+
+    impl TrafficSample for Sample {
+        fn start(&self) -> i64 {
+            self.start
+        }
+
+        fn set_start(&mut self, start: i64) {
+            self.start = start;
+        }
+
+        fn traffic(&self) -> (u64, u64) {
+            (self.rx, self.tx)
+        }
+
+        fn set_traffic(&mut self, rx: u64, tx: u64) {
+            self.rx = rx;
+            self.tx = tx;
+        }
+    }
+
+    /// Returns a record of 1 byte received and 2 transmitted at each of
+    /// `starts`.
+    fn samples(starts: &[i64]) -> Vec<Sample> {
+        starts
+            .iter()
+            .map(|&start| Sample {
+                start,
+                rx: 1,
+                tx: 2,
+            })
+            .collect()
+    }
+
+    /// Returns the start and received bytes of each record.
+    fn summary(records: &[Sample]) -> Vec<(i64, u64)> {
+        records.iter().map(|r| (r.start, r.rx)).collect()
+    }
+
+    #[test]
+    fn unevenly_spaced_records_merge_into_their_buckets() {
+        // Buckets of 24.75 seconds over [0, 98]; the two in the gap stay
+        // empty and produce no record.
+        let records = samples(&[98, 0, 2, 1, 97, 3]);
+
+        let decimated = decimate(records, 4, TimeRange::default());
+
+        assert_eq!(summary(&decimated), [(0, 4), (75, 2)]);
+        assert_eq!(decimated[0].tx, 8);
+    }
+
+    #[test]
+    fn the_range_sets_the_buckets() {
+        let records = samples(&[-50, 10, 20, 60, 70, 80]);
+        let range = TimeRange {
+            from: Some(0),
+            to: Some(99),
+        };
+
+        let decimated = decimate(records, 2, range);
+
+        // The record starting before `from` counts towards the first bucket.
+        assert_eq!(summary(&decimated), [(0, 3), (50, 3)]);
+    }
+
+    #[test]
+    fn merged_traffic_saturates() {
+        let mut records = samples(&[0, 1, 2]);
+        records[0].rx = u64::MAX;
+
+        let decimated = decimate(records, 1, TimeRange::default());
+
+        assert_eq!(summary(&decimated), [(0, u64::MAX)]);
+        assert_eq!(decimated[0].tx, 6);
+    }
+
+    #[test]
+    fn fewer_records_than_points_are_returned_unchanged() {
+        for points in [3, 4, 100, 0] {
+            let records = samples(&[20, 0, 10]);
+
+            let decimated = decimate(records.clone(), points, TimeRange::default());
+
+            assert_eq!(decimated, records, "points={}", points);
+        }
+    }
+
+    #[test]
+    fn nothing_to_decimate_gives_nothing() {
+        let range = TimeRange {
+            from: Some(0),
+            to: Some(3_600),
+        };
+
+        for points in [0, 1, 12] {
+            assert!(decimate(Vec::<Sample>::new(), points, range).is_empty());
+            assert!(decimate(Vec::<Sample>::new(), points, TimeRange::default()).is_empty());
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use crate::model::response::DeltaResponse;
+use crate::model::vnstat::{Interface, Total};
+use crate::service::aggregate::{SECONDS_PER_DAY, month_end};
+use crate::service::decimate::{SeriesGranularity, TrafficSample};
+use crate::utils::timestamp;
+
+/// The series tried for a delta, finest first.
+const RESOLUTIONS: [SeriesGranularity; 5] = [
+    SeriesGranularity::FiveMinute,
+    SeriesGranularity::Hour,
+    SeriesGranularity::Day,
+    SeriesGranularity::Month,
+    SeriesGranularity::Year,
+];
+
+/// A record's period `[start, end)` and traffic.
+struct Period {
+    start: i64,
+    end: i64,
+    traffic: Total,
+}
+
+/// Returns the periods of the records of `granularity` of `interface`.
+fn periods(interface: &Interface, granularity: SeriesGranularity) -> Vec<Period> {
+    let traffic = &interface.traffic;
+    match granularity {
+        SeriesGranularity::FiveMinute => periods_of(&traffic.fiveminute, |r| r.timestamp + 300),
+        SeriesGranularity::Hour => periods_of(&traffic.hour, |r| r.timestamp + 3_600),
+        SeriesGranularity::Day => periods_of(&traffic.day, |r| r.timestamp + SECONDS_PER_DAY),
+        SeriesGranularity::Month => {
+            periods_of(&traffic.month, |r| month_end(&r.date).unwrap_or(i64::MAX))
+        }
+        SeriesGranularity::Year => periods_of(&traffic.year, |r| {
+            timestamp::from_local_date(r.date.year + 1, 1, 1, 0, 0).unwrap_or(i64::MAX)
+        }),
+    }
+}
+
+fn periods_of<T: TrafficSample>(records: &[T], end: impl Fn(&T) -> i64) -> Vec<Period> {
+    records
+        .iter()
+        .map(|record| {
+            let (rx, tx) = record.traffic();
+            Period {
+                start: record.start(),
+                end: end(record),
+                traffic: Total { rx, tx },
+            }
+        })
+        .collect()
+}
+
+/// Picks the series a delta over `[from, to)` is summed from.
+///
+/// This is the finest series whose oldest record starts no later than
+/// `from` (or than the creation of the interface, if that is later), i.e.
+/// whose retention still reaches back to the start of the window. If none
+/// does, the series reaching back the furthest is used, the finer one on a
+/// tie; if every series is empty, the five-minute one.
+fn resolution(interface: &Interface, from: i64) -> (SeriesGranularity, Vec<Period>) {
+    let since = from.max(interface.created.timestamp);
+
+    let mut fallback: Option<(i64, SeriesGranularity, Vec<Period>)> = None;
+    for granularity in RESOLUTIONS {
+        let periods = periods(interface, granularity);
+        let Some(oldest) = periods.iter().map(|period| period.start).min() else {
+            continue;
+        };
+        if oldest <= since {
+            return (granularity, periods);
+        }
+        if fallback.as_ref().is_none_or(|(reach, ..)| oldest < *reach) {
+            fallback = Some((oldest, granularity, periods));
+        }
+    }
+
+    fallback.map_or(
+        (SeriesGranularity::FiveMinute, Vec::new()),
+        |(_, granularity, periods)| (granularity, periods),
+    )
+}
+
+/// Checks the window `[from, to)` of a delta request.
+///
+/// # Errors
+///
+/// Returns the reason, for a `400` answer, if a bound is missing or `from`
+/// is not before `to`.
+pub fn delta_window(from: Option<i64>, to: Option<i64>) -> Result<(i64, i64), &'static str> {
+    let (Some(from), Some(to)) = (from, to) else {
+        return Err("`from` and `to` are required");
+    };
+    if from >= to {
+        return Err("`from` must be before `to`");
+    }
+    Ok((from, to))
+}
+
+/// Sums the traffic of `interface` between `from` and `to`.
+///
+/// The records are taken from a single series, picked by how far back its
+/// retention reaches (see [`resolution`]): five-minute records where they
+/// are still kept, otherwise hour, day, month or year records. Every record
+/// whose period overlaps the window `[from, to)` is counted in full, so the
+/// sums cover `coveredFrom` to `coveredTo`, which may extend beyond the
+/// window by up to one period at each end.
+///
+/// # Arguments
+///
+/// * `interface` - The interface to sum.
+/// * `from` - Start of the window (Unix seconds).
+/// * `to` - End of the window (Unix seconds), exclusive; after `from`.
+///
+/// # Returns
+///
+/// The (saturating) sums, the series used, and the share of the window
+/// covered by records, which is below `1` where the window has no data,
+/// e.g. before the interface was created or in the future.
+pub fn interface_delta(interface: &Interface, from: i64, to: i64) -> DeltaResponse {
+    let (resolution, periods) = resolution(interface, from);
+
+    let mut total = Total::default();
+    let mut covered_seconds: i64 = 0;
+    let mut covered_from: Option<i64> = None;
+    let mut covered_to: Option<i64> = None;
+    for period in periods
+        .iter()
+        .filter(|period| period.start < to && period.end > from)
+    {
+        total.rx = total.rx.saturating_add(period.traffic.rx);
+        total.tx = total.tx.saturating_add(period.traffic.tx);
+        covered_seconds = covered_seconds
+            .saturating_add(period.end.min(to).saturating_sub(period.start.max(from)));
+        covered_from = Some(covered_from.map_or(period.start, |start| start.min(period.start)));
+        covered_to = Some(covered_to.map_or(period.end, |end| end.max(period.end)));
+    }
+
+    DeltaResponse {
+        name: interface.name.clone(),
+        from,
+        to,
+        resolution,
+        rx: total.rx,
+        tx: total.tx,
+        coverage: (covered_seconds as f64 / to.saturating_sub(from) as f64).min(1.0),
+        covered_from,
+        covered_to,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Start of the oldest five-minute record, on an hour boundary.
+    const BASE: i64 = 1_740_999_600;
+    const HOUR: i64 = 3_600;
+
+    /// Returns an interface with ten hours of hour records before `BASE`
+    /// and five-minute records from `BASE` on, each moving 1 kB down and
+    /// 100 B up per five minutes, for two hours.
+    fn interface() -> Interface {
+        let time = json!({ "hour": 0, "minute": 0 });
+        let date = json!({ "year": 2025, "month": 3, "day": 3 });
+        let hours: Vec<_> = (-10..2)
+            .map(|i| {
+                json!({
+                    "date": date, "time": time, "timestamp": BASE + i * HOUR,
+                    "rx": 12_000, "tx": 1_200,
+                })
+            })
+            .collect();
+        let fiveminutes: Vec<_> = (0..24)
+            .map(|i| {
+                json!({
+                    "date": date, "time": time, "timestamp": BASE + i * 300,
+                    "rx": 1_000, "tx": 100,
+                })
+            })
+            .collect();
+        serde_json::from_value(json!({
+            "name": "eth0",
+            "created": { "date": { "year": 2025 }, "timestamp": BASE - 10 * HOUR },
+            "updated": { "date": date, "timestamp": BASE + 2 * HOUR },
+            "traffic": { "hour": hours, "fiveminute": fiveminutes },
+        }))
+        .expect("valid interface")
+    }
+
+    #[test]
+    fn uses_five_minute_records_within_their_retention() {
+        let delta = interface_delta(&interface(), BASE + 600, BASE + 1_800);
+
+        assert_eq!(delta.resolution, SeriesGranularity::FiveMinute);
+        assert_eq!((delta.rx, delta.tx), (4_000, 400));
+        assert_eq!(delta.coverage, 1.0);
+        assert_eq!(delta.covered_from, Some(BASE + 600));
+        assert_eq!(delta.covered_to, Some(BASE + 1_800));
+    }
+
+    #[test]
+    fn falls_back_to_hours_across_the_retention_boundary() {
+        let delta = interface_delta(&interface(), BASE - HOUR, BASE + 1_800);
+
+        assert_eq!(delta.resolution, SeriesGranularity::Hour);
+        assert_eq!((delta.rx, delta.tx), (24_000, 2_400));
+        assert_eq!(delta.coverage, 1.0);
+        assert_eq!(delta.covered_from, Some(BASE - HOUR));
+        assert_eq!(delta.covered_to, Some(BASE + HOUR));
+    }
+
+    #[test]
+    fn a_future_window_is_empty() {
+        let delta = interface_delta(&interface(), BASE + 100 * HOUR, BASE + 101 * HOUR);
+
+        assert_eq!((delta.rx, delta.tx), (0, 0));
+        assert_eq!(delta.coverage, 0.0);
+        assert_eq!(delta.covered_from, None);
+        assert_eq!(delta.covered_to, None);
+    }
+
+    #[test]
+    fn rejects_reversed_and_missing_bounds() {
+        assert_eq!(delta_window(Some(10), Some(20)), Ok((10, 20)));
+        assert!(delta_window(Some(20), Some(10)).is_err());
+        assert!(delta_window(Some(10), Some(10)).is_err());
+        assert!(delta_window(None, Some(10)).is_err());
+        assert!(delta_window(Some(10), None).is_err());
+    }
+}
@@ -0,0 +1,128 @@
+use std::fmt;
+use std::time::Duration;
+
+/// Typed failures of the vnStat backend that callers may want to tell apart
+/// from generic fetch errors.
+///
+/// These are wrapped in [`anyhow::Error`] and recovered with
+/// `downcast_ref` where a specific [`ErrorCode`](crate::error_code::ErrorCode)
+/// should be reported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VnstatError {
+    /// vnStat emitted a `jsonversion` this API does not understand.
+    UnsupportedJsonVersion(String),
+    /// A vnStat invocation did not finish within the configured timeout.
+    Timeout(Duration),
+    /// The vnStat process could not be started.
+    SpawnFailed(String),
+    /// vnStat's output is not valid UTF-8 or not valid vnStat JSON.
+    InvalidOutput(String),
+    /// vnStat exited unsuccessfully.
+    CommandFailed {
+        /// The exit code, or `None` if vnStat was killed by a signal.
+        code: Option<i32>,
+        /// Whatever vnStat wrote to stderr, trimmed.
+        stderr: String,
+    },
+}
+
+impl fmt::Display for VnstatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VnstatError::UnsupportedJsonVersion(v) => {
+                write!(f, "unsupported vnStat jsonversion `{}`", v)
+            }
+            VnstatError::Timeout(timeout) => {
+                write!(f, "vnStat did not finish within {}s", timeout.as_secs())
+            }
+            VnstatError::SpawnFailed(reason) => write!(f, "failed to run vnStat: {}", reason),
+            VnstatError::InvalidOutput(reason) => {
+                write!(f, "failed to parse vnStat output: {}", reason)
+            }
+            VnstatError::CommandFailed { code, stderr } => {
+                match code {
+                    Some(code) => write!(f, "vnStat exited with code {}", code)?,
+                    None => write!(f, "vnStat was killed by a signal")?,
+                }
+                if !stderr.is_empty() {
+                    write!(f, ": {}", stderr)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for VnstatError {}
+
+/// An interface was looked up by an alias that several interfaces share.
+///
+/// Wrapped in [`anyhow::Error`] like [`VnstatError`], and reported with
+/// [`ErrorCode::AmbiguousInterface`](crate::error_code::ErrorCode::AmbiguousInterface).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AmbiguousInterface {
+    /// The name the interface was looked up by.
+    pub label: String,
+    /// The real names of the interfaces carrying the alias.
+    pub candidates: Vec<String>,
+}
+
+impl fmt::Display for AmbiguousInterface {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` is the alias of several interfaces: {}",
+            self.label,
+            self.candidates.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for AmbiguousInterface {}
+
+/// vnStat monitors no interface (or the interface filter hides all of
+/// them), e.g. right after installing it.
+///
+/// Wrapped in [`anyhow::Error`] like [`VnstatError`], and reported with
+/// [`ErrorCode::NoInterfacesConfigured`](crate::error_code::ErrorCode::NoInterfacesConfigured).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoInterfaces;
+
+impl fmt::Display for NoInterfaces {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(
+            "vnStat does not monitor any interface yet; add one with `vnstat --add -i <interface>`",
+        )
+    }
+}
+
+impl std::error::Error for NoInterfaces {}
+
+/// vnStat was not run because it failed repeatedly and the circuit breaker
+/// is open (see [`CircuitBreaker`](super::circuit_breaker::CircuitBreaker)).
+///
+/// Wrapped in [`anyhow::Error`] like [`VnstatError`], and reported with
+/// [`ErrorCode::VnstatUnavailable`](crate::error_code::ErrorCode::VnstatUnavailable).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VnstatUnavailable {
+    /// Consecutive failures so far.
+    pub failures: u32,
+    /// Time until vnStat is tried again.
+    pub retry_in: Duration,
+    /// The last failure.
+    pub last_error: String,
+}
+
+impl fmt::Display for VnstatUnavailable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "vnStat failed {} times in a row and is not run again for {}s; last error: {}",
+            self.failures,
+            self.retry_in.as_secs().max(1),
+            self.last_error
+        )
+    }
+}
+
+impl std::error::Error for VnstatUnavailable {}
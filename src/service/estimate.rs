@@ -0,0 +1,94 @@
+use crate::model::response::{Estimate, EstimateResponse};
+use crate::model::vnstat::{Interface, Timestamped};
+use crate::utils::timestamp;
+use time::Month;
+
+/// Minimum amount of data, in seconds, a period needs before it is
+/// extrapolated; earlier projections would be dominated by noise.
+const MIN_ELAPSED_SECONDS: i64 = 3_600;
+
+/// How far the interface's `updated` timestamp may lag behind the current
+/// time before the estimate is flagged as unreliable.
+const MAX_UPDATE_AGE_SECONDS: i64 = 3_600;
+
+/// Projects the traffic of `interface` at the end of the current day and
+/// month, the way vnStat's `estimated` line does.
+///
+/// The traffic recorded so far in a period is extrapolated linearly over
+/// the whole period. For interfaces created during the period, only the
+/// time since creation counts as elapsed.
+///
+/// # Arguments
+///
+/// * `interface` - The interface to project.
+/// * `now` - The current Unix time in seconds.
+///
+/// # Returns
+///
+/// The day and month estimates; each is `None` if there is no record for
+/// the current period or it covers less than an hour. `reliable` is `false`
+/// when vnStat last updated the interface more than an hour ago, since the
+/// recorded traffic then lags behind the elapsed time.
+pub fn estimate_interface(interface: &Interface, now: i64) -> EstimateResponse {
+    let created = interface.created.timestamp;
+
+    let day = interface.traffic.day.last().and_then(|record| {
+        let start = record.date_timestamp().unwrap_or(record.timestamp);
+        let (year, month, day) = next_day(
+            record.date.year,
+            record.date.month.unwrap_or(1),
+            record.date.day.unwrap_or(1),
+        )?;
+        let end = timestamp::from_local_date(year, month, day, 0, 0)?;
+        project(record.rx, record.tx, start.max(created), end, now)
+    });
+
+    let month = interface.traffic.month.last().and_then(|record| {
+        let start = record.date_timestamp().unwrap_or(record.timestamp);
+        let (year, month) = match record.date.month {
+            12 => (record.date.year + 1, 1),
+            month => (record.date.year, month + 1),
+        };
+        let end = timestamp::from_local_date(year, month, 1, 0, 0)?;
+        project(record.rx, record.tx, start.max(created), end, now)
+    });
+
+    EstimateResponse {
+        name: interface.name.clone(),
+        updated_timestamp: interface.updated.timestamp,
+        reliable: now - interface.updated.timestamp <= MAX_UPDATE_AGE_SECONDS,
+        day,
+        month,
+    }
+}
+
+/// Extrapolates `rx`/`tx`, recorded between `start` and `now`, to the end of
+/// the period at `end`.
+///
+/// Returns `None` if `now` is outside the period or less than
+/// [`MIN_ELAPSED_SECONDS`] have elapsed.
+fn project(rx: u64, tx: u64, start: i64, end: i64, now: i64) -> Option<Estimate> {
+    if now >= end || now - start < MIN_ELAPSED_SECONDS {
+        return None;
+    }
+
+    let elapsed = (now - start) as u128;
+    let period = (end - start) as u128;
+    let scale = |bytes: u64| u64::try_from(bytes as u128 * period / elapsed).unwrap_or(u64::MAX);
+    let (rx, tx) = (scale(rx), scale(tx));
+
+    Some(Estimate {
+        period_end: end,
+        rx,
+        tx,
+        total: rx.saturating_add(tx),
+    })
+}
+
+/// Returns the calendar date following `year-month-day`.
+fn next_day(year: i32, month: u8, day: u8) -> Option<(i32, u8, u8)> {
+    let date = time::Date::from_calendar_date(year, Month::try_from(month).ok()?, day).ok()?;
+    let next = date.next_day()?;
+
+    Some((next.year(), next.month() as u8, next.day()))
+}
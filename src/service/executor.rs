@@ -0,0 +1,194 @@
+use crate::config::vnstat::VnstatConfig;
+use crate::service::error::VnstatError;
+use crate::task_handle::TaskSource;
+use crate::utils::process::{ProcessEnv, ProcessPriority, SpawnOptions};
+use anyhow::{Context, Result};
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::process::Stdio;
+use std::time::Duration;
+use tracing::debug;
+
+/// The future returned by [`VnstatExecutor::run`].
+pub type CommandFuture<'a> = Pin<Box<dyn Future<Output = Result<CommandOutput>> + Send + 'a>>;
+
+/// The programs a [`VnstatExecutor`] runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Program {
+    /// `vnstat` itself.
+    Vnstat,
+    /// `vnstati`, vnStat's image output.
+    Vnstati,
+}
+
+/// What a finished command left behind.
+#[derive(Debug, Clone, Default)]
+pub struct CommandOutput {
+    /// The exit code, or `None` if the process was killed by a signal.
+    pub code: Option<i32>,
+    /// Everything written to stdout.
+    pub stdout: Vec<u8>,
+    /// Everything written to stderr.
+    pub stderr: Vec<u8>,
+}
+
+impl CommandOutput {
+    /// Returns `true` if the command exited with code `0`.
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+}
+
+/// Runs vnStat on behalf of [`VnstatService`](super::vnstat_service::VnstatService).
+///
+/// Everything the service learns from vnStat goes through this trait, so an
+/// embedding application can serve the API from another backend, such as
+/// canned fixtures, by passing its own implementation to
+/// [`VnstatService::with_executor`](super::vnstat_service::VnstatService::with_executor).
+pub trait VnstatExecutor: Send + Sync {
+    /// Runs `program` with `args` to completion.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`VnstatError::Timeout`] if the program does not finish
+    /// within `timeout`, or [`VnstatError::SpawnFailed`] if it cannot be
+    /// started. An unsuccessful exit is not an error; callers check
+    /// [`CommandOutput::success`].
+    fn run<'a>(
+        &'a self,
+        program: Program,
+        args: &'a [&'a str],
+        timeout: Duration,
+    ) -> CommandFuture<'a>;
+
+    /// Returns the task producing the output of a long-running
+    /// `vnstat <args>` (e.g. live mode), one broadcast line per stdout
+    /// line.
+    fn live(&self, args: Vec<String>) -> TaskSource;
+
+    /// Returns `true` if `program` can be run.
+    fn is_available(&self, program: Program) -> bool;
+}
+
+/// The default [`VnstatExecutor`], spawning the executables configured in
+/// `[vnstat]`.
+#[derive(Debug, Clone)]
+pub struct CliExecutor {
+    vnstat: String,
+    vnstati: Option<String>,
+    /// Arguments passed before the command's own, see
+    /// [`VnstatConfig::base_args`].
+    base_args: Vec<String>,
+    /// Environment and priority of every spawned process.
+    spawn_options: SpawnOptions,
+}
+
+impl CliExecutor {
+    /// Creates an executor for the `executable` and `vnstati_executable`
+    /// of `config`, passing its `config_file` and `db_dir` to every run.
+    ///
+    /// Processes run with a minimal environment (see
+    /// [`ProcessEnv::minimal`]) unless `inherit_env` is set, and with the
+    /// configured `nice` and `ionice_class`.
+    pub fn new(config: &VnstatConfig) -> Self {
+        let priority = ProcessPriority {
+            nice: config.nice,
+            io_class: config.ionice_class,
+        };
+        if !priority.is_inherited() {
+            debug!("vnStat runs with {}", priority);
+        }
+
+        Self {
+            vnstat: config.executable.clone(),
+            vnstati: config.vnstati_executable.clone(),
+            base_args: config.base_args(),
+            spawn_options: SpawnOptions {
+                env: if config.inherit_env {
+                    ProcessEnv::Inherit
+                } else {
+                    ProcessEnv::minimal(config.keep_tz)
+                },
+                priority,
+            },
+        }
+    }
+
+    /// Describes why spawning failed, pointing at the configured priority
+    /// when setting it may have been refused.
+    fn spawn_error(&self, error: &std::io::Error) -> String {
+        let priority = &self.spawn_options.priority;
+        if error.kind() == std::io::ErrorKind::PermissionDenied && !priority.is_inherited() {
+            format!(
+                "{}; the configured {} may require more privileges",
+                error, priority
+            )
+        } else {
+            error.to_string()
+        }
+    }
+
+    /// Returns the configured path of `program`, if any.
+    fn path(&self, program: Program) -> Option<&str> {
+        match program {
+            Program::Vnstat => Some(&self.vnstat),
+            Program::Vnstati => self.vnstati.as_deref(),
+        }
+    }
+}
+
+impl VnstatExecutor for CliExecutor {
+    fn run<'a>(
+        &'a self,
+        program: Program,
+        args: &'a [&'a str],
+        timeout: Duration,
+    ) -> CommandFuture<'a> {
+        Box::pin(async move {
+            let executable = self.path(program).ok_or_else(|| {
+                VnstatError::SpawnFailed(format!("{:?} is not configured", program))
+            })?;
+
+            let mut command = tokio::process::Command::new(executable);
+            command
+                .args(&self.base_args)
+                .args(args)
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .kill_on_drop(true);
+            self.spawn_options.apply(&mut command);
+
+            let child = command
+                .spawn()
+                .map_err(|e| VnstatError::SpawnFailed(self.spawn_error(&e)))?;
+
+            // Dropping the `wait_with_output` future on timeout drops the
+            // child, which kills it thanks to `kill_on_drop`.
+            let output = match tokio::time::timeout(timeout, child.wait_with_output()).await {
+                Ok(output) => output.context("failed to wait for vnStat")?,
+                Err(_) => return Err(VnstatError::Timeout(timeout).into()),
+            };
+
+            Ok(CommandOutput {
+                code: output.status.code(),
+                stdout: output.stdout,
+                stderr: output.stderr,
+            })
+        })
+    }
+
+    fn live(&self, args: Vec<String>) -> TaskSource {
+        let mut cmd = Vec::with_capacity(self.base_args.len() + args.len() + 1);
+        cmd.push(self.vnstat.clone());
+        cmd.extend(self.base_args.iter().cloned());
+        cmd.extend(args);
+        TaskSource::Command(cmd, self.spawn_options.clone())
+    }
+
+    fn is_available(&self, program: Program) -> bool {
+        self.path(program)
+            .is_some_and(|path| Path::new(path).exists())
+    }
+}
@@ -0,0 +1,191 @@
+use crate::model::vnstat::VnstatData;
+use crate::service::decimate::{SeriesGranularity, TrafficSample};
+use std::fmt::Write;
+use std::sync::Arc;
+
+/// Measurement name of the exported InfluxDB points.
+const MEASUREMENT: &str = "vnstat";
+
+/// One record of one interface, as an InfluxDB point.
+#[derive(Debug, Clone)]
+pub struct InfluxPoint {
+    /// The interface name, already escaped as a tag value.
+    pub interface: Arc<str>,
+    /// Start of the record's period (Unix seconds).
+    pub timestamp: i64,
+    /// Bytes received.
+    pub rx: u64,
+    /// Bytes transmitted.
+    pub tx: u64,
+}
+
+/// The points of one export request.
+#[derive(Debug, Clone, Default)]
+pub struct InfluxExport {
+    /// The points, oldest first.
+    pub points: Vec<InfluxPoint>,
+    /// The newest timestamp among `points`; passing it as `since` resumes
+    /// the export after them.
+    pub next_since: Option<i64>,
+    /// Whether newer records were left out because of the cap.
+    pub truncated: bool,
+}
+
+/// Collects the records of `granularity` newer than `since` of every
+/// interface in `data`, oldest first.
+///
+/// At most `max_points` points are returned. When more records are newer
+/// than `since`, the export stops at a timestamp boundary: the points of
+/// the last timestamp are only returned if all of them fit, so that
+/// resuming with `since` set to [`InfluxExport::next_since`] neither skips
+/// nor repeats a point. A single timestamp with more points than
+/// `max_points` is still returned whole.
+pub fn influx_export(
+    data: &VnstatData,
+    granularity: SeriesGranularity,
+    since: Option<i64>,
+    max_points: usize,
+) -> InfluxExport {
+    let mut points = Vec::new();
+    for interface in &data.interfaces {
+        let name: Arc<str> = Arc::from(escape_tag(&interface.name));
+        let traffic = &interface.traffic;
+        match granularity {
+            SeriesGranularity::FiveMinute => {
+                collect(&mut points, &name, &traffic.fiveminute, since)
+            }
+            SeriesGranularity::Hour => collect(&mut points, &name, &traffic.hour, since),
+            SeriesGranularity::Day => collect(&mut points, &name, &traffic.day, since),
+            SeriesGranularity::Month => collect(&mut points, &name, &traffic.month, since),
+            SeriesGranularity::Year => collect(&mut points, &name, &traffic.year, since),
+        }
+    }
+    points.sort_by(|a, b| (a.timestamp, &a.interface).cmp(&(b.timestamp, &b.interface)));
+
+    let total = points.len();
+    if total > max_points {
+        let boundary = points[max_points].timestamp;
+        let keep = points.partition_point(|point| point.timestamp < boundary);
+        let keep = if keep > 0 {
+            keep
+        } else {
+            points.partition_point(|point| point.timestamp <= boundary)
+        };
+        points.truncate(keep);
+    }
+
+    InfluxExport {
+        next_since: points.last().map(|point| point.timestamp),
+        truncated: points.len() < total,
+        points,
+    }
+}
+
+fn collect<T: TrafficSample>(
+    points: &mut Vec<InfluxPoint>,
+    interface: &Arc<str>,
+    records: &[T],
+    since: Option<i64>,
+) {
+    points.extend(
+        records
+            .iter()
+            .filter(|record| since.is_none_or(|since| record.start() > since))
+            .map(|record| {
+                let (rx, tx) = record.traffic();
+                InfluxPoint {
+                    interface: interface.clone(),
+                    timestamp: record.start(),
+                    rx,
+                    tx,
+                }
+            }),
+    );
+}
+
+/// Appends `point` to `out` in InfluxDB line protocol, e.g.
+/// `vnstat,interface=eth0 rx=123i,tx=456i 1792195200000000000`, with the
+/// timestamp in nanoseconds.
+///
+/// The fields are signed 64-bit integers, so byte counts beyond
+/// `i64::MAX` are capped at it.
+pub fn write_line(out: &mut String, point: &InfluxPoint) {
+    let field = |bytes: u64| i64::try_from(bytes).unwrap_or(i64::MAX);
+    let _ = writeln!(
+        out,
+        "{},interface={} rx={}i,tx={}i {}",
+        MEASUREMENT,
+        point.interface,
+        field(point.rx),
+        field(point.tx),
+        point.timestamp.saturating_mul(1_000_000_000)
+    );
+}
+
+/// Escapes a tag value for the line protocol: backslashes, commas, equals
+/// signs and spaces are preceded by a backslash.
+pub fn escape_tag(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | ',' | '=' | ' ') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses `tests/fixtures/vnstat2.json`, with its interfaces renamed to
+    /// `names`.
+    fn fixture(names: [&str; 2]) -> VnstatData {
+        let json = include_str!("../../tests/fixtures/vnstat2.json");
+        let mut data: VnstatData = serde_json::from_str(json).expect("valid 2.x document");
+        for (interface, name) in data.interfaces.iter_mut().zip(names) {
+            interface.name = name.to_string();
+        }
+        data
+    }
+
+    /// Writes the points of `export` in line protocol.
+    fn lines(export: &InfluxExport) -> String {
+        let mut out = String::new();
+        for point in &export.points {
+            write_line(&mut out, point);
+        }
+        out
+    }
+
+    #[test]
+    fn escapes_special_characters_in_tags() {
+        let cases = [
+            ("eth0", "eth0"),
+            ("my uplink", r"my\ uplink"),
+            ("a,b", r"a\,b"),
+            ("k=v", r"k\=v"),
+            (r"C:\net", r"C:\\net"),
+            (r"tail\", r"tail\\"),
+            (r"\ ,", r"\\\ \,"),
+        ];
+        for (name, escaped) in cases {
+            assert_eq!(escape_tag(name), escaped, "{}", name);
+        }
+    }
+
+    #[test]
+    fn exports_interfaces_with_spaces_and_commas_in_their_names() {
+        let data = fixture(["my uplink", r"wifi,5\GHz"]);
+
+        let export = influx_export(&data, SeriesGranularity::FiveMinute, Some(1741604699), 2);
+        let out = lines(&export);
+
+        assert_eq!(
+            out,
+            "vnstat,interface=my\\ uplink rx=2000i,tx=800i 1741604700000000000\n\
+             vnstat,interface=wifi\\,5\\\\GHz rx=1000i,tx=400i 1741604700000000000\n"
+        );
+    }
+}
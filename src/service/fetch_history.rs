@@ -0,0 +1,49 @@
+use crate::model::admin::{FetchAttempt, FetchKind};
+use crate::utils::timestamp;
+use std::collections::VecDeque;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+/// Number of vnStat runs kept by a [`FetchHistory`].
+pub const FETCH_HISTORY_LEN: usize = 20;
+
+/// The last [`FETCH_HISTORY_LEN`] vnStat runs and their outcome, for
+/// `GET /admin/vnstat-status`, so that failures can be investigated after
+/// the logs are gone.
+///
+/// Appending takes a short lock and drops the oldest entry once full.
+#[derive(Debug, Default)]
+pub struct FetchHistory(Mutex<VecDeque<FetchAttempt>>);
+
+impl FetchHistory {
+    /// Records a run of `kind` that took `duration`, with the size of its
+    /// output if it succeeded.
+    pub fn record<T>(
+        &self,
+        kind: FetchKind,
+        duration: Duration,
+        result: &anyhow::Result<T>,
+        output_bytes: impl FnOnce(&T) -> usize,
+    ) {
+        let attempt = FetchAttempt {
+            timestamp: timestamp::get_in_secs(),
+            kind,
+            duration_ms: duration.as_millis() as u64,
+            success: result.is_ok(),
+            error: result.as_ref().err().map(|e| format!("{:#}", e)),
+            output_bytes: result.as_ref().ok().map(output_bytes),
+        };
+
+        let mut entries = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        if entries.len() == FETCH_HISTORY_LEN {
+            entries.pop_front();
+        }
+        entries.push_back(attempt);
+    }
+
+    /// Returns the recorded runs, newest first.
+    pub fn entries(&self) -> Vec<FetchAttempt> {
+        let entries = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        entries.iter().rev().cloned().collect()
+    }
+}
@@ -0,0 +1,146 @@
+use crate::model::response::{InterfaceView, TrafficView};
+use crate::model::vnstat::Interface;
+
+/// A field of an interface's traffic data, selectable with `?include=` and
+/// `?exclude=`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrafficField {
+    /// The totals since the interface was created.
+    Total,
+    /// The five-minute records.
+    FiveMinute,
+    /// The hour records.
+    Hour,
+    /// The day records.
+    Day,
+    /// The month records.
+    Month,
+    /// The year records.
+    Year,
+    /// The top days.
+    Top,
+}
+
+impl TrafficField {
+    /// Every field, in the order listed in error messages.
+    pub const ALL: [TrafficField; 7] = [
+        TrafficField::Total,
+        TrafficField::FiveMinute,
+        TrafficField::Hour,
+        TrafficField::Day,
+        TrafficField::Month,
+        TrafficField::Year,
+        TrafficField::Top,
+    ];
+
+    /// Returns the name of the field, as serialised.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Total => "total",
+            Self::FiveMinute => "fiveminute",
+            Self::Hour => "hour",
+            Self::Day => "day",
+            Self::Month => "month",
+            Self::Year => "year",
+            Self::Top => "top",
+        }
+    }
+
+    const fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// A set of [`TrafficField`]s to serialise; all of them by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrafficFields(u8);
+
+impl Default for TrafficFields {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl TrafficFields {
+    /// Every field.
+    pub const ALL: TrafficFields = TrafficFields((1 << TrafficField::ALL.len()) - 1);
+
+    /// No field.
+    pub const NONE: TrafficFields = TrafficFields(0);
+
+    /// Returns `true` if `field` is in the set.
+    pub fn contains(self, field: TrafficField) -> bool {
+        self.0 & field.bit() != 0
+    }
+
+    /// Builds the selection of the `?include=` and `?exclude=` query
+    /// parameters, each a comma-separated list of field names.
+    ///
+    /// # Returns
+    ///
+    /// The fields named in `include`, or all but those named in `exclude`;
+    /// `None` if neither is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns the reason to report with code `10008` when both are set or
+    /// a name is not a field.
+    pub fn from_query(
+        include: Option<&str>,
+        exclude: Option<&str>,
+    ) -> Result<Option<TrafficFields>, String> {
+        match (include, exclude) {
+            (Some(_), Some(_)) => Err("`include` and `exclude` cannot be combined".to_string()),
+            (Some(include), None) => Ok(Some(Self::parse("include", include)?)),
+            (None, Some(exclude)) => {
+                let excluded = Self::parse("exclude", exclude)?;
+                Ok(Some(TrafficFields(Self::ALL.0 & !excluded.0)))
+            }
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// Parses a comma-separated list of field names, ignoring blanks.
+    fn parse(parameter: &str, list: &str) -> Result<TrafficFields, String> {
+        let mut fields = Self::NONE;
+        for name in list
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            let Some(field) = TrafficField::ALL.into_iter().find(|f| f.name() == name) else {
+                let expected: Vec<_> = TrafficField::ALL.iter().map(|f| f.name()).collect();
+                return Err(format!(
+                    "unknown `{}` field `{}`, expected any of `{}`",
+                    parameter,
+                    name,
+                    expected.join("`, `")
+                ));
+            };
+            fields.0 |= field.bit();
+        }
+        Ok(fields)
+    }
+
+    /// Returns `interface` with only the traffic fields in this set.
+    pub fn view(self, interface: Interface) -> InterfaceView {
+        let traffic = interface.traffic;
+        let field = |field: TrafficField| self.contains(field);
+
+        InterfaceView {
+            alias: interface.alias,
+            created: interface.created,
+            name: interface.name,
+            traffic: TrafficView {
+                day: field(TrafficField::Day).then_some(traffic.day),
+                fiveminute: field(TrafficField::FiveMinute).then_some(traffic.fiveminute),
+                hour: field(TrafficField::Hour).then_some(traffic.hour),
+                month: field(TrafficField::Month).then_some(traffic.month),
+                top: field(TrafficField::Top).then_some(traffic.top),
+                total: field(TrafficField::Total).then_some(traffic.total),
+                year: field(TrafficField::Year).then_some(traffic.year),
+            },
+            updated: interface.updated,
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use crate::model::grafana::GrafanaSeries;
+use crate::model::vnstat::{Interface, VnstatData};
+use crate::service::aggregate::{SECONDS_PER_DAY, TimeRange};
+use crate::service::busiest::TrafficMetric;
+use crate::service::decimate::{SeriesGranularity, SeriesWindow, window_interface};
+
+/// Data points per series when Grafana does not tell how many its panel
+/// can show.
+pub const DEFAULT_MAX_DATA_POINTS: usize = 1_000;
+
+/// The series charted for Grafana with the length of their records in
+/// seconds, finest first.
+const GRANULARITIES: [(SeriesGranularity, i64); 3] = [
+    (SeriesGranularity::FiveMinute, 300),
+    (SeriesGranularity::Hour, 3_600),
+    (SeriesGranularity::Day, SECONDS_PER_DAY),
+];
+
+/// Lists the series Grafana can query: `<interface>.rx`, `<interface>.tx`
+/// and `<interface>.total` for every interface of `data`.
+///
+/// # Returns
+///
+/// The names containing `query`, every name if it is empty.
+pub fn grafana_targets(data: &VnstatData, query: &str) -> Vec<String> {
+    data.interfaces
+        .iter()
+        .flat_map(|interface| {
+            TrafficMetric::ALL
+                .iter()
+                .map(move |metric| format!("{}.{}", interface.name, metric.name()))
+        })
+        .filter(|target| target.contains(query))
+        .collect()
+}
+
+/// Splits a series name into the interface and the metric.
+///
+/// The metric follows the last `.`, since interface names may contain dots
+/// themselves (e.g. the VLAN `eth0.100`).
+///
+/// # Returns
+///
+/// `None` if the name does not end in `.rx`, `.tx` or `.total`.
+pub fn parse_target(target: &str) -> Option<(&str, TrafficMetric)> {
+    let (interface, metric) = target.rsplit_once('.')?;
+    let metric = TrafficMetric::ALL
+        .into_iter()
+        .find(|m| m.name() == metric)?;
+    Some((interface, metric))
+}
+
+/// Picks the series charting `[from, to]`: the finest one with at most
+/// `max_points` records in the range, or the day records if even the hour
+/// records are too many.
+pub fn grafana_granularity(from: i64, to: i64, max_points: usize) -> SeriesGranularity {
+    let span = to.saturating_sub(from).max(1);
+    GRANULARITIES
+        .into_iter()
+        .find(|&(_, seconds)| (span as u64).div_ceil(seconds as u64) <= max_points as u64)
+        .map_or(SeriesGranularity::Day, |(granularity, _)| granularity)
+}
+
+/// Builds the data points of `metric` for `interface`, from the records of
+/// `granularity` whose period overlaps `range`.
+pub fn grafana_series(
+    target: &str,
+    interface: &Interface,
+    metric: TrafficMetric,
+    granularity: SeriesGranularity,
+    range: TimeRange,
+) -> GrafanaSeries {
+    let window = SeriesWindow {
+        range,
+        ..SeriesWindow::default()
+    };
+    let mut samples = granularity.samples(&window_interface(interface, &window).traffic);
+    samples.sort_by_key(|(start, _)| *start);
+
+    GrafanaSeries {
+        target: target.to_string(),
+        datapoints: samples
+            .into_iter()
+            .map(|(start, traffic)| (metric.of(&traffic), start.saturating_mul(1_000)))
+            .collect(),
+    }
+}
@@ -0,0 +1,84 @@
+use axum::body::Bytes;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Default width of vnstati's images, which `--scale` is relative to.
+pub const BASE_WIDTH: u32 = 500;
+
+/// Smallest and largest `--scale` percentage vnstati accepts.
+const SCALE_RANGE: (u32, u32) = (50, 250);
+
+/// Smallest image width that can be requested, in pixels.
+pub const MIN_WIDTH: u32 = BASE_WIDTH * SCALE_RANGE.0 / 100;
+
+/// Largest image width that can be requested, in pixels.
+pub const MAX_WIDTH: u32 = BASE_WIDTH * SCALE_RANGE.1 / 100;
+
+/// The graphs `vnstati` can render.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageKind {
+    /// Totals with today, yesterday and the current and previous month.
+    #[default]
+    Summary,
+    /// Traffic per hour of the last day.
+    Hours,
+    /// Traffic per day.
+    Days,
+    /// Traffic per month.
+    Months,
+}
+
+impl ImageKind {
+    /// The vnstati option selecting this graph.
+    pub fn flag(self) -> &'static str {
+        match self {
+            ImageKind::Summary => "-s",
+            ImageKind::Hours => "-h",
+            ImageKind::Days => "-d",
+            ImageKind::Months => "-m",
+        }
+    }
+}
+
+/// Returns the `--scale` percentage approximating `width` pixels.
+///
+/// `width` must lie within [`MIN_WIDTH`]..=[`MAX_WIDTH`].
+pub fn scale_for_width(width: u32) -> u32 {
+    (width * 100 / BASE_WIDTH).clamp(SCALE_RANGE.0, SCALE_RANGE.1)
+}
+
+/// Key of a rendered image: real interface name, graph and requested width.
+type ImageKey = (String, ImageKind, Option<u32>);
+
+/// Rendered PNG images, each kept for the cache lifetime passed in.
+#[derive(Default)]
+pub struct ImageCache {
+    images: HashMap<ImageKey, (Bytes, Instant)>,
+}
+
+impl ImageCache {
+    /// Returns the image for `key` if it was rendered less than `ttl` ago.
+    pub fn get(&self, key: &ImageKey, ttl: Duration) -> Option<Bytes> {
+        self.images
+            .get(key)
+            .filter(|(_, rendered_at)| rendered_at.elapsed() < ttl)
+            .map(|(image, _)| image.clone())
+    }
+
+    /// Stores a freshly rendered image, dropping entries older than `ttl`
+    /// so that images of removed interfaces or unused widths do not pile
+    /// up.
+    pub fn insert(&mut self, key: ImageKey, image: Bytes, ttl: Duration) {
+        self.images
+            .retain(|_, (_, rendered_at)| rendered_at.elapsed() < ttl);
+        self.images.insert(key, (image, Instant::now()));
+    }
+
+    /// Drops every cached image.
+    pub fn clear(&mut self) {
+        self.images.clear();
+    }
+}
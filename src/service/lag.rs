@@ -0,0 +1,60 @@
+use crate::task_handle::{Output, TaskMessage};
+use serde::Deserialize;
+use tokio::sync::broadcast::Receiver;
+use tokio::sync::broadcast::error::TryRecvError;
+
+/// What a live stream does when its client falls behind and messages are
+/// dropped from the broadcast buffer (`?lag=`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LagPolicy {
+    /// Send a comment saying messages were dropped and go on.
+    #[default]
+    Skip,
+    /// End the stream with a `lagged` event.
+    Disconnect,
+    /// Drop everything still queued and go on with the newest data line.
+    Latest,
+}
+
+/// Messages left after [`drain_to_latest`].
+#[derive(Default)]
+pub struct Drained {
+    /// The newest data line among the drained messages.
+    pub latest: Option<(u64, Output)>,
+    /// The first message that is not a data line, which stopped the
+    /// draining; it must be handled after `latest`. End-of-stream if the
+    /// channel was closed.
+    pub next: Option<TaskMessage>,
+    /// Number of data lines dropped, not counting `latest` and the lines
+    /// already lost to the lag.
+    pub dropped: u64,
+}
+
+/// Takes every message queued in `receiver` without waiting, keeping only
+/// the newest data line, up to the first message of another kind.
+pub fn drain_to_latest(receiver: &mut Receiver<TaskMessage>) -> Drained {
+    let mut drained = Drained::default();
+
+    loop {
+        match receiver.try_recv() {
+            Ok(TaskMessage::Data(seq, line)) => {
+                if drained.latest.replace((seq, line)).is_some() {
+                    drained.dropped += 1;
+                }
+            }
+            Ok(message) => {
+                drained.next = Some(message);
+                break;
+            }
+            Err(TryRecvError::Lagged(n)) => drained.dropped += n,
+            Err(TryRecvError::Empty) => break,
+            Err(TryRecvError::Closed) => {
+                drained.next = Some(TaskMessage::Eof(None));
+                break;
+            }
+        }
+    }
+
+    drained
+}
@@ -0,0 +1,83 @@
+use serde::Deserialize;
+
+/// What `vnstat -l` reports between two samples (`?mode=`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LiveMode {
+    /// Packets per second (vnStat's live mode `0`).
+    Packets,
+    /// Transfer counters since the stream started (live mode `1`).
+    Traffic,
+}
+
+/// The unit `vnstat -l` reports rates in (`?rateunit=`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RateUnit {
+    /// Bytes per second (`--rateunit 0`).
+    Bytes,
+    /// Bits per second (`--rateunit 1`).
+    Bits,
+}
+
+impl LiveMode {
+    /// Returns the mode argument of `vnstat --live`.
+    pub const fn arg(self) -> u8 {
+        match self {
+            Self::Packets => 0,
+            Self::Traffic => 1,
+        }
+    }
+}
+
+impl RateUnit {
+    /// Returns the argument of `vnstat --rateunit`.
+    pub const fn arg(self) -> u8 {
+        match self {
+            Self::Bytes => 0,
+            Self::Bits => 1,
+        }
+    }
+}
+
+/// Options of a live stream that change what vnStat prints; unset ones
+/// keep vnStat's configured behaviour.
+///
+/// Streams with different options need their own `vnstat -l` process, so
+/// the options are part of the task key (see [`task_key`](Self::task_key)).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LiveOptions {
+    pub mode: Option<LiveMode>,
+    pub rate_unit: Option<RateUnit>,
+}
+
+impl LiveOptions {
+    /// Returns the `vnstat` arguments selecting live mode with these
+    /// options, e.g. `-l 1 --rateunit 0`.
+    pub fn args(&self) -> Vec<String> {
+        let mut args = vec!["-l".to_string()];
+        if let Some(mode) = self.mode {
+            args.push(mode.arg().to_string());
+        }
+        if let Some(unit) = self.rate_unit {
+            args.push("--rateunit".to_string());
+            args.push(unit.arg().to_string());
+        }
+        args
+    }
+
+    /// Returns the key of the live task of `if_name` with these options:
+    /// the interface name alone without options, so that such streams share
+    /// one process with the WebSocket and multi-interface streams, and e.g.
+    /// `eth0:mode=1:unit=0` otherwise.
+    pub fn task_key(&self, if_name: &str) -> String {
+        let mut key = if_name.to_string();
+        if let Some(mode) = self.mode {
+            key.push_str(&format!(":mode={}", mode.arg()));
+        }
+        if let Some(unit) = self.rate_unit {
+            key.push_str(&format!(":unit={}", unit.arg()));
+        }
+        key
+    }
+}
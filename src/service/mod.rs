@@ -1 +1,28 @@
+pub mod aggregate;
+pub mod batch;
+pub mod billing;
+pub mod busiest;
+pub mod cache_refresher;
+pub mod circuit_breaker;
+pub mod compare;
+pub mod decimate;
+pub mod delta;
+pub mod error;
+pub mod estimate;
+pub mod executor;
+pub mod export;
+pub mod fetch_history;
+pub mod fields;
+pub mod grafana;
+pub mod image;
+pub mod lag;
+pub mod live;
+pub mod mqtt_publisher;
+pub mod normalize;
+pub mod quota;
+pub mod quota_notifier;
+pub mod series;
+pub mod stats;
+pub mod updates;
 pub mod vnstat_service;
+pub mod week;
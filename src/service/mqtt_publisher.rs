@@ -0,0 +1,348 @@
+use crate::config::mqtt::MqttConfig;
+use crate::model::response::InterfaceEntry;
+use crate::model::vnstat::Total;
+use crate::service::vnstat_service::VnstatService;
+use rumqttc::{AsyncClient, Event, EventLoop, LastWill, MqttOptions, Outgoing, Packet, QoS};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+/// Capacity of the queue between the publisher and the MQTT connection.
+const REQUEST_CAPACITY: usize = 64;
+
+/// Interval of the MQTT keep-alive pings.
+const KEEP_ALIVE: Duration = Duration::from_secs(30);
+
+/// Delay before reconnecting after the connection to the broker failed.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// How long shutdown waits for the offline status to reach the broker.
+const DISCONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Payload of the status topic while the bridge is connected.
+const ONLINE: &str = "online";
+
+/// Payload of the status topic once the bridge is gone, also sent by the
+/// broker as last will.
+const OFFLINE: &str = "offline";
+
+/// Home Assistant discovery message of one sensor.
+#[derive(Debug, Serialize)]
+struct DiscoverySensor<'a> {
+    name: String,
+    unique_id: String,
+    state_topic: &'a str,
+    value_template: &'static str,
+    unit_of_measurement: &'static str,
+    device_class: &'static str,
+    state_class: &'static str,
+    availability_topic: &'a str,
+    device: DiscoveryDevice<'a>,
+}
+
+/// The Home Assistant device grouping the sensors of one bridge.
+#[derive(Debug, Serialize)]
+struct DiscoveryDevice<'a> {
+    identifiers: [&'a str; 1],
+    name: &'a str,
+    manufacturer: &'static str,
+    sw_version: &'static str,
+}
+
+/// Background task publishing interface summaries to an MQTT broker
+/// (`[integrations.mqtt]`).
+///
+/// Every `publish_interval_seconds`, and right after each (re)connect, the
+/// [`InterfaceEntry`] of every interface is published to
+/// `<prefix>/<interface>/summary` and its total counters to
+/// `<prefix>/<interface>/total`, from the shared vnStat data cache.
+/// `<prefix>/status` holds `online` while the bridge is connected and
+/// `offline` otherwise, through the broker's last will if the connection
+/// drops. With Home Assistant discovery, each interface's counters are
+/// announced as sensors once per connection.
+///
+/// Nothing is published while the broker is unreachable; the connection is
+/// retried every five seconds.
+pub struct MqttPublisher {
+    vnstat: Arc<VnstatService>,
+    config: MqttConfig,
+    options: MqttOptions,
+    qos: QoS,
+    /// Interfaces announced to Home Assistant on the current connection.
+    announced: HashSet<String>,
+}
+
+impl MqttPublisher {
+    /// Creates a publisher for the broker and topics in `config`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the broker URL is invalid.
+    pub fn new(vnstat: Arc<VnstatService>, config: MqttConfig) -> anyhow::Result<Self> {
+        let (host, port) = config.broker_address()?;
+        let qos = match config.qos {
+            0 => QoS::AtMostOnce,
+            1 => QoS::AtLeastOnce,
+            _ => QoS::ExactlyOnce,
+        };
+
+        let mut options = MqttOptions::new(&config.client_id, host, port);
+        options
+            .set_keep_alive(KEEP_ALIVE)
+            .set_last_will(LastWill::new(status_topic(&config), OFFLINE, qos, true));
+        if let Some(username) = &config.username {
+            options.set_credentials(username, config.password.clone().unwrap_or_default());
+        }
+
+        Ok(Self {
+            vnstat,
+            config,
+            options,
+            qos,
+            announced: HashSet::new(),
+        })
+    }
+
+    /// Publishes until `shutdown` is cancelled, then marks the bridge
+    /// offline and disconnects, giving up after [`DISCONNECT_TIMEOUT`].
+    pub async fn run(mut self, shutdown: CancellationToken) {
+        let (client, events) = AsyncClient::new(self.options.clone(), REQUEST_CAPACITY);
+        let (connected_tx, mut connected) = watch::channel(false);
+        let stop = CancellationToken::new();
+        let mut connection = tokio::spawn(drive(
+            events,
+            self.config.broker.clone(),
+            connected_tx,
+            stop.clone(),
+        ));
+
+        let mut interval = tokio::time::interval(self.config.publish_interval());
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                Ok(()) = connected.changed() => {
+                    if !*connected.borrow_and_update() {
+                        continue;
+                    }
+                    self.announced.clear();
+                    self.send(&client, status_topic(&self.config), ONLINE, true)
+                        .await;
+                }
+                _ = interval.tick() => {
+                    if !*connected.borrow() {
+                        continue;
+                    }
+                }
+            }
+
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = self.publish(&client) => {}
+            }
+        }
+
+        let farewell = async {
+            if *connected.borrow() {
+                self.send(&client, status_topic(&self.config), OFFLINE, true)
+                    .await;
+                let _ = client.disconnect().await;
+            } else {
+                stop.cancel();
+            }
+            let _ = (&mut connection).await;
+        };
+        if tokio::time::timeout(DISCONNECT_TIMEOUT, farewell)
+            .await
+            .is_err()
+        {
+            stop.cancel();
+            let _ = connection.await;
+        }
+
+        debug!("MQTT publisher stopped");
+    }
+
+    /// Publishes the summary and totals of every interface once, announcing
+    /// interfaces new to Home Assistant first.
+    async fn publish(&mut self, client: &AsyncClient) {
+        let summaries = match self.vnstat.interface_summaries().await {
+            Ok(summaries) => summaries.value,
+            Err(e) => {
+                warn!(
+                    "MQTT publication skipped, fetching vnStat data failed: {:#}",
+                    e
+                );
+                return;
+            }
+        };
+
+        for entry in &summaries {
+            let topic = interface_topic(&self.config, &entry.name);
+
+            if self.config.home_assistant_discovery && !self.announced.contains(&entry.name) {
+                self.announce(client, entry, &topic).await;
+                self.announced.insert(entry.name.clone());
+            }
+
+            let total = Total {
+                rx: entry.total_rx,
+                tx: entry.total_tx,
+            };
+            self.send_json(client, format!("{}/summary", topic), entry)
+                .await;
+            self.send_json(client, format!("{}/total", topic), &total)
+                .await;
+        }
+    }
+
+    /// Publishes the Home Assistant discovery messages of the received and
+    /// transmitted counters of `entry`, read from `<topic>/total`.
+    async fn announce(&self, client: &AsyncClient, entry: &InterfaceEntry, topic: &str) {
+        let node_id = object_id(&self.config.client_id);
+        let label = if entry.alias.is_empty() {
+            &entry.name
+        } else {
+            &entry.alias
+        };
+        let state_topic = format!("{}/total", topic);
+        let availability_topic = status_topic(&self.config);
+
+        for (field, direction, value_template) in [
+            ("rx", "received", "{{ value_json.rx }}"),
+            ("tx", "transmitted", "{{ value_json.tx }}"),
+        ] {
+            let object_id = format!("{}_{}", object_id(&entry.name), field);
+            let sensor = DiscoverySensor {
+                name: format!("{} {}", label, direction),
+                unique_id: format!("{}_{}", node_id, object_id),
+                state_topic: &state_topic,
+                value_template,
+                unit_of_measurement: "B",
+                device_class: "data_size",
+                state_class: "total_increasing",
+                availability_topic: &availability_topic,
+                device: DiscoveryDevice {
+                    identifiers: [&node_id],
+                    name: &self.config.client_id,
+                    manufacturer: "vnstat-rs-api",
+                    sw_version: env!("CARGO_PKG_VERSION"),
+                },
+            };
+            let config_topic = format!(
+                "{}/sensor/{}/{}/config",
+                self.config.discovery_prefix, node_id, object_id
+            );
+            match serde_json::to_vec(&sensor) {
+                Ok(payload) => self.send(client, config_topic, payload, true).await,
+                Err(e) => warn!("Failed to serialise discovery for `{}`: {}", entry.name, e),
+            }
+        }
+    }
+
+    /// Publishes `value` serialised as JSON to `topic`, without retaining it.
+    async fn send_json<T: Serialize>(&self, client: &AsyncClient, topic: String, value: &T) {
+        match serde_json::to_vec(value) {
+            Ok(payload) => self.send(client, topic, payload, false).await,
+            Err(e) => warn!("Failed to serialise the message to `{}`: {}", topic, e),
+        }
+    }
+
+    /// Queues `payload` for `topic`; the message is dropped if the
+    /// connection is gone.
+    async fn send(
+        &self,
+        client: &AsyncClient,
+        topic: String,
+        payload: impl Into<Vec<u8>>,
+        retain: bool,
+    ) {
+        if let Err(e) = client.publish(&topic, self.qos, retain, payload).await {
+            debug!("MQTT message to `{}` dropped: {}", topic, e);
+        }
+    }
+}
+
+/// Drives the connection to `broker` until `stop` is cancelled or a
+/// disconnect was sent, reporting through `connected` whether it is up.
+///
+/// Only the first failure of a streak is logged above `debug`.
+async fn drive(
+    mut events: EventLoop,
+    broker: String,
+    connected: watch::Sender<bool>,
+    stop: CancellationToken,
+) {
+    let mut failing = false;
+
+    loop {
+        let event = tokio::select! {
+            _ = stop.cancelled() => break,
+            event = events.poll() => event,
+        };
+
+        match event {
+            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                info!("Connected to MQTT broker `{}`", broker);
+                failing = false;
+                connected.send_replace(true);
+            }
+            Ok(Event::Outgoing(Outgoing::Disconnect)) => break,
+            Ok(_) => {}
+            Err(e) => {
+                if failing {
+                    debug!("MQTT broker `{}` still unreachable: {}", broker, e);
+                } else {
+                    warn!(
+                        "MQTT connection to `{}` failed, retrying every {}s: {}",
+                        broker,
+                        RECONNECT_DELAY.as_secs(),
+                        e
+                    );
+                }
+                failing = true;
+                connected.send_replace(false);
+
+                tokio::select! {
+                    _ = stop.cancelled() => break,
+                    _ = tokio::time::sleep(RECONNECT_DELAY) => {}
+                }
+            }
+        }
+    }
+}
+
+/// Returns the topic holding the bridge's `online` / `offline` status.
+fn status_topic(config: &MqttConfig) -> String {
+    format!("{}/status", config.topic_prefix)
+}
+
+/// Returns the topic level of the interface `name`, with the characters
+/// MQTT reserves in topic names (`/`, `+`, `#`) replaced by `_`.
+fn interface_topic(config: &MqttConfig, name: &str) -> String {
+    let level: String = name
+        .chars()
+        .map(|c| if matches!(c, '/' | '+' | '#') { '_' } else { c })
+        .collect();
+    format!("{}/{}", config.topic_prefix, level)
+}
+
+/// Returns `value` as a Home Assistant object ID, which allows only ASCII
+/// letters, digits, `_` and `-`.
+fn object_id(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '_' | '-') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
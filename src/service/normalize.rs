@@ -0,0 +1,354 @@
+use crate::config::vnstat::FutureTimestampPolicy;
+use crate::model::vnstat::{Date, Interface, Time, Timestamped, VnstatData};
+use crate::service::decimate::TrafficSample;
+use crate::utils::timestamp;
+use std::cmp::Reverse;
+use tracing::warn;
+
+/// Applies the future-timestamp policy to freshly fetched vnStat data.
+///
+/// Any record (or interface `updated` stamp) whose timestamp lies more than
+/// `tolerance` seconds past `now` is considered future-dated. Depending on
+/// `policy`, such records are clamped into the current period, removed, or
+/// left untouched. The number of future-dated records found is stored in
+/// [`VnstatData::future_records`] and logged as a warning when non-zero.
+///
+/// After this pass (with `clamp` or `exclude`), consumers may assume that no
+/// timestamp in `data` exceeds `now + tolerance`.
+///
+/// # Arguments
+///
+/// * `data` - The data to normalise in place.
+/// * `policy` - What to do with future-dated records.
+/// * `tolerance` - Allowed clock skew in seconds.
+/// * `now` - The current Unix time in seconds.
+pub fn normalize_future_timestamps(
+    data: &mut VnstatData,
+    policy: FutureTimestampPolicy,
+    tolerance: u64,
+    now: i64,
+) {
+    let limit = now.saturating_add_unsigned(tolerance);
+
+    let count: usize = data
+        .interfaces
+        .iter_mut()
+        .map(|iface| normalize_interface(iface, policy, limit, now))
+        .sum();
+
+    data.future_records = count;
+
+    if count > 0 {
+        warn!(
+            "vnstat data contains {} future-dated record(s) (policy: {:?})",
+            count, policy
+        );
+    }
+}
+
+/// Normalises a single interface and returns how many future-dated
+/// timestamps were found in it.
+fn normalize_interface(
+    iface: &mut Interface,
+    policy: FutureTimestampPolicy,
+    limit: i64,
+    now: i64,
+) -> usize {
+    let traffic = &mut iface.traffic;
+
+    let mut count = normalize_records(&mut traffic.fiveminute, policy, limit, now, Order::Time)
+        + normalize_records(&mut traffic.hour, policy, limit, now, Order::Time)
+        + normalize_records(&mut traffic.day, policy, limit, now, Order::Time)
+        + normalize_records(&mut traffic.month, policy, limit, now, Order::Time)
+        + normalize_records(&mut traffic.year, policy, limit, now, Order::Time)
+        + normalize_records(&mut traffic.top, policy, limit, now, Order::Traffic);
+
+    // The `updated` stamp cannot be excluded, so it is clamped under both
+    // `clamp` and `exclude`.
+    if iface.updated.timestamp > limit {
+        count += 1;
+        if policy != FutureTimestampPolicy::Keep {
+            iface.updated.timestamp = now;
+            if let Some((year, month, day, hour, minute)) = timestamp::to_local_datetime(now) {
+                iface.updated.date = Date::ymd(year, month, day);
+                iface.updated.time = Some(Time { hour, minute });
+            }
+        }
+    }
+
+    count
+}
+
+/// How vnStat orders a record list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Order {
+    /// Oldest first.
+    Time,
+    /// Most traffic first (the `top` list).
+    Traffic,
+}
+
+/// Applies `policy` to one record list and returns the number of
+/// future-dated records it contained.
+fn normalize_records<T: Timestamped + TrafficSample>(
+    records: &mut Vec<T>,
+    policy: FutureTimestampPolicy,
+    limit: i64,
+    now: i64,
+    order: Order,
+) -> usize {
+    let count = records.iter().filter(|r| r.timestamp() > limit).count();
+    if count == 0 {
+        return 0;
+    }
+
+    match policy {
+        FutureTimestampPolicy::Clamp => clamp_records(records, limit, now, order),
+        FutureTimestampPolicy::Exclude => records.retain(|r| r.timestamp() <= limit),
+        FutureTimestampPolicy::Keep => {}
+    }
+
+    count
+}
+
+/// Moves the records past `limit` into the period containing `now`, dates
+/// included (see [`Timestamped::move_to_period_of`]).
+///
+/// A moved record whose period is already in the list is merged into it,
+/// adding up their traffic, so that no two records share a period. The
+/// list is then put back in `order`.
+fn clamp_records<T: Timestamped + TrafficSample>(
+    records: &mut Vec<T>,
+    limit: i64,
+    now: i64,
+    order: Order,
+) {
+    let (future, mut kept): (Vec<T>, Vec<T>) =
+        records.drain(..).partition(|r| r.timestamp() > limit);
+
+    for mut record in future {
+        record.move_to_period_of(now);
+        match kept
+            .iter_mut()
+            .find(|r| r.timestamp() == record.timestamp())
+        {
+            Some(existing) => {
+                let (rx, tx) = existing.traffic();
+                let (more_rx, more_tx) = record.traffic();
+                existing.set_traffic(rx.saturating_add(more_rx), tx.saturating_add(more_tx));
+            }
+            None => kept.push(record),
+        }
+    }
+
+    match order {
+        Order::Time => kept.sort_by_key(|r| r.timestamp()),
+        Order::Traffic => kept.sort_by_key(|r| {
+            let (rx, tx) = r.traffic();
+            Reverse(rx.saturating_add(tx))
+        }),
+    }
+    *records = kept;
+}
+
+/// Fills in timestamps that older vnStat releases do not emit.
+///
+/// Every record (and the interface `created` / `updated` stamps) whose
+/// timestamp is `0` gets one synthesised from its date fields, so later
+/// passes can rely on timestamps being present.
+pub fn fill_missing_timestamps(data: &mut VnstatData) {
+    for iface in &mut data.interfaces {
+        let traffic = &mut iface.traffic;
+        fill_records(&mut traffic.fiveminute);
+        fill_records(&mut traffic.hour);
+        fill_records(&mut traffic.day);
+        fill_records(&mut traffic.month);
+        fill_records(&mut traffic.year);
+        fill_records(&mut traffic.top);
+
+        if iface.created.timestamp == 0 {
+            let date = &iface.created.date;
+            iface.created.timestamp = timestamp::from_local_date(
+                date.year,
+                date.month.unwrap_or(1),
+                date.day.unwrap_or(1),
+                0,
+                0,
+            )
+            .unwrap_or_default();
+        }
+
+        if iface.updated.timestamp == 0 {
+            let date = &iface.updated.date;
+            let (hour, minute) = iface
+                .updated
+                .time
+                .as_ref()
+                .map_or((0, 0), |t| (t.hour, t.minute));
+            iface.updated.timestamp = timestamp::from_local_date(
+                date.year,
+                date.month.unwrap_or(1),
+                date.day.unwrap_or(1),
+                hour,
+                minute,
+            )
+            .unwrap_or_default();
+        }
+    }
+}
+
+/// Synthesises missing (`0`) timestamps of one record list from the
+/// records' date fields.
+fn fill_records<T: Timestamped>(records: &mut [T]) {
+    for record in records.iter_mut().filter(|r| r.timestamp() == 0) {
+        if let Some(ts) = record.date_timestamp() {
+            record.set_timestamp(ts);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::vnstat::{DayRecord, FiveMinuteRecord, TopRecord};
+
+    const DAY: i64 = 86_400;
+
+    /// Returns the start of the local day containing `ts`.
+    fn day_start(ts: i64) -> i64 {
+        let (year, month, day) = timestamp::to_local_date(ts).unwrap();
+        timestamp::from_local_date(year, month, day, 0, 0).unwrap()
+    }
+
+    fn day(ts: i64, rx: u64, tx: u64) -> DayRecord {
+        let (year, month, day) = timestamp::to_local_date(ts).unwrap();
+        DayRecord {
+            date: Date::ymd(year, month, day),
+            id: 0,
+            rx,
+            timestamp: ts,
+            tx,
+        }
+    }
+
+    #[test]
+    fn clamp_moves_future_days_into_today_and_merges_them() {
+        let now = day_start(1_741_608_000) + 12 * 3600;
+        let today = day_start(now);
+        let mut records = vec![
+            day(today - DAY, 100, 10),
+            day(today, 20, 2),
+            day(today + 2 * DAY, 5, 1),
+            day(today + 3 * DAY, 7, 1),
+        ];
+
+        let count = normalize_records(
+            &mut records,
+            FutureTimestampPolicy::Clamp,
+            now + 300,
+            now,
+            Order::Time,
+        );
+
+        assert_eq!(count, 2);
+        assert_eq!(records.len(), 2);
+        let last = &records[1];
+        assert_eq!(last.timestamp, today);
+        assert_eq!((last.rx, last.tx), (32, 4));
+        let (year, month, day) = timestamp::to_local_date(now).unwrap();
+        assert_eq!(
+            (last.date.year, last.date.month, last.date.day),
+            (year, Some(month), Some(day))
+        );
+    }
+
+    #[test]
+    fn clamp_adds_a_record_for_the_current_period_if_missing() {
+        let now = day_start(1_741_608_000) + 12 * 3600 + 7 * 60;
+        let future = now + 3600;
+        let (year, month, day) = timestamp::to_local_date(future).unwrap();
+        let mut records = vec![FiveMinuteRecord {
+            date: Date::ymd(year, month, day),
+            id: 0,
+            rx: 1,
+            time: Time {
+                hour: 13,
+                minute: 5,
+            },
+            timestamp: future,
+            tx: 1,
+        }];
+
+        normalize_records(
+            &mut records,
+            FutureTimestampPolicy::Clamp,
+            now + 300,
+            now,
+            Order::Time,
+        );
+
+        let record = &records[0];
+        assert_eq!(record.time.minute % 5, 0);
+        assert_eq!(record.date_timestamp(), Some(record.timestamp));
+        assert!(record.timestamp <= now && now - record.timestamp < 300);
+    }
+
+    #[test]
+    fn clamp_keeps_the_top_list_ordered_by_traffic() {
+        let now = day_start(1_741_608_000) + 12 * 3600;
+        let today = day_start(now);
+        let top = |ts, rx| TopRecord {
+            date: day(ts, 0, 0).date,
+            id: 0,
+            rx,
+            timestamp: ts,
+            tx: 0,
+        };
+        let mut records = vec![
+            top(today - 9 * DAY, 50),
+            top(today + DAY, 30),
+            top(today, 25),
+        ];
+
+        normalize_records(
+            &mut records,
+            FutureTimestampPolicy::Clamp,
+            now + 300,
+            now,
+            Order::Traffic,
+        );
+
+        let ranked: Vec<_> = records.iter().map(|r| (r.timestamp, r.rx)).collect();
+        assert_eq!(ranked, [(today, 55), (today - 9 * DAY, 50)]);
+    }
+
+    #[test]
+    fn exclude_drops_and_keep_leaves_future_records() {
+        let now = day_start(1_741_608_000) + 12 * 3600;
+        let today = day_start(now);
+        let records = vec![day(today, 20, 2), day(today + DAY, 5, 1)];
+
+        let mut excluded = records.clone();
+        let count = normalize_records(
+            &mut excluded,
+            FutureTimestampPolicy::Exclude,
+            now + 300,
+            now,
+            Order::Time,
+        );
+        assert_eq!(count, 1);
+        assert_eq!(excluded.len(), 1);
+        assert_eq!(excluded[0].timestamp, today);
+
+        let mut kept = records;
+        let count = normalize_records(
+            &mut kept,
+            FutureTimestampPolicy::Keep,
+            now + 300,
+            now,
+            Order::Time,
+        );
+        assert_eq!(count, 1);
+        assert_eq!(kept[1].timestamp, today + DAY);
+    }
+}
@@ -0,0 +1,92 @@
+use crate::config::quota::QuotaConfig;
+use crate::model::response::{QuotaResponse, QuotaStatus};
+use crate::model::vnstat::Interface;
+use crate::utils::timestamp;
+
+/// Computes the usage of `interface` against its quota `limit` in the
+/// billing period containing `now`.
+///
+/// A billing period starts on `config.period_start_day` of a month and ends
+/// on that day of the next month. Usage is the rx + tx of all day records
+/// dated within the period, so it is only complete if vnStat keeps day
+/// records for at least a month.
+///
+/// # Arguments
+///
+/// * `interface` - The interface to check.
+/// * `limit` - Bytes allowed per billing period.
+/// * `config` - Billing period and status thresholds.
+/// * `now` - The current Unix time in seconds.
+pub fn quota_usage(
+    interface: &Interface,
+    limit: u64,
+    config: &QuotaConfig,
+    now: i64,
+) -> QuotaResponse {
+    let (start, end) = billing_period(now, config.period_start_day);
+
+    let used = interface
+        .traffic
+        .day
+        .iter()
+        .filter(|record| {
+            let date = (
+                record.date.year,
+                record.date.month.unwrap_or(1),
+                record.date.day.unwrap_or(1),
+            );
+            start <= date && date < end
+        })
+        .fold(0u64, |sum, record| {
+            sum.saturating_add(record.rx).saturating_add(record.tx)
+        });
+
+    let percent = used as f64 / limit as f64 * 100.0;
+    let status = if percent >= config.exceeded_percent {
+        QuotaStatus::Exceeded
+    } else if percent >= config.warning_percent {
+        QuotaStatus::Warning
+    } else {
+        QuotaStatus::Ok
+    };
+
+    QuotaResponse {
+        name: interface.name.clone(),
+        period_start: local_midnight(start),
+        period_end: local_midnight(end),
+        limit,
+        used,
+        remaining: limit.saturating_sub(used),
+        percent,
+        status,
+    }
+}
+
+/// A calendar date as `(year, month, day)`, ordered chronologically.
+type Date = (i32, u8, u8);
+
+/// Returns the first day of the billing period containing `now` and the
+/// first day of the following one.
+fn billing_period(now: i64, start_day: u8) -> (Date, Date) {
+    let (year, month, day) = timestamp::to_local_date(now).unwrap_or((1970, 1, 1));
+
+    let start = if day >= start_day {
+        (year, month, start_day)
+    } else {
+        match month {
+            1 => (year - 1, 12, start_day),
+            month => (year, month - 1, start_day),
+        }
+    };
+    let end = match start.1 {
+        12 => (start.0 + 1, 1, start_day),
+        month => (start.0, month + 1, start_day),
+    };
+
+    (start, end)
+}
+
+/// Returns the Unix timestamp of local midnight at the start of `date`.
+fn local_midnight((year, month, day): Date) -> i64 {
+    timestamp::from_local_date(year, month, day, 0, 0).unwrap_or_default()
+}
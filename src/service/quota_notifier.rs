@@ -0,0 +1,216 @@
+use crate::config::quota::QuotaConfig;
+use crate::model::response::{QuotaResponse, QuotaStatus};
+use crate::service::quota::quota_usage;
+use crate::service::vnstat_service::VnstatService;
+use crate::utils::reloadable::Reloadable;
+use crate::utils::timestamp;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+/// Delay before the first retry of a failed delivery; doubled per retry.
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Body of a webhook notification.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct QuotaNotification<'a> {
+    /// The system-level interface name (e.g. `eth0`).
+    interface: &'a str,
+    /// Unix epoch timestamp (seconds) at which the billing period started.
+    period_start: i64,
+    /// Unix epoch timestamp (seconds) at which the billing period ends.
+    period_end: i64,
+    /// Bytes used in the billing period so far.
+    used: u64,
+    /// Bytes allowed per billing period.
+    limit: u64,
+    /// `used` as a percentage of `limit`.
+    percent: f64,
+    /// The threshold that was crossed.
+    threshold: QuotaStatus,
+    /// The configured percentage of that threshold.
+    threshold_percent: f64,
+}
+
+/// Background task posting to the configured webhooks when an interface
+/// crosses its `warning` or `exceeded` quota threshold.
+///
+/// Each threshold fires at most once per interface and billing period; if
+/// both are crossed between two checks, only `exceeded` is sent. What has
+/// fired is kept in memory only, so a restart may repeat the notification
+/// for a threshold that is still crossed.
+///
+/// Limits and thresholds are read anew for every check, so reloaded quotas
+/// apply from the next one on; the webhook settings are those at startup.
+pub struct QuotaNotifier {
+    vnstat: Arc<VnstatService>,
+    config: Reloadable<QuotaConfig>,
+    client: reqwest::Client,
+    /// Highest threshold notified per interface, with the start of the
+    /// billing period it was notified in.
+    fired: HashMap<String, (i64, QuotaStatus)>,
+}
+
+impl QuotaNotifier {
+    /// Creates a notifier for the quotas and webhooks in `config`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP client cannot be initialised.
+    pub fn new(
+        vnstat: Arc<VnstatService>,
+        config: Reloadable<QuotaConfig>,
+    ) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(config.load().webhooks.timeout())
+            .build()?;
+
+        Ok(Self {
+            vnstat,
+            config,
+            client,
+            fired: HashMap::new(),
+        })
+    }
+
+    /// Evaluates the quotas every `check_interval_seconds` until `shutdown`
+    /// is cancelled. Pending deliveries are abandoned on shutdown.
+    pub async fn run(mut self, shutdown: CancellationToken) {
+        let mut interval = tokio::time::interval(self.config.load().webhooks.check_interval());
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = interval.tick() => {}
+            }
+
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = self.check() => {}
+            }
+        }
+
+        debug!("Quota notifier stopped");
+    }
+
+    /// Evaluates every interface with a quota once and notifies the
+    /// thresholds crossed since the last check.
+    async fn check(&mut self) {
+        let data = match self.vnstat.fetch_vnstat_data().await {
+            Ok(data) => data.value,
+            Err(e) => {
+                warn!("Quota check skipped, fetching vnStat data failed: {:#}", e);
+                return;
+            }
+        };
+
+        let config = self.config.load();
+        let now = timestamp::get_in_secs();
+        for interface in &data.interfaces {
+            let Some(limit) = config.limit(&interface.name) else {
+                continue;
+            };
+
+            let usage = quota_usage(interface, limit, &config, now);
+            let notified = match self.fired.get(&usage.name) {
+                Some((period_start, status)) if *period_start == usage.period_start => *status,
+                _ => QuotaStatus::Ok,
+            };
+
+            if usage.status > notified {
+                self.notify(&config, &usage).await;
+                self.fired
+                    .insert(usage.name.clone(), (usage.period_start, usage.status));
+            }
+        }
+    }
+
+    /// Posts the threshold crossed by `usage` to every webhook.
+    async fn notify(&self, config: &QuotaConfig, usage: &QuotaResponse) {
+        let threshold_percent = match usage.status {
+            QuotaStatus::Exceeded => config.exceeded_percent,
+            _ => config.warning_percent,
+        };
+        let notification = QuotaNotification {
+            interface: &usage.name,
+            period_start: usage.period_start,
+            period_end: usage.period_end,
+            used: usage.used,
+            limit: usage.limit,
+            percent: usage.percent,
+            threshold: usage.status,
+            threshold_percent,
+        };
+
+        info!(
+            "Interface `{}` reached {:.1} % of its quota, notifying webhooks",
+            usage.name, usage.percent
+        );
+
+        for url in &config.webhooks.urls {
+            self.deliver(config, url, &notification).await;
+        }
+    }
+
+    /// Posts `notification` to `url`, retrying failures with exponential
+    /// backoff up to `retries` times.
+    async fn deliver(&self, config: &QuotaConfig, url: &str, notification: &QuotaNotification<'_>) {
+        let target = redact(url);
+        let attempts = config.webhooks.retries + 1;
+        let mut backoff = INITIAL_BACKOFF;
+
+        for attempt in 1..=attempts {
+            let result = self
+                .client
+                .post(url)
+                .json(notification)
+                .send()
+                .await
+                .and_then(reqwest::Response::error_for_status)
+                .map_err(reqwest::Error::without_url);
+
+            match result {
+                Ok(_) => {
+                    debug!("Webhook `{}` notified", target);
+                    return;
+                }
+                Err(e) if attempt < attempts => {
+                    warn!(
+                        "Webhook `{}` failed (attempt {}/{}), retrying in {}s: {}",
+                        target,
+                        attempt,
+                        attempts,
+                        backoff.as_secs(),
+                        e
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                Err(e) => {
+                    warn!(
+                        "Webhook `{}` failed after {} attempts, giving up: {}",
+                        target, attempts, e
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Returns the scheme and host of `url` for logging, since webhook URLs
+/// often embed a secret token in their path.
+fn redact(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) => format!(
+            "{}://{}",
+            parsed.scheme(),
+            parsed.host_str().unwrap_or_default()
+        ),
+        Err(_) => "<invalid url>".to_string(),
+    }
+}
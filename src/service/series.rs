@@ -0,0 +1,82 @@
+use crate::model::response::{
+    InterfaceSeries, SeriesPoint, SeriesTotal, TotalResponse, TotalSeries, TotalTrafficSeries,
+    TrafficSeries,
+};
+use crate::model::vnstat::{Interface, Total};
+use crate::service::decimate::TrafficSample;
+use crate::service::fields::{TrafficField, TrafficFields};
+use crate::utils::traffic_unit::TrafficUnit;
+use serde::Deserialize;
+
+/// How the record series of the traffic endpoints are serialised
+/// (`?format=`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrafficFormat {
+    /// One object per record, with all its fields.
+    #[default]
+    Objects,
+    /// One `[timestamp, rx, tx]` array per record, as consumed by charting
+    /// libraries such as uPlot or Chart.js.
+    Series,
+}
+
+/// Converts `records` to `[timestamp, rx, tx]` arrays in `unit`, keeping
+/// their order.
+pub fn compact<T: TrafficSample>(records: &[T], unit: TrafficUnit) -> Vec<SeriesPoint> {
+    records
+        .iter()
+        .map(|record| {
+            let (rx, tx) = record.traffic();
+            (record.start(), unit.amount(rx), unit.amount(tx))
+        })
+        .collect()
+}
+
+/// Returns the record series of `interface` in the compact form, with
+/// traffic in `unit`; the totals and series not in `fields` are left out.
+pub fn interface_series(
+    interface: &Interface,
+    unit: TrafficUnit,
+    fields: TrafficFields,
+) -> InterfaceSeries {
+    let traffic = &interface.traffic;
+    let field = |field: TrafficField| fields.contains(field);
+
+    InterfaceSeries {
+        name: interface.name.clone(),
+        alias: interface.alias.clone(),
+        rx_unit: unit.name(),
+        total: field(TrafficField::Total).then(|| series_total(&traffic.total, unit)),
+        series: TrafficSeries {
+            fiveminute: field(TrafficField::FiveMinute).then(|| compact(&traffic.fiveminute, unit)),
+            hour: field(TrafficField::Hour).then(|| compact(&traffic.hour, unit)),
+            day: field(TrafficField::Day).then(|| compact(&traffic.day, unit)),
+            month: field(TrafficField::Month).then(|| compact(&traffic.month, unit)),
+            year: field(TrafficField::Year).then(|| compact(&traffic.year, unit)),
+            top: field(TrafficField::Top).then(|| compact(&traffic.top, unit)),
+        },
+    }
+}
+
+/// Returns the summed series of `total` in the compact form, with traffic
+/// in `unit`.
+pub fn total_series(total: TotalResponse, unit: TrafficUnit) -> TotalSeries {
+    TotalSeries {
+        interfaces: total.interfaces,
+        rx_unit: unit.name(),
+        total: series_total(&total.total, unit),
+        series: TotalTrafficSeries {
+            day: compact(&total.day, unit),
+            month: compact(&total.month, unit),
+        },
+    }
+}
+
+/// Converts lifetime traffic to `unit`.
+fn series_total(total: &Total, unit: TrafficUnit) -> SeriesTotal {
+    SeriesTotal {
+        rx: unit.amount(total.rx),
+        tx: unit.amount(total.tx),
+    }
+}
@@ -0,0 +1,142 @@
+use crate::model::response::{RateStats, StatsResponse};
+use crate::model::vnstat::Interface;
+use crate::service::aggregate::TimeRange;
+use crate::service::decimate::TrafficSample;
+use serde::{Deserialize, Serialize};
+
+/// The record series statistics are computed from (`?granularity=`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    /// The five-minute records.
+    #[default]
+    FiveMinute,
+    /// The hour records.
+    Hour,
+}
+
+impl Granularity {
+    /// Returns the length of one record's interval, in seconds.
+    pub const fn seconds(self) -> i64 {
+        match self {
+            Self::FiveMinute => 300,
+            Self::Hour => 3_600,
+        }
+    }
+}
+
+/// Computes rate statistics of `interface` over the records of
+/// `granularity` whose interval overlaps `range`.
+///
+/// Each record's byte counts are turned into an average rate in bits per
+/// second over its interval. The minimum, maximum, mean, median and 95th
+/// percentile of these rates are reported separately for rx and tx; see
+/// [`percentile`] for how the median and the 95th percentile are picked.
+///
+/// # Returns
+///
+/// The statistics, whose values are `None` when no record overlaps the
+/// range.
+pub fn interface_stats(
+    interface: &Interface,
+    granularity: Granularity,
+    range: TimeRange,
+) -> StatsResponse {
+    let interval = granularity.seconds();
+    let (mut rx, mut tx): (Vec<f64>, Vec<f64>) = match granularity {
+        Granularity::FiveMinute => rates(&interface.traffic.fiveminute, interval, range),
+        Granularity::Hour => rates(&interface.traffic.hour, interval, range),
+    };
+
+    StatsResponse {
+        name: interface.name.clone(),
+        granularity,
+        interval_seconds: interval,
+        samples: rx.len(),
+        rx: rate_stats(&mut rx),
+        tx: rate_stats(&mut tx),
+    }
+}
+
+/// Returns the rx and tx rates, in bits per second, of the records whose
+/// interval overlaps `range`.
+fn rates<T: TrafficSample>(records: &[T], interval: i64, range: TimeRange) -> (Vec<f64>, Vec<f64>) {
+    let rate = |bytes: u64| bytes as f64 * 8.0 / interval as f64;
+    records
+        .iter()
+        .filter(|record| range.overlaps(record.start(), record.start() + interval))
+        .map(|record| {
+            let (rx, tx) = record.traffic();
+            (rate(rx), rate(tx))
+        })
+        .unzip()
+}
+
+/// Computes the statistics of `rates`, sorting them in place.
+fn rate_stats(rates: &mut [f64]) -> RateStats {
+    rates.sort_by(f64::total_cmp);
+    let mean = (!rates.is_empty()).then(|| rates.iter().sum::<f64>() / rates.len() as f64);
+
+    RateStats {
+        min: rates.first().copied(),
+        max: rates.last().copied(),
+        mean,
+        median: percentile(rates, 50.0),
+        p95: percentile(rates, 95.0),
+    }
+}
+
+/// Picks the `p`th percentile of `sorted` with the nearest-rank method.
+///
+/// The percentile is the value at rank `⌈p / 100 × n⌉` (counting from 1,
+/// and at least 1) of the `n` ascending values, so it is always one of the
+/// samples and never interpolated. For the 95th percentile of a month of
+/// five-minute samples, this discards the busiest 5% of the intervals, as
+/// burstable billing does. The median is picked the same way, i.e. the
+/// lower of the two middle values for an even `n`.
+///
+/// # Returns
+///
+/// `None` if `sorted` is empty.
+pub fn percentile(sorted: &[f64], p: f64) -> Option<f64> {
+    let n = sorted.len();
+    let rank = (p / 100.0 * n as f64).ceil() as usize;
+    sorted.get(rank.clamp(1, n.max(1)) - 1).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentile_of_nothing_is_none() {
+        assert_eq!(percentile(&[], 50.0), None);
+        assert_eq!(percentile(&[], 95.0), None);
+    }
+
+    #[test]
+    fn percentile_of_one_sample_is_that_sample() {
+        for p in [0.0, 50.0, 95.0, 100.0] {
+            assert_eq!(percentile(&[7.0], p), Some(7.0), "p{}", p);
+        }
+    }
+
+    #[test]
+    fn percentile_picks_the_nearest_rank_for_an_even_count() {
+        let sorted = [1.0, 2.0, 3.0, 4.0];
+
+        // The lower middle value, not the mean of the two.
+        assert_eq!(percentile(&sorted, 50.0), Some(2.0));
+        assert_eq!(percentile(&sorted, 75.0), Some(3.0));
+        assert_eq!(percentile(&sorted, 95.0), Some(4.0));
+        assert_eq!(percentile(&sorted, 0.0), Some(1.0));
+        assert_eq!(percentile(&sorted, 100.0), Some(4.0));
+    }
+
+    #[test]
+    fn p95_discards_the_busiest_five_percent() {
+        let sorted: Vec<f64> = (1..=100).map(f64::from).collect();
+
+        assert_eq!(percentile(&sorted, 95.0), Some(95.0));
+    }
+}
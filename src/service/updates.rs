@@ -0,0 +1,70 @@
+use crate::error_code::ErrorCode;
+use crate::model::response::InterfaceUpdate;
+use crate::model::vnstat::Total;
+use crate::service::vnstat_service::VnstatService;
+use crate::task_handle::TaskSink;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, warn};
+
+/// Watches an interface for new data and publishes an [`InterfaceUpdate`]
+/// to `sink` whenever vnStat has written to its database.
+///
+/// The cached data is checked every `interval`; an update is published only
+/// when the interface's `updated` timestamp has changed since the previous
+/// check. The first check merely records the baseline, as subscribers get
+/// their own snapshot on connect. Failed checks are reported as comments and
+/// the watcher keeps going; it runs until its task is cancelled.
+///
+/// # Arguments
+///
+/// * `vnstat` - The service to fetch the interface from.
+/// * `if_name` - The real name of the interface.
+/// * `interval` - Time between two checks.
+/// * `sink` - Where updates are published.
+pub async fn watch_interface_updates(
+    vnstat: Arc<VnstatService>,
+    if_name: String,
+    interval: Duration,
+    sink: TaskSink,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut last: Option<(i64, Total)> = None;
+
+    loop {
+        ticker.tick().await;
+
+        let interface = match vnstat.fetch_interface_stats(&if_name).await {
+            Ok(fetched) => fetched.value,
+            Err(e) => {
+                warn!("Update check for `{}` failed: {:#}", if_name, e);
+                let code = ErrorCode::from_error(&e, ErrorCode::NoSuchInterface);
+                sink.comment(format!("Update check failed: {}", code.message()));
+                continue;
+            }
+        };
+
+        let updated = interface.updated.timestamp;
+        let total = interface.traffic.total.clone();
+
+        if let Some((last_updated, last_total)) = last.replace((updated, total.clone()))
+            && last_updated != updated
+        {
+            debug!("Interface `{}` updated at {}", if_name, updated);
+            let update = InterfaceUpdate {
+                interface: &interface,
+                delta: Some(Total {
+                    rx: total.rx.saturating_sub(last_total.rx),
+                    tx: total.tx.saturating_sub(last_total.tx),
+                }),
+            };
+
+            match serde_json::to_string(&update) {
+                Ok(json) => sink.data(json),
+                Err(e) => warn!("Failed to serialise update of `{}`: {}", if_name, e),
+            }
+        }
+    }
+}
@@ -1,260 +1,2322 @@
-use crate::model::vnstat::{Interface, VnstatData};
-use crate::task_handle::TaskMessage;
+use crate::config::cache::CacheConfig;
+use crate::config::vnstat::{FutureTimestampPolicy, VnstatConfig};
+use crate::error_code::ErrorCode;
+use crate::model::admin::{BreakerStatus, CacheStats, FetchKind, VnstatStatusInfo};
+use crate::model::jsend::JsendResponse;
+use crate::model::response::{
+    BatchEntry, CompareResponse, EstimateResponse, InterfaceEntry, InterfaceUpdate,
+    InterfacesResponse, LivePollLine, TotalResponse,
+};
+use crate::model::vnstat::{Interface, JsonVersionProbe, VnstatData};
+use crate::model::vnstat_v1::VnstatDataV1;
+use crate::service::aggregate::{TimeRange, aggregate_interfaces};
+use crate::service::batch::{BatchPart, batch_entry};
+use crate::service::circuit_breaker::{CircuitBreaker, Permit};
+use crate::service::compare::compare_interfaces;
+use crate::service::decimate::SeriesGranularity;
+use crate::service::error::{AmbiguousInterface, NoInterfaces, VnstatError};
+use crate::service::estimate::estimate_interface;
+use crate::service::executor::{CliExecutor, CommandOutput, Program, VnstatExecutor};
+use crate::service::fetch_history::FetchHistory;
+use crate::service::image::{ImageCache, ImageKind, scale_for_width};
+use crate::service::lag::{LagPolicy, drain_to_latest};
+use crate::service::live::LiveOptions;
+use crate::service::normalize::{fill_missing_timestamps, normalize_future_timestamps};
+use crate::service::updates::watch_interface_updates;
+use crate::task_handle::{TaskDropGuard, TaskMessage, TaskSource};
 use crate::task_manager::TaskManager;
+use crate::utils::interface_filter::InterfaceFilter;
+use crate::utils::interface_name::{validate_alias, validate_interface_name};
+use crate::utils::reloadable::Reloadable;
 use crate::utils::timestamp;
+use crate::utils::version::Version;
 use anyhow::{Context, Result};
 use async_stream::stream;
+use axum::body::Bytes;
 use axum::response::sse::Event;
-use cached::cached;
-use futures_util::Stream;
+use futures_util::{Stream, StreamExt};
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::Infallible;
+use std::fmt;
+use std::ops::Deref;
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
 use std::time::Duration;
-use tokio::sync::broadcast::error::RecvError;
-use tracing::warn;
+use tokio::sync::Mutex;
+use tokio::sync::broadcast::Receiver;
+use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+use tokio::time::Instant;
+use tracing::{debug, error, info, warn};
 
-/// Timeout for vnstat command execution.
-const VNSTAT_TIMEOUT: Duration = Duration::from_secs(15);
+/// Base delay between retries of a failed `vnstat --json` call; the n-th
+/// retry waits n times this long.
+const RETRY_BACKOFF: Duration = Duration::from_millis(250);
+
+/// After a failed refresh, how long stale data is served before vnStat is
+/// invoked again.
+const REFRESH_BACKOFF: Duration = Duration::from_secs(5);
+
+/// How long a live long poll keeps its subscription after answering, so
+/// that the client's next poll shares the same `vnstat -l` process.
+const LIVE_POLL_LINGER: Duration = Duration::from_secs(10);
+
+/// Everything that determines how `vnstat --json` is run and its output
+/// normalised.
+#[derive(Debug, Clone)]
+struct FetchOptions {
+    /// Arguments of the run, ending with `--json`.
+    args: Vec<String>,
+    timeout: Duration,
+    retries: u32,
+    future_policy: FutureTimestampPolicy,
+    future_tolerance: u64,
+}
+
+/// The subscription that the last long poll of each live task keeps for
+/// [`LIVE_POLL_LINGER`] after answering (see
+/// [`VnstatService::poll_interface_live`]).
+///
+/// A new poll replaces the previous poll's subscription, so a polling
+/// client holds at most one lingering subscription per task (and one
+/// `max_sse_clients` slot) however fast it polls.
+#[derive(Default)]
+struct PollLingers {
+    /// Identifies each lingering subscription, so that its timer only
+    /// releases it if no later poll replaced it.
+    next_id: AtomicU64,
+    by_key: std::sync::Mutex<HashMap<String, (u64, TaskDropGuard)>>,
+}
+
+impl PollLingers {
+    /// Keeps `guard` as the lingering subscription of `key` for
+    /// [`LIVE_POLL_LINGER`], releasing the one it replaces.
+    fn linger(self: &Arc<Self>, key: String, guard: TaskDropGuard) {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let replaced = self.lock().insert(key.clone(), (id, guard));
+        drop(replaced);
+
+        let lingers = Arc::clone(self);
+        tokio::spawn(async move {
+            tokio::time::sleep(LIVE_POLL_LINGER).await;
+            let mut by_key = lingers.lock();
+            let expired = match by_key.get(&key) {
+                Some((current, _)) if *current == id => by_key.remove(&key),
+                _ => None,
+            };
+            drop(by_key);
+            drop(expired);
+        });
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, (u64, TaskDropGuard)>> {
+        self.by_key
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+/// The answer to a long poll, see [`VnstatService::poll_interface_live`].
+#[derive(Debug, Clone)]
+pub struct LivePoll {
+    /// The lines after `since`, oldest first.
+    pub lines: Vec<LivePollLine>,
+    /// Whether lines after `since` were no longer buffered (or `since` is
+    /// unknown), so some are missing before `lines`.
+    pub gap: bool,
+}
+
+/// A single cached `vnstat --json` result: the original output bytes and the
+/// normalised, parsed model.
+///
+/// Cloning is cheap; clones share the parsed data and rendered responses.
+#[derive(Clone)]
+struct VnstatSnapshot {
+    /// The untouched stdout of `vnstat --json`.
+    raw: Bytes,
+    /// The parsed and normalised data.
+    data: Arc<VnstatData>,
+    /// JSend responses serialised from `data`, filled on first use.
+    rendered: Arc<RenderedResponses>,
+    /// Unix time (seconds) at which vnStat was invoked for this snapshot.
+    generated_at: i64,
+}
+
+/// One interface of a cached snapshot, shared instead of copied.
+///
+/// Dereferences to the [`Interface`] and serialises exactly like it.
+#[derive(Clone)]
+pub struct InterfaceRef {
+    data: Arc<VnstatData>,
+    index: usize,
+}
+
+impl Deref for InterfaceRef {
+    type Target = Interface;
+
+    fn deref(&self) -> &Interface {
+        &self.data.interfaces[self.index]
+    }
+}
+
+impl fmt::Debug for InterfaceRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl Serialize for InterfaceRef {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        (**self).serialize(serializer)
+    }
+}
+
+/// Responses whose serialised JSend body is cached with the snapshot, see
+/// [`VnstatService::fetch_json`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonView {
+    /// The whole (filtered and aliased) [`VnstatData`], as served by
+    /// `GET /vnstat`.
+    Data,
+    /// The bare interface names, as served by
+    /// `GET /vnstat/interfaces?names_only=true`.
+    InterfaceNames,
+}
+
+/// The serialised responses of one snapshot, per [`JsonView`] and for
+/// fresh (index `0`) and stale (index `1`) data.
+#[derive(Default)]
+struct RenderedResponses {
+    data: [OnceLock<Bytes>; 2],
+    interface_names: [OnceLock<Bytes>; 2],
+}
+
+impl RenderedResponses {
+    /// Returns the slot of `view` for fresh or `stale` data.
+    fn slot(&self, view: JsonView, stale: bool) -> &OnceLock<Bytes> {
+        let slots = match view {
+            JsonView::Data => &self.data,
+            JsonView::InterfaceNames => &self.interface_names,
+        };
+        &slots[usize::from(stale)]
+    }
+}
+
+/// The last successful snapshot and when it was fetched.
+struct SnapshotCache {
+    snapshot: Option<(VnstatSnapshot, Instant)>,
+    /// When the most recent refresh failed, if it did.
+    failed_at: Option<Instant>,
+    /// Bumped whenever the snapshot is dropped, so that a refresh started
+    /// before does not store what it loaded.
+    generation: u64,
+}
+
+/// Which interfaces are served and under which alias; replaced when the
+/// configuration is reloaded.
+#[derive(Debug)]
+struct InterfaceView {
+    filter: InterfaceFilter,
+    aliases: BTreeMap<String, String>,
+}
+
+/// The `extra_args` and `live_extra_args` of the configuration; replaced
+/// when the configuration is reloaded.
+#[derive(Debug, PartialEq, Eq)]
+struct ExtraArgs {
+    fetch: Vec<String>,
+    live: Vec<String>,
+}
+
+impl ExtraArgs {
+    fn new(config: &VnstatConfig) -> Self {
+        Self {
+            fetch: config.extra_args.clone(),
+            live: config.live_extra_args.clone(),
+        }
+    }
+}
+
+impl InterfaceView {
+    fn new(config: &VnstatConfig) -> Self {
+        Self {
+            filter: config.interface_filter.clone(),
+            aliases: config.aliases.clone(),
+        }
+    }
+}
+
+/// Counters of how the snapshot cache served its callers.
+#[derive(Debug, Default)]
+struct CacheCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    refresh_failures: AtomicU64,
+}
+
+/// A value derived from vnStat data, together with how old that data is.
+#[derive(Debug, Clone)]
+pub struct Fetched<T> {
+    /// The requested data.
+    pub value: T,
+    /// Time since the underlying `vnstat --json` call.
+    pub age: Duration,
+    /// Unix time (seconds) of the underlying `vnstat --json` call.
+    pub generated_at: i64,
+    /// `true` if the last refresh failed and `value` comes from an older,
+    /// still acceptable snapshot.
+    pub stale: bool,
+    /// Number of future-dated records found in the underlying data (see
+    /// [`VnstatData::future_records`]).
+    pub future_records: usize,
+}
+
+impl<T> Fetched<T> {
+    /// Transforms the value while keeping its age and staleness.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Fetched<U> {
+        Fetched {
+            value: f(self.value),
+            age: self.age,
+            generated_at: self.generated_at,
+            stale: self.stale,
+            future_records: self.future_records,
+        }
+    }
+
+    /// Like [`map`](Self::map), for fallible transformations.
+    ///
+    /// # Errors
+    ///
+    /// Propagates the error returned by `f`.
+    pub fn try_map<U>(self, f: impl FnOnce(T) -> Result<U>) -> Result<Fetched<U>> {
+        Ok(Fetched {
+            value: f(self.value)?,
+            age: self.age,
+            generated_at: self.generated_at,
+            stale: self.stale,
+            future_records: self.future_records,
+        })
+    }
+}
 
 /// Service for interacting with the `vnstat` command-line tool.
 ///
 /// Encapsulates vnStat data fetching, interface listing, health checks, and
-/// live-traffic streaming. Uses a cached (1-entry, 60-second TTL) backend for
-/// periodic data queries to avoid redundant subprocess invocations.
+/// live-traffic streaming. Periodic data queries are served from a
+/// stale-while-revalidate cache: a snapshot is reused for 60 seconds, and
+/// when a refresh fails the previous snapshot keeps being served (flagged
+/// as stale) for up to `max_stale_seconds`. After repeated failures a
+/// [`CircuitBreaker`] stops running vnStat for a while.
 pub struct VnstatService {
-    executable: String,
+    config: VnstatConfig,
+    /// Interface filter and aliases, reloadable unlike the rest of `config`.
+    view: Reloadable<InterfaceView>,
+    /// Reloadable `max_stale_seconds`.
+    max_stale_seconds: AtomicU64,
+    /// Reloadable `cache.ttl_seconds`.
+    cache_ttl_seconds: AtomicU64,
+    /// Reloadable `extra_args` and `live_extra_args`.
+    extra_args: Reloadable<ExtraArgs>,
+    /// Runs vnStat and vnstati.
+    executor: Arc<dyn VnstatExecutor>,
+    /// The last successful `vnstat --json` result.
+    cache: Mutex<SnapshotCache>,
+    /// Held while vnStat runs for a refresh, so that one runs at a time.
+    refreshing: Mutex<()>,
+    /// Hits, misses and failed refreshes of `cache`.
+    cache_counters: CacheCounters,
+    /// Stops data fetches and health checks from running vnStat after
+    /// repeated failures.
+    breaker: CircuitBreaker,
+    /// The last vnStat runs of data fetches and health checks.
+    history: FetchHistory,
+    /// PNG graphs rendered by vnstati, kept as long as a snapshot.
+    images: Mutex<ImageCache>,
+    /// The vnStat version detected by [`check_version`](Self::check_version).
+    version: OnceLock<Version>,
+    /// Set when the startup version check failed but was not enforced.
+    degraded: AtomicBool,
+    /// Subscriptions kept between the long polls of live clients.
+    poll_lingers: Arc<PollLingers>,
 }
 
-impl VnstatService {
-    /// Creates a new `VnstatService`.
+impl VnstatService {
+    /// Creates a new `VnstatService`.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The `[vnstat]` configuration section, providing the
+    ///   executable path and data normalisation settings.
+    ///
+    /// # Returns
+    ///
+    /// A new `VnstatService` instance that will delegate all vnStat calls to
+    /// the configured executable.
+    pub fn new(config: VnstatConfig) -> Self {
+        let executor = Arc::new(CliExecutor::new(&config));
+        Self::with_executor(config, executor)
+    }
+
+    /// Creates a new `VnstatService` that runs vnStat through `executor`
+    /// instead of spawning the configured executables.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The `[vnstat]` configuration section; its executable
+    ///   paths are only used by the default [`CliExecutor`].
+    /// * `executor` - Runs every vnStat and vnstati command.
+    pub fn with_executor(config: VnstatConfig, executor: Arc<dyn VnstatExecutor>) -> Self {
+        let service = Self {
+            view: Reloadable::new(InterfaceView::new(&config)),
+            max_stale_seconds: AtomicU64::new(config.max_stale_seconds),
+            cache_ttl_seconds: AtomicU64::new(CacheConfig::default().ttl_seconds),
+            extra_args: Reloadable::new(ExtraArgs::new(&config)),
+            breaker: CircuitBreaker::new(
+                config.breaker_threshold,
+                Duration::from_secs(config.breaker_cooldown_seconds),
+            ),
+            config,
+            executor,
+            cache: Mutex::new(SnapshotCache {
+                snapshot: None,
+                failed_at: None,
+                generation: 0,
+            }),
+            refreshing: Mutex::new(()),
+            cache_counters: CacheCounters::default(),
+            history: FetchHistory::default(),
+            images: Mutex::new(ImageCache::default()),
+            version: OnceLock::new(),
+            degraded: AtomicBool::new(false),
+            poll_lingers: Arc::default(),
+        };
+        service.log_commands();
+        service
+    }
+
+    /// Logs, at `debug`, the command lines of data fetches and live
+    /// streams as the default [`CliExecutor`] runs them.
+    fn log_commands(&self) {
+        let command = |args: Vec<String>| {
+            let mut command = vec![self.config.executable.clone()];
+            command.extend(self.config.base_args());
+            command.extend(args);
+            command.join(" ")
+        };
+
+        debug!("vnStat data command: {}", command(self.json_args()));
+        debug!(
+            "vnStat live command: {}",
+            command(self.live_args("<interface>", LiveOptions::default()))
+        );
+    }
+
+    /// Detects the installed vnStat version and checks it against the
+    /// configured `min_version`.
+    ///
+    /// Runs `vnstat --version` and parses the reported version. If the
+    /// version is too old, or the executable does not identify itself as
+    /// vnStat, the check fails: with `enforce_min_version` this is an error,
+    /// otherwise a warning is logged and the service is marked degraded
+    /// (see [`is_degraded`](Self::is_degraded)).
+    ///
+    /// Intended to be called once at startup.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the check fails and `enforce_min_version` is set.
+    pub async fn check_version(&self) -> Result<()> {
+        let result = self.detect_version().await.and_then(|version| {
+            let _ = self.version.set(version);
+
+            if version < self.config.min_version {
+                anyhow::bail!(
+                    "vnStat {} is older than the minimum supported version {}",
+                    version,
+                    self.config.min_version
+                );
+            }
+
+            info!("Detected vnStat {}", version);
+            Ok(())
+        });
+
+        match result {
+            Ok(()) => Ok(()),
+            Err(e) if self.config.enforce_min_version => Err(e),
+            Err(e) => {
+                error!("vnStat version check failed, running degraded: {:#}", e);
+                self.degraded.store(true, Ordering::Relaxed);
+                Ok(())
+            }
+        }
+    }
+
+    /// Runs `vnstat --version` and parses its output.
+    async fn detect_version(&self) -> Result<Version> {
+        let output = self
+            .executor
+            .run(Program::Vnstat, &["--version"], self.timeout())
+            .await
+            .context("failed to execute vnStat --version")?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Version::from_vnstat_output(&stdout)
+    }
+
+    /// Returns the vnStat version detected at startup, if any.
+    pub fn version(&self) -> Option<Version> {
+        self.version.get().copied()
+    }
+
+    /// Returns the configured minimum vnStat version.
+    pub fn min_version(&self) -> Version {
+        self.config.min_version
+    }
+
+    /// Returns the configured day of the month billing months start on.
+    pub fn month_rotate_day(&self) -> u8 {
+        self.config.month_rotate_day
+    }
+
+    /// Returns `true` if the startup version check failed without being
+    /// enforced.
+    pub fn is_degraded(&self) -> bool {
+        self.degraded.load(Ordering::Relaxed)
+    }
+
+    /// Returns `false` if the vnStat data shows no (visible) interface,
+    /// `true` if it shows some or cannot be fetched.
+    pub async fn has_interfaces(&self) -> bool {
+        match self.fetch_vnstat_data().await {
+            Ok(fetched) => !fetched.value.interfaces.is_empty(),
+            Err(_) => true,
+        }
+    }
+
+    /// Fetches the full vnStat data JSON.
+    ///
+    /// Served from the snapshot cache (see [`VnstatService`]).
+    ///
+    /// Interfaces hidden by the configured include/exclude patterns are
+    /// stripped from the result, and configured aliases replace the alias
+    /// reported by vnStat.
+    ///
+    /// # Returns
+    ///
+    /// A [`VnstatData`] struct deserialized from the `vnstat --json` output,
+    /// with its age.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the vnStat subprocess fails, times out, produces
+    /// invalid UTF-8, or returns JSON that cannot be deserialized, and no
+    /// cached snapshot younger than `max_stale_seconds` is available.
+    pub async fn fetch_vnstat_data(&self) -> Result<Fetched<Arc<VnstatData>>> {
+        let fetched = self.fetch_snapshot().await?;
+        Ok(fetched.map(|snapshot| snapshot.data))
+    }
+
+    /// Returns the serialised JSend success response of `view`, with
+    /// [`ErrorCode::StaleData`] if stale data is served.
+    ///
+    /// The body is serialised once per snapshot and then served from the
+    /// cache, so it is dropped together with the snapshot it was built
+    /// from.
+    ///
+    /// # Errors
+    ///
+    /// Propagates any error from [`fetch_vnstat_data`](Self::fetch_vnstat_data)
+    /// or from serialisation. [`JsonView::InterfaceNames`] fails with
+    /// [`NoInterfaces`] if vnStat monitors no interface, while
+    /// [`JsonView::Data`] is served with an empty `interfaces` array.
+    pub async fn fetch_json(&self, view: JsonView) -> Result<Fetched<Bytes>> {
+        let fetched = self.fetch_snapshot().await?;
+        if view != JsonView::Data {
+            ensure_interfaces(&fetched.value.data)?;
+        }
+        let slot = fetched.value.rendered.slot(view, fetched.stale);
+
+        let body = match slot.get() {
+            Some(body) => body.clone(),
+            None => {
+                let data = fetched.value.data.as_ref();
+                let body = match view {
+                    JsonView::Data => render_json(data, fetched.stale)?,
+                    JsonView::InterfaceNames => render_json(interface_names(data), fetched.stale)?,
+                };
+                slot.get_or_init(|| body).clone()
+            }
+        };
+
+        Ok(fetched.map(|_| body))
+    }
+
+    /// Applies the interface filter and configured aliases to `data`.
+    ///
+    /// Runs once per snapshot, so the cached data is what every endpoint
+    /// serves.
+    fn present(&self, data: &mut VnstatData) {
+        let view = self.view.load();
+        data.interfaces.retain(|i| view.filter.is_visible(&i.name));
+
+        for interface in &mut data.interfaces {
+            if let Some(alias) = view.aliases.get(&interface.name) {
+                interface.alias = alias.clone();
+            }
+        }
+    }
+
+    /// Returns `true` if the raw passthrough endpoints are enabled.
+    pub fn raw_enabled(&self) -> bool {
+        self.config.expose_raw
+    }
+
+    /// Fetches vnStat's `--json` output without parsing it into the typed
+    /// model.
+    ///
+    /// The cached bytes are returned untouched unless an interface filter is
+    /// configured, in which case hidden interfaces are removed from the
+    /// `interfaces` array (all other fields are preserved).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the underlying fetch fails or, when filtering,
+    /// the output is not a JSON document.
+    pub async fn fetch_vnstat_raw(&self) -> Result<Fetched<Bytes>> {
+        let fetched = self.fetch_snapshot().await?;
+
+        fetched.try_map(|snapshot| {
+            let filter = &self.view.load().filter;
+            if filter.is_empty() {
+                return Ok(snapshot.raw);
+            }
+
+            let mut value: Value =
+                serde_json::from_slice(&snapshot.raw).context("failed to parse raw vnStat JSON")?;
+            if let Some(interfaces) = value.get_mut("interfaces").and_then(Value::as_array_mut) {
+                interfaces.retain(|i| raw_interface_name(i).is_some_and(|n| filter.is_visible(n)));
+            }
+
+            Ok(serde_json::to_vec(&value)?.into())
+        })
+    }
+
+    /// Fetches one interface object from vnStat's `--json` output without
+    /// parsing it into the typed model.
+    ///
+    /// # Arguments
+    ///
+    /// * `if_name` - Real name or alias of the interface, see
+    ///   [`fetch_interface_stats`](Self::fetch_interface_stats).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the interface does not exist or is hidden, an
+    /// [`AmbiguousInterface`] if the alias is shared, or an error if the
+    /// underlying fetch fails.
+    pub async fn fetch_interface_raw(&self, if_name: impl AsRef<str>) -> Result<Fetched<Bytes>> {
+        let fetched = self.fetch_snapshot().await?;
+        fetched.try_map(|snapshot| {
+            let index = resolve_interface(&snapshot.data, if_name.as_ref())?;
+            self.find_raw_interface(&snapshot.raw, &snapshot.data.interfaces[index].name)
+        })
+    }
+
+    /// Extracts the interface with the real name `if_name` from raw vnStat
+    /// JSON, honouring the interface filter.
+    fn find_raw_interface(&self, raw: &[u8], if_name: &str) -> Result<Bytes> {
+        let value: Value =
+            serde_json::from_slice(raw).context("failed to parse raw vnStat JSON")?;
+        let interfaces = value
+            .get("interfaces")
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        let filter = &self.view.load().filter;
+        let interface = interfaces
+            .iter()
+            .find(|i| raw_interface_name(i) == Some(if_name))
+            .filter(|i| raw_interface_name(i).is_some_and(|n| filter.is_visible(n)))
+            .ok_or_else(|| anyhow::anyhow!("interface not found"))?;
+
+        Ok(serde_json::to_vec(interface)?.into())
+    }
+
+    /// Lists all network interfaces tracked by vnStat.
+    ///
+    /// # Arguments
+    ///
+    /// * `names_only` - Return bare interface names instead of summary
+    ///   objects.
+    ///
+    /// # Returns
+    ///
+    /// One [`InterfaceEntry`] (name, alias, last update, staleness and
+    /// totals) per interface present in the vnStat database, or just the
+    /// names when `names_only` is set.
+    ///
+    /// # Errors
+    ///
+    /// Propagates any error from [`fetch_vnstat_data`](Self::fetch_vnstat_data),
+    /// and returns [`NoInterfaces`] if vnStat monitors no interface.
+    pub async fn list_vnstat_interfaces(
+        &self,
+        names_only: bool,
+    ) -> Result<Fetched<InterfacesResponse>> {
+        let fetched = self.fetch_vnstat_data().await?;
+        ensure_interfaces(&fetched.value)?;
+        let stale_after = self.config.stale_after_seconds;
+        let now = timestamp::get_in_secs();
+        Ok(fetched.map(|data| interface_list(&data, names_only, stale_after, now)))
+    }
+
+    /// Returns the [`InterfaceEntry`] (name, alias, last update, staleness
+    /// and totals) of every interface, as listed by
+    /// [`list_vnstat_interfaces`](Self::list_vnstat_interfaces).
+    ///
+    /// # Errors
+    ///
+    /// Propagates any error from [`fetch_vnstat_data`](Self::fetch_vnstat_data).
+    pub async fn interface_summaries(&self) -> Result<Fetched<Vec<InterfaceEntry>>> {
+        let fetched = self.fetch_vnstat_data().await?;
+        let stale_after = self.config.stale_after_seconds;
+        let now = timestamp::get_in_secs();
+        Ok(fetched.map(|data| interface_entries(&data, stale_after, now).collect()))
+    }
+
+    /// Lists the interfaces vnStat has not updated for longer than
+    /// `stale_after_seconds`, see [`is_interface_stale`](Self::is_interface_stale).
+    ///
+    /// # Errors
+    ///
+    /// Propagates any error from [`fetch_vnstat_data`](Self::fetch_vnstat_data).
+    pub async fn list_stale_interfaces(&self) -> Result<Fetched<Vec<InterfaceEntry>>> {
+        let fetched = self.fetch_vnstat_data().await?;
+        let stale_after = self.config.stale_after_seconds;
+        let now = timestamp::get_in_secs();
+        Ok(fetched.map(|data| {
+            interface_entries(&data, stale_after, now)
+                .filter(|entry| entry.stale)
+                .collect()
+        }))
+    }
+
+    /// Returns `true` if an interface last updated by vnStat at
+    /// `updated_timestamp` is stale: the update is more than
+    /// `stale_after_seconds` old (and that setting is not `0`).
+    pub fn is_interface_stale(&self, updated_timestamp: i64) -> bool {
+        is_stale(
+            updated_timestamp,
+            self.config.stale_after_seconds,
+            timestamp::get_in_secs(),
+        )
+    }
+
+    /// Sums the traffic of all visible interfaces.
+    ///
+    /// Operates on the cached snapshot; see
+    /// [`aggregate_interfaces`](crate::service::aggregate::aggregate_interfaces)
+    /// for how records are aligned and summed.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - Restricts the day and month series to this time range.
+    ///
+    /// # Errors
+    ///
+    /// Propagates any error from [`fetch_vnstat_data`](Self::fetch_vnstat_data).
+    pub async fn aggregate_all_interfaces(
+        &self,
+        range: TimeRange,
+    ) -> Result<Fetched<TotalResponse>> {
+        let fetched = self.fetch_vnstat_data().await?;
+        Ok(fetched.map(|data| aggregate_interfaces(&data, range)))
+    }
+
+    /// Retrieves detailed statistics for a specific network interface.
+    ///
+    /// # Arguments
+    ///
+    /// * `if_name` - Real name (e.g. `"eth0"`) or alias (e.g. `"WAN"`,
+    ///   matched case-insensitively) of the interface to look up. Real names
+    ///   take precedence; the alias is the configured one if any, otherwise
+    ///   vnStat's.
+    ///
+    /// # Returns
+    ///
+    /// The [`Interface`] struct matching the requested interface.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the interface is not found in the vnStat data (or
+    /// is hidden by the interface filter), an [`AmbiguousInterface`] if
+    /// several interfaces carry the alias, or an error if the underlying
+    /// data fetch fails.
+    pub async fn fetch_interface_stats(
+        &self,
+        if_name: impl AsRef<str>,
+    ) -> Result<Fetched<InterfaceRef>> {
+        let fetched = self.fetch_vnstat_data().await?;
+        fetched.try_map(|data| self.find_interface(data, if_name.as_ref()))
+    }
+
+    /// Projects the traffic of an interface at the end of the current day
+    /// and month.
+    ///
+    /// See [`estimate_interface`](crate::service::estimate::estimate_interface).
+    ///
+    /// # Arguments
+    ///
+    /// * `if_name` - Real name or alias of the interface.
+    ///
+    /// # Errors
+    ///
+    /// Propagates any error from
+    /// [`fetch_interface_stats`](Self::fetch_interface_stats).
+    pub async fn estimate_interface_traffic(
+        &self,
+        if_name: impl AsRef<str>,
+    ) -> Result<Fetched<EstimateResponse>> {
+        let fetched = self.fetch_interface_stats(if_name).await?;
+        let now = timestamp::get_in_secs();
+        Ok(fetched.map(|interface| estimate_interface(&interface, now)))
+    }
+
+    /// Compares the traffic of several interfaces side by side.
+    ///
+    /// See [`compare_interfaces`]; a name that does not exist (or is hidden
+    /// by the interface filter) gets an error entry instead of failing the
+    /// whole comparison.
+    ///
+    /// # Arguments
+    ///
+    /// * `labels` - Real names or aliases of the interfaces.
+    /// * `granularity` - The record series to compare.
+    /// * `range` - The time range to compare.
+    ///
+    /// # Errors
+    ///
+    /// Propagates any error from
+    /// [`fetch_vnstat_data`](Self::fetch_vnstat_data).
+    pub async fn compare_interface_traffic(
+        &self,
+        labels: &[String],
+        granularity: SeriesGranularity,
+        range: TimeRange,
+    ) -> Result<Fetched<CompareResponse>> {
+        let fetched = self.fetch_vnstat_data().await?;
+        Ok(fetched.map(|data| {
+            let found: Vec<_> = labels
+                .iter()
+                .map(|label| self.find_interface(Arc::clone(&data), label).ok())
+                .collect();
+            let interfaces: Vec<_> = labels
+                .iter()
+                .zip(&found)
+                .map(|(label, interface)| (label.as_str(), interface.as_deref()))
+                .collect();
+            compare_interfaces(&interfaces, granularity, range)
+        }))
+    }
+
+    /// Returns the requested parts of several interfaces, from a single
+    /// snapshot.
+    ///
+    /// # Arguments
+    ///
+    /// * `labels` - Real names or aliases of the interfaces.
+    /// * `include` - The parts to return for each interface.
+    ///
+    /// # Returns
+    ///
+    /// One [`BatchEntry`] per label, keyed by the label. A name that does
+    /// not exist (or is hidden by the interface filter) gets an entry with
+    /// just an `error` instead of failing the batch.
+    ///
+    /// # Errors
+    ///
+    /// Propagates any error from
+    /// [`fetch_vnstat_data`](Self::fetch_vnstat_data).
+    pub async fn batch_interfaces(
+        &self,
+        labels: &[String],
+        include: &[BatchPart],
+    ) -> Result<Fetched<BTreeMap<String, BatchEntry>>> {
+        let fetched = self.fetch_vnstat_data().await?;
+        let stale_after = self.config.stale_after_seconds;
+        let now = timestamp::get_in_secs();
+
+        Ok(fetched.map(|data| {
+            labels
+                .iter()
+                .map(|label| {
+                    let entry = match self.find_interface(Arc::clone(&data), label) {
+                        Ok(interface) => {
+                            let summary = include
+                                .contains(&BatchPart::Summary)
+                                .then(|| interface_entry(&interface, stale_after, now));
+                            batch_entry(&interface, include, summary)
+                        }
+                        Err(e) => {
+                            BatchEntry::error(ErrorCode::from_error(&e, ErrorCode::NoSuchInterface))
+                        }
+                    };
+                    (label.clone(), entry)
+                })
+                .collect()
+        }))
+    }
+
+    /// Looks up an interface by real name, then by alias; see
+    /// [`resolve_interface`].
+    fn find_interface(&self, data: Arc<VnstatData>, if_name: &str) -> Result<InterfaceRef> {
+        let index = resolve_interface(&data, if_name)?;
+        Ok(InterfaceRef { data, index })
+    }
+
+    /// Returns the cached vnStat snapshot, refreshing it once it is older
+    /// than the cache lifetime.
+    ///
+    /// A single refresh runs at a time, without the cache locked: while it
+    /// runs, other callers are served the cached snapshot if it is no older
+    /// than `max_stale_seconds`, and only callers without one wait for it.
+    /// If the refresh fails, the previous snapshot is returned with `stale`
+    /// set as long as it is no older than `max_stale_seconds`; further
+    /// refreshes are then skipped for [`REFRESH_BACKOFF`], so that a
+    /// hanging vnStat is not run for every request.
+    ///
+    /// # Errors
+    ///
+    /// Returns the refresh error if no acceptable snapshot is cached.
+    async fn fetch_snapshot(&self) -> Result<Fetched<VnstatSnapshot>> {
+        if let Some(fetched) = self.cached_snapshot(false).await {
+            return Ok(fetched);
+        }
+
+        let refreshing = match self.refreshing.try_lock() {
+            Ok(refreshing) => refreshing,
+            Err(_) => {
+                if let Some(fetched) = self.cached_snapshot(true).await {
+                    return Ok(fetched);
+                }
+                self.refreshing.lock().await
+            }
+        };
+        // The refresh waited for may have filled the cache.
+        if let Some(fetched) = self.cached_snapshot(false).await {
+            return Ok(fetched);
+        }
+
+        self.cache_counters.misses.fetch_add(1, Ordering::Relaxed);
+        let result = self.load_into_cache().await;
+        drop(refreshing);
+
+        match result {
+            Ok(snapshot) => Ok(Fetched {
+                generated_at: snapshot.generated_at,
+                future_records: snapshot.data.future_records,
+                value: snapshot,
+                age: Duration::ZERO,
+                stale: false,
+            }),
+            Err(e) => {
+                let cache = self.cache.lock().await;
+                match &cache.snapshot {
+                    Some((snapshot, fetched_at)) if fetched_at.elapsed() <= self.max_stale() => {
+                        warn!("vnStat refresh failed, serving stale data: {:#}", e);
+                        Ok(Fetched {
+                            generated_at: snapshot.generated_at,
+                            value: snapshot.clone(),
+                            age: fetched_at.elapsed(),
+                            stale: true,
+                            future_records: snapshot.data.future_records,
+                        })
+                    }
+                    _ => Err(e),
+                }
+            }
+        }
+    }
+
+    /// Returns the cached snapshot if it can be served without a refresh:
+    /// it is younger than the cache lifetime, or no older than
+    /// `max_stale_seconds` while refreshes back off after a failure or,
+    /// with `refreshing`, while another caller refreshes it.
+    async fn cached_snapshot(&self, refreshing: bool) -> Option<Fetched<VnstatSnapshot>> {
+        let cache = self.cache.lock().await;
+        let (snapshot, fetched_at) = cache.snapshot.as_ref()?;
+        let age = fetched_at.elapsed();
+        let backing_off = cache
+            .failed_at
+            .is_some_and(|failed_at| failed_at.elapsed() < REFRESH_BACKOFF);
+
+        if age >= self.cache_ttl() && !((backing_off || refreshing) && age <= self.max_stale()) {
+            return None;
+        }
+        self.cache_counters.hits.fetch_add(1, Ordering::Relaxed);
+        Some(Fetched {
+            generated_at: snapshot.generated_at,
+            value: snapshot.clone(),
+            age,
+            stale: cache.failed_at.is_some(),
+            future_records: snapshot.data.future_records,
+        })
+    }
+
+    /// Runs vnStat and stores the new snapshot in the cache, or records the
+    /// failure. Callers hold `refreshing`.
+    ///
+    /// A snapshot loaded while the cache was dropped (e.g. by a reload) is
+    /// returned but not stored, since it may predate the change.
+    async fn load_into_cache(&self) -> Result<VnstatSnapshot> {
+        let generation = self.cache.lock().await.generation;
+        let result = self.load_snapshot().await;
+
+        let mut cache = self.cache.lock().await;
+        match &result {
+            Ok(snapshot) if cache.generation == generation => {
+                cache.snapshot = Some((snapshot.clone(), Instant::now()));
+                cache.failed_at = None;
+            }
+            Ok(_) => debug!("Cache dropped during the refresh, not storing its snapshot"),
+            Err(_) => {
+                cache.failed_at = Some(Instant::now());
+                self.cache_counters
+                    .refresh_failures
+                    .fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        result
+    }
+
+    /// Drops the cached snapshot, and any snapshot a running refresh is
+    /// about to store.
+    async fn invalidate(&self) {
+        let mut cache = self.cache.lock().await;
+        cache.snapshot = None;
+        cache.generation += 1;
+    }
+
+    /// Fetches a new snapshot into the cache, whatever the age of the
+    /// cached one.
+    ///
+    /// Goes through the same single refresh as on-demand fetches: it waits
+    /// for a running refresh to end rather than running vnStat alongside
+    /// it, and requests keep being served the cached snapshot meanwhile. A
+    /// failure is recorded like one of an on-demand refresh, flagging the
+    /// cached snapshot as stale. Used by the
+    /// [`CacheRefresher`](super::cache_refresher::CacheRefresher).
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the vnStat call; the cached snapshot is kept.
+    pub async fn refresh(&self) -> Result<()> {
+        let _refreshing = self.refreshing.lock().await;
+        self.load_into_cache().await.map(|_| ())
+    }
+
+    /// Runs `vnstat --json` and builds a snapshot of the visible
+    /// interfaces.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`VnstatUnavailable`](super::error::VnstatUnavailable)
+    /// without running vnStat while the circuit breaker is open, which
+    /// callers handle like any failed refresh.
+    async fn load_snapshot(&self) -> Result<VnstatSnapshot> {
+        let permit = self.breaker.allow()?;
+        let started = Instant::now();
+        let result = fetch_vnstat_snapshot(self.executor.as_ref(), &self.fetch_options(), |data| {
+            self.present(data)
+        })
+        .await;
+        self.record_run(FetchKind::Data, permit, started, &result, |snapshot| {
+            snapshot.raw.len()
+        });
+        result
+    }
+
+    /// Records the outcome of a vnStat run started at `started` with
+    /// `permit` in the circuit breaker and the fetch history.
+    fn record_run<T>(
+        &self,
+        kind: FetchKind,
+        permit: Permit<'_>,
+        started: Instant,
+        result: &Result<T>,
+        output_bytes: impl FnOnce(&T) -> usize,
+    ) {
+        match result {
+            Ok(_) => self.breaker.record_success(permit),
+            Err(e) => self.breaker.record_failure(permit, e),
+        }
+        self.history
+            .record(kind, started.elapsed(), result, output_bytes);
+    }
+
+    /// Returns the state of the circuit breaker guarding vnStat runs.
+    pub fn breaker_status(&self) -> BreakerStatus {
+        self.breaker.status()
+    }
+
+    /// Returns the circuit breaker and cache state along with the last
+    /// vnStat runs, for `GET /admin/vnstat-status`.
+    pub async fn status(&self) -> VnstatStatusInfo {
+        VnstatStatusInfo {
+            breaker: self.breaker_status(),
+            cache: self.cache_stats(),
+            data_age_seconds: self.data_age().await.map(|age| age.as_secs()),
+            history: self.history.entries(),
+        }
+    }
+
+    /// Returns the hits, misses and failed refreshes of the snapshot cache
+    /// so far.
+    pub fn cache_stats(&self) -> CacheStats {
+        let counters = &self.cache_counters;
+        CacheStats {
+            hits: counters.hits.load(Ordering::Relaxed),
+            misses: counters.misses.load(Ordering::Relaxed),
+            refresh_failures: counters.refresh_failures.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Returns the arguments of a `vnstat --json` run fetching data: the
+    /// configured `extra_args`, then `--json`.
+    fn json_args(&self) -> Vec<String> {
+        let mut args = self.extra_args.load().fetch.clone();
+        args.push("--json".to_string());
+        args
+    }
+
+    /// Returns how `vnstat --json` is run and normalised.
+    fn fetch_options(&self) -> FetchOptions {
+        FetchOptions {
+            args: self.json_args(),
+            timeout: self.timeout(),
+            retries: self.config.command_retries,
+            future_policy: self.config.future_timestamps,
+            future_tolerance: self.config.future_tolerance,
+        }
+    }
+
+    /// Returns whether the interface management endpoints are enabled
+    /// (`allow_management`).
+    pub fn management_enabled(&self) -> bool {
+        self.config.allow_management
+    }
+
+    /// Adds `if_name` to the vnStat database (`vnstat --add -i <if_name>`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is invalid or vnStat fails (see
+    /// [`run_management_command`](Self::run_management_command)).
+    pub async fn add_interface(&self, if_name: &str) -> Result<()> {
+        validate_interface_name(if_name)?;
+        self.run_management_command(&["--add", "-i", if_name]).await
+    }
+
+    /// Removes `if_name` and all its data from the vnStat database
+    /// (`vnstat --remove -i <if_name> --force`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is invalid or vnStat fails (see
+    /// [`run_management_command`](Self::run_management_command)).
+    pub async fn remove_interface(&self, if_name: &str) -> Result<()> {
+        validate_interface_name(if_name)?;
+        self.run_management_command(&["--remove", "-i", if_name, "--force"])
+            .await
+    }
+
+    /// Sets the alias vnStat stores for `if_name`
+    /// (`vnstat -i <if_name> --setalias <alias>`); an empty alias removes
+    /// it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name or alias is invalid or vnStat fails (see
+    /// [`run_management_command`](Self::run_management_command)).
+    pub async fn set_interface_alias(&self, if_name: &str, alias: &str) -> Result<()> {
+        validate_interface_name(if_name)?;
+        validate_alias(alias)?;
+        self.run_management_command(&["-i", if_name, "--setalias", alias])
+            .await
+    }
+
+    /// Runs a vnStat command that modifies the database and drops the
+    /// cached snapshot on success, so the change is visible immediately.
+    ///
+    /// vnStat reports some failures on stderr without a non-zero exit, so
+    /// any stderr line starting with `Error` also counts as a failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`VnstatError::CommandFailed`] if vnStat fails, or the error
+    /// from running it.
+    async fn run_management_command(&self, args: &[&str]) -> Result<()> {
+        let output = self
+            .executor
+            .run(Program::Vnstat, args, self.timeout())
+            .await?;
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reported_error = stderr
+            .lines()
+            .any(|line| line.trim_start().starts_with("Error"));
+        if !output.success() || reported_error {
+            return Err(command_failed(&output).into());
+        }
+
+        info!("vnStat database changed: vnstat {}", args.join(" "));
+        self.invalidate().await;
+        self.images.lock().await.clear();
+
+        Ok(())
+    }
+
+    /// Returns `true` if vnstati can be run (`vnstati_executable` is
+    /// configured), enabling the image endpoint.
+    pub fn images_enabled(&self) -> bool {
+        self.executor.is_available(Program::Vnstati)
+    }
+
+    /// Renders a PNG graph of an interface with vnstati
+    /// (`vnstati -i <name> <kind> -o -`).
+    ///
+    /// Images are cached per interface, graph and width for as long as a
+    /// vnStat snapshot, so repeated requests do not re-render.
+    ///
+    /// # Arguments
+    ///
+    /// * `if_name` - Real name or alias of the interface.
+    /// * `kind` - The graph to render.
+    /// * `width` - Approximate image width in pixels, passed to vnstati as
+    ///   `--scale` (see [`scale_for_width`]); vnstati's default if `None`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if vnstati is not configured, the interface does not
+    /// exist or is hidden, or vnstati fails, times out or prints nothing.
+    pub async fn render_interface_image(
+        &self,
+        if_name: impl AsRef<str>,
+        kind: ImageKind,
+        width: Option<u32>,
+    ) -> Result<Bytes> {
+        let name = self
+            .fetch_interface_stats(if_name)
+            .await?
+            .value
+            .name
+            .clone();
+        validate_interface_name(&name)?;
+
+        let key = (name, kind, width);
+        if let Some(image) = self.images.lock().await.get(&key, self.cache_ttl()) {
+            return Ok(image);
+        }
+
+        let scale = width.map(|w| scale_for_width(w).to_string());
+        let mut args = vec!["-i", key.0.as_str(), kind.flag(), "-o", "-"];
+        if let Some(scale) = &scale {
+            args.extend(["--scale", scale.as_str()]);
+        }
+
+        let output = self
+            .executor
+            .run(Program::Vnstati, &args, self.timeout())
+            .await?;
+        if !output.success() {
+            return Err(command_failed(&output).into());
+        }
+        if output.stdout.is_empty() {
+            return Err(VnstatError::InvalidOutput("vnstati printed no image".to_string()).into());
+        }
+
+        let image = Bytes::from(output.stdout);
+        self.images
+            .lock()
+            .await
+            .insert(key, image.clone(), self.cache_ttl());
+
+        Ok(image)
+    }
+
+    /// Returns the age of the most recent successfully fetched snapshot, or
+    /// `None` if vnStat data has not been fetched yet.
+    pub async fn data_age(&self) -> Option<Duration> {
+        let cache = self.cache.lock().await;
+        cache
+            .snapshot
+            .as_ref()
+            .map(|(_, fetched_at)| fetched_at.elapsed())
+    }
+
+    /// Returns the configured maximum age of stale data.
+    pub fn max_stale(&self) -> Duration {
+        Duration::from_secs(self.max_stale_seconds.load(Ordering::Relaxed))
+    }
+
+    /// Returns how long a fetched snapshot is served before a refresh is
+    /// attempted.
+    pub fn cache_ttl(&self) -> Duration {
+        Duration::from_secs(self.cache_ttl_seconds.load(Ordering::Relaxed))
+    }
+
+    /// Sets the lifetime of cached snapshots and images
+    /// (`cache.ttl_seconds`). Until called, the default of [`CacheConfig`]
+    /// applies.
+    pub fn set_cache_ttl(&self, ttl: Duration) {
+        self.cache_ttl_seconds
+            .store(ttl.as_secs(), Ordering::Relaxed);
+    }
+
+    /// Applies the reloadable settings of `config`: the interface filter,
+    /// the aliases and `max_stale_seconds`. Every other setting keeps the
+    /// value the service was created with.
+    ///
+    /// The cached snapshot and images are dropped, so that the next request
+    /// presents fresh data with the new filter and aliases.
+    pub async fn reload(&self, config: &VnstatConfig) {
+        self.view.store(InterfaceView::new(config));
+        self.max_stale_seconds
+            .store(config.max_stale_seconds, Ordering::Relaxed);
+
+        let extra_args = ExtraArgs::new(config);
+        if *self.extra_args.load() != extra_args {
+            self.extra_args.store(extra_args);
+            self.log_commands();
+        }
+
+        self.invalidate().await;
+        self.images.lock().await.clear();
+    }
+
+    /// Returns the configured maximum duration of a live stream connection,
+    /// or `None` if unlimited.
+    fn live_max_duration(&self) -> Option<Duration> {
+        (self.config.live_max_duration_seconds > 0)
+            .then(|| Duration::from_secs(self.config.live_max_duration_seconds))
+    }
+
+    /// Returns how long a live long poll asking to wait `requested` seconds
+    /// waits, at most `live_poll_max_timeout_seconds`.
+    pub fn live_poll_timeout(&self, requested: u64) -> Duration {
+        Duration::from_secs(requested.min(self.config.live_poll_max_timeout_seconds))
+    }
+
+    /// Returns the configured interval between two checks of an update
+    /// watcher.
+    fn updates_poll_interval(&self) -> Duration {
+        Duration::from_secs(self.config.updates_poll_seconds)
+    }
+
+    /// Returns the configured timeout for a single vnStat invocation.
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.config.command_timeout_seconds)
+    }
+
+    /// Performs a quick health check by running `vnstat --json`.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the vnStat executable responds successfully within the
+    /// configured timeout.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the vnStat subprocess fails, exits with a non-zero
+    /// status code, or does not complete within the configured
+    /// `command_timeout_seconds`, or without running vnStat while the
+    /// circuit breaker is open.
+    pub async fn check_health(&self) -> Result<()> {
+        let permit = self.breaker.allow()?;
+        let started = Instant::now();
+        let result = self.run_health_check().await;
+        self.record_run(FetchKind::HealthCheck, permit, started, &result, |&len| len);
+        result.map(|_| ())
+    }
+
+    /// Runs `vnstat --json` for [`check_health`](Self::check_health),
+    /// returning the size of its output.
+    async fn run_health_check(&self) -> Result<usize> {
+        let args = self.json_args();
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let output = self
+            .executor
+            .run(Program::Vnstat, &args, self.timeout())
+            .await
+            .context("failed to execute vnStat for health check")?;
+
+        if !output.success() {
+            return Err(command_failed(&output).into());
+        }
+
+        Ok(output.stdout.len())
+    }
+
+    /// Returns `true` if the configured vnStat executable exists on disk.
+    pub fn executable_exists(&self) -> bool {
+        self.executor.is_available(Program::Vnstat)
+    }
+
+    /// Builds the task that streams live traffic for a given interface.
+    ///
+    /// The returned [`TaskSource`] comes from the executor; by default it
+    /// spawns a long-running `vnstat` process that emits JSON traffic
+    /// updates.
     ///
     /// # Arguments
     ///
-    /// * `executable` - Path or name of the `vnstat` binary (e.g. `"vnstat"`).
+    /// * `if_name` - The network interface to monitor in real time.
+    /// * `options` - The live mode and rate unit, if not vnStat's defaults.
     ///
     /// # Returns
     ///
-    /// A new `VnstatService` instance that will delegate all vnStat calls to
-    /// the given executable.
-    pub fn new(executable: impl Into<String>) -> Self {
-        Self {
-            executable: executable.into(),
-        }
+    /// The source running `vnstat <live_extra_args> -i <if_name> --json -l`,
+    /// followed by the arguments of `options`.
+    pub fn live_stream_source(&self, if_name: impl AsRef<str>, options: LiveOptions) -> TaskSource {
+        self.executor
+            .live(self.live_args(if_name.as_ref(), options))
     }
 
-    /// Fetches the full vnStat data JSON.
+    /// Returns the arguments of the live stream of `if_name` with
+    /// `options`, see [`live_stream_source`](Self::live_stream_source).
+    fn live_args(&self, if_name: &str, options: LiveOptions) -> Vec<String> {
+        let mut args = self.extra_args.load().live.clone();
+        args.extend(["-i".to_string(), if_name.to_string(), "--json".to_string()]);
+        args.extend(options.args());
+        args
+    }
+
+    /// Returns a streaming response of Server-Sent Events (SSE) that yields
+    /// live traffic statistics for the requested interface.
     ///
-    /// This is a public convenience wrapper around the cached free function
-    /// [`fetch_vnstat_data_cached`], using the service's configured
-    /// executable path as the cache key.
+    /// The stream subscribes to a [`TaskManager`] channel that wraps a
+    /// long-running `vnstat -l` process. Each line of JSON output is emitted
+    /// as an SSE event whose ID is the line's sequence number, which
+    /// increases monotonically per interface.
     ///
-    /// # Returns
+    /// A new client first receives the last line of the running process as
+    /// an `event: snapshot` event, or a comment saying the stream is warming
+    /// up if it has printed nothing yet. A client reconnecting with
+    /// `Last-Event-ID` instead receives the buffered lines it missed; if
+    /// some are no longer buffered, a comment saying so precedes them.
     ///
-    /// A [`VnstatData`] struct deserialized from the `vnstat --json` output.
+    /// The subscription is made before the stream is returned, so a refused
+    /// subscription can still be answered with an error status.
     ///
-    /// # Errors
+    /// # Arguments
     ///
-    /// Returns an error if the vnStat subprocess fails, times out, produces
-    /// invalid UTF-8, or returns JSON that cannot be deserialized.
-    pub async fn fetch_vnstat_data(&self) -> Result<VnstatData> {
-        fetch_vnstat_data_cached(self.executable.clone()).await
-    }
-
-    /// Lists all network interfaces tracked by vnStat.
+    /// * `manager` - Shared [`TaskManager`] responsible for managing the
+    ///   underlying vnStat subprocess.
+    /// * `if_name` - The network interface to stream live data for.
+    /// * `last_event_id` - The `Last-Event-ID` sent by a reconnecting client.
+    /// * `heartbeat` - If set, a `heartbeat <ms timestamp>` comment is sent
+    ///   whenever vnStat has produced nothing for this long, so proxies see
+    ///   traffic on idle interfaces.
+    /// * `lag` - What to do when the client falls behind and messages are
+    ///   dropped: see [`LagPolicy`]. With [`LagPolicy::Disconnect`] the
+    ///   stream ends with an `event: lagged` event carrying the number of
+    ///   dropped messages.
+    /// * `options` - The live mode and rate unit. Streams with the same
+    ///   options share a process, keyed by [`LiveOptions::task_key`].
     ///
     /// # Returns
     ///
-    /// A `Vec<String>` containing the names of every interface present in
-    /// the vnStat database.
+    /// A pinned, boxed [`Stream`] of events that never fails:
+    /// * a live-traffic data point;
+    /// * a comment (heartbeat, message lag, cancellation notice);
+    /// * an `event: error` event with a JSON payload
+    ///   `{"code": 10006, "message": "..."}` when the vnStat subprocess
+    ///   fails.
+    ///
+    /// The stream ends when the subprocess terminates, right after a fatal
+    /// error (spawn failure, no output in time, or an error once the
+    /// restarts are used up), or when the broadcast channel is closed. After
+    /// other errors, e.g. a read error followed by a restart, it goes on.
+    /// With `live_max_duration_seconds` configured, it also ends that long
+    /// after this connection subscribed, with a final `event: timeout`
+    /// event; the deadline is per connection, so other clients of the same
+    /// process are unaffected.
     ///
     /// # Errors
     ///
-    /// Propagates any error from [`fetch_vnstat_data`](Self::fetch_vnstat_data).
-    pub async fn list_vnstat_interfaces(&self) -> Result<Vec<String>> {
-        let data = self.fetch_vnstat_data().await?;
-        let interfaces: Vec<_> = data.interfaces.iter().map(|i| i.name.clone()).collect();
+    /// Returns an error if the command cannot be built, or a
+    /// [`SubscribeError`](crate::task_manager::SubscribeError) if a
+    /// live-stream limit is reached.
+    pub async fn stream_interface_live_stats(
+        &self,
+        manager: Arc<TaskManager>,
+        if_name: String,
+        last_event_id: Option<u64>,
+        heartbeat: Option<Duration>,
+        lag: LagPolicy,
+        options: LiveOptions,
+    ) -> Result<Pin<Box<dyn Stream<Item = std::result::Result<Event, Infallible>> + Send>>> {
+        let source = self.live_stream_source(&if_name, options);
+        let key = options.task_key(&if_name);
+        let stream_name = key.clone();
+        let max_duration = self.live_max_duration();
+        let (mut receiver, replay, guard) = manager.subscribe(key, source, last_event_id).await?;
+
+        Ok(Box::pin(stream! {
+            let _guard = guard;
+
+            if replay.skipped {
+                yield Ok(Event::default().comment("Some events were skipped: no longer buffered"));
+            }
+            for (seq, line) in replay.lines {
+                yield Ok(Event::default().data(line).id(seq.to_string()));
+            }
+            // A new client gets the current sample right away instead of
+            // waiting for the next one.
+            if last_event_id.is_none() {
+                match replay.latest {
+                    Some((seq, line)) => {
+                        yield Ok(Event::default().event("snapshot").data(line).id(seq.to_string()));
+                    }
+                    None => yield Ok(Event::default().comment("Stream warming up, waiting for the first sample")),
+                }
+            }
+            let deadline = max_duration.map(|duration| Instant::now() + duration);
+            // A message taken from the receiver while skipping to the latest
+            // data line, handled before receiving the next one.
+            let mut pending = None;
+
+            loop {
+                let event = match pending.take() {
+                    Some(message) => LiveEvent::Message(Ok(message)),
+                    None => {
+                        let next_heartbeat = heartbeat.map(|interval| Instant::now() + interval);
+                        tokio::select! {
+                            message = receiver.recv() => LiveEvent::Message(message),
+                            _ = tokio::time::sleep_until(next_heartbeat.unwrap_or_else(Instant::now)),
+                                if next_heartbeat.is_some() => LiveEvent::Heartbeat,
+                            _ = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now)),
+                                if deadline.is_some() => LiveEvent::Expired,
+                        }
+                    }
+                };
 
-        Ok(interfaces)
+                match event {
+                    LiveEvent::Message(Ok(message)) => match message {
+                        TaskMessage::Data(seq, data) => yield Ok(Event::default().data(data).id(seq.to_string())),
+                        TaskMessage::Error { message, fatal } => {
+                            yield Ok(error_event(&message));
+                            if fatal {
+                                break;
+                            }
+                        }
+                        TaskMessage::Comment(comment) => yield Ok(Event::default().comment(comment)),
+                        TaskMessage::Eof(_) => break
+                    },
+                    LiveEvent::Message(Err(RecvError::Closed)) => break,
+                    LiveEvent::Message(Err(RecvError::Lagged(n))) => match lag {
+                        LagPolicy::Skip => {
+                            warn!("SSE [{}] message lagged: dropped {} messages", stream_name, n);
+                            yield Ok(Event::default().comment("Message dropped (lag)"));
+                        }
+                        LagPolicy::Disconnect => {
+                            warn!("SSE [{}] message lagged: dropped {} messages, disconnecting", stream_name, n);
+                            yield Ok(Event::default()
+                                .event("lagged")
+                                .data(format!("{{\"droppedMessages\":{}}}", n)));
+                            break;
+                        }
+                        LagPolicy::Latest => {
+                            let drained = drain_to_latest(&mut receiver);
+                            let dropped = n + drained.dropped;
+                            warn!("SSE [{}] message lagged: dropped {} messages, skipping to the latest", stream_name, dropped);
+                            yield Ok(Event::default().comment(format!("{} messages dropped (lag), skipped to the latest", dropped)));
+                            if let Some((seq, data)) = drained.latest {
+                                yield Ok(Event::default().data(data).id(seq.to_string()));
+                            }
+                            pending = drained.next;
+                        }
+                    },
+                    LiveEvent::Heartbeat => {
+                        yield Ok(Event::default().comment(format!("heartbeat {}", timestamp::get_in_ms())));
+                    }
+                    LiveEvent::Expired => {
+                        let seconds = max_duration.unwrap_or_default().as_secs();
+                        info!("SSE [{}] closed after the maximum duration of {}s", stream_name, seconds);
+                        yield Ok(Event::default()
+                            .event("timeout")
+                            .data(format!("{{\"maxDurationSeconds\":{}}}", seconds)));
+                        break;
+                    }
+                }
+            }
+        }))
     }
 
-    /// Retrieves detailed statistics for a specific network interface.
+    /// Returns a single SSE stream merging the live traffic statistics of
+    /// several interfaces.
     ///
-    /// # Arguments
+    /// Each interface is subscribed to its own [`TaskManager`] task, so the
+    /// `vnstat -l` processes are shared with single-interface clients. Every
+    /// event is a JSON object tagged with the interface it belongs to:
     ///
-    /// * `if_name` - Name of the interface to look up (e.g. `"eth0"`).
+    /// * `{"interface": "eth0", "data": {...}}` — a line from `vnstat -l`.
+    /// * `{"interface": "wg0", "error": {"code": ..., "message": "..."}}` —
+    ///   the interface could not be resolved or subscribed, or its vnStat
+    ///   process failed. The interface stops streaming after a fatal error
+    ///   (see [`TaskMessage::Error`]); the other interfaces keep streaming.
     ///
-    /// # Returns
+    /// Heartbeats and the `live_max_duration_seconds` deadline apply to the
+    /// connection as a whole, as in
+    /// [`stream_interface_live_stats`](Self::stream_interface_live_stats).
+    /// The stream ends when every interface's stream has ended; dropping it
+    /// releases all subscriptions.
+    ///
+    /// # Arguments
     ///
-    /// The [`Interface`] struct matching the requested interface name.
+    /// * `manager` - Shared [`TaskManager`] responsible for managing the
+    ///   underlying vnStat subprocesses.
+    /// * `interfaces` - The interfaces to stream, as pairs of the name used
+    ///   to tag events and the resolved interface name (or the error that
+    ///   prevented resolving it).
+    /// * `heartbeat` - If set, a `heartbeat <ms timestamp>` comment is sent
+    ///   whenever no interface has produced anything for this long.
     ///
     /// # Errors
     ///
-    /// Returns an error if the interface is not found in the vnStat data, or
-    /// if the underlying data fetch fails.
-    pub async fn fetch_interface_stats(&self, if_name: impl AsRef<str>) -> Result<Interface> {
-        let data = self.fetch_vnstat_data().await?;
+    /// Returns the first resolution or subscription error if none of the
+    /// interfaces could be subscribed.
+    pub async fn stream_multi_interface_live_stats(
+        &self,
+        manager: Arc<TaskManager>,
+        interfaces: Vec<(String, Result<String>)>,
+        heartbeat: Option<Duration>,
+    ) -> Result<Pin<Box<dyn Stream<Item = std::result::Result<Event, Infallible>> + Send>>> {
+        let max_duration = self.live_max_duration();
+        let mut streams = Vec::with_capacity(interfaces.len());
+        let mut failures = Vec::new();
 
-        data.interfaces
-            .iter()
-            .find(|i| i.name == if_name.as_ref())
-            .cloned()
-            .ok_or_else(|| anyhow::anyhow!("interface not found"))
+        for (label, if_name) in interfaces {
+            let subscription = match if_name {
+                Ok(if_name) => self.subscribe_interface_live(&manager, if_name).await,
+                Err(e) => Err(e),
+            };
+
+            match subscription {
+                Ok((receiver, guard)) => streams.push(tagged_messages(label, receiver, guard)),
+                Err(e) => failures.push((label, e)),
+            }
+        }
+
+        if streams.is_empty() && !failures.is_empty() {
+            return Err(failures.swap_remove(0).1);
+        }
+
+        let mut merged = futures_util::stream::select_all(streams);
+
+        Ok(Box::pin(stream! {
+            for (label, e) in failures {
+                let code = ErrorCode::from_error(&e, ErrorCode::NoSuchInterface);
+                let message = match e.downcast_ref::<AmbiguousInterface>() {
+                    Some(ambiguous) => ambiguous.to_string(),
+                    None => code.message().to_string(),
+                };
+                yield Ok(Event::default().data(tagged_error(&label, code, &message)));
+            }
+            let deadline = max_duration.map(|duration| Instant::now() + duration);
+
+            loop {
+                let next_heartbeat = heartbeat.map(|interval| Instant::now() + interval);
+                let event = tokio::select! {
+                    message = merged.next() => LiveEvent::Message(message),
+                    _ = tokio::time::sleep_until(next_heartbeat.unwrap_or_else(Instant::now)),
+                        if next_heartbeat.is_some() => LiveEvent::Heartbeat,
+                    _ = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now)),
+                        if deadline.is_some() => LiveEvent::Expired,
+                };
+
+                match event {
+                    LiveEvent::Message(Some((label, message))) => match message {
+                        Ok(TaskMessage::Data(_, data)) => {
+                            let tag = Value::from(label.as_str());
+                            yield Ok(Event::default().data(format!("{{\"interface\":{},\"data\":{}}}", tag, data.trim())));
+                        }
+                        Ok(TaskMessage::Error { message, .. }) => {
+                            let code = ErrorCode::VnstatExecutionFailed;
+                            yield Ok(Event::default().data(tagged_error(&label, code, &message)));
+                        }
+                        Ok(TaskMessage::Comment(comment)) => {
+                            yield Ok(Event::default().comment(format!("{}: {}", label, comment)));
+                        }
+                        Ok(TaskMessage::Eof(_)) | Err(RecvError::Closed) => {}
+                        Err(RecvError::Lagged(n)) => {
+                            warn!("SSE [{}] message lagged: dropped {} messages", label, n);
+                            yield Ok(Event::default().comment(format!("{}: Message dropped (lag)", label)));
+                        }
+                    },
+                    LiveEvent::Message(None) => break,
+                    LiveEvent::Heartbeat => {
+                        yield Ok(Event::default().comment(format!("heartbeat {}", timestamp::get_in_ms())));
+                    }
+                    LiveEvent::Expired => {
+                        let seconds = max_duration.unwrap_or_default().as_secs();
+                        info!("Multiplexed SSE closed after the maximum duration of {}s", seconds);
+                        yield Ok(Event::default()
+                            .event("timeout")
+                            .data(format!("{{\"maxDurationSeconds\":{}}}", seconds)));
+                        break;
+                    }
+                }
+            }
+        }))
     }
 
-    /// Performs a quick health check by running `vnstat --json`.
+    /// Returns an SSE stream that pushes an interface's data whenever vnStat
+    /// has written new data for it.
     ///
-    /// # Returns
+    /// The stream starts with the current data of the interface. After that,
+    /// each event is an [`InterfaceUpdate`] carrying the fresh data and the
+    /// traffic since the previous update. All clients of an interface share
+    /// one watcher task (key `updates:<name>`, see
+    /// [`watch_interface_updates`]), which polls the cached data every
+    /// `updates_poll_seconds` and stops with its last client.
     ///
-    /// `Ok(())` if the vnStat executable responds successfully within the
-    /// configured timeout.
+    /// # Arguments
+    ///
+    /// * `manager` - Shared [`TaskManager`] running the watcher.
+    /// * `if_name` - Real name or alias of the interface.
     ///
     /// # Errors
     ///
-    /// Returns an error if the vnStat subprocess fails, exits with a non-zero
-    /// status code, or does not complete within [`VNSTAT_TIMEOUT`].
-    pub async fn check_health(&self) -> Result<()> {
-        tokio::time::timeout(VNSTAT_TIMEOUT, async {
-            let output = tokio::process::Command::new(&self.executable)
-                .arg("--json")
-                .output()
-                .await
-                .context("failed to execute vnStat for health check")?;
-
-            if !output.status.success() {
-                anyhow::bail!(
-                    "vnstat returned non-zero exit code: {:?}",
-                    output.status.code()
-                );
-            }
+    /// Returns an error if the interface cannot be fetched, or a
+    /// [`SubscribeError`](crate::task_manager::SubscribeError) if the SSE
+    /// client limit is reached.
+    pub async fn stream_interface_updates(
+        self: &Arc<Self>,
+        manager: Arc<TaskManager>,
+        if_name: String,
+    ) -> Result<Pin<Box<dyn Stream<Item = std::result::Result<Event, Infallible>> + Send>>> {
+        let interface = self.fetch_interface_stats(&if_name).await?.value;
+        let name = interface.name.clone();
 
-            Ok(())
+        let vnstat = Arc::clone(self);
+        let watched = name.clone();
+        let interval = self.updates_poll_interval();
+        let source = TaskSource::Watcher(Box::new(move |sink| {
+            Box::pin(watch_interface_updates(vnstat, watched, interval, sink))
+        }));
+        let (mut receiver, _replay, guard) = manager
+            .subscribe(format!("updates:{}", name), source, None)
+            .await?;
+
+        let snapshot = serde_json::to_string(&InterfaceUpdate {
+            interface: &interface,
+            delta: None,
         })
-        .await
-        .context("vnstat health check timed out")?
+        .context("failed to serialise interface snapshot")?;
+
+        Ok(Box::pin(stream! {
+            let _guard = guard;
+
+            yield Ok(Event::default().data(snapshot));
+
+            loop {
+                match receiver.recv().await {
+                    Ok(TaskMessage::Data(seq, data)) => yield Ok(Event::default().data(data).id(seq.to_string())),
+                    Ok(TaskMessage::Error { message, fatal }) => {
+                        yield Ok(error_event(&message));
+                        if fatal {
+                            break;
+                        }
+                    }
+                    Ok(TaskMessage::Comment(comment)) => yield Ok(Event::default().comment(comment)),
+                    Ok(TaskMessage::Eof(_)) | Err(RecvError::Closed) => break,
+                    Err(RecvError::Lagged(n)) => {
+                        warn!("SSE [updates:{}] message lagged: dropped {} messages", name, n);
+                        yield Ok(Event::default().comment("Message dropped (lag)"));
+                    }
+                }
+            }
+        }))
     }
 
-    /// Builds the command-line arguments required to start a live-traffic
-    /// stream for a given interface.
+    /// Subscribes to the live-traffic task of an interface without wrapping
+    /// it in an SSE stream, for transports that forward the messages
+    /// themselves (e.g. WebSockets).
     ///
-    /// The returned `Vec<String>` is suitable for spawning a long-running
-    /// `vnstat` process that emits JSON traffic updates.
+    /// The subscription shares the `vnstat -l` process with SSE clients of
+    /// the same interface; dropping the returned guard releases it.
     ///
     /// # Arguments
     ///
-    /// * `if_name` - The network interface to monitor in real time.
+    /// * `manager` - Shared [`TaskManager`] responsible for managing the
+    ///   underlying vnStat subprocess.
+    /// * `if_name` - The network interface to stream live data for.
     ///
     /// # Returns
     ///
-    /// A vector of command tokens: `[executable, "-i", if_name, "--json", "-l"]`.
+    /// The task's broadcast receiver and the subscription's drop guard.
     ///
     /// # Errors
     ///
-    /// This function currently never returns `Err`; the `Result` return type
-    /// is reserved for future validation.
-    pub fn build_live_stream_command(&self, if_name: impl AsRef<str>) -> Result<Vec<String>> {
-        Ok(vec![
-            self.executable.clone(),
-            "-i".to_string(),
-            if_name.as_ref().to_string(),
-            "--json".to_string(),
-            "-l".to_string(),
-        ])
+    /// Returns an error if the command cannot be built, or a
+    /// [`SubscribeError`](crate::task_manager::SubscribeError) if a
+    /// live-stream limit is reached.
+    pub async fn subscribe_interface_live(
+        &self,
+        manager: &Arc<TaskManager>,
+        if_name: String,
+    ) -> Result<(Receiver<TaskMessage>, TaskDropGuard)> {
+        let source = self.live_stream_source(&if_name, LiveOptions::default());
+        let (receiver, _replay, guard) = manager.subscribe(if_name, source, None).await?;
+
+        Ok((receiver, guard))
     }
 
-    /// Returns a streaming response of Server-Sent Events (SSE) that yields
-    /// live traffic statistics for the requested interface.
+    /// Waits for the live traffic of an interface after the line numbered
+    /// `since`, for clients that cannot keep a stream open (long polling).
     ///
-    /// The stream subscribes to a [`TaskManager`] channel that wraps a
-    /// long-running `vnstat -l` process. Each line of JSON output is emitted
-    /// as an SSE event with a millisecond-precision timestamp as the event ID.
+    /// The poll subscribes to the same task as the SSE and WebSocket
+    /// clients of the interface and keeps its subscription for
+    /// [`LIVE_POLL_LINGER`] after answering, so that a client polling again
+    /// right away finds the `vnstat -l` process still running and misses no
+    /// lines; once no poll has come for that long, the process is stopped
+    /// as for any other client. Only the last poll of a task lingers: its
+    /// subscription replaces the one of the poll before.
     ///
     /// # Arguments
     ///
     /// * `manager` - Shared [`TaskManager`] responsible for managing the
     ///   underlying vnStat subprocess.
-    /// * `if_name` - The network interface to stream live data for.
+    /// * `if_name` - The network interface to poll live data for.
+    /// * `since` - The sequence number of the last line the client has;
+    ///   `None` for a first poll.
+    /// * `timeout` - How long to wait for a new line.
     ///
     /// # Returns
     ///
-    /// A pinned, boxed [`Stream`] producing `Result<Event, String>` items:
-    /// * `Ok(Event)` — a live-traffic data point (or a comment on message lag).
-    /// * `Err(String)` — an unrecoverable error from the vnStat subprocess.
+    /// The buffered lines after `since` if there are any, otherwise (and
+    /// for a first poll, the latest line if there is one) the lines printed
+    /// until the first one arrives, oldest first. Empty if nothing arrived
+    /// within `timeout` or the process ended. [`LivePoll::gap`] is set when
+    /// lines after `since` are missing.
     ///
-    /// The stream ends when the subprocess terminates or the broadcast channel
-    /// is closed.
-    pub async fn stream_interface_live_stats(
+    /// # Errors
+    ///
+    /// Returns an error if the command cannot be built, a
+    /// [`SubscribeError`](crate::task_manager::SubscribeError) if a
+    /// live-stream limit is reached, or [`VnstatError::SpawnFailed`] if the
+    /// process failed for good while waiting.
+    pub async fn poll_interface_live(
         &self,
-        manager: Arc<TaskManager>,
+        manager: &Arc<TaskManager>,
         if_name: String,
-    ) -> Pin<Box<dyn Stream<Item = Result<Event, String>> + Send>> {
-        let cmd = match self.build_live_stream_command(&if_name) {
-            Ok(cmd) => cmd,
-            Err(e) => {
-                warn!("Failed to build live stream command: {}", e);
-                return Box::pin(futures_util::stream::once(async move {
-                    Err(format!("Failed to start live stream: {}", e))
-                }));
-            }
-        };
-        let stream_name = if_name.clone();
-
-        Box::pin(stream! {
-            let (mut receiver, _guard) = manager.subscribe(if_name.clone(), cmd).await;
+        since: Option<u64>,
+        timeout: Duration,
+    ) -> Result<LivePoll> {
+        let source = self.live_stream_source(&if_name, LiveOptions::default());
+        let (mut receiver, replay, guard) =
+            manager.subscribe(if_name.clone(), source, since).await?;
 
-            loop {
-                match receiver.recv().await {
-                    Ok(message) => match message {
-                        TaskMessage::Data(data) => yield Ok(Event::default().data(data).id(timestamp::get_in_ms().to_string())),
-                        TaskMessage::Error(error) => yield Err(error),
-                        TaskMessage::Eof => break
-                    },
-                    Err(RecvError::Closed) => break,
-                    Err(RecvError::Lagged(n)) => {
-                        warn!("SSE [{}] message lagged: dropped {} messages", stream_name, n);
-                        yield Ok(Event::default().comment("Message dropped (lag)"));
+        let gap = replay.skipped;
+        let mut lines = replay.lines;
+        if since.is_none() {
+            lines.extend(replay.latest);
+        }
+        let result = async {
+            if lines.is_empty() {
+                let deadline = Instant::now() + timeout;
+                loop {
+                    match tokio::time::timeout_at(deadline, receiver.recv()).await {
+                        Ok(Ok(TaskMessage::Data(seq, line))) => {
+                            lines.push((seq, line));
+                            break;
+                        }
+                        Ok(Ok(TaskMessage::Error {
+                            message,
+                            fatal: true,
+                        })) => return Err(VnstatError::SpawnFailed(message).into()),
+                        Ok(Ok(TaskMessage::Eof(_))) | Ok(Err(RecvError::Closed)) | Err(_) => break,
+                        Ok(Ok(_)) | Ok(Err(RecvError::Lagged(_))) => {}
                     }
                 }
             }
-        })
+            // Lines printed meanwhile.
+            loop {
+                match receiver.try_recv() {
+                    Ok(TaskMessage::Data(seq, line)) => lines.push((seq, line)),
+                    Ok(_) | Err(TryRecvError::Lagged(_)) => {}
+                    Err(_) => break,
+                }
+            }
+            Ok(LivePoll {
+                lines: lines
+                    .into_iter()
+                    .map(|(seq, line)| LivePollLine { seq, line })
+                    .collect(),
+                gap,
+            })
+        }
+        .await;
+
+        self.poll_lingers.linger(if_name, guard);
+
+        result
+    }
+}
+
+/// What woke up a live stream: a message from the task(s), or one of its
+/// timers.
+enum LiveEvent<M = std::result::Result<TaskMessage, RecvError>> {
+    /// A task broadcast a message (or the channel lagged or closed).
+    Message(M),
+    /// vnStat produced nothing for the heartbeat interval.
+    Heartbeat,
+    /// The connection reached `live_max_duration_seconds`.
+    Expired,
+}
+
+/// A task message (or receive error) tagged with the name of its interface.
+type TaggedMessage = (String, std::result::Result<TaskMessage, RecvError>);
+
+/// Turns a task subscription into a stream of its messages tagged with
+/// `label`, ending after the task's last message (end of stream, error or
+/// closed channel). The subscription is released when the stream is
+/// dropped.
+fn tagged_messages(
+    label: String,
+    mut receiver: Receiver<TaskMessage>,
+    guard: TaskDropGuard,
+) -> Pin<Box<dyn Stream<Item = TaggedMessage> + Send>> {
+    Box::pin(stream! {
+        let _guard = guard;
+
+        loop {
+            let message = receiver.recv().await;
+            let last = matches!(
+                message,
+                Ok(TaskMessage::Eof(_))
+                    | Ok(TaskMessage::Error { fatal: true, .. })
+                    | Err(RecvError::Closed)
+            );
+            yield (label.clone(), message);
+            if last {
+                break;
+            }
+        }
+    })
+}
+
+/// Builds the data of a multiplexed live-stream event reporting an error
+/// for one interface.
+fn tagged_error(label: &str, code: ErrorCode, message: &str) -> String {
+    serde_json::json!({
+        "interface": label,
+        "error": { "code": code.code(), "message": message },
+    })
+    .to_string()
+}
+
+/// Builds the `event: error` event of a live stream reporting a task error,
+/// e.g. `{"code":10006,"message":"..."}`.
+fn error_event(message: &str) -> Event {
+    let code = ErrorCode::VnstatExecutionFailed;
+    Event::default()
+        .event("error")
+        .data(serde_json::json!({ "code": code.code(), "message": message }).to_string())
+}
+
+/// Returns the JSON document in the stdout of `vnstat --json`.
+///
+/// Bytes that are not valid UTF-8 (e.g. from an odd locale) are replaced
+/// with U+FFFD, and text around the document (e.g. timing information
+/// printed by a wrapper script) is dropped, each with a warning, instead of
+/// failing every request.
+///
+/// # Errors
+///
+/// Returns [`VnstatError::InvalidOutput`] if the output holds no `{`.
+fn extract_vnstat_json(stdout: Vec<u8>) -> Result<String, VnstatError> {
+    let mut text = match String::from_utf8(stdout) {
+        Ok(text) => text,
+        Err(e) => {
+            warn!("vnStat output is not valid UTF-8, replacing the invalid bytes");
+            String::from_utf8_lossy(e.as_bytes()).into_owned()
+        }
+    };
+
+    let start = text
+        .find('{')
+        .ok_or_else(|| VnstatError::InvalidOutput("no JSON document in the output".to_string()))?;
+
+    // The end of the first complete document; if there is none, the whole
+    // rest is kept for the parser to report what is wrong with it.
+    let mut documents =
+        serde_json::Deserializer::from_str(&text[start..]).into_iter::<serde::de::IgnoredAny>();
+    let end = match documents.next() {
+        Some(Ok(_)) => start + documents.byte_offset(),
+        _ => text.len(),
+    };
+
+    let ignored = text[..start].trim().len() + text[end..].trim().len();
+    if ignored > 0 {
+        warn!(
+            "Ignoring {} bytes of vnStat output around its JSON document",
+            ignored
+        );
+    }
+
+    text.truncate(end);
+    text.drain(..start);
+    Ok(text)
+}
+
+/// Parses `vnstat --json` output of any supported schema version into the
+/// version-2 model.
+///
+/// The `jsonversion` field is inspected first: `1` (vnStat 1.x) documents
+/// are converted via [`VnstatDataV1`], `2` documents are parsed directly.
+///
+/// # Errors
+///
+/// Returns [`VnstatError::UnsupportedJsonVersion`] for any other version, or
+/// an error if the document does not match its schema.
+fn parse_vnstat_json(json: &str) -> Result<VnstatData> {
+    let invalid =
+        |what: &str, e: serde_json::Error| VnstatError::InvalidOutput(format!("{}: {}", what, e));
+
+    let probe: JsonVersionProbe =
+        serde_json::from_str(json).map_err(|e| invalid("no readable jsonversion", e))?;
+
+    match probe.jsonversion.split('.').next().unwrap_or_default() {
+        "1" => {
+            let v1: VnstatDataV1 =
+                serde_json::from_str(json).map_err(|e| invalid("invalid vnStat 1.x JSON", e))?;
+            Ok(v1.into())
+        }
+        "2" => Ok(serde_json::from_str(json).map_err(|e| invalid("invalid vnStat JSON", e))?),
+        _ => Err(VnstatError::UnsupportedJsonVersion(probe.jsonversion).into()),
+    }
+}
+
+/// Returns the `name` field of a raw vnStat interface object.
+fn raw_interface_name(interface: &Value) -> Option<&str> {
+    interface.get("name").and_then(Value::as_str)
+}
+
+/// Builds the error for a vnStat process that exited unsuccessfully and
+/// logs its exit status and stderr.
+fn command_failed(output: &CommandOutput) -> VnstatError {
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    match output.code {
+        Some(code) => warn!("vnStat failed (exit code {}): {}", code, stderr),
+        None => warn!("vnStat failed (killed by a signal): {}", stderr),
+    }
+
+    VnstatError::CommandFailed {
+        code: output.code,
+        stderr,
+    }
+}
+
+/// Runs `vnstat --json`, retrying up to `options.retries` times with a
+/// linear backoff when vnStat exits unsuccessfully without printing
+/// anything (a transient failure, e.g. the database being locked).
+///
+/// # Errors
+///
+/// Returns the last failure once retries are exhausted; timeouts and spawn
+/// failures are not retried.
+async fn run_vnstat_json(executor: &dyn VnstatExecutor, options: &FetchOptions) -> Result<Vec<u8>> {
+    let mut attempt = 0;
+
+    let args: Vec<&str> = options.args.iter().map(String::as_str).collect();
+    loop {
+        let output = executor
+            .run(Program::Vnstat, &args, options.timeout)
+            .await?;
+
+        if output.success() {
+            return Ok(output.stdout);
+        }
+
+        // Even when stdout holds valid JSON, a non-zero exit is an error.
+        let error = command_failed(&output);
+        let transient = output.stdout.iter().all(u8::is_ascii_whitespace);
+        if !transient || attempt >= options.retries {
+            return Err(error.into());
+        }
+
+        attempt += 1;
+        warn!("Retrying vnstat --json ({}/{})", attempt, options.retries);
+        tokio::time::sleep(RETRY_BACKOFF * attempt).await;
     }
 }
 
-/// Fetches vnStat data with a single-entry, 60-second in-memory cache.
+/// Runs `vnstat --json` and builds a snapshot from its output.
 ///
-/// The cache key is the executable path (constant at runtime), so repeated
-/// calls within the 60-second window return the cached result without
-/// re-invoking the subprocess. The TTL refreshes on every cache hit.
+/// The parsed data is passed through [`fill_missing_timestamps`] and
+/// [`normalize_future_timestamps`].
 ///
 /// # Arguments
 ///
-/// * `executable` — Path or name of the `vnstat` binary.
+/// * `executor` — Runs `vnstat --json`.
+/// * `options` — Timeout, retry and normalisation settings.
+/// * `present` — Applied to the normalised data before it is wrapped, see
+///   [`VnstatService::present`].
 ///
 /// # Returns
 ///
-/// A [`VnstatData`] struct deserialized from the `vnstat --json` output.
+/// A [`VnstatSnapshot`] holding the raw `vnstat --json` output and the
+/// [`VnstatData`] deserialized from it.
 ///
 /// # Errors
 ///
 /// Returns an error if:
-/// * The vnStat subprocess fails to start or returns a non-zero exit code.
-/// * The command times out after [`VNSTAT_TIMEOUT`].
-/// * The stdout is not valid UTF-8.
-/// * The JSON payload cannot be deserialized into [`VnstatData`].
-#[cached(max_size = 1, ttl = 60, refresh = true)]
-async fn fetch_vnstat_data_cached(executable: String) -> Result<VnstatData> {
-    let output = tokio::time::timeout(VNSTAT_TIMEOUT, async {
-        tokio::process::Command::new(&executable)
-            .arg("--json")
-            .output()
-            .await
-            .context("failed to execute vnStat")
+/// * The vnStat subprocess fails to start or returns a non-zero exit code
+///   (after the configured retries).
+/// * The command times out after `options.timeout`.
+/// * The stdout holds no JSON document (see [`extract_vnstat_json`]).
+/// * The JSON payload has an unsupported `jsonversion` or cannot be
+///   deserialized into [`VnstatData`].
+async fn fetch_vnstat_snapshot(
+    executor: &dyn VnstatExecutor,
+    options: &FetchOptions,
+    present: impl FnOnce(&mut VnstatData),
+) -> Result<VnstatSnapshot> {
+    let generated_at = timestamp::get_in_secs();
+    let stdout = run_vnstat_json(executor, options).await?;
+
+    let json = extract_vnstat_json(stdout)?;
+
+    let mut data = parse_vnstat_json(&json)?;
+
+    fill_missing_timestamps(&mut data);
+    normalize_future_timestamps(
+        &mut data,
+        options.future_policy,
+        options.future_tolerance,
+        timestamp::get_in_secs(),
+    );
+    present(&mut data);
+
+    Ok(VnstatSnapshot {
+        raw: Bytes::from(json),
+        data: Arc::new(data),
+        rendered: Arc::default(),
+        generated_at,
     })
-    .await
-    .context("vnstat command timed out")?
-    .context("failed to execute vnStat")?;
+}
+
+/// Builds the interface list of `data`: one [`InterfaceEntry`] per
+/// interface, or just the names when `names_only` is set.
+fn interface_list(
+    data: &VnstatData,
+    names_only: bool,
+    stale_after: u64,
+    now: i64,
+) -> InterfacesResponse {
+    if names_only {
+        return interface_names(data);
+    }
+
+    InterfacesResponse::Entries(interface_entries(data, stale_after, now).collect())
+}
+
+/// Builds the interface list of `data` holding just the names.
+fn interface_names(data: &VnstatData) -> InterfacesResponse {
+    InterfacesResponse::Names(data.interfaces.iter().map(|i| i.name.clone()).collect())
+}
+
+/// Finds the interface labelled `label` in `data`: the one with that real
+/// name, otherwise the one whose alias equals it, ignoring case. Empty
+/// aliases never match.
+///
+/// # Returns
+///
+/// The index of the interface in `data.interfaces`.
+///
+/// # Errors
+///
+/// Returns [`NoInterfaces`] if `data` has no interface at all, an error if
+/// no interface matches, or an [`AmbiguousInterface`] listing the
+/// candidates if several interfaces carry the alias.
+fn resolve_interface(data: &VnstatData, label: &str) -> Result<usize> {
+    ensure_interfaces(data)?;
+    if let Some(index) = data.interfaces.iter().position(|i| i.name == label) {
+        return Ok(index);
+    }
+
+    let label_lower = label.to_lowercase();
+    let matches: Vec<usize> = data
+        .interfaces
+        .iter()
+        .enumerate()
+        .filter(|(_, i)| !i.alias.is_empty() && i.alias.to_lowercase() == label_lower)
+        .map(|(index, _)| index)
+        .collect();
+
+    match matches.as_slice() {
+        [] => Err(anyhow::anyhow!("interface not found")),
+        [index] => Ok(*index),
+        _ => Err(AmbiguousInterface {
+            label: label.to_string(),
+            candidates: matches
+                .iter()
+                .map(|&index| data.interfaces[index].name.clone())
+                .collect(),
+        }
+        .into()),
+    }
+}
+
+/// Returns [`NoInterfaces`] if `data` has no (visible) interface.
+fn ensure_interfaces(data: &VnstatData) -> Result<()> {
+    if data.interfaces.is_empty() {
+        return Err(NoInterfaces.into());
+    }
+    Ok(())
+}
+
+/// Builds the [`InterfaceEntry`] of every interface of `data`, as of `now`.
+fn interface_entries(
+    data: &VnstatData,
+    stale_after: u64,
+    now: i64,
+) -> impl Iterator<Item = InterfaceEntry> + '_ {
+    data.interfaces
+        .iter()
+        .map(move |i| interface_entry(i, stale_after, now))
+}
+
+/// Builds the [`InterfaceEntry`] of `interface`, as of `now`.
+fn interface_entry(interface: &Interface, stale_after: u64, now: i64) -> InterfaceEntry {
+    InterfaceEntry {
+        name: interface.name.clone(),
+        alias: interface.alias.clone(),
+        updated_timestamp: interface.updated.timestamp,
+        seconds_since_update: seconds_since(interface.updated.timestamp, now),
+        stale: is_stale(interface.updated.timestamp, stale_after, now),
+        total_rx: interface.traffic.total.rx,
+        total_tx: interface.traffic.total.tx,
+    }
+}
+
+/// Returns the seconds from `updated_timestamp` to `now`; `0` if the
+/// update lies in the future.
+fn seconds_since(updated_timestamp: i64, now: i64) -> i64 {
+    now.saturating_sub(updated_timestamp).max(0)
+}
+
+/// Returns `true` if `updated_timestamp` is more than `stale_after` seconds
+/// before `now`; never if `stale_after` is `0`.
+fn is_stale(updated_timestamp: i64, stale_after: u64, now: i64) -> bool {
+    stale_after > 0 && seconds_since(updated_timestamp, now).unsigned_abs() > stale_after
+}
+
+/// Serialises `value` as a JSend success response, flagged with
+/// [`ErrorCode::StaleData`] if `stale`.
+fn render_json<T: Serialize>(value: T, stale: bool) -> Result<Bytes> {
+    let response = if stale {
+        JsendResponse::success_with_data_and_code(value, ErrorCode::StaleData)
+    } else {
+        JsendResponse::success_with_data(value)
+    };
+
+    Ok(Bytes::from(serde_json::to_vec(&response)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../../tests/fixtures/vnstat2.json");
 
-    let json_str =
-        String::from_utf8(output.stdout).context("failed to parse vnStat response as UTF-8")?;
+    /// Extracts and parses `stdout` like a fetch does.
+    fn parse(stdout: impl Into<Vec<u8>>) -> Result<VnstatData> {
+        parse_vnstat_json(&extract_vnstat_json(stdout.into())?)
+    }
+
+    /// Returns the [`VnstatError`] of `result`.
+    fn vnstat_error(result: Result<VnstatData>) -> VnstatError {
+        let error = result.expect_err("corrupted output");
+        error
+            .downcast_ref::<VnstatError>()
+            .unwrap_or_else(|| panic!("not a VnstatError: {:#}", error))
+            .clone()
+    }
+
+    #[test]
+    fn truncated_output_is_invalid() {
+        let document = FIXTURE.trim_end();
+        for end in (1..document.len()).step_by(41) {
+            let error = vnstat_error(parse(&document.as_bytes()[..end]));
+            assert!(
+                matches!(error, VnstatError::InvalidOutput(_)),
+                "cut at {}: {:?}",
+                end,
+                error
+            );
+        }
+
+        assert_eq!(
+            vnstat_error(parse("vnstat: database is locked\n")),
+            VnstatError::InvalidOutput("no JSON document in the output".to_string())
+        );
+        assert!(matches!(
+            vnstat_error(parse("")),
+            VnstatError::InvalidOutput(_)
+        ));
+    }
+
+    #[test]
+    fn invalid_utf8_is_replaced() {
+        let before = [&b"\xff"[..], FIXTURE.as_bytes()].concat();
+        let inside = {
+            let mut bytes = FIXTURE.as_bytes().to_vec();
+            let at = FIXTURE.find("uplink").expect("an alias") + 2;
+            bytes[at] = 0xff;
+            bytes
+        };
+
+        assert!(parse(before).is_ok(), "an invalid byte before the document");
+        let data = parse(inside).expect("an invalid byte inside a string");
+        assert_eq!(data.interfaces[0].alias, "up\u{FFFD}ink");
+    }
+
+    #[test]
+    fn text_around_the_document_is_dropped() {
+        let document = FIXTURE.trim();
+        for stdout in [
+            format!("{}\nreal\t0m0.012s\n", document),
+            format!("Database loaded.\n{}", document),
+            format!("{}{}", document, document),
+            format!("{} }}", document),
+        ] {
+            assert_eq!(
+                extract_vnstat_json(stdout.clone().into_bytes()).unwrap(),
+                document,
+                "{}",
+                stdout
+            );
+            assert_eq!(parse(stdout).unwrap().interfaces.len(), 2);
+        }
+    }
 
-    serde_json::from_str(&json_str).context("failed to deserialize vnStat JSON response")
+    #[test]
+    fn garbage_after_a_brace_is_invalid() {
+        for stdout in ["{ not json }", "{\"jsonversion\": \"2\", garbage", "{}"] {
+            let error = vnstat_error(parse(stdout));
+            assert!(
+                matches!(error, VnstatError::InvalidOutput(_)),
+                "{}: {:?}",
+                stdout,
+                error
+            );
+        }
+    }
 }
@@ -0,0 +1,258 @@
+use crate::model::response::WeekRecord;
+use crate::model::vnstat::{Date, DayRecord};
+use crate::utils::timestamp;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use time::{Duration, Weekday};
+
+/// The day weeks start on (`?week_start=`).
+///
+/// Defaults to Monday, as in ISO 8601.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WeekStart {
+    #[default]
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl WeekStart {
+    /// Returns the first day of the week containing `date`.
+    fn week_of(self, date: time::Date) -> Option<time::Date> {
+        let weekday: Weekday = self.into();
+        let offset =
+            (7 + date.weekday().number_days_from_monday() - weekday.number_days_from_monday()) % 7;
+        date.checked_sub(Duration::days(i64::from(offset)))
+    }
+}
+
+impl From<WeekStart> for Weekday {
+    fn from(start: WeekStart) -> Self {
+        match start {
+            WeekStart::Monday => Weekday::Monday,
+            WeekStart::Tuesday => Weekday::Tuesday,
+            WeekStart::Wednesday => Weekday::Wednesday,
+            WeekStart::Thursday => Weekday::Thursday,
+            WeekStart::Friday => Weekday::Friday,
+            WeekStart::Saturday => Weekday::Saturday,
+            WeekStart::Sunday => Weekday::Sunday,
+        }
+    }
+}
+
+/// Groups day records into weeks starting on `start`.
+///
+/// Days are assigned to weeks by their calendar date rather than their
+/// timestamp, so DST changes and year boundaries do not shift them. A
+/// week's traffic is the (saturating) sum of its days; days missing from
+/// the database simply contribute nothing, and `days` tells how many were
+/// recorded.
+///
+/// # Arguments
+///
+/// * `days` - The interface's day records, in any order. Records without a
+///   valid date are ignored.
+/// * `start` - The day weeks start on.
+/// * `weeks` - How many calendar weeks to return, counting back from the
+///   current week. Weeks without any record produce no entry.
+/// * `now` - The current Unix time in seconds.
+///
+/// # Returns
+///
+/// The weeks in chronological order. A week is `partial` if it has not
+/// ended yet, or if it starts before the oldest day record, i.e. its first
+/// days were already dropped by vnStat's retention.
+pub fn weekly_traffic(
+    days: &[DayRecord],
+    start: WeekStart,
+    weeks: usize,
+    now: i64,
+) -> Vec<WeekRecord> {
+    let mut totals: BTreeMap<time::Date, (u64, u64, u8)> = BTreeMap::new();
+    let mut oldest: Option<time::Date> = None;
+    for record in days {
+        let Some(date) = calendar_date(&record.date) else {
+            continue;
+        };
+        let Some(week) = start.week_of(date) else {
+            continue;
+        };
+        oldest = Some(oldest.map_or(date, |oldest| oldest.min(date)));
+
+        let (rx, tx, count) = totals.entry(week).or_default();
+        *rx = rx.saturating_add(record.rx);
+        *tx = tx.saturating_add(record.tx);
+        *count = count.saturating_add(1);
+    }
+
+    let today = timestamp::to_local_date(now)
+        .and_then(|(year, month, day)| calendar_date_of(year, month, day))
+        .or_else(|| totals.keys().next_back().copied());
+    let Some(current) = today.and_then(|today| start.week_of(today)) else {
+        return Vec::new();
+    };
+    // Capped so that the duration cannot overflow; far beyond any date.
+    let span = weeks.saturating_sub(1).min(u32::MAX as usize) as i64;
+    let first = current
+        .checked_sub(Duration::weeks(span))
+        .unwrap_or(time::Date::MIN);
+
+    totals
+        .range(first..)
+        .map(|(&week, &(rx, tx, days))| {
+            let end = week
+                .checked_add(Duration::weeks(1))
+                .unwrap_or(time::Date::MAX);
+            let truncated = oldest.is_some_and(|oldest| week < oldest);
+            let ongoing = today.is_some_and(|today| end > today);
+
+            WeekRecord {
+                date: Date {
+                    day: Some(week.day()),
+                    month: Some(week.month() as u8),
+                    year: week.year(),
+                },
+                rx,
+                timestamp: timestamp::from_local_date(
+                    week.year(),
+                    week.month() as u8,
+                    week.day(),
+                    0,
+                    0,
+                )
+                .unwrap_or_default(),
+                tx,
+                days,
+                partial: truncated || ongoing,
+            }
+        })
+        .collect()
+}
+
+/// Converts a vnStat day date into a calendar date.
+pub fn calendar_date(date: &Date) -> Option<time::Date> {
+    calendar_date_of(date.year, date.month?, date.day?)
+}
+
+/// Builds a calendar date, or `None` if the fields do not form one.
+pub fn calendar_date_of(year: i32, month: u8, day: u8) -> Option<time::Date> {
+    time::Date::from_calendar_date(year, time::Month::try_from(month).ok()?, day).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns local noon of a date, as a Unix timestamp.
+    fn noon(year: i32, month: u8, day: u8) -> i64 {
+        timestamp::from_local_date(year, month, day, 12, 0).unwrap()
+    }
+
+    /// A day record of `rx` bytes down and a tenth of that up, stamped
+    /// `timestamp`.
+    fn day_at(year: i32, month: u8, day: u8, timestamp: i64, rx: u64) -> DayRecord {
+        DayRecord {
+            date: Date::ymd(year, month, day),
+            id: 0,
+            rx,
+            timestamp,
+            tx: rx / 10,
+        }
+    }
+
+    /// A day record stamped at local midnight.
+    fn day(year: i32, month: u8, day: u8, rx: u64) -> DayRecord {
+        let midnight = timestamp::from_local_date(year, month, day, 0, 0).unwrap();
+        day_at(year, month, day, midnight, rx)
+    }
+
+    /// Returns the `(year, month, day)` a week starts on.
+    fn start_of(week: &WeekRecord) -> (i32, Option<u8>, Option<u8>) {
+        (week.date.year, week.date.month, week.date.day)
+    }
+
+    #[test]
+    fn weeks_span_the_year_boundary() {
+        let days = [
+            day(2024, 12, 30, 100),
+            day(2024, 12, 31, 100),
+            day(2025, 1, 1, 100),
+            day(2025, 1, 5, 100),
+            day(2025, 1, 6, 100),
+        ];
+
+        let weeks = weekly_traffic(&days, WeekStart::Monday, 10, noon(2025, 1, 8));
+
+        assert_eq!(weeks.len(), 2);
+        assert_eq!(start_of(&weeks[0]), (2024, Some(12), Some(30)));
+        assert_eq!((weeks[0].rx, weeks[0].tx, weeks[0].days), (400, 40, 4));
+        assert!(!weeks[0].partial);
+        assert_eq!(start_of(&weeks[1]), (2025, Some(1), Some(6)));
+        assert_eq!(weeks[1].days, 1);
+        assert!(weeks[1].partial, "the current week is ongoing");
+
+        let weeks = weekly_traffic(&days, WeekStart::Sunday, 10, noon(2025, 1, 8));
+        assert_eq!(start_of(&weeks[0]), (2024, Some(12), Some(29)));
+        assert_eq!(weeks[0].days, 3);
+        assert_eq!(start_of(&weeks[1]), (2025, Some(1), Some(5)));
+        assert_eq!(weeks[1].days, 2);
+    }
+
+    #[test]
+    fn days_are_grouped_by_date_across_dst_changes() {
+        // Around the EU switch to summer time on 2025-03-30, a day's
+        // midnight may be stamped an hour off; its date decides.
+        let days = [
+            day(2025, 3, 29, 100),
+            day_at(2025, 3, 30, noon(2025, 3, 30) - 12 * 3_600 - 3_600, 100),
+            day_at(2025, 3, 31, noon(2025, 3, 31) - 12 * 3_600 - 3_600, 100),
+        ];
+
+        let weeks = weekly_traffic(&days, WeekStart::Monday, 10, noon(2025, 4, 9));
+
+        assert_eq!(weeks.len(), 2);
+        assert_eq!(start_of(&weeks[0]), (2025, Some(3), Some(24)));
+        assert_eq!(weeks[0].days, 2);
+        assert_eq!(start_of(&weeks[1]), (2025, Some(3), Some(31)));
+        assert_eq!(weeks[1].days, 1);
+        assert_eq!(
+            weeks[1].timestamp,
+            timestamp::from_local_date(2025, 3, 31, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn missing_days_count_for_nothing() {
+        let days = [
+            day(2025, 3, 5, 100),
+            day(2025, 3, 7, 50),
+            // No record in the week of 2025-03-10.
+            day(2025, 3, 18, 10),
+        ];
+
+        let weeks = weekly_traffic(&days, WeekStart::Monday, 10, noon(2025, 3, 26));
+
+        assert_eq!(weeks.len(), 2);
+        assert_eq!(start_of(&weeks[0]), (2025, Some(3), Some(3)));
+        assert_eq!((weeks[0].rx, weeks[0].days), (150, 2));
+        assert!(weeks[0].partial, "starts before the oldest record");
+        assert_eq!(start_of(&weeks[1]), (2025, Some(3), Some(17)));
+        assert_eq!((weeks[1].rx, weeks[1].days), (10, 1));
+        assert!(!weeks[1].partial);
+    }
+
+    #[test]
+    fn only_the_requested_weeks_are_returned() {
+        let days = [day(2025, 3, 5, 100), day(2025, 3, 18, 10)];
+
+        let weeks = weekly_traffic(&days, WeekStart::Monday, 2, noon(2025, 3, 26));
+
+        assert_eq!(weeks.len(), 1);
+        assert_eq!(start_of(&weeks[0]), (2025, Some(3), Some(17)));
+    }
+}
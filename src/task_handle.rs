@@ -1,11 +1,23 @@
+use crate::utils::process::SpawnOptions;
 use anyhow::{Context, Result, anyhow, bail};
-use tracing::{debug, error, trace, warn};
-use std::process::Stdio;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::process::{ExitStatus, Stdio};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
 use tokio::io::{AsyncBufReadExt, BufReader};
-use tokio::process::Command;
+use tokio::process::{Child, ChildStdout, Command};
 use tokio::sync::broadcast::{self, Sender};
+use tokio::time::Instant;
 use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, trace, warn};
+
+/// Default number of messages buffered for subscribers that fall behind.
+pub const DEFAULT_BUFFER_SIZE: usize = 100;
 
 /// The type of each output line produced by a managed child process.
 pub type Output = String;
@@ -18,12 +30,96 @@ pub type Output = String;
 #[derive(Clone)]
 #[allow(dead_code)]
 pub enum TaskMessage {
-    /// A single line of stdout data from the child process.
-    Data(Output),
+    /// A single line of stdout data from the child process, with its
+    /// sequence number (see [`TaskHandle::subscribe`]).
+    Data(u64, Output),
     /// An error or diagnostic string (e.g., spawn failure, read error).
-    Error(Output),
-    /// The process has exited and its stdout pipe has been closed.
-    Eof,
+    /// `fatal` if the task ended with it; otherwise the task goes on, e.g.
+    /// with a restarted process.
+    Error { message: Output, fatal: bool },
+    /// An informational note for subscribers, forwarded as an SSE comment.
+    Comment(Output),
+    /// The process has exited and its stdout pipe has been closed, with its
+    /// exit code if it exited normally. Watchers and cancelled tasks end
+    /// without one.
+    Eof(Option<i32>),
+}
+
+/// The future run by a [`TaskSource::Watcher`].
+pub type WatcherFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// What a task runs to produce the messages it broadcasts.
+pub enum TaskSource {
+    /// A child process (program followed by its arguments) and how it is
+    /// spawned; every stdout line is broadcast as data.
+    Command(Vec<String>, SpawnOptions),
+    /// An in-process producer, started with a [`TaskSink`] to publish
+    /// through. The future is dropped when the task is cancelled, and
+    /// end-of-stream is broadcast when it completes.
+    Watcher(Box<dyn FnOnce(TaskSink) -> WatcherFuture + Send>),
+}
+
+impl TaskSource {
+    /// Returns the kind of task this source runs.
+    pub fn kind(&self) -> TaskKind {
+        match self {
+            TaskSource::Command(..) => TaskKind::Process,
+            TaskSource::Watcher(_) => TaskKind::Watcher,
+        }
+    }
+}
+
+impl From<Vec<String>> for TaskSource {
+    /// Wraps a command vector in [`TaskSource::Command`], inheriting the
+    /// server's environment and priority.
+    fn from(cmd: Vec<String>) -> Self {
+        TaskSource::Command(cmd, SpawnOptions::default())
+    }
+}
+
+impl fmt::Debug for TaskSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskSource::Command(cmd, options) => {
+                f.debug_tuple("Command").field(cmd).field(options).finish()
+            }
+            TaskSource::Watcher(_) => f.write_str("Watcher"),
+        }
+    }
+}
+
+/// Whether a task runs a child process or an in-process watcher.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskKind {
+    /// A child process, see [`TaskSource::Command`].
+    #[default]
+    Process,
+    /// An in-process producer, see [`TaskSource::Watcher`].
+    Watcher,
+}
+
+/// Publishing end handed to a [`TaskSource::Watcher`].
+///
+/// Data lines get sequence numbers and go through the replay buffer exactly
+/// like the stdout lines of a process.
+#[derive(Clone)]
+pub struct TaskSink {
+    tx: Sender<TaskMessage>,
+    state: Arc<Mutex<State>>,
+    replay_capacity: usize,
+}
+
+impl TaskSink {
+    /// Broadcasts a data line to all subscribers.
+    pub fn data(&self, line: Output) {
+        TaskHandle::publish(&self.tx, &self.state, self.replay_capacity, line);
+    }
+
+    /// Broadcasts an informational note, forwarded as an SSE comment.
+    pub fn comment(&self, comment: Output) {
+        TaskHandle::broadcast(&self.tx, &self.state, TaskMessage::Comment(comment));
+    }
 }
 
 /// Internal state protected by a single mutex to guarantee atomicity
@@ -35,14 +131,87 @@ struct State {
     ref_count: usize,
     /// Cancellation token for the running process, if one has been spawned.
     cancel_token: Option<CancellationToken>,
+    /// Incremented each time a process or watcher is started, so that a
+    /// background task ending only clears its own `cancel_token`.
+    generation: u64,
+    /// When the running process was spawned.
+    started_at: Option<SystemTime>,
+    /// What the task runs, as of the last spawn.
+    kind: TaskKind,
+    /// Number of messages broadcast over the handle's lifetime.
+    messages: u64,
+    /// Sequence number of the last data line; increases monotonically over
+    /// the handle's lifetime, across process restarts.
+    seq: u64,
+    /// The most recent data lines, oldest first, for replay to
+    /// reconnecting subscribers.
+    replay: VecDeque<(u64, Output)>,
+    /// The last data line of the running process or watcher; cleared when
+    /// it ends, so that a new run never shows a sample of the previous one.
+    latest: Option<(u64, Output)>,
+}
+
+impl State {
+    /// Collects the buffered lines after sequence number `last_seq`.
+    fn replay_since(&self, last_seq: u64) -> Replay {
+        let lines: Vec<_> = self
+            .replay
+            .iter()
+            .filter(|(seq, _)| *seq > last_seq)
+            .cloned()
+            .collect();
+        let first = lines.first().map_or(self.seq + 1, |(seq, _)| *seq);
+
+        Replay {
+            skipped: last_seq > self.seq || first > last_seq + 1,
+            lines,
+            latest: self.latest.clone(),
+        }
+    }
+}
+
+/// Data lines missed by a reconnecting subscriber, as returned by
+/// [`TaskHandle::subscribe`].
+#[derive(Debug, Clone, Default)]
+pub struct Replay {
+    /// Buffered `(sequence number, line)` pairs after the subscriber's last
+    /// seen line, oldest first.
+    pub lines: Vec<(u64, Output)>,
+    /// Whether lines were lost because they have fallen out of the buffer,
+    /// or the given sequence number is unknown.
+    pub skipped: bool,
+    /// The last line of the running process or watcher, whether or not the
+    /// subscriber saw it, e.g. for a snapshot to new subscribers. `None`
+    /// until the current run has printed a line.
+    pub latest: Option<(u64, Output)>,
+}
+
+/// Point-in-time view of a [`TaskHandle`], as returned by
+/// [`TaskHandle::snapshot`].
+#[derive(Debug, Clone)]
+pub struct TaskSnapshot {
+    /// Number of current subscribers.
+    pub ref_count: usize,
+    /// Number of live broadcast receivers; lower than `ref_count` if
+    /// subscribers are gone without having unsubscribed.
+    pub receivers: usize,
+    /// Whether a process is currently running (a cancel token exists).
+    pub running: bool,
+    /// When the running process was spawned, if one is running.
+    pub started_at: Option<SystemTime>,
+    /// Whether the task runs a process or a watcher.
+    pub kind: TaskKind,
+    /// Number of messages (data, errors, comments, end-of-stream) broadcast
+    /// so far, including those no subscriber received.
+    pub messages: u64,
 }
 
 /// A handle to a long-running child process that broadcasts its output to
 /// multiple subscribers.
 ///
 /// The handle uses a reference-counting scheme so that the underlying process
-/// is spawned only when the first subscriber arrives and is cancelled only
-/// when the last subscriber leaves. Output lines are forwarded to all
+/// is spawned only when a subscriber arrives while none is running, and is
+/// cancelled only when the last subscriber leaves. Output lines are forwarded to all
 /// current subscribers via a `tokio::sync::broadcast` channel.
 ///
 /// # Cancellation safety
@@ -55,6 +224,28 @@ pub struct TaskHandle {
     tx: Sender<TaskMessage>,
     /// Shared, mutex-protected state for reference counting and cancellation.
     state: Arc<Mutex<State>>,
+    /// How long a freshly spawned process may take to print its first line
+    /// before it is killed. `None` waits indefinitely.
+    startup_timeout: Option<Duration>,
+    /// Number of recent data lines kept for replay.
+    replay_capacity: usize,
+    /// How many times in a row a process that died is restarted while
+    /// subscribers remain.
+    max_restarts: u32,
+    /// Wait before the first restart; the n-th waits n times as long.
+    restart_backoff: Duration,
+    /// How long a process asked to terminate may take before it is killed.
+    stop_grace: Duration,
+    /// How long a process may run without any receiver before it is
+    /// stopped. `None` never stops it.
+    orphan_timeout: Option<Duration>,
+}
+
+impl Default for TaskHandle {
+    /// Equivalent to [`TaskHandle::new`].
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl TaskHandle {
@@ -65,69 +256,239 @@ impl TaskHandle {
     /// A new `TaskHandle` ready to accept subscribers. No process is spawned
     /// until the first [`subscribe`](Self::subscribe) call.
     pub fn new() -> Self {
-        let (tx, _) = broadcast::channel(100);
+        let (tx, _) = broadcast::channel(DEFAULT_BUFFER_SIZE);
         Self {
             tx,
             state: Arc::new(Mutex::new(State::default())),
+            startup_timeout: None,
+            replay_capacity: 0,
+            max_restarts: 0,
+            restart_backoff: Duration::ZERO,
+            stop_grace: Duration::ZERO,
+            orphan_timeout: None,
         }
     }
 
-    /// Registers a new subscriber and optionally spawns the child process.
+    /// Creates a new `TaskHandle` whose process is killed if it does not
+    /// produce its first line of output within `startup_timeout`.
+    ///
+    /// # Arguments
+    ///
+    /// * `startup_timeout` - Maximum time between spawning the process and
+    ///   its first stdout line.
+    ///
+    /// # Returns
+    ///
+    /// A new `TaskHandle` ready to accept subscribers.
+    pub fn with_startup_timeout(startup_timeout: Duration) -> Self {
+        Self {
+            startup_timeout: Some(startup_timeout),
+            ..Self::new()
+        }
+    }
+
+    /// Sets how many recent data lines are kept for replay to reconnecting
+    /// subscribers. `0`, the default, keeps none.
+    ///
+    /// # Arguments
+    ///
+    /// * `replay_capacity` - Maximum number of buffered lines.
+    ///
+    /// # Returns
+    ///
+    /// The handle with the replay buffer size applied.
+    pub fn with_replay_capacity(self, replay_capacity: usize) -> Self {
+        Self {
+            replay_capacity,
+            ..self
+        }
+    }
+
+    /// Sets how many messages are buffered for each subscriber. A
+    /// subscriber that falls further behind loses the oldest ones (see
+    /// [`RecvError::Lagged`](tokio::sync::broadcast::error::RecvError::Lagged)).
+    ///
+    /// Must be called before the first subscriber.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer_size` - Capacity of the broadcast channel; at least 1.
+    ///
+    /// # Returns
+    ///
+    /// The handle with the buffer size applied.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buffer_size` is zero.
+    pub fn with_buffer_size(self, buffer_size: usize) -> Self {
+        let (tx, _) = broadcast::channel(buffer_size);
+        Self { tx, ..self }
+    }
+
+    /// Sets how a process that exits or whose output cannot be read is
+    /// restarted while it still has subscribers. `0` restarts, the default,
+    /// ends the subscribers' streams instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_restarts` - Maximum number of consecutive restarts; a
+    ///   restarted process that prints a line resets the count.
+    /// * `backoff` - Wait before the first restart; the n-th consecutive
+    ///   restart waits n times as long.
+    ///
+    /// # Returns
+    ///
+    /// The handle with the restart policy applied.
+    pub fn with_restarts(self, max_restarts: u32, backoff: Duration) -> Self {
+        Self {
+            max_restarts,
+            restart_backoff: backoff,
+            ..self
+        }
+    }
+
+    /// Sets how long a process being stopped may take to exit after
+    /// `SIGTERM` before it is killed. Zero, the default, kills it right
+    /// away.
+    ///
+    /// # Arguments
+    ///
+    /// * `grace` - Time between `SIGTERM` and `SIGKILL`.
+    ///
+    /// # Returns
+    ///
+    /// The handle with the grace period applied.
+    pub fn with_stop_grace(self, grace: Duration) -> Self {
+        Self {
+            stop_grace: grace,
+            ..self
+        }
+    }
+
+    /// Sets how long a process may keep running while no subscriber holds
+    /// a receiver, before it is stopped. `None`, the default, lets it run
+    /// until the last subscriber unsubscribes.
+    ///
+    /// This guards against subscribers that are gone without having
+    /// unsubscribed, which would otherwise keep the process alive forever.
+    /// The subscriber count is left as is, so that such a subscriber still
+    /// unsubscribes correctly if it ever does; the next subscriber starts a
+    /// new process.
+    ///
+    /// # Arguments
+    ///
+    /// * `orphan_timeout` - Time without receivers after which the process
+    ///   is stopped.
+    ///
+    /// # Returns
+    ///
+    /// The handle with the orphan timeout applied.
+    pub fn with_orphan_timeout(self, orphan_timeout: Option<Duration>) -> Self {
+        Self {
+            orphan_timeout,
+            ..self
+        }
+    }
+
+    /// Registers a new subscriber and optionally spawns the child process
+    /// or watcher.
     ///
     /// Each call to this method increments an internal reference count and
-    /// returns a new broadcast receiver. If this is the **first** subscriber
-    /// (`ref_count` transitions from 0 → 1) **and** there is no running
-    /// process yet, a [`CancellationToken`] is created and the command is
-    /// spawned immediately (outside the lock).
+    /// returns a new broadcast receiver. If no process is running (no
+    /// [`CancellationToken`] is stored), whether because this is the first
+    /// subscriber or because the previous process has ended while its
+    /// subscribers are still attached, a token is created and the source is
+    /// started immediately (outside the lock).
     ///
     /// If spawning fails, an error message is broadcast to all subscribers
     /// and the token is rolled back so the next subscriber can retry.
     ///
+    /// Every data line carries a sequence number. A subscriber that saw
+    /// lines before (e.g. an SSE client reconnecting with `Last-Event-ID`)
+    /// passes the last one it received and gets the buffered lines after it
+    /// as a [`Replay`]. The replay and the receiver are taken atomically, so
+    /// no line is missed or delivered twice between them.
+    ///
     /// # Arguments
     ///
-    /// * `cmd` - The command and its arguments to run. The first element is
-    ///   the program name; subsequent elements are passed as arguments.
+    /// * `source` - What to run: a command and its arguments, or a watcher.
+    ///   Ignored if the task is already running.
+    /// * `last_seq` - Sequence number of the last line the subscriber saw,
+    ///   if any.
     ///
     /// # Returns
     ///
-    /// A `tokio::sync::broadcast::Receiver` that yields [`TaskMessage`]
-    /// values (data lines, errors, or end-of-stream).
+    /// A tuple of:
+    ///
+    /// * A `tokio::sync::broadcast::Receiver` that yields [`TaskMessage`]
+    ///   values (data lines, errors, or end-of-stream).
+    /// * The [`Replay`] of lines after `last_seq` (empty without one).
     pub async fn subscribe(
         &self,
-        cmd: Vec<String>,
-    ) -> tokio::sync::broadcast::Receiver<TaskMessage> {
-        // Decide whether to spawn outside the critical section to avoid long hold times.
-        let mut need_spawn: Option<(Vec<String>, CancellationToken)> = None;
-
-        {
+        source: TaskSource,
+        last_seq: Option<u64>,
+    ) -> (tokio::sync::broadcast::Receiver<TaskMessage>, Replay) {
+        // Decide whether to spawn, and subscribe, in one critical section, so
+        // that a task ending concurrently either delivers its end-of-stream
+        // to this receiver or leaves no token behind.
+        let (rx, replay, need_spawn) = {
             let mut st = self.state.lock().expect("TaskHandle.state mutex poisoned");
             st.ref_count += 1;
 
-            // First subscriber and no running process: prepare to spawn.
-            if st.ref_count == 1 && st.cancel_token.is_none() {
+            // No running process: prepare to spawn.
+            let need_spawn = if st.cancel_token.is_none() {
                 let token = CancellationToken::new();
                 st.cancel_token = Some(token.clone());
-                need_spawn = Some((cmd.clone(), token));
-            }
-        }
+                st.started_at = Some(SystemTime::now());
+                st.kind = source.kind();
+                st.generation += 1;
+                Some((source, token, st.generation))
+            } else {
+                None
+            };
+
+            let replay = match last_seq {
+                Some(last_seq) => st.replay_since(last_seq),
+                None => Replay {
+                    latest: st.latest.clone(),
+                    ..Replay::default()
+                },
+            };
 
-        if let Some((cmd_to_spawn, token)) = need_spawn {
-            if let Err(error) = self.spawn_process(cmd_to_spawn, token.clone()) {
+            (self.tx.subscribe(), replay, need_spawn)
+        };
+
+        if let Some((source, token, generation)) = need_spawn {
+            let spawned = match source {
+                TaskSource::Command(cmd, options) => {
+                    self.spawn_process(cmd, options, token, generation)
+                }
+                TaskSource::Watcher(watcher) => {
+                    self.spawn_watcher(watcher, token, generation);
+                    Ok(())
+                }
+            };
+
+            if let Err(error) = spawned {
                 error!("Spawn task failed! Error: {}", error);
-                TaskHandle::broadcast(
-                    &self.tx,
-                    TaskMessage::Error("Spawn task failed".to_string()),
-                );
 
                 // Roll back token to allow future retries.
-                let mut st = self.state.lock().expect("TaskHandle.state mutex poisoned");
-                st.cancel_token = None;
+                TaskHandle::finish(
+                    &self.tx,
+                    &self.state,
+                    generation,
+                    Some(TaskMessage::Error {
+                        message: "Spawn task failed".to_string(),
+                        fatal: true,
+                    }),
+                );
             } else {
                 debug!("Cancel token stored and process spawned");
             }
         }
 
-        self.tx.subscribe()
+        (rx, replay)
     }
 
     /// Deregisters a subscriber and cancels the process if it was the last.
@@ -159,9 +520,59 @@ impl TaskHandle {
         }
     }
 
+    /// Returns a point-in-time view of the subscriber count, the running
+    /// process and the number of messages broadcast.
+    pub fn snapshot(&self) -> TaskSnapshot {
+        let st = self.state.lock().expect("TaskHandle.state mutex poisoned");
+
+        TaskSnapshot {
+            ref_count: st.ref_count,
+            receivers: self.tx.receiver_count(),
+            running: st.cancel_token.is_some(),
+            started_at: st.started_at,
+            kind: st.kind,
+            messages: st.messages,
+        }
+    }
+
+    /// Force-cancels the running process regardless of the subscriber count.
+    ///
+    /// The process is killed and a [`TaskMessage::Comment`] explaining why
+    /// is broadcast, followed by [`TaskMessage::Eof`], so subscribers end
+    /// their streams and unsubscribe. The next subscriber
+    /// after that spawns a fresh process.
+    ///
+    /// # Returns
+    ///
+    /// `true` if a process was running and has been cancelled.
+    pub fn cancel(&self) -> bool {
+        let mut st = self.state.lock().expect("TaskHandle.state mutex poisoned");
+        st.started_at = None;
+        let Some(token) = st.cancel_token.take() else {
+            return false;
+        };
+
+        // Under the lock, so that a subscriber spawning the next process
+        // does not receive them.
+        token.cancel();
+        TaskHandle::send(
+            &self.tx,
+            &mut st,
+            TaskMessage::Comment("Stream cancelled by an administrator".to_string()),
+        );
+        TaskHandle::send(&self.tx, &mut st, TaskMessage::Eof(None));
+
+        true
+    }
+
     /// Spawns the child process and launches a background task to forward
     /// its stdout.
     ///
+    /// If a startup timeout is configured and the process prints nothing
+    /// before it expires, the process is killed and an error is broadcast.
+    /// A process that exits while subscribers remain is restarted as set by
+    /// [`with_restarts`](Self::with_restarts).
+    ///
     /// The caller **must** have already stored the provided
     /// `cancel_token` in [`State::cancel_token`] under the mutex before
     /// calling this method. This function does **not** touch the reference
@@ -172,8 +583,11 @@ impl TaskHandle {
     ///
     /// * `cmd` - The command vector where `cmd[0]` is the program and
     ///   `cmd[1..]` are its arguments.
+    /// * `options` - How the process and its restarts are spawned.
     /// * `cancel_token` - A cancellation token that will signal the
     ///   background reader task to kill the child process.
+    /// * `generation` - The [`State::generation`] the token was stored
+    ///   with.
     ///
     /// # Returns
     ///
@@ -186,87 +600,644 @@ impl TaskHandle {
     /// * The OS fails to spawn the child process (e.g., the program is not
     ///   found).
     /// * The spawned process's stdout cannot be captured.
-    fn spawn_process(&self, cmd: Vec<String>, cancel_token: CancellationToken) -> Result<()> {
+    fn spawn_process(
+        &self,
+        cmd: Vec<String>,
+        options: SpawnOptions,
+        cancel_token: CancellationToken,
+        generation: u64,
+    ) -> Result<()> {
         if cmd.is_empty() {
             bail!("spawn_process called with empty cmd, skipping spawn.");
         }
 
-        let program = cmd[0].clone();
-        let args = cmd[1..].to_vec();
-
-        trace!("Spawning process: {} {:?}", &program, &args);
+        let process = start_process(&cmd, &options)?;
+        let runner = ProcessRunner {
+            cmd,
+            options,
+            tx: self.tx.clone(),
+            state: Arc::clone(&self.state),
+            startup_timeout: self.startup_timeout,
+            replay_capacity: self.replay_capacity,
+            max_restarts: self.max_restarts,
+            restart_backoff: self.restart_backoff,
+            stop_grace: self.stop_grace,
+            orphan_timeout: self.orphan_timeout,
+            generation,
+        };
 
-        let mut child = Command::new(&program)
-            .args(&args)
-            .stdout(Stdio::piped())
-            .spawn()
-            .with_context(|| format!("Failed to spawn child process: {} {:?}", program, args))?;
+        // Background task: forward output lines, handle cancellation, clean up token on exit.
+        tokio::spawn(runner.run(process, cancel_token));
 
-        let stdout = child
-            .stdout
-            .take()
-            .ok_or_else(|| anyhow!("Failed to take stdout of process: {:?}", cmd))?;
+        Ok(())
+    }
 
+    /// Starts a watcher in a background task that runs until it completes
+    /// or `cancel_token` is cancelled.
+    ///
+    /// As with [`spawn_process`](Self::spawn_process), the caller must have
+    /// stored `cancel_token` in [`State::cancel_token`] beforehand.
+    ///
+    /// # Arguments
+    ///
+    /// * `watcher` - Builds the watcher future from its [`TaskSink`].
+    /// * `cancel_token` - Signals the background task to drop the watcher.
+    /// * `generation` - The [`State::generation`] the token was stored
+    ///   with.
+    fn spawn_watcher(
+        &self,
+        watcher: Box<dyn FnOnce(TaskSink) -> WatcherFuture + Send>,
+        cancel_token: CancellationToken,
+        generation: u64,
+    ) {
+        let sink = TaskSink {
+            tx: self.tx.clone(),
+            state: Arc::clone(&self.state),
+            replay_capacity: self.replay_capacity,
+        };
         let tx = self.tx.clone();
-        let mut reader = BufReader::new(stdout).lines();
-        let cancel_token_clone = cancel_token.clone();
         let state = Arc::clone(&self.state);
+        let future = watcher(sink);
 
-        // Background task: forward output lines, handle cancellation, clean up token on exit.
         tokio::spawn(async move {
-            loop {
-                tokio::select! {
-                    _ = cancel_token_clone.cancelled() => {
-                        debug!("Cancellation received, killing process: {:?}", cmd);
-                        if let Err(e) = child.kill().await {
-                            TaskHandle::broadcast(&tx, TaskMessage::Error(format!("Failed to kill child: {e}")));
-                        }
-                        break;
-                    }
-                    line = reader.next_line() => {
-                        match line {
-                            Ok(Some(line)) => {
-                                TaskHandle::broadcast(&tx, TaskMessage::Data(line));
-                            }
-                            Ok(None) => {
-                                warn!("Process finished (EOF): {:?}", cmd);
-                                TaskHandle::broadcast(&tx, TaskMessage::Eof);
-                                break;
-                            }
-                            Err(e) => {
-                                error!("Read error from process {:?}: {}", cmd, e);
-                                TaskHandle::broadcast(&tx, TaskMessage::Error(format!(
-                                    "Failed to read line from process: {:?}, err: {}", cmd, e
-                                )));
-                                break;
-                            }
-                        }
-                    }
+            let message = tokio::select! {
+                _ = cancel_token.cancelled() => {
+                    debug!("Cancellation received, stopping watcher");
+                    None
                 }
-            }
-
-            trace!("Process handler exited: {:?}", cmd);
+                _ = future => {
+                    debug!("Watcher finished");
+                    Some(TaskMessage::Eof(None))
+                }
+            };
 
-            // Clean up token after the process ends, enabling next 0->1 transition to spawn again.
-            let mut st = state.lock().expect("TaskHandle.state mutex poisoned");
-            st.cancel_token = None;
+            // As for processes, allow the next subscriber to start it again.
+            TaskHandle::finish(&tx, &state, generation, message);
         });
+    }
 
-        Ok(())
+    /// Assigns the next sequence number to a data line, records it in the
+    /// replay buffer and broadcasts it.
+    ///
+    /// All of this happens under the state lock, so that
+    /// [`subscribe`](Self::subscribe) sees each line either in the replay or
+    /// through its receiver, never both or neither.
+    ///
+    /// # Arguments
+    ///
+    /// * `tx` - The broadcast sender.
+    /// * `state` - The handle's state.
+    /// * `replay_capacity` - Maximum number of buffered lines.
+    /// * `line` - The line to publish.
+    fn publish(
+        tx: &Sender<TaskMessage>,
+        state: &Mutex<State>,
+        replay_capacity: usize,
+        line: Output,
+    ) {
+        let mut st = state.lock().expect("TaskHandle.state mutex poisoned");
+        st.seq += 1;
+        st.messages += 1;
+        let seq = st.seq;
+
+        if replay_capacity > 0 {
+            if st.replay.len() >= replay_capacity {
+                st.replay.pop_front();
+            }
+            st.replay.push_back((seq, line.clone()));
+        }
+        st.latest = Some((seq, line.clone()));
+
+        // No receivers is not an error here: the line is still buffered.
+        let _ = tx.send(TaskMessage::Data(seq, line));
     }
 
     /// Sends a message to all active broadcast receivers, suppressing errors
-    /// when there are no receivers.
+    /// when there are no receivers, and counts it in
+    /// [`State::messages`].
     ///
     /// # Arguments
     ///
     /// * `tx` - The broadcast sender.
+    /// * `state` - The handle's state holding the message counter.
     /// * `msg` - The message to broadcast.
-    fn broadcast<T>(tx: &Sender<T>, msg: T) {
+    fn broadcast(tx: &Sender<TaskMessage>, state: &Mutex<State>, msg: TaskMessage) {
+        let mut st = state.lock().expect("TaskHandle.state mutex poisoned");
+        TaskHandle::send(tx, &mut st, msg);
+    }
+
+    /// Like [`broadcast`](Self::broadcast), with the state already locked.
+    fn send(tx: &Sender<TaskMessage>, st: &mut State, msg: TaskMessage) {
+        st.messages += 1;
+
         if let Err(e) = tx.send(msg) {
             warn!("broadcast failed: {:?}", e.to_string());
         }
     }
+
+    /// Ends the background task started as `generation`: clears its token,
+    /// so that the next subscriber starts the source again, and broadcasts
+    /// its final `message`, if any.
+    ///
+    /// Both happen under the state lock, so every subscriber either
+    /// receives `message` or finds no token and starts a new task. Nothing
+    /// happens if the task was cancelled and another one started since.
+    fn finish(
+        tx: &Sender<TaskMessage>,
+        state: &Mutex<State>,
+        generation: u64,
+        message: Option<TaskMessage>,
+    ) {
+        let mut st = state.lock().expect("TaskHandle.state mutex poisoned");
+        if st.generation != generation {
+            return;
+        }
+
+        st.cancel_token = None;
+        st.started_at = None;
+        st.latest = None;
+        if let Some(message) = message {
+            TaskHandle::send(tx, &mut st, message);
+        }
+    }
+}
+
+/// How long a process whose stdout was closed may take to exit before it is
+/// killed.
+const EXIT_GRACE: Duration = Duration::from_secs(2);
+
+/// Longest interval between two checks for receivers of a process with an
+/// orphan timeout.
+const ORPHAN_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A spawned child process and the reader over its stdout lines.
+type Process = (Child, OutputLines);
+
+/// Reads the stdout of a process line by line, like
+/// [`Lines`](tokio::io::Lines), but replaces bytes that are not valid
+/// UTF-8 with U+FFFD instead of failing, so that a stray byte does not end
+/// the stream.
+struct OutputLines {
+    reader: BufReader<ChildStdout>,
+    /// The line being read; kept across calls, so that reading is
+    /// cancel-safe in `select!`.
+    buf: Vec<u8>,
+    /// Whether invalid UTF-8 was already reported for this process.
+    warned: bool,
+}
+
+impl OutputLines {
+    fn new(stdout: ChildStdout) -> Self {
+        Self {
+            reader: BufReader::new(stdout),
+            buf: Vec::new(),
+            warned: false,
+        }
+    }
+
+    /// Returns the next line without its `\n` or `\r\n`, or `None` at the
+    /// end of the output. The last line need not end with a newline.
+    ///
+    /// # Cancel safety
+    ///
+    /// Cancel-safe: bytes read before the future is dropped stay in the
+    /// buffer and start the next line.
+    async fn next_line(&mut self) -> io::Result<Option<Output>> {
+        let read = self.reader.read_until(b'\n', &mut self.buf).await?;
+        if read == 0 && self.buf.is_empty() {
+            return Ok(None);
+        }
+
+        let mut bytes = std::mem::take(&mut self.buf);
+        if bytes.last() == Some(&b'\n') {
+            bytes.pop();
+            if bytes.last() == Some(&b'\r') {
+                bytes.pop();
+            }
+        }
+
+        Ok(Some(match String::from_utf8(bytes) {
+            Ok(line) => line,
+            Err(e) => {
+                if !self.warned {
+                    warn!("Process printed invalid UTF-8, replacing the invalid bytes");
+                    self.warned = true;
+                }
+                String::from_utf8_lossy(e.as_bytes()).into_owned()
+            }
+        }))
+    }
+}
+
+/// Spawns `cmd` with its stdout piped, set up with `options`.
+///
+/// On Unix the child leads a new process group, so that
+/// [`terminate`] also reaches the processes it starts.
+fn start_process(cmd: &[String], options: &SpawnOptions) -> Result<Process> {
+    let program = &cmd[0];
+    let args = &cmd[1..];
+
+    trace!("Spawning process: {} {:?}", program, args);
+
+    let mut command = Command::new(program);
+    command.args(args).stdout(Stdio::piped()).kill_on_drop(true);
+    options.apply(&mut command);
+    #[cfg(unix)]
+    command.process_group(0);
+
+    let mut child = command
+        .spawn()
+        .with_context(|| format!("Failed to spawn child process: {} {:?}", program, args))?;
+
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| anyhow!("Failed to take stdout of process: {:?}", cmd))?;
+
+    Ok((child, OutputLines::new(stdout)))
+}
+
+/// Stops `child` and its process group (led by `pid`), then reaps it.
+///
+/// The group is asked to terminate (`SIGTERM`) first and killed only if
+/// the child has not exited after `grace`, or right away if `grace` is
+/// zero. Outside Unix the child is always killed right away.
+async fn terminate(child: &mut Child, pid: Option<u32>, grace: Duration) -> io::Result<ExitStatus> {
+    if !grace.is_zero() && signal_group(pid, GroupSignal::Terminate) {
+        if let Ok(status) = tokio::time::timeout(grace, child.wait()).await {
+            // Whatever the child started may not have exited with it.
+            signal_group(pid, GroupSignal::Kill);
+            return status;
+        }
+        warn!(
+            "Process did not exit within {:?} of SIGTERM, killing it",
+            grace
+        );
+    }
+
+    signal_group(pid, GroupSignal::Kill);
+    child.kill().await?;
+    child.wait().await
+}
+
+/// A signal sent by [`signal_group`].
+#[derive(Debug, Clone, Copy)]
+enum GroupSignal {
+    /// `SIGTERM`
+    Terminate,
+    /// `SIGKILL`
+    Kill,
+}
+
+/// Sends `signal` to the process group led by `pid`, so that the processes
+/// a child started are stopped with it.
+///
+/// Returns whether the signal was sent; it never is outside Unix.
+fn signal_group(pid: Option<u32>, signal: GroupSignal) -> bool {
+    #[cfg(unix)]
+    if let Some(pid) = pid.and_then(|pid| i32::try_from(pid).ok()) {
+        let signal = match signal {
+            GroupSignal::Terminate => libc::SIGTERM,
+            GroupSignal::Kill => libc::SIGKILL,
+        };
+        // SAFETY: kill(2) takes no pointers; a group that is already gone
+        // only yields ESRCH.
+        return unsafe { libc::kill(-pid, signal) } == 0;
+    }
+    #[cfg(not(unix))]
+    let _ = (pid, signal);
+
+    false
+}
+
+/// Reaps `child` after its stdout was closed, stopping it (and its process
+/// group, see [`terminate`]) if it does not exit within [`EXIT_GRACE`].
+async fn reap(child: &mut Child, pid: Option<u32>, grace: Duration) -> io::Result<ExitStatus> {
+    match tokio::time::timeout(EXIT_GRACE, child.wait()).await {
+        Ok(status) => status,
+        Err(_) => {
+            warn!("Process closed its output but did not exit, killing it");
+            terminate(child, pid, grace).await
+        }
+    }
+}
+
+/// What [`ProcessRunner::forward`] waits for.
+enum ProcessEvent {
+    /// The task was cancelled.
+    Cancelled,
+    /// The startup timeout expired before the first line.
+    Stalled,
+    /// The next stdout line, end of output or a read error.
+    Line(io::Result<Option<Output>>),
+    /// The process exited; its output may not be closed yet.
+    Exited(io::Result<ExitStatus>),
+    /// Time to check whether the process still has receivers.
+    OrphanCheck,
+}
+
+/// How one run of a process ended.
+enum ProcessEnd {
+    /// Cancelled; the task ends.
+    Cancelled,
+    /// Stopped for having had no receivers for the orphan timeout; the
+    /// task has already been detached from the handle.
+    Orphaned,
+    /// Killed for producing no output in time; the task ends with
+    /// `message`.
+    Stalled(TaskMessage),
+    /// The process exited or its output could not be read. `message` ends
+    /// the task unless the process is restarted, in which case an error is
+    /// still broadcast as non-fatal; `produced_output` whether it printed
+    /// anything first.
+    Died {
+        message: TaskMessage,
+        produced_output: bool,
+    },
+}
+
+/// The background task of a [`TaskSource::Command`]: forwards the output of
+/// the process and restarts it when it dies while subscribers remain.
+struct ProcessRunner {
+    cmd: Vec<String>,
+    options: SpawnOptions,
+    tx: Sender<TaskMessage>,
+    state: Arc<Mutex<State>>,
+    startup_timeout: Option<Duration>,
+    replay_capacity: usize,
+    max_restarts: u32,
+    restart_backoff: Duration,
+    stop_grace: Duration,
+    orphan_timeout: Option<Duration>,
+    /// The [`State::generation`] the task was started as.
+    generation: u64,
+}
+
+impl ProcessRunner {
+    /// Runs `process`, and its restarts, until the task is cancelled, the
+    /// process stalls or it died more than `max_restarts` times in a row.
+    ///
+    /// The n-th consecutive restart waits n times `restart_backoff`; a
+    /// restarted process that prints a line resets the count. Cancelling
+    /// `cancel_token` during the wait (the last subscriber leaving, or
+    /// [`TaskHandle::cancel`]) abandons the restart. Each restart is
+    /// announced with a [`TaskMessage::Comment`], since lines may have been
+    /// missed meanwhile.
+    async fn run(self, process: Process, cancel_token: CancellationToken) {
+        let mut process = Some(process);
+        let mut restarts = 0;
+
+        let message = loop {
+            let end = match process.take() {
+                Some((child, reader)) => self.forward(child, reader, &cancel_token).await,
+                None => ProcessEnd::Died {
+                    message: TaskMessage::Error {
+                        message: "Spawn task failed".to_string(),
+                        fatal: true,
+                    },
+                    produced_output: false,
+                },
+            };
+
+            let (message, produced_output) = match end {
+                ProcessEnd::Cancelled | ProcessEnd::Orphaned => break None,
+                ProcessEnd::Stalled(message) => break Some(message),
+                ProcessEnd::Died {
+                    message,
+                    produced_output,
+                } => (message, produced_output),
+            };
+
+            if produced_output {
+                restarts = 0;
+            }
+            let subscribed = {
+                let mut st = self.state.lock().expect("TaskHandle.state mutex poisoned");
+                // The process is gone; its last line must not greet the
+                // subscribers of the next one.
+                if st.generation == self.generation {
+                    st.latest = None;
+                }
+                st.ref_count > 0
+            };
+            if restarts >= self.max_restarts || !subscribed {
+                break Some(message);
+            }
+
+            // The process is restarted, so its error does not end the streams.
+            if let TaskMessage::Error { message, .. } = message {
+                TaskHandle::broadcast(
+                    &self.tx,
+                    &self.state,
+                    TaskMessage::Error {
+                        message,
+                        fatal: false,
+                    },
+                );
+            }
+
+            restarts += 1;
+            let delay = self.restart_backoff * restarts;
+            warn!(
+                "Restarting process in {:?} (attempt {}/{}): {:?}",
+                delay, restarts, self.max_restarts, self.cmd
+            );
+            tokio::select! {
+                _ = cancel_token.cancelled() => {
+                    debug!("Cancellation received, restart abandoned: {:?}", self.cmd);
+                    break None;
+                }
+                _ = tokio::time::sleep(delay) => {}
+            }
+
+            match start_process(&self.cmd, &self.options) {
+                Ok(restarted) => {
+                    {
+                        let mut st = self.state.lock().expect("TaskHandle.state mutex poisoned");
+                        st.started_at = Some(SystemTime::now());
+                    }
+                    TaskHandle::broadcast(
+                        &self.tx,
+                        &self.state,
+                        TaskMessage::Comment(
+                            "Stream restarted, some events may be missing".to_string(),
+                        ),
+                    );
+                    process = Some(restarted);
+                }
+                Err(e) => error!("Restarting process failed: {:#}", e),
+            }
+        };
+
+        trace!("Process handler exited: {:?}", self.cmd);
+
+        // Clean up token after the process ends, enabling the next subscriber to spawn again.
+        TaskHandle::finish(&self.tx, &self.state, self.generation, message);
+    }
+
+    /// Forwards the stdout lines of one process until it ends.
+    ///
+    /// If a startup timeout is configured and the process prints nothing
+    /// before it expires, the process is killed and an error is broadcast.
+    /// Once the process exits, what remains of its process group is killed,
+    /// since it may hold the output open, and the remaining lines are
+    /// forwarded. The process is always reaped before this returns.
+    async fn forward(
+        &self,
+        mut child: Child,
+        mut reader: OutputLines,
+        cancel_token: &CancellationToken,
+    ) -> ProcessEnd {
+        let (tx, state, cmd) = (&self.tx, &self.state, &self.cmd);
+        let pid = child.id();
+        let startup_timeout = self.startup_timeout;
+        let startup_deadline = startup_timeout.map(|timeout| Instant::now() + timeout);
+        let mut started = false;
+        let mut exit: Option<io::Result<ExitStatus>> = None;
+        let mut orphan_check = tokio::time::interval(
+            self.orphan_timeout
+                .map_or(ORPHAN_CHECK_INTERVAL, |timeout| {
+                    timeout.min(ORPHAN_CHECK_INTERVAL)
+                })
+                .max(Duration::from_millis(100)),
+        );
+        let mut orphaned_since = None;
+
+        loop {
+            let event = tokio::select! {
+                _ = cancel_token.cancelled() => ProcessEvent::Cancelled,
+                _ = tokio::time::sleep_until(startup_deadline.unwrap_or_else(Instant::now)),
+                    if !started && startup_deadline.is_some() => ProcessEvent::Stalled,
+                line = reader.next_line() => ProcessEvent::Line(line),
+                status = child.wait(), if exit.is_none() => ProcessEvent::Exited(status),
+                _ = orphan_check.tick(), if self.orphan_timeout.is_some() => ProcessEvent::OrphanCheck,
+            };
+
+            match event {
+                ProcessEvent::Cancelled => {
+                    debug!("Cancellation received, killing process: {:?}", cmd);
+                    match terminate(&mut child, pid, self.stop_grace).await {
+                        Ok(status) => debug!("Process {:?} exited: {}", cmd, status),
+                        Err(e) => TaskHandle::broadcast(
+                            tx,
+                            state,
+                            TaskMessage::Error {
+                                message: format!("Failed to kill child: {e}"),
+                                fatal: false,
+                            },
+                        ),
+                    }
+                    return ProcessEnd::Cancelled;
+                }
+                ProcessEvent::Stalled => {
+                    error!(
+                        "Process produced no output within {:?}, killing: {:?}",
+                        startup_timeout, cmd
+                    );
+                    if let Err(e) = terminate(&mut child, pid, self.stop_grace).await {
+                        warn!("Failed to kill stalled child {:?}: {}", cmd, e);
+                    }
+                    return ProcessEnd::Stalled(TaskMessage::Error {
+                        message: format!(
+                            "Process produced no output within {}s",
+                            startup_timeout.unwrap_or_default().as_secs()
+                        ),
+                        fatal: true,
+                    });
+                }
+                ProcessEvent::Line(Ok(Some(line))) => {
+                    started = true;
+                    TaskHandle::publish(tx, state, self.replay_capacity, line);
+                }
+                ProcessEvent::Line(Ok(None)) => {
+                    let status = match exit {
+                        Some(status) => status,
+                        None => reap(&mut child, pid, self.stop_grace).await,
+                    };
+                    let code = match status {
+                        Ok(status) => {
+                            warn!("Process finished (EOF), {}: {:?}", status, cmd);
+                            status.code()
+                        }
+                        Err(e) => {
+                            warn!(
+                                "Process finished (EOF), exit status unknown: {:?}: {}",
+                                cmd, e
+                            );
+                            None
+                        }
+                    };
+                    return ProcessEnd::Died {
+                        message: TaskMessage::Eof(code),
+                        produced_output: started,
+                    };
+                }
+                ProcessEvent::Line(Err(e)) => {
+                    error!("Read error from process {:?}: {}", cmd, e);
+                    if let Err(e) = terminate(&mut child, pid, self.stop_grace).await {
+                        warn!("Failed to kill child {:?}: {}", cmd, e);
+                    }
+                    return ProcessEnd::Died {
+                        message: TaskMessage::Error {
+                            message: format!(
+                                "Failed to read line from process: {:?}, err: {}",
+                                cmd, e
+                            ),
+                            fatal: true,
+                        },
+                        produced_output: started,
+                    };
+                }
+                ProcessEvent::Exited(status) => {
+                    debug!("Process exited, draining its output: {:?}", cmd);
+                    signal_group(pid, GroupSignal::Kill);
+                    exit = Some(status);
+                }
+                ProcessEvent::OrphanCheck => {
+                    if !self.detach_if_orphaned(&mut orphaned_since) {
+                        continue;
+                    }
+                    if let Err(e) = terminate(&mut child, pid, self.stop_grace).await {
+                        warn!("Failed to kill orphaned child {:?}: {}", cmd, e);
+                    }
+                    return ProcessEnd::Orphaned;
+                }
+            }
+        }
+    }
+
+    /// Detaches the task from the handle if the process has had no
+    /// receivers for the orphan timeout, tracking since when in
+    /// `orphaned_since`.
+    ///
+    /// Detaching clears the token, so that the next subscriber starts a new
+    /// process; this is decided under the state lock, where
+    /// [`TaskHandle::subscribe`] creates receivers. Returns whether the
+    /// process must be stopped.
+    fn detach_if_orphaned(&self, orphaned_since: &mut Option<Instant>) -> bool {
+        let mut st = self.state.lock().expect("TaskHandle.state mutex poisoned");
+        if self.tx.receiver_count() > 0 {
+            *orphaned_since = None;
+            return false;
+        }
+
+        let since = *orphaned_since.get_or_insert_with(Instant::now);
+        if since.elapsed() < self.orphan_timeout.unwrap_or_default() {
+            return false;
+        }
+
+        if st.generation == self.generation {
+            warn!(
+                "Process has had no receivers for {:?} with {} subscriber(s) left, stopping: {:?}",
+                since.elapsed(),
+                st.ref_count,
+                self.cmd
+            );
+            st.cancel_token = None;
+            st.started_at = None;
+        }
+        true
+    }
 }
 
 /// A guard that runs a cleanup closure when dropped.
@@ -276,6 +1247,10 @@ impl TaskHandle {
 /// cleanup runs automatically when the guard goes out of scope, even
 /// through early returns or panics (subject to normal panic unwind
 /// behaviour).
+///
+/// The closure runs synchronously on the dropping thread and must not need
+/// a Tokio runtime: guards are also dropped while the runtime shuts down or
+/// after it is gone, and a cleanup spawned there would be lost.
 pub struct TaskDropGuard {
     /// The optional closure to execute on `drop`.
     cleanup: Option<Box<dyn FnOnce() + Send>>,
@@ -314,3 +1289,235 @@ impl Drop for TaskDropGuard {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::broadcast::Receiver;
+
+    /// Upper bound on anything a test waits for.
+    const TEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// A task running `script` with `sh -c`.
+    fn sh(script: &str) -> TaskSource {
+        vec!["sh".to_string(), "-c".to_string(), script.to_string()].into()
+    }
+
+    /// Returns the next message other than a comment.
+    async fn next_message(rx: &mut Receiver<TaskMessage>) -> TaskMessage {
+        loop {
+            match tokio::time::timeout(TEST_TIMEOUT, rx.recv()).await {
+                Ok(Ok(TaskMessage::Comment(_))) => continue,
+                Ok(Ok(message)) => return message,
+                Ok(Err(e)) => panic!("receive failed: {e}"),
+                Err(_) => panic!("no message within {:?}", TEST_TIMEOUT),
+            }
+        }
+    }
+
+    /// Returns the next data line.
+    async fn next_line(rx: &mut Receiver<TaskMessage>) -> Output {
+        match next_message(rx).await {
+            TaskMessage::Data(_, line) => line,
+            TaskMessage::Error { message, .. } => panic!("expected data, got error: {message}"),
+            TaskMessage::Eof(code) => panic!("expected data, got EOF ({code:?})"),
+            TaskMessage::Comment(_) => unreachable!(),
+        }
+    }
+
+    /// Returns the state letter of process `pid` (`R`, `S`, `Z`, ...), or
+    /// `None` if it no longer exists.
+    #[cfg(target_os = "linux")]
+    fn process_state(pid: &str) -> Option<char> {
+        let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+        stat.rsplit_once(") ")?.1.chars().next()
+    }
+
+    /// Waits until `done` holds, polling every 20 ms.
+    async fn wait_for(what: &str, mut done: impl FnMut() -> bool) {
+        let deadline = Instant::now() + TEST_TIMEOUT;
+        while !done() {
+            assert!(Instant::now() < deadline, "timed out waiting for {what}");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn cancel_reaps_the_process_and_kills_its_group() {
+        let handle = TaskHandle::new();
+        let (mut rx, _) = handle
+            .subscribe(sh("sleep 30 & echo $$ $!; wait"), None)
+            .await;
+        let line = next_line(&mut rx).await;
+        let (shell, sleep) = line.split_once(' ').expect("two pids");
+        assert_eq!(process_state(sleep).map(|s| s != 'Z'), Some(true));
+
+        handle.unsubscribe();
+
+        // Reaped, not left `<defunct>`.
+        wait_for("the shell to be reaped", || process_state(shell).is_none()).await;
+        // Killed with the group; reaping it is up to init.
+        wait_for("the group to be killed", || {
+            process_state(sleep).is_none_or(|state| state == 'Z')
+        })
+        .await;
+        assert!(!handle.snapshot().running);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn eof_reaps_the_process_and_reports_its_exit_code() {
+        let handle = TaskHandle::new();
+        let (mut rx, _) = handle.subscribe(sh("echo $$; exit 3"), None).await;
+        let shell = next_line(&mut rx).await;
+
+        assert!(matches!(
+            next_message(&mut rx).await,
+            TaskMessage::Eof(Some(3))
+        ));
+        assert_eq!(process_state(&shell), None);
+        handle.unsubscribe();
+    }
+
+    #[tokio::test]
+    async fn a_dying_process_is_restarted_a_bounded_number_of_times() {
+        let spawns =
+            std::env::temp_dir().join(format!("task-handle-spawns-{}", std::process::id()));
+        let _ = std::fs::remove_file(&spawns);
+        let handle = TaskHandle::new().with_restarts(2, Duration::from_millis(10));
+        let (mut rx, _) = handle
+            .subscribe(sh(&format!("echo >> {}; exit 1", spawns.display())), None)
+            .await;
+
+        let mut restarts = 0;
+        loop {
+            match tokio::time::timeout(TEST_TIMEOUT, rx.recv()).await {
+                Ok(Ok(TaskMessage::Comment(comment))) if comment.contains("restarted") => {
+                    restarts += 1
+                }
+                Ok(Ok(TaskMessage::Comment(_) | TaskMessage::Error { fatal: false, .. })) => {}
+                Ok(Ok(TaskMessage::Eof(code))) => {
+                    assert_eq!(code, Some(1));
+                    break;
+                }
+                Ok(Ok(TaskMessage::Data(..))) => panic!("expected no data"),
+                Ok(Ok(TaskMessage::Error { message, .. })) => panic!("fatal error: {message}"),
+                Ok(Err(e)) => panic!("receive failed: {e}"),
+                Err(_) => panic!("no end of stream within {:?}", TEST_TIMEOUT),
+            }
+        }
+
+        assert_eq!(restarts, 2);
+        wait_for("the task to stop", || !handle.snapshot().running).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let spawned = std::fs::read_to_string(&spawns).expect("spawn count");
+        let _ = std::fs::remove_file(&spawns);
+        assert_eq!(spawned.lines().count(), 3);
+        handle.unsubscribe();
+    }
+
+    #[tokio::test]
+    async fn a_second_subscriber_gets_the_latest_line_of_a_slow_producer() {
+        let handle = TaskHandle::new();
+        let slow = "echo first; sleep 30";
+        let (mut first, replay) = handle.subscribe(sh(slow), None).await;
+        assert!(replay.latest.is_none(), "nothing printed yet");
+        assert_eq!(next_line(&mut first).await, "first");
+
+        // The producer prints nothing more for a while; the snapshot is
+        // all a late subscriber gets.
+        let (_second, replay) = handle.subscribe(sh(slow), None).await;
+        let (_, line) = replay.latest.expect("the latest line");
+        assert_eq!(line, "first");
+
+        handle.unsubscribe();
+        handle.unsubscribe();
+    }
+
+    #[tokio::test]
+    async fn the_latest_line_does_not_outlive_its_process() {
+        let handle = TaskHandle::new();
+        let (mut rx, _) = handle.subscribe(sh("echo old"), None).await;
+        assert_eq!(next_line(&mut rx).await, "old");
+        assert!(matches!(next_message(&mut rx).await, TaskMessage::Eof(_)));
+        handle.unsubscribe();
+
+        let (_rx, replay) = handle.subscribe(sh("sleep 30"), None).await;
+        assert!(replay.latest.is_none(), "a line of the previous run");
+        handle.unsubscribe();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn a_process_trapping_sigterm_stops_cleanly_within_the_grace() {
+        let trapped = std::env::temp_dir().join(format!("task-handle-trap-{}", std::process::id()));
+        let _ = std::fs::remove_file(&trapped);
+        let handle = TaskHandle::new().with_stop_grace(TEST_TIMEOUT);
+        let script = format!(
+            "trap 'echo trapped > {}; exit 0' TERM; echo $$; while :; do sleep 0.1; done",
+            trapped.display()
+        );
+        let (mut rx, _) = handle.subscribe(sh(&script), None).await;
+        let shell = next_line(&mut rx).await;
+
+        let stopping = Instant::now();
+        handle.unsubscribe();
+        wait_for("the shell to be reaped", || process_state(&shell).is_none()).await;
+
+        assert!(stopping.elapsed() < TEST_TIMEOUT, "killed after the grace");
+        let marker = std::fs::read_to_string(&trapped).expect("the trap ran");
+        let _ = std::fs::remove_file(&trapped);
+        assert_eq!(marker.trim(), "trapped");
+        assert!(!handle.snapshot().running);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn a_process_ignoring_sigterm_is_killed_after_the_grace() {
+        const GRACE: Duration = Duration::from_millis(300);
+        let handle = TaskHandle::new().with_stop_grace(GRACE);
+        let (mut rx, _) = handle
+            .subscribe(
+                sh("trap '' TERM; echo $$; while :; do sleep 0.1; done"),
+                None,
+            )
+            .await;
+        let shell = next_line(&mut rx).await;
+
+        let stopping = Instant::now();
+        handle.unsubscribe();
+        tokio::time::sleep(GRACE / 2).await;
+        assert_eq!(
+            process_state(&shell).map(|s| s != 'Z'),
+            Some(true),
+            "SIGTERM stopped a process ignoring it"
+        );
+
+        wait_for("the shell to be killed", || process_state(&shell).is_none()).await;
+        assert!(stopping.elapsed() >= GRACE, "killed before the grace");
+        assert!(!handle.snapshot().running);
+    }
+
+    #[tokio::test]
+    async fn a_subscriber_after_eof_gets_a_fresh_process() {
+        let handle = TaskHandle::new();
+        let (mut first, _) = handle.subscribe(sh("echo $$"), None).await;
+        let first_pid = next_line(&mut first).await;
+        assert!(matches!(
+            next_message(&mut first).await,
+            TaskMessage::Eof(_)
+        ));
+        wait_for("the task to stop", || !handle.snapshot().running).await;
+
+        // The first receiver is still held and subscribed.
+        let (mut second, _) = handle.subscribe(sh("echo $$"), None).await;
+        let second_pid = next_line(&mut second).await;
+        assert_ne!(second_pid, first_pid, "no process was started");
+        assert_eq!(next_line(&mut first).await, second_pid);
+        assert_eq!(handle.snapshot().ref_count, 2);
+
+        handle.unsubscribe();
+        handle.unsubscribe();
+    }
+}
@@ -1,8 +1,14 @@
-use crate::task_handle::{TaskDropGuard, TaskHandle, TaskMessage};
+use crate::task_handle::{
+    DEFAULT_BUFFER_SIZE, Replay, TaskDropGuard, TaskHandle, TaskKind, TaskMessage, TaskSnapshot,
+    TaskSource,
+};
 use dashmap::DashMap;
-use tracing::{debug, trace, warn};
+use std::fmt;
 use std::sync::Arc;
-use tokio::sync::broadcast;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::{Mutex, broadcast};
+use tracing::{debug, trace, warn};
 
 /// A unique identifier for a managed task.
 ///
@@ -11,6 +17,31 @@ use tokio::sync::broadcast;
 /// [`TaskHandle`].
 type TaskKey = String;
 
+/// Why [`TaskManager::subscribe`] refused a subscription.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubscribeError {
+    /// The limit on concurrent subscribers across all tasks is reached.
+    TooManySubscribers(usize),
+    /// The subscription would start a new process, but the limit on
+    /// concurrently running processes is reached.
+    TooManyTasks(usize),
+}
+
+impl fmt::Display for SubscribeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SubscribeError::TooManySubscribers(max) => {
+                write!(f, "too many live streams (limit {})", max)
+            }
+            SubscribeError::TooManyTasks(max) => {
+                write!(f, "too many live vnStat processes (limit {})", max)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SubscribeError {}
+
 /// Manages the lifecycle of keyed subprocesses with reference counting.
 ///
 /// `TaskManager` provides a central registry of running tasks, each identified
@@ -30,6 +61,40 @@ type TaskKey = String;
 #[derive(Clone)]
 pub struct TaskManager {
     tasks: Arc<DashMap<TaskKey, Arc<TaskHandle>>>,
+    /// Startup timeout applied to every [`TaskHandle`] created by this
+    /// manager, if any.
+    startup_timeout: Option<Duration>,
+    /// Maximum number of concurrent subscribers across all tasks; `0` means
+    /// unlimited.
+    max_subscribers: usize,
+    /// Maximum number of tasks with a running process; `0` means unlimited.
+    max_running: usize,
+    /// Replay buffer size of every [`TaskHandle`] created by this manager.
+    replay_capacity: usize,
+    /// Broadcast buffer size of every [`TaskHandle`] created by this
+    /// manager.
+    buffer_size: usize,
+    /// Consecutive restarts of a dead process, for every [`TaskHandle`]
+    /// created by this manager.
+    max_restarts: u32,
+    /// Wait before the first restart of a dead process.
+    restart_backoff: Duration,
+    /// Time between `SIGTERM` and `SIGKILL` when stopping a process.
+    stop_grace: Duration,
+    /// Time a process may run without receivers, if limited.
+    orphan_timeout: Option<Duration>,
+    /// Number of current subscribers across all tasks, decremented by the
+    /// [`TaskDropGuard`].
+    subscribers: Arc<AtomicUsize>,
+    /// Serialises the limit checks with the subscriptions they admit.
+    admission: Arc<Mutex<()>>,
+}
+
+impl Default for TaskManager {
+    /// Equivalent to [`TaskManager::new`].
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl TaskManager {
@@ -44,6 +109,145 @@ impl TaskManager {
     pub fn new() -> Self {
         Self {
             tasks: Arc::new(DashMap::new()),
+            startup_timeout: None,
+            max_subscribers: 0,
+            max_running: 0,
+            replay_capacity: 0,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            max_restarts: 0,
+            restart_backoff: Duration::ZERO,
+            stop_grace: Duration::ZERO,
+            orphan_timeout: None,
+            subscribers: Arc::new(AtomicUsize::new(0)),
+            admission: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Creates a new, empty `TaskManager` whose processes are killed if
+    /// they produce no output within `startup_timeout` of being spawned.
+    ///
+    /// # Arguments
+    ///
+    /// * `startup_timeout` - Passed to
+    ///   [`TaskHandle::with_startup_timeout`] for every new task.
+    ///
+    /// # Returns
+    ///
+    /// A new `TaskManager` with no registered tasks.
+    pub fn with_startup_timeout(startup_timeout: Duration) -> Self {
+        Self {
+            startup_timeout: Some(startup_timeout),
+            ..Self::new()
+        }
+    }
+
+    /// Sets the limits enforced by [`subscribe`](Self::subscribe).
+    ///
+    /// # Arguments
+    ///
+    /// * `max_subscribers` - Maximum number of concurrent subscribers
+    ///   across all tasks; `0` disables the check.
+    /// * `max_running` - Maximum number of tasks with a running process;
+    ///   `0` disables the check.
+    ///
+    /// # Returns
+    ///
+    /// The manager with the limits applied.
+    pub fn with_limits(self, max_subscribers: usize, max_running: usize) -> Self {
+        Self {
+            max_subscribers,
+            max_running,
+            ..self
+        }
+    }
+
+    /// Sets how many recent lines each task keeps for replay to
+    /// reconnecting subscribers (see [`TaskHandle::with_replay_capacity`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `replay_capacity` - Maximum number of buffered lines per task.
+    ///
+    /// # Returns
+    ///
+    /// The manager with the replay buffer size applied.
+    pub fn with_replay_capacity(self, replay_capacity: usize) -> Self {
+        Self {
+            replay_capacity,
+            ..self
+        }
+    }
+
+    /// Sets how many messages each task buffers for subscribers that fall
+    /// behind (see [`TaskHandle::with_buffer_size`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer_size` - Broadcast buffer size per task; at least 1.
+    ///
+    /// # Returns
+    ///
+    /// The manager with the buffer size applied.
+    pub fn with_buffer_size(self, buffer_size: usize) -> Self {
+        Self {
+            buffer_size,
+            ..self
+        }
+    }
+
+    /// Sets how each task restarts a process that dies while it has
+    /// subscribers (see [`TaskHandle::with_restarts`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `max_restarts` - Maximum number of consecutive restarts; `0`
+    ///   disables restarting.
+    /// * `backoff` - Wait before the first restart.
+    ///
+    /// # Returns
+    ///
+    /// The manager with the restart policy applied.
+    pub fn with_restarts(self, max_restarts: u32, backoff: Duration) -> Self {
+        Self {
+            max_restarts,
+            restart_backoff: backoff,
+            ..self
+        }
+    }
+
+    /// Sets how long each task's process may take to exit after `SIGTERM`
+    /// before it is killed (see [`TaskHandle::with_stop_grace`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `grace` - Time between `SIGTERM` and `SIGKILL`; zero kills right
+    ///   away.
+    ///
+    /// # Returns
+    ///
+    /// The manager with the grace period applied.
+    pub fn with_stop_grace(self, grace: Duration) -> Self {
+        Self {
+            stop_grace: grace,
+            ..self
+        }
+    }
+
+    /// Sets how long each task's process may run without receivers before
+    /// it is stopped (see [`TaskHandle::with_orphan_timeout`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `orphan_timeout` - Time without receivers; `None` never stops a
+    ///   process for that.
+    ///
+    /// # Returns
+    ///
+    /// The manager with the orphan timeout applied.
+    pub fn with_orphan_timeout(self, orphan_timeout: Option<Duration>) -> Self {
+        Self {
+            orphan_timeout,
+            ..self
         }
     }
 
@@ -52,18 +256,27 @@ impl TaskManager {
     /// If a task with the given `key` already exists, this method attaches a
     /// new subscriber to the existing handle. Otherwise, a new
     /// [`TaskHandle`] is created and stored in the registry, and the
-    /// associated process is spawned using the provided `cmd`.
+    /// associated process or watcher is started from `source`.
     ///
     /// The returned [`TaskDropGuard`] **must** be kept alive for the duration
     /// of the subscription. Dropping it will automatically unsubscribe, and
     /// when the last subscriber drops, the underlying task is stopped.
     ///
+    /// The limits set with [`with_limits`](Self::with_limits) are checked
+    /// first: the subscription is refused if the subscriber limit is
+    /// reached, or if it would start a process while the process limit is
+    /// reached. Subscribing to a task whose process is already running never
+    /// counts against the process limit, and neither do watchers, which run
+    /// no process.
+    ///
     /// # Arguments
     ///
     /// * `key` - The unique key identifying the task. Callers that use the
     ///   same key share the same underlying process.
-    /// * `cmd` - The command and its arguments used to spawn the process
-    ///   when the task is first created.
+    /// * `source` - The command (and its arguments) or watcher started when
+    ///   the task is not running.
+    /// * `last_seq` - Sequence number of the last line the caller saw in an
+    ///   earlier subscription, if any (see [`TaskHandle::subscribe`]).
     ///
     /// # Returns
     ///
@@ -71,44 +284,145 @@ impl TaskManager {
     ///
     /// * [`broadcast::Receiver<TaskMessage>`] — a receiver for messages
     ///   emitted by the task (e.g. output lines, exit events).
+    /// * [`Replay`] — the buffered lines after `last_seq`, and the task's
+    ///   last line.
     /// * [`TaskDropGuard`] — an RAII guard that, when dropped, unsubscribes
     ///   this caller from the task.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`SubscribeError`] if a limit is reached.
     pub async fn subscribe(
         self: &Arc<Self>,
         key: TaskKey,
-        cmd: Vec<String>,
-    ) -> (broadcast::Receiver<TaskMessage>, TaskDropGuard) {
+        source: impl Into<TaskSource>,
+        last_seq: Option<u64>,
+    ) -> Result<(broadcast::Receiver<TaskMessage>, Replay, TaskDropGuard), SubscribeError> {
+        let source = source.into();
+        let _admission = self.admission.lock().await;
+
+        if self.max_subscribers > 0
+            && self.subscribers.load(Ordering::SeqCst) >= self.max_subscribers
+        {
+            return Err(SubscribeError::TooManySubscribers(self.max_subscribers));
+        }
+
         // Insert a new task entry if one does not already exist for this key.
         let entry = self
             .tasks
             .entry(key.clone())
             .or_insert_with(|| {
                 debug!("Created task entry for key: {:?}", key);
-                Arc::new(TaskHandle::new())
+                let handle = match self.startup_timeout {
+                    Some(timeout) => TaskHandle::with_startup_timeout(timeout),
+                    None => TaskHandle::new(),
+                };
+                Arc::new(
+                    handle
+                        .with_buffer_size(self.buffer_size)
+                        .with_replay_capacity(self.replay_capacity)
+                        .with_restarts(self.max_restarts, self.restart_backoff)
+                        .with_stop_grace(self.stop_grace)
+                        .with_orphan_timeout(self.orphan_timeout),
+                )
             })
             .clone();
 
-        // Return the receiver and a drop guard for cleanup.
-        (entry.subscribe(cmd).await, self.get_drop_guard(key))
+        if self.max_running > 0 && source.kind() == TaskKind::Process && !entry.snapshot().running {
+            let running = self
+                .tasks
+                .iter()
+                .map(|task| task.value().snapshot())
+                .filter(|task| task.running && task.kind == TaskKind::Process)
+                .count();
+            if running >= self.max_running {
+                return Err(SubscribeError::TooManyTasks(self.max_running));
+            }
+        }
+
+        self.subscribers.fetch_add(1, Ordering::SeqCst);
+
+        let (receiver, replay) = entry.subscribe(source, last_seq).await;
+        // The last subscriber of the entry may have left, and removed it,
+        // since it was looked up; register it again.
+        self.tasks
+            .entry(key.clone())
+            .or_insert_with(|| Arc::clone(&entry));
+
+        // Return the receiver, the replay and a drop guard for cleanup.
+        Ok((receiver, replay, self.get_drop_guard(key, entry)))
     }
 
-    /// Unsubscribes from the task identified by `key`.
+    /// Returns a snapshot of every registered task, sorted by key.
     ///
-    /// Decrements the reference count on the associated [`TaskHandle`]. When
-    /// the count reaches zero, the handle stops its underlying process and
-    /// removes itself from the registry.
+    /// A task is registered from its first subscriber until its last one
+    /// leaves.
     ///
-    /// This method is a no-op (with a warning) if no task is registered for
-    /// the given `key`.
+    /// # Returns
+    ///
+    /// `(key, snapshot)` pairs, see [`TaskHandle::snapshot`].
+    pub fn snapshot(&self) -> Vec<(TaskKey, TaskSnapshot)> {
+        let mut tasks: Vec<_> = self
+            .tasks
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().snapshot()))
+            .collect();
+        tasks.sort_by(|(a, _), (b, _)| a.cmp(b));
+        tasks
+    }
+
+    /// Returns the number of current subscribers across all tasks.
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers.load(Ordering::SeqCst)
+    }
+
+    /// Returns the limit on concurrent subscribers; `0` means unlimited.
+    pub fn max_subscribers(&self) -> usize {
+        self.max_subscribers
+    }
+
+    /// Force-cancels the task identified by `key`, killing its process and
+    /// ending all subscribers' streams (see [`TaskHandle::cancel`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key of the task to cancel.
+    ///
+    /// # Returns
+    ///
+    /// * `None` if no task is registered for `key`.
+    /// * `Some(true)` if a running process was cancelled.
+    /// * `Some(false)` if the task had no running process.
+    pub fn cancel(&self, key: &str) -> Option<bool> {
+        let handle = self.tasks.get(key).map(|entry| Arc::clone(entry.value()))?;
+        let cancelled = handle.cancel();
+        if cancelled {
+            warn!("Task {:?} cancelled by request", key);
+        }
+        Some(cancelled)
+    }
+
+    /// Unsubscribes from `handle`, the task registered for `key` when the
+    /// caller subscribed.
+    ///
+    /// Decrements the manager-wide subscriber count and the reference count
+    /// on `handle`. When the latter reaches zero, the handle stops its
+    /// underlying process and is removed from the registry, unless `key`
+    /// has been registered to another handle since.
     ///
     /// # Arguments
     ///
     /// * `key` - The key of the task to unsubscribe from.
-    fn unsubscribe(&self, key: &TaskKey) {
-        if let Some(entry) = self.tasks.get(key) {
-            entry.unsubscribe();
-        } else {
-            warn!("Tried to unsubscribe from nonexistent key: {:?}", key);
+    /// * `handle` - The handle subscribed to.
+    fn unsubscribe(&self, key: &TaskKey, handle: &Arc<TaskHandle>) {
+        self.subscribers.fetch_sub(1, Ordering::SeqCst);
+        handle.unsubscribe();
+
+        let removed = self.tasks.remove_if(key, |_, registered| {
+            Arc::ptr_eq(registered, handle) && registered.snapshot().ref_count == 0
+        });
+        if removed.is_some() {
+            debug!("Removed task entry for key: {:?}", key);
         }
     }
 
@@ -117,25 +431,130 @@ impl TaskManager {
     /// The returned guard holds a weak reference to the `TaskManager`. When
     /// dropped, it calls [`unsubscribe`](Self::unsubscribe) on the manager,
     /// ensuring the caller's subscription is cleaned up even if the guard is
-    /// discarded without an explicit unsubscribe call.
+    /// discarded without an explicit unsubscribe call. Unsubscribing only
+    /// updates counters and cancels a token, so it is done right in `drop`,
+    /// with or without a runtime.
     ///
     /// # Arguments
     ///
     /// * `key` - The task key that the drop guard should clean up.
+    /// * `handle` - The handle registered for `key`.
     ///
     /// # Returns
     ///
     /// A [`TaskDropGuard`] that will unsubscribe from the given `key` when
     /// it is dropped.
-    fn get_drop_guard(self: &Arc<Self>, key: TaskKey) -> TaskDropGuard {
+    fn get_drop_guard(self: &Arc<Self>, key: TaskKey, handle: Arc<TaskHandle>) -> TaskDropGuard {
         let weak_manager = Arc::downgrade(self);
-        let cleanup_key = key.clone();
 
         TaskDropGuard::new(move || {
             trace!("SSE Stream closed");
             if let Some(manager) = weak_manager.upgrade() {
-                manager.unsubscribe(&cleanup_key);
+                manager.unsubscribe(&key, &handle);
             }
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A task running a command that prints nothing until it is stopped.
+    fn idle() -> TaskSource {
+        vec!["sleep".to_string(), "30".to_string()].into()
+    }
+
+    #[tokio::test]
+    async fn rejects_the_subscriber_over_the_limit() {
+        let manager = Arc::new(TaskManager::new().with_limits(2, 0));
+
+        let first = manager
+            .subscribe("a".into(), idle(), None)
+            .await
+            .expect("first");
+        let _second = manager
+            .subscribe("b".into(), idle(), None)
+            .await
+            .expect("second");
+        let third = manager.subscribe("a".into(), idle(), None).await;
+        assert_eq!(third.err(), Some(SubscribeError::TooManySubscribers(2)));
+
+        drop(first);
+        assert_eq!(manager.subscriber_count(), 1);
+        assert!(manager.subscribe("a".into(), idle(), None).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn rejects_the_process_over_the_limit() {
+        let manager = Arc::new(TaskManager::new().with_limits(0, 1));
+
+        let _first = manager
+            .subscribe("a".into(), idle(), None)
+            .await
+            .expect("first");
+        let second = manager.subscribe("b".into(), idle(), None).await;
+        assert_eq!(second.err(), Some(SubscribeError::TooManyTasks(1)));
+
+        // Joining the running process starts none.
+        let _joined = manager
+            .subscribe("a".into(), idle(), None)
+            .await
+            .expect("join");
+        assert_eq!(manager.subscriber_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn the_last_subscriber_removes_the_task() {
+        let manager = Arc::new(TaskManager::new());
+        let first = manager
+            .subscribe("a".into(), idle(), None)
+            .await
+            .expect("first");
+        let second = manager
+            .subscribe("a".into(), idle(), None)
+            .await
+            .expect("second");
+
+        drop(first);
+        assert_eq!(
+            manager.snapshot().len(),
+            1,
+            "removed with a subscriber left"
+        );
+        drop(second);
+        assert!(manager.snapshot().is_empty());
+
+        // A new subscriber registers the task again.
+        let _third = manager
+            .subscribe("a".into(), idle(), None)
+            .await
+            .expect("third");
+        assert_eq!(manager.snapshot().len(), 1);
+    }
+
+    #[test]
+    fn a_guard_dropped_without_a_runtime_cancels_the_task() {
+        let runtime = tokio::runtime::Runtime::new().expect("runtime");
+        let manager = Arc::new(TaskManager::new());
+        let (_rx, _, guard) = runtime
+            .block_on(manager.subscribe("a".into(), idle(), None))
+            .expect("subscribe");
+
+        std::thread::spawn(move || drop(guard))
+            .join()
+            .expect("dropping the guard outside a runtime panicked");
+        assert_eq!(manager.subscriber_count(), 0);
+
+        let running = || manager.snapshot().iter().any(|(_, task)| task.running);
+        runtime.block_on(async {
+            tokio::time::timeout(Duration::from_secs(5), async {
+                while running() {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+            })
+            .await
+            .expect("the process outlived its last subscriber");
+        });
+    }
+}
@@ -0,0 +1,117 @@
+use anyhow::{Context, Result};
+use axum::extract::ConnectInfo;
+use axum::{Extension, Router};
+use hyper::server::conn::http1;
+use hyper_util::rt::TokioIo;
+use hyper_util::service::TowerToHyperService;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::task::JoinSet;
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::rustls;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error};
+use vnstat_rs_api::config::listener::TlsConfig;
+
+/// Time a client has to complete the TLS handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Pause after a failed `accept`, e.g. when out of file descriptors, as
+/// `axum::serve` does.
+const ACCEPT_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Builds the TLS acceptor of an HTTPS listener from its PEM files.
+///
+/// # Errors
+///
+/// Returns an error if a file cannot be read, holds no certificate or
+/// private key, or the key does not match the certificate.
+pub fn acceptor(tls: &TlsConfig) -> Result<TlsAcceptor> {
+    let certs = CertificateDer::pem_file_iter(&tls.cert)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .with_context(|| format!("Failed to read certificates from `{}`", tls.cert.display()))?;
+    let key = PrivateKeyDer::from_pem_file(&tls.key)
+        .with_context(|| format!("Failed to read private key from `{}`", tls.key.display()))?;
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let mut config = rustls::ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .context("Failed to set up TLS")?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .context("Invalid TLS certificate or key")?;
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+/// Serves `app` over HTTPS on `listener` until `shutdown` is cancelled,
+/// then waits for the open connections to finish their current request.
+///
+/// Like `axum::serve`, each request carries the client's
+/// [`ConnectInfo`] and connections may be upgraded (e.g. to
+/// WebSockets). Failed handshakes are logged at `debug` and dropped.
+pub async fn serve(
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    app: Router,
+    shutdown: CancellationToken,
+) -> Result<()> {
+    let mut connections = JoinSet::new();
+
+    loop {
+        let (stream, addr) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    error!("Failed to accept a connection: {}", e);
+                    tokio::time::sleep(ACCEPT_BACKOFF).await;
+                    continue;
+                }
+            },
+            Some(_) = connections.join_next(), if !connections.is_empty() => continue,
+            _ = shutdown.cancelled() => break,
+        };
+
+        let acceptor = acceptor.clone();
+        let app = app.clone().layer(Extension(ConnectInfo(addr)));
+        let shutdown = shutdown.clone();
+        connections.spawn(async move {
+            let stream =
+                match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                    Ok(Ok(stream)) => stream,
+                    Ok(Err(e)) => {
+                        debug!("TLS handshake with {} failed: {}", addr, e);
+                        return;
+                    }
+                    Err(_) => {
+                        debug!("TLS handshake with {} timed out", addr);
+                        return;
+                    }
+                };
+
+            let connection = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), TowerToHyperService::new(app))
+                .with_upgrades();
+            tokio::pin!(connection);
+
+            let result = tokio::select! {
+                result = connection.as_mut() => result,
+                _ = shutdown.cancelled() => {
+                    connection.as_mut().graceful_shutdown();
+                    connection.await
+                }
+            };
+            if let Err(e) = result {
+                debug!("Connection with {} failed: {}", addr, e);
+            }
+        });
+    }
+
+    while connections.join_next().await.is_some() {}
+
+    Ok(())
+}
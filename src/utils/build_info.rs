@@ -0,0 +1,13 @@
+/// Version of this API (the crate version).
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Short git commit the API was built from, or `unknown`.
+pub const GIT_COMMIT: &str = env!("VNSTAT_RS_API_GIT_COMMIT");
+
+/// Build time as Unix seconds, as recorded by the build script.
+const BUILD_TIMESTAMP: &str = env!("VNSTAT_RS_API_BUILD_TIMESTAMP");
+
+/// Returns the time the API was built (Unix seconds); `0` if unknown.
+pub fn build_timestamp() -> i64 {
+    BUILD_TIMESTAMP.parse().unwrap_or_default()
+}
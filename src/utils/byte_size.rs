@@ -0,0 +1,235 @@
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Deserializer};
+use serde_json::{Map, Value};
+use std::fmt;
+use std::str::FromStr;
+
+/// IEC units used by [`ByteSize`]'s `Display`, each 1024 times the previous.
+const IEC_UNITS: [&str; 7] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+/// Byte counts that [`humanize_json`] adds a readable form of.
+const HUMAN_KEYS: [&str; 7] = [
+    "rx",
+    "tx",
+    "totalRx",
+    "totalTx",
+    "limit",
+    "used",
+    "remaining",
+];
+
+/// A byte count given in human units, e.g. `"2TB"`, `"1.5 GiB"` or `"500"`.
+///
+/// Decimal suffixes (`KB`, `MB`, `GB`, `TB`, `PB`) are powers of 1000,
+/// binary suffixes (`KiB`, `MiB`, `GiB`, `TiB`, `PiB`) powers of 1024, as
+/// billed by most hosting providers. Suffixes are case-insensitive and a
+/// bare number is taken as bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ByteSize(pub u64);
+
+impl ByteSize {
+    /// Units accepted after the number, with their size in bytes.
+    const UNITS: [(&'static str, u64); 11] = [
+        ("b", 1),
+        ("kb", 1_000),
+        ("mb", 1_000_000),
+        ("gb", 1_000_000_000),
+        ("tb", 1_000_000_000_000),
+        ("pb", 1_000_000_000_000_000),
+        ("kib", 1 << 10),
+        ("mib", 1 << 20),
+        ("gib", 1 << 30),
+        ("tib", 1 << 40),
+        ("pib", 1 << 50),
+    ];
+
+    /// Returns the number of bytes.
+    pub const fn bytes(&self) -> u64 {
+        self.0
+    }
+}
+
+impl FromStr for ByteSize {
+    type Err = anyhow::Error;
+
+    /// Parses a number, optionally fractional, followed by an optional unit.
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let split = s
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+
+        let number: f64 = number
+            .parse()
+            .with_context(|| format!("invalid size `{}`", s))?;
+        let unit = unit.trim().to_ascii_lowercase();
+        let multiplier = match unit.as_str() {
+            "" => 1,
+            unit => match Self::UNITS.iter().find(|(name, _)| *name == unit) {
+                Some((_, multiplier)) => *multiplier,
+                None => bail!("unknown unit in size `{}`", s),
+            },
+        };
+
+        let bytes = number * multiplier as f64;
+        if bytes >= u64::MAX as f64 {
+            bail!("size `{}` is too large", s);
+        }
+
+        Ok(ByteSize(bytes.round() as u64))
+    }
+}
+
+impl fmt::Display for ByteSize {
+    /// Formats the size the way the vnStat CLI does: whole bytes below
+    /// 1 KiB, otherwise the largest IEC unit it reaches with two decimals,
+    /// e.g. `"512 B"` or `"1.24 GiB"`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut value = self.0 as f64;
+        let mut unit = 0;
+        while value >= 1024.0 && unit < IEC_UNITS.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+
+        if unit == 0 {
+            write!(f, "{} {}", self.0, IEC_UNITS[0])
+        } else {
+            write!(f, "{:.2} {}", value, IEC_UNITS[unit])
+        }
+    }
+}
+
+/// Adds a readable form (see [`ByteSize`]'s `Display`) of the byte counts
+/// anywhere in `value`: next to each `rx`, `tx`, `totalRx`, `totalTx`,
+/// `limit`, `used` and `remaining` field holding an integer, a string field
+/// of the same name suffixed with `Human`, e.g. `"rxHuman": "1.24 GiB"`.
+pub fn humanize_json(value: &mut Value) {
+    match value {
+        Value::Object(object) => humanize_object(object),
+        Value::Array(items) => items.iter_mut().for_each(humanize_json),
+        _ => {}
+    }
+}
+
+fn humanize_object(object: &mut Map<String, Value>) {
+    let mut human = Vec::new();
+    for (key, value) in object.iter_mut() {
+        match value.as_u64() {
+            Some(bytes) if HUMAN_KEYS.contains(&key.as_str()) => {
+                human.push((format!("{}Human", key), ByteSize(bytes).to_string()));
+            }
+            _ => humanize_json(value),
+        }
+    }
+
+    for (key, text) in human {
+        object.insert(key, Value::from(text));
+    }
+}
+
+impl<'de> Deserialize<'de> for ByteSize {
+    /// Deserialises from a size string (e.g. `"2TB"`) or a plain number of
+    /// bytes.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Bytes(u64),
+            Text(String),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Bytes(bytes) => Ok(ByteSize(bytes)),
+            Raw::Text(s) => s.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn displays_bytes_below_one_kib() {
+        assert_eq!(ByteSize(0).to_string(), "0 B");
+        assert_eq!(ByteSize(1).to_string(), "1 B");
+        assert_eq!(ByteSize(1023).to_string(), "1023 B");
+    }
+
+    #[test]
+    fn displays_the_largest_unit_reached() {
+        assert_eq!(ByteSize(1024).to_string(), "1.00 KiB");
+        assert_eq!(ByteSize(1025).to_string(), "1.00 KiB");
+        assert_eq!(ByteSize(1536).to_string(), "1.50 KiB");
+        assert_eq!(ByteSize(1 << 20).to_string(), "1.00 MiB");
+        assert_eq!(ByteSize(1_331_439_861).to_string(), "1.24 GiB");
+        assert_eq!(ByteSize(1 << 60).to_string(), "1.00 EiB");
+        assert_eq!(ByteSize(u64::MAX).to_string(), "16.00 EiB");
+    }
+
+    #[test]
+    fn parses_decimal_and_binary_units() {
+        let cases = [
+            ("0", 0),
+            ("1", 1),
+            ("1023", 1023),
+            ("1024 B", 1024),
+            ("1KB", 1_000),
+            ("1KiB", 1024),
+            ("1 kib", 1024),
+            ("1.5 GiB", 3 << 29),
+            ("2TB", 2_000_000_000_000),
+            ("0.5b", 1),
+            ("16383 PiB", 16383 << 50),
+        ];
+        for (text, bytes) in cases {
+            assert_eq!(
+                text.parse::<ByteSize>().unwrap(),
+                ByteSize(bytes),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_and_oversized_sizes() {
+        for text in ["", "GiB", "-1", "1.2.3", "5 XB", "1 KiBs"] {
+            assert!(text.parse::<ByteSize>().is_err(), "{}", text);
+        }
+
+        // u64::MAX rounds up to 2^64 in floating point.
+        let error = u64::MAX.to_string().parse::<ByteSize>().unwrap_err();
+        assert!(error.to_string().contains("too large"), "{}", error);
+        assert!("16384 PiB".parse::<ByteSize>().is_err());
+    }
+
+    #[test]
+    fn deserializes_from_strings_and_numbers() {
+        let parsed: ByteSize = serde_json::from_value(json!("1 KiB")).unwrap();
+        assert_eq!(parsed, ByteSize(1024));
+        let parsed: ByteSize = serde_json::from_value(json!(u64::MAX)).unwrap();
+        assert_eq!(parsed, ByteSize(u64::MAX));
+        assert!(serde_json::from_value::<ByteSize>(json!(-1)).is_err());
+    }
+
+    #[test]
+    fn humanizes_byte_counts_at_any_depth() {
+        let mut value = json!({
+            "rx": 1023,
+            "id": 1024,
+            "days": [{ "tx": 1024, "used": u64::MAX, "limit": "2TB" }],
+        });
+
+        humanize_json(&mut value);
+
+        assert_eq!(value["rxHuman"], "1023 B");
+        assert_eq!(value["days"][0]["txHuman"], "1.00 KiB");
+        assert_eq!(value["days"][0]["usedHuman"], "16.00 EiB");
+        assert!(value.get("idHuman").is_none());
+        assert!(value["days"][0].get("limitHuman").is_none());
+    }
+}
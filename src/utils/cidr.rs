@@ -0,0 +1,91 @@
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Deserializer};
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+/// An IP network in CIDR notation, e.g. `"10.0.0.0/8"` or `"fd00::/8"`.
+///
+/// A bare address (`"192.0.2.1"`) is a network holding only that address.
+/// IPv4-mapped IPv6 addresses match the IPv4 networks they map to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cidr {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl Cidr {
+    /// Returns `true` if `ip` lies within this network.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => prefix_matches(
+                network.to_bits().into(),
+                ip.to_bits().into(),
+                32,
+                self.prefix_len,
+            ),
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                prefix_matches(network.to_bits(), ip.to_bits(), 128, self.prefix_len)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Returns `true` if the first `prefix_len` of the `width` low bits of `a`
+/// and `b` are equal.
+fn prefix_matches(a: u128, b: u128, width: u8, prefix_len: u8) -> bool {
+    if prefix_len == 0 {
+        return true;
+    }
+    let shift = width - prefix_len;
+    a >> shift == b >> shift
+}
+
+impl FromStr for Cidr {
+    type Err = anyhow::Error;
+
+    /// Parses `<address>/<prefix length>` or a bare address.
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let (address, prefix_len) = match s.split_once('/') {
+            Some((address, prefix_len)) => (address, Some(prefix_len)),
+            None => (s, None),
+        };
+
+        let network: IpAddr = address
+            .parse()
+            .with_context(|| format!("invalid address in network `{}`", s))?;
+        let network = network.to_canonical();
+        let max = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix_len {
+            Some(prefix_len) => prefix_len
+                .parse()
+                .with_context(|| format!("invalid prefix length in network `{}`", s))?,
+            None => max,
+        };
+        if prefix_len > max {
+            bail!("prefix length of network `{}` exceeds {}", s, max);
+        }
+
+        Ok(Cidr {
+            network,
+            prefix_len,
+        })
+    }
+}
+
+impl fmt::Display for Cidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix_len)
+    }
+}
+
+impl<'de> Deserialize<'de> for Cidr {
+    /// Deserialises from a string in CIDR notation.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
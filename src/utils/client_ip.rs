@@ -0,0 +1,203 @@
+use crate::utils::cidr::Cidr;
+use axum::http::HeaderMap;
+use std::net::{IpAddr, SocketAddr};
+
+/// Header listing the addresses a request was forwarded for, client first.
+const FORWARDED_FOR: &str = "x-forwarded-for";
+
+/// Header carrying the client address set by some proxies (e.g. nginx).
+const REAL_IP: &str = "x-real-ip";
+
+/// The address of the client that sent a request, looking through trusted
+/// reverse proxies.
+///
+/// Stored in the request extensions by the client-IP middleware; logging
+/// and access control use it instead of the peer address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub IpAddr);
+
+/// Determines the client address of a request received from `peer`.
+///
+/// Forwarding headers are only read when `peer` is one of the `trusted`
+/// proxies; otherwise anyone could claim any address. Then the
+/// `X-Forwarded-For` chain is walked from the right, skipping trusted
+/// proxies, and the first untrusted hop is the client. A malformed hop
+/// ends the walk at the last valid one, since nothing left of it can be
+/// trusted. Without `X-Forwarded-For`, a valid `X-Real-IP` is used.
+///
+/// # Returns
+///
+/// The client address, or the peer address if no usable header is present.
+pub fn resolve(peer: IpAddr, headers: &HeaderMap, trusted: &[Cidr]) -> IpAddr {
+    let is_trusted = |ip: IpAddr| trusted.iter().any(|cidr| cidr.contains(ip));
+    if !is_trusted(peer) {
+        return peer;
+    }
+
+    let hops: Vec<&str> = headers
+        .get_all(FORWARDED_FOR)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .filter(|hop| !hop.is_empty())
+        .collect();
+
+    if !hops.is_empty() {
+        let mut client = peer;
+        for hop in hops.into_iter().rev() {
+            match parse_hop(hop) {
+                Some(ip) if is_trusted(ip) => client = ip,
+                Some(ip) => return ip,
+                None => break,
+            }
+        }
+        return client;
+    }
+
+    headers
+        .get(REAL_IP)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| parse_hop(v.trim()))
+        .unwrap_or(peer)
+}
+
+/// Parses one forwarded address: a bare IP, `ip:port` or `[ipv6]:port`.
+fn parse_hop(hop: &str) -> Option<IpAddr> {
+    hop.parse::<IpAddr>()
+        .or_else(|_| hop.parse::<SocketAddr>().map(|addr| addr.ip()))
+        .ok()
+        .map(|ip| ip.to_canonical())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    /// The reverse proxies of the tests: a local one and a load balancer.
+    fn trusted() -> Vec<Cidr> {
+        ["127.0.0.1", "10.0.0.0/8"]
+            .iter()
+            .map(|cidr| cidr.parse().unwrap())
+            .collect()
+    }
+
+    /// Parses an address of the tests.
+    fn ip(ip: &str) -> IpAddr {
+        ip.parse().unwrap()
+    }
+
+    /// Resolves a request from `peer` carrying `headers` (name, value).
+    fn resolve_with(peer: &str, headers: &[(&'static str, &str)]) -> IpAddr {
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            map.append(*name, HeaderValue::from_str(value).unwrap());
+        }
+        resolve(ip(peer), &map, &trusted())
+    }
+
+    #[test]
+    fn headers_from_untrusted_peers_are_ignored() {
+        let resolved = resolve_with(
+            "203.0.113.9",
+            &[(FORWARDED_FOR, "198.51.100.1"), (REAL_IP, "198.51.100.2")],
+        );
+        assert_eq!(resolved, ip("203.0.113.9"));
+    }
+
+    #[test]
+    fn walks_the_chain_through_trusted_proxies() {
+        let chain = "198.51.100.1, 10.0.0.2, 10.0.0.3";
+        assert_eq!(
+            resolve_with("127.0.0.1", &[(FORWARDED_FOR, chain)]),
+            ip("198.51.100.1")
+        );
+
+        // The same chain split across several headers.
+        let split = [
+            (FORWARDED_FOR, "198.51.100.1"),
+            (FORWARDED_FOR, "10.0.0.2, 10.0.0.3"),
+        ];
+        assert_eq!(resolve_with("127.0.0.1", &split), ip("198.51.100.1"));
+    }
+
+    #[test]
+    fn the_rightmost_untrusted_hop_is_the_client() {
+        // The first entry is whatever the client claimed to forward.
+        let chain = "192.0.2.66, 198.51.100.1, 10.0.0.2";
+        assert_eq!(
+            resolve_with("127.0.0.1", &[(FORWARDED_FOR, chain)]),
+            ip("198.51.100.1")
+        );
+    }
+
+    #[test]
+    fn a_chain_of_trusted_proxies_resolves_to_the_leftmost() {
+        assert_eq!(
+            resolve_with("127.0.0.1", &[(FORWARDED_FOR, "10.0.0.2, 10.0.0.3")]),
+            ip("10.0.0.2")
+        );
+    }
+
+    #[test]
+    fn a_malformed_hop_ends_the_walk() {
+        for chain in ["garbage, 10.0.0.2", "198.51.100.1, :::1:, 10.0.0.2"] {
+            assert_eq!(
+                resolve_with("127.0.0.1", &[(FORWARDED_FOR, chain)]),
+                ip("10.0.0.2"),
+                "{}",
+                chain
+            );
+        }
+        assert_eq!(
+            resolve_with("127.0.0.1", &[(FORWARDED_FOR, "unknown")]),
+            ip("127.0.0.1")
+        );
+    }
+
+    #[test]
+    fn empty_headers_fall_back() {
+        assert_eq!(
+            resolve_with("127.0.0.1", &[(FORWARDED_FOR, " , ")]),
+            ip("127.0.0.1")
+        );
+        assert_eq!(
+            resolve_with(
+                "127.0.0.1",
+                &[(FORWARDED_FOR, ""), (REAL_IP, "198.51.100.2")]
+            ),
+            ip("198.51.100.2")
+        );
+        assert_eq!(resolve_with("127.0.0.1", &[(REAL_IP, "")]), ip("127.0.0.1"));
+        assert_eq!(
+            resolve_with("127.0.0.1", &[(REAL_IP, "garbage")]),
+            ip("127.0.0.1")
+        );
+    }
+
+    #[test]
+    fn hops_may_carry_a_port() {
+        let cases = [
+            ("198.51.100.1:4711", "198.51.100.1"),
+            ("[2001:db8::1]:4711", "2001:db8::1"),
+            ("2001:db8::1", "2001:db8::1"),
+            // IPv4-mapped addresses are reported as IPv4.
+            ("[::ffff:198.51.100.1]:80", "198.51.100.1"),
+        ];
+        for (hop, client) in cases {
+            assert_eq!(
+                resolve_with("127.0.0.1", &[(FORWARDED_FOR, hop)]),
+                ip(client),
+                "{}",
+                hop
+            );
+        }
+
+        // Without brackets, the port cannot be told from the address.
+        assert_eq!(
+            resolve_with("127.0.0.1", &[(FORWARDED_FOR, "2001:db8::1:4711")]),
+            ip("2001:db8::1:4711")
+        );
+    }
+}
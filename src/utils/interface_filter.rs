@@ -0,0 +1,54 @@
+use anyhow::{Context, Result};
+use glob::Pattern;
+
+/// Compiled interface allowlist / denylist.
+///
+/// Built once from the `include_interfaces` / `exclude_interfaces` glob
+/// patterns in the `[vnstat]` configuration. At most one of the two lists is
+/// non-empty (enforced by config validation); when both are empty every
+/// interface is visible.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InterfaceFilter {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+impl InterfaceFilter {
+    /// Compiles the given glob patterns (e.g. `veth*`, `docker0`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the first pattern that is not a valid glob.
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self> {
+        Ok(Self {
+            include: compile(include)?,
+            exclude: compile(exclude)?,
+        })
+    }
+
+    /// Returns `true` if no patterns are configured, i.e. every interface is
+    /// visible.
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Returns `true` if the interface `name` may be exposed through the API.
+    ///
+    /// With an include list, only matching names are visible. With an
+    /// exclude list, matching names are hidden.
+    pub fn is_visible(&self, name: &str) -> bool {
+        if !self.include.is_empty() && !self.include.iter().any(|p| p.matches(name)) {
+            return false;
+        }
+
+        !self.exclude.iter().any(|p| p.matches(name))
+    }
+}
+
+/// Compiles a list of glob patterns.
+fn compile(patterns: &[String]) -> Result<Vec<Pattern>> {
+    patterns
+        .iter()
+        .map(|p| Pattern::new(p).with_context(|| format!("Invalid interface pattern `{}`", p)))
+        .collect()
+}
@@ -0,0 +1,62 @@
+use anyhow::{Result, bail};
+
+/// Longest interface name the kernel accepts (`IFNAMSIZ` minus the NUL).
+const MAX_NAME_LEN: usize = 15;
+
+/// Longest alias vnStat stores.
+const MAX_ALIAS_LEN: usize = 31;
+
+/// Checks that `name` is safe to pass to vnStat as an interface name.
+///
+/// Accepts 1–15 ASCII letters, digits and `.`, `_`, `-`, `:`, `@`, not
+/// starting with `-` (which vnStat would read as an option) and not `.` or
+/// `..`.
+///
+/// # Errors
+///
+/// Returns an error describing why the name is rejected.
+pub fn validate_interface_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        bail!(
+            "Interface name must be 1 to {} characters long",
+            MAX_NAME_LEN
+        );
+    }
+
+    if !name
+        .bytes()
+        .all(|b| b.is_ascii_alphanumeric() || b"._-:@".contains(&b))
+    {
+        bail!("Interface name may only contain letters, digits and `._-:@`");
+    }
+
+    if name.starts_with('-') || name == "." || name == ".." {
+        bail!("`{}` is not a valid interface name", name);
+    }
+
+    Ok(())
+}
+
+/// Checks that `alias` is safe to pass to vnStat as an interface alias.
+///
+/// Accepts up to 31 printable ASCII characters or spaces, not starting with
+/// `-`. An empty alias removes the alias.
+///
+/// # Errors
+///
+/// Returns an error describing why the alias is rejected.
+pub fn validate_alias(alias: &str) -> Result<()> {
+    if alias.len() > MAX_ALIAS_LEN {
+        bail!("Alias must be at most {} characters long", MAX_ALIAS_LEN);
+    }
+
+    if !alias.bytes().all(|b| b.is_ascii_graphic() || b == b' ') {
+        bail!("Alias may only contain printable ASCII characters");
+    }
+
+    if alias.starts_with('-') {
+        bail!("Alias must not start with `-`");
+    }
+
+    Ok(())
+}
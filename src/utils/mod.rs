@@ -1,2 +1,15 @@
+pub mod build_info;
+pub mod byte_size;
+pub mod cidr;
+pub mod client_ip;
+pub mod interface_filter;
+pub mod interface_name;
+pub mod process;
+pub mod reloadable;
+pub mod request_id;
+pub mod secret;
 pub mod sse;
 pub mod timestamp;
+pub mod traffic_unit;
+pub mod version;
+pub mod ws;
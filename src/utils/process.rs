@@ -0,0 +1,233 @@
+use serde::Deserialize;
+use std::env;
+use std::fmt;
+use tokio::process::Command;
+
+/// How a child process is spawned, beyond its command line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpawnOptions {
+    /// The environment variables the child sees.
+    pub env: ProcessEnv,
+    /// The CPU and I/O priority the child runs at.
+    pub priority: ProcessPriority,
+}
+
+impl SpawnOptions {
+    /// Sets up `command` with these options.
+    pub fn apply(&self, command: &mut Command) {
+        self.env.apply(command);
+        self.priority.apply(command);
+    }
+}
+
+/// The environment a spawned child process runs with.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ProcessEnv {
+    /// Everything the server was started with.
+    #[default]
+    Inherit,
+    /// Only these variables; everything else is cleared.
+    Clean(Vec<(String, String)>),
+}
+
+impl ProcessEnv {
+    /// Returns a clean environment holding the server's `PATH`, `LC_ALL=C`
+    /// so that numbers and dates are printed the same on every host, and
+    /// the server's `TZ` if `keep_tz` is set.
+    pub fn minimal(keep_tz: bool) -> Self {
+        let mut vars = Vec::new();
+        if let Some(path) = env::var_os("PATH") {
+            vars.push(("PATH".to_string(), path.to_string_lossy().into_owned()));
+        }
+        vars.push(("LC_ALL".to_string(), "C".to_string()));
+        if keep_tz && let Some(tz) = env::var_os("TZ") {
+            vars.push(("TZ".to_string(), tz.to_string_lossy().into_owned()));
+        }
+
+        Self::Clean(vars)
+    }
+
+    /// Sets up the environment of `command` accordingly.
+    pub fn apply(&self, command: &mut Command) {
+        if let Self::Clean(vars) = self {
+            command.env_clear().envs(vars.iter().map(|(k, v)| (k, v)));
+        }
+    }
+}
+
+/// Linux I/O scheduling classes, as set by `ionice -c`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IoClass {
+    /// Served first; requires `CAP_SYS_ADMIN`.
+    Realtime,
+    /// The default class, shared fairly.
+    BestEffort,
+    /// Served only when no other process needs the disk.
+    Idle,
+}
+
+impl IoClass {
+    /// Returns the `IOPRIO_CLASS_*` value of the class.
+    #[cfg(target_os = "linux")]
+    const fn ioprio_class(self) -> libc::c_int {
+        match self {
+            Self::Realtime => 1,
+            Self::BestEffort => 2,
+            Self::Idle => 3,
+        }
+    }
+}
+
+impl fmt::Display for IoClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Realtime => "realtime",
+            Self::BestEffort => "best-effort",
+            Self::Idle => "idle",
+        })
+    }
+}
+
+/// CPU and I/O priority of a spawned child process; unset parts keep the
+/// priority inherited from the server.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProcessPriority {
+    /// Niceness, from -20 (highest priority) to 19 (lowest).
+    pub nice: Option<i32>,
+    /// I/O scheduling class, at level 4 for the realtime and best-effort
+    /// classes. Linux only.
+    pub io_class: Option<IoClass>,
+}
+
+impl ProcessPriority {
+    /// Returns `true` if neither the niceness nor the I/O class is set.
+    pub fn is_inherited(&self) -> bool {
+        self.nice.is_none() && self.io_class.is_none()
+    }
+
+    /// Makes `command` set the priority in the child before executing the
+    /// program; spawning fails if it cannot be set, e.g. a negative
+    /// niceness without `CAP_SYS_NICE`.
+    pub fn apply(&self, command: &mut Command) {
+        if self.is_inherited() {
+            return;
+        }
+
+        #[cfg(unix)]
+        {
+            let priority = *self;
+            // SAFETY: the closure only makes the async-signal-safe
+            // setpriority(2) and ioprio_set(2) calls and allocates nothing.
+            unsafe {
+                command.pre_exec(move || priority.set_current());
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = command;
+    }
+
+    /// Applies the priority to the calling process.
+    #[cfg(unix)]
+    fn set_current(&self) -> std::io::Result<()> {
+        if let Some(nice) = self.nice {
+            // SAFETY: setpriority(2) takes no pointers.
+            if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) } == -1 {
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+
+        #[cfg(target_os = "linux")]
+        if let Some(class) = self.io_class {
+            const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+            const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+            let level = if class == IoClass::Idle { 0 } else { 4 };
+            let ioprio = (class.ioprio_class() << IOPRIO_CLASS_SHIFT) | level;
+            // SAFETY: ioprio_set(2) takes no pointers.
+            if unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, ioprio) } == -1 {
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for ProcessPriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.nice {
+            Some(nice) => write!(f, "nice {}", nice)?,
+            None => f.write_str("inherited niceness")?,
+        }
+        match self.io_class {
+            Some(class) => write!(f, ", I/O class {}", class),
+            None => f.write_str(", inherited I/O class"),
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::os::unix::fs::PermissionsExt;
+
+    /// Runs a script named `name` printing its environment with `options`
+    /// and returns the variables it saw, apart from the `PWD` the shell
+    /// sets itself.
+    async fn child_env(name: &str, options: &SpawnOptions) -> BTreeMap<String, String> {
+        let script = env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+        std::fs::write(&script, "#!/bin/sh\nexec env\n").expect("write script");
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755))
+            .expect("make script executable");
+
+        let mut command = Command::new(&script);
+        options.apply(&mut command);
+        let output = command.output().await.expect("run script");
+        let _ = std::fs::remove_file(&script);
+        assert!(output.status.success(), "{:?}", output);
+
+        String::from_utf8(output.stdout)
+            .expect("UTF-8 environment")
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .filter(|(name, _)| *name != "PWD")
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn a_minimal_environment_passes_only_path_locale_and_tz() {
+        let path = env::var("PATH").expect("PATH is set");
+        let tz = env::var("TZ").ok();
+
+        for keep_tz in [false, true] {
+            let options = SpawnOptions {
+                env: ProcessEnv::minimal(keep_tz),
+                ..SpawnOptions::default()
+            };
+
+            let seen = child_env("process-env-minimal", &options).await;
+
+            let mut expected = BTreeMap::from([
+                ("PATH".to_string(), path.clone()),
+                ("LC_ALL".to_string(), "C".to_string()),
+            ]);
+            if keep_tz && let Some(tz) = &tz {
+                expected.insert("TZ".to_string(), tz.clone());
+            }
+            assert_eq!(seen, expected, "keep_tz={}", keep_tz);
+        }
+    }
+
+    #[tokio::test]
+    async fn an_inherited_environment_passes_everything() {
+        let seen = child_env("process-env-inherit", &SpawnOptions::default()).await;
+
+        // Multi-line values cannot be told apart in the output of `env`.
+        let vars = env::vars().filter(|(name, value)| name != "PWD" && !value.contains('\n'));
+        for (name, value) in vars {
+            assert_eq!(seen.get(&name), Some(&value), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use std::sync::{Arc, PoisonError, RwLock};
+
+/// A value that can be replaced at runtime, e.g. on a configuration reload.
+///
+/// Readers take a cheap snapshot with [`load`](Self::load) and keep using it
+/// even if the value is replaced meanwhile. Cloning shares the value: a
+/// [`store`](Self::store) through one clone is seen by all of them.
+#[derive(Debug, Default)]
+pub struct Reloadable<T>(Arc<RwLock<Arc<T>>>);
+
+impl<T> Reloadable<T> {
+    /// Creates a reloadable holding `value`.
+    pub fn new(value: T) -> Self {
+        Self(Arc::new(RwLock::new(Arc::new(value))))
+    }
+
+    /// Returns the current value.
+    pub fn load(&self) -> Arc<T> {
+        self.0
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Replaces the value; snapshots already loaded are not affected.
+    pub fn store(&self, value: T) {
+        *self.0.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(value);
+    }
+}
+
+impl<T> Clone for Reloadable<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
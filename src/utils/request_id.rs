@@ -0,0 +1,85 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+tokio::task_local! {
+    /// ID of the request currently being handled on this task.
+    static CURRENT: RequestId;
+}
+
+/// Identifier of a single HTTP request, taken from the `X-Request-Id`
+/// header or generated.
+///
+/// Stored in the request extensions by the request-ID middleware.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    /// Generates a random version 4 UUID, e.g.
+    /// `"3f1c2a9e-6b4d-4c1a-9f0e-2d7b8a6c5e41"`.
+    pub fn generate() -> Self {
+        let bytes = random_u128().to_be_bytes();
+        let mut b = bytes;
+        b[6] = (b[6] & 0x0f) | 0x40; // version 4
+        b[8] = (b[8] & 0x3f) | 0x80; // RFC 4122 variant
+
+        let hex: String = b.iter().map(|byte| format!("{:02x}", byte)).collect();
+        RequestId(format!(
+            "{}-{}-{}-{}-{}",
+            &hex[0..8],
+            &hex[8..12],
+            &hex[12..16],
+            &hex[16..20],
+            &hex[20..32]
+        ))
+    }
+
+    /// Accepts a client-supplied ID if it is 1–128 visible ASCII characters.
+    pub fn parse(value: &str) -> Option<Self> {
+        let valid =
+            !value.is_empty() && value.len() <= 128 && value.bytes().all(|b| b.is_ascii_graphic());
+
+        valid.then(|| RequestId(value.to_string()))
+    }
+
+    /// Runs `f` with this ID as the [`current`] request ID.
+    pub async fn scope<F: Future>(self, f: F) -> F::Output {
+        CURRENT.scope(self, f).await
+    }
+}
+
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Returns the ID of the request being handled by the current task, if any.
+pub fn current() -> Option<RequestId> {
+    CURRENT.try_with(RequestId::clone).ok()
+}
+
+/// Returns 128 random bits.
+///
+/// Uses std's randomly keyed SipHash over a process-wide counter and the
+/// current time; unpredictable enough for correlation IDs without pulling
+/// in an RNG crate.
+fn random_u128() -> u128 {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let mut halves = [0u64; 2];
+    for half in &mut halves {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+        hasher.write_u128(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos(),
+        );
+        *half = hasher.finish();
+    }
+
+    (u128::from(halves[0]) << 64) | u128::from(halves[1])
+}
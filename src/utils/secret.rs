@@ -0,0 +1,12 @@
+/// Compares a presented secret (token, API key) with the expected one.
+///
+/// The comparison takes the same time for every secret of the expected
+/// length, so response timing does not reveal how much of it matched.
+pub fn secrets_match(expected: &str, presented: &str) -> bool {
+    expected.len() == presented.len()
+        && expected
+            .bytes()
+            .zip(presented.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
@@ -1,7 +1,8 @@
 use axum::http::HeaderValue;
-use axum::response::{IntoResponse, Response, Sse};
 use axum::response::sse::Event;
+use axum::response::{IntoResponse, Response, Sse};
 use futures_util::Stream;
+use std::convert::Infallible;
 
 /// Wraps an SSE stream with common HTTP response headers for long-lived SSE
 /// connections.
@@ -20,7 +21,7 @@ use futures_util::Stream;
 ///   attached.
 pub fn sse_with_default_headers<T>(sse: Sse<T>) -> Response
 where
-    T: Stream<Item = Result<Event, String>> + Send + 'static,
+    T: Stream<Item = Result<Event, Infallible>> + Send + 'static,
 {
     let mut res = sse.into_response();
 
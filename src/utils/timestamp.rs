@@ -1,4 +1,6 @@
+use std::sync::OnceLock;
 use std::time::{SystemTime, UNIX_EPOCH};
+use time::{Month, OffsetDateTime, PrimitiveDateTime, UtcOffset};
 
 /// Returns the current system time as a millisecond timestamp since the Unix
 /// epoch.
@@ -14,3 +16,67 @@ pub fn get_in_ms() -> u128 {
 
     since_the_epoch.as_millis()
 }
+
+/// Returns the current system time as whole seconds since the Unix epoch.
+///
+/// Falls back to `0` when the system clock is set before the Unix epoch.
+///
+/// * Returns: The number of whole seconds since `1970-01-01 00:00:00 UTC`.
+pub fn get_in_secs() -> i64 {
+    let start = SystemTime::now();
+    let since_the_epoch = start.duration_since(UNIX_EPOCH).unwrap_or_default();
+
+    since_the_epoch.as_secs() as i64
+}
+
+/// Converts a local calendar date and time into a Unix timestamp (seconds).
+///
+/// vnStat records are aligned to local-time boundaries, so the host's UTC
+/// offset (determined once, falling back to UTC when it cannot be read) is
+/// applied.
+///
+/// * Returns: `None` if the fields do not form a valid date and time.
+pub fn from_local_date(year: i32, month: u8, day: u8, hour: u8, minute: u8) -> Option<i64> {
+    let date = time::Date::from_calendar_date(year, Month::try_from(month).ok()?, day).ok()?;
+    let time = time::Time::from_hms(hour, minute, 0).ok()?;
+
+    Some(
+        PrimitiveDateTime::new(date, time)
+            .assume_offset(local_offset())
+            .unix_timestamp(),
+    )
+}
+
+/// Converts a Unix timestamp (seconds) into the local calendar date, using
+/// the same UTC offset as [`from_local_date`].
+///
+/// * Returns: `(year, month, day)`, or `None` if the timestamp is out of
+///   range.
+pub fn to_local_date(timestamp: i64) -> Option<(i32, u8, u8)> {
+    to_local_datetime(timestamp).map(|(year, month, day, _, _)| (year, month, day))
+}
+
+/// Like [`to_local_date`], with the local time of day.
+///
+/// * Returns: `(year, month, day, hour, minute)`, or `None` if the
+///   timestamp is out of range.
+pub fn to_local_datetime(timestamp: i64) -> Option<(i32, u8, u8, u8, u8)> {
+    let datetime = OffsetDateTime::from_unix_timestamp(timestamp)
+        .ok()?
+        .to_offset(local_offset());
+
+    Some((
+        datetime.year(),
+        datetime.month() as u8,
+        datetime.day(),
+        datetime.hour(),
+        datetime.minute(),
+    ))
+}
+
+/// Returns the host's UTC offset, determined once and falling back to UTC
+/// when it cannot be read.
+fn local_offset() -> UtcOffset {
+    static OFFSET: OnceLock<UtcOffset> = OnceLock::new();
+    *OFFSET.get_or_init(|| UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC))
+}
@@ -0,0 +1,247 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Keys of the byte counts converted by [`TrafficUnit::convert_json`].
+const TRAFFIC_KEYS: [&str; 4] = ["rx", "tx", "totalRx", "totalTx"];
+
+/// The unit traffic values are reported in (`?unit=`).
+///
+/// Binary units are powers of 1024, so `kib` is 1024 bytes; `bits`
+/// multiplies by 8, for providers that bill in bits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrafficUnit {
+    /// Raw byte counts, as stored by vnStat.
+    #[default]
+    Bytes,
+    /// Kibibytes (1024 bytes).
+    Kib,
+    /// Mebibytes (1024² bytes).
+    Mib,
+    /// Gibibytes (1024³ bytes).
+    Gib,
+    /// Bits (1/8 byte).
+    Bits,
+}
+
+/// A traffic value: a byte count, or a converted floating-point amount.
+///
+/// Serialised as a bare number.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Amount {
+    /// A raw byte count.
+    Bytes(u64),
+    /// An amount in another [`TrafficUnit`].
+    Converted(f64),
+}
+
+impl TrafficUnit {
+    /// Returns the name of the unit, as accepted by `?unit=`.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Bytes => "bytes",
+            Self::Kib => "kib",
+            Self::Mib => "mib",
+            Self::Gib => "gib",
+            Self::Bits => "bits",
+        }
+    }
+
+    /// Converts `bytes` to this unit.
+    ///
+    /// The conversion happens in floating point, so that bit counts of
+    /// byte counts near `u64::MAX` do not overflow.
+    pub fn convert(self, bytes: u64) -> f64 {
+        let bytes = bytes as f64;
+        match self {
+            Self::Bytes => bytes,
+            Self::Kib => bytes / 1024.0,
+            Self::Mib => bytes / (1024.0 * 1024.0),
+            Self::Gib => bytes / (1024.0 * 1024.0 * 1024.0),
+            Self::Bits => bytes * 8.0,
+        }
+    }
+
+    /// Returns `bytes` in this unit; byte counts stay integers.
+    pub fn amount(self, bytes: u64) -> Amount {
+        match self {
+            Self::Bytes => Amount::Bytes(bytes),
+            unit => Amount::Converted(unit.convert(bytes)),
+        }
+    }
+
+    /// Converts the byte counts (`rx`, `tx`, `totalRx` and `totalTx`
+    /// fields) anywhere in `value` to this unit, and records the unit in a
+    /// `unit` field: of `value` itself if it is an object, of each of its
+    /// objects if it is an array.
+    pub fn convert_json(self, value: &mut Value) {
+        self.convert_fields(value);
+
+        let unit = Value::from(self.name());
+        match value {
+            Value::Object(object) => {
+                object.insert("unit".to_string(), unit);
+            }
+            Value::Array(items) => {
+                for object in items.iter_mut().filter_map(Value::as_object_mut) {
+                    object.insert("unit".to_string(), unit.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Recursively converts the byte counts in `value`.
+    fn convert_fields(self, value: &mut Value) {
+        match value {
+            Value::Object(object) => self.convert_object(object),
+            Value::Array(items) => items.iter_mut().for_each(|item| self.convert_fields(item)),
+            _ => {}
+        }
+    }
+
+    fn convert_object(self, object: &mut Map<String, Value>) {
+        for (key, value) in object.iter_mut() {
+            match value.as_u64() {
+                Some(bytes) if TRAFFIC_KEYS.contains(&key.as_str()) => {
+                    *value = Value::from(self.convert(bytes));
+                }
+                _ => self.convert_fields(value),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Every unit, in declaration order.
+    const ALL: [TrafficUnit; 5] = [
+        TrafficUnit::Bytes,
+        TrafficUnit::Kib,
+        TrafficUnit::Mib,
+        TrafficUnit::Gib,
+        TrafficUnit::Bits,
+    ];
+
+    #[test]
+    fn names_are_accepted_by_the_query() {
+        for unit in ALL {
+            let parsed: TrafficUnit = serde_json::from_value(json!(unit.name())).unwrap();
+            assert_eq!(parsed, unit);
+        }
+        assert!(serde_json::from_value::<TrafficUnit>(json!("GiB")).is_err());
+        assert!(serde_json::from_value::<TrafficUnit>(json!("tib")).is_err());
+    }
+
+    #[test]
+    fn converts_every_unit() {
+        const GIB: u64 = 1 << 30;
+        let cases = [
+            (0, [0.0, 0.0, 0.0, 0.0, 0.0]),
+            (
+                1,
+                [1.0, 1.0 / 1024.0, 1.0 / 1048576.0, 1.0 / 1073741824.0, 8.0],
+            ),
+            (1024, [1024.0, 1.0, 1.0 / 1024.0, 1.0 / 1048576.0, 8192.0]),
+            (GIB, [GIB as f64, 1048576.0, 1024.0, 1.0, 8.0 * GIB as f64]),
+            (
+                3 * GIB / 2,
+                [1610612736.0, 1572864.0, 1536.0, 1.5, 12884901888.0],
+            ),
+        ];
+        for (bytes, expected) in cases {
+            for (unit, expected) in ALL.into_iter().zip(expected) {
+                assert_eq!(unit.convert(bytes), expected, "{} {}", bytes, unit.name());
+            }
+        }
+    }
+
+    #[test]
+    fn converts_counts_near_u64_max_without_overflow() {
+        let max = u64::MAX as f64;
+        for bytes in [u64::MAX, u64::MAX - 1, u64::MAX / 8 + 1] {
+            for unit in ALL {
+                let converted = unit.convert(bytes);
+                assert!(converted.is_finite(), "{} {}", bytes, unit.name());
+                assert!(converted > 0.0, "{} {}", bytes, unit.name());
+            }
+        }
+        assert_eq!(TrafficUnit::Bits.convert(u64::MAX), max * 8.0);
+        assert_eq!(TrafficUnit::Gib.convert(u64::MAX), max / 1073741824.0);
+
+        // Still a JSON number, not `null`.
+        let mut value = json!({ "rx": u64::MAX });
+        TrafficUnit::Bits.convert_json(&mut value);
+        assert_eq!(value["rx"].as_f64(), Some(max * 8.0));
+    }
+
+    #[test]
+    fn byte_amounts_stay_integers() {
+        assert_eq!(TrafficUnit::Bytes.amount(u64::MAX), Amount::Bytes(u64::MAX));
+        assert_eq!(
+            serde_json::to_value(Amount::Bytes(u64::MAX)).unwrap(),
+            json!(u64::MAX)
+        );
+        assert_eq!(TrafficUnit::Kib.amount(1536), Amount::Converted(1.5));
+        assert_eq!(
+            serde_json::to_value(Amount::Converted(1.5)).unwrap(),
+            json!(1.5)
+        );
+    }
+
+    #[test]
+    fn converts_traffic_fields_at_any_depth() {
+        let mut value = json!({
+            "name": "eth0",
+            "id": 2048,
+            "traffic": {
+                "total": { "rx": 2048, "tx": 1024 },
+                "day": [{ "id": 7, "rx": 1024, "tx": 0, "timestamp": 1024 }],
+            },
+            "totalRx": 3072,
+            "totalTx": 4096,
+        });
+
+        TrafficUnit::Kib.convert_json(&mut value);
+
+        assert_eq!(value["unit"], "kib");
+        assert_eq!(value["traffic"]["total"], json!({ "rx": 2.0, "tx": 1.0 }));
+        assert_eq!(
+            value["traffic"]["day"][0],
+            json!({ "id": 7, "rx": 1.0, "tx": 0.0, "timestamp": 1024 })
+        );
+        assert_eq!(value["totalRx"], 3.0);
+        assert_eq!(value["totalTx"], 4.0);
+        // Other numbers are left alone.
+        assert_eq!(value["id"], 2048);
+        assert!(value["traffic"].get("unit").is_none());
+    }
+
+    #[test]
+    fn marks_every_object_of_an_array() {
+        let mut value = json!([{ "rx": 1024 }, { "tx": 2048 }, 5]);
+        TrafficUnit::Kib.convert_json(&mut value);
+        assert_eq!(
+            value,
+            json!([{ "rx": 1.0, "unit": "kib" }, { "tx": 2.0, "unit": "kib" }, 5])
+        );
+
+        let mut scalar = json!(1024);
+        TrafficUnit::Kib.convert_json(&mut scalar);
+        assert_eq!(scalar, json!(1024));
+    }
+
+    #[test]
+    fn leaves_values_that_are_no_byte_counts() {
+        let mut value = json!({ "rx": -1, "tx": "1024", "totalRx": 1.5, "totalTx": null });
+        TrafficUnit::Bits.convert_json(&mut value);
+        assert_eq!(
+            value,
+            json!({ "rx": -1, "tx": "1024", "totalRx": 1.5, "totalTx": null, "unit": "bits" })
+        );
+    }
+}
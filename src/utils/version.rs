@@ -0,0 +1,89 @@
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// A `major.minor[.patch]` version number as used by vnStat (e.g. `2.12`,
+/// `1.18`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl Version {
+    /// Creates a version from its components.
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Extracts the vnStat version from `vnstat --version` output such as
+    /// `vnStat 2.12 by Teemu Toivola <tst at iki dot fi>`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the output does not start with `vnStat` followed
+    /// by a version number, i.e. the binary is probably not vnStat.
+    pub fn from_vnstat_output(output: &str) -> Result<Self> {
+        let mut words = output.split_whitespace();
+
+        match (words.next(), words.next()) {
+            (Some(name), Some(version)) if name.eq_ignore_ascii_case("vnstat") => version
+                .parse()
+                .with_context(|| format!("unparseable vnStat version `{}`", version)),
+            _ => bail!(
+                "`--version` output does not look like vnStat: `{}`",
+                output.lines().next().unwrap_or_default()
+            ),
+        }
+    }
+}
+
+impl FromStr for Version {
+    type Err = anyhow::Error;
+
+    /// Parses `major[.minor[.patch]]`; missing components default to `0`.
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = s.trim().splitn(3, '.');
+        let mut next = || -> Result<u32> {
+            match parts.next() {
+                Some(p) => p
+                    .parse()
+                    .with_context(|| format!("invalid version `{}`", s)),
+                None => Ok(0),
+            }
+        };
+
+        Ok(Version::new(next()?, next()?, next()?))
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.patch == 0 {
+            write!(f, "{}.{}", self.major, self.minor)
+        } else {
+            write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+        }
+    }
+}
+
+impl Serialize for Version {
+    /// Serialises as the version string (e.g. `"2.12"`).
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Version {
+    /// Deserialises from a version string (e.g. `"1.13"`).
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
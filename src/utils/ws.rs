@@ -0,0 +1,98 @@
+use crate::task_handle::{TaskDropGuard, TaskMessage};
+use axum::extract::ws::{CloseFrame, Message, WebSocket, close_code};
+use tokio::sync::broadcast::Receiver;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{trace, warn};
+
+/// Maximum length of a WebSocket close reason, in bytes (RFC 6455 §5.5).
+const MAX_CLOSE_REASON: usize = 123;
+
+/// Forwards the messages of a live task to a WebSocket until either side
+/// ends.
+///
+/// * Data lines are sent as text frames.
+/// * A fatal error closes the socket with code `1011` and a JSON reason
+///   (`{"error":"..."}`); other errors (e.g. before a process restart) are
+///   only logged.
+/// * End of stream closes the socket normally (`1000`).
+/// * Comments have no WebSocket equivalent and are dropped.
+///
+/// Client pings are answered automatically by the WebSocket
+/// implementation; other client messages are ignored. The `guard` is held
+/// until the function returns, so closing the socket releases the
+/// subscription.
+///
+/// # Arguments
+///
+/// * `socket` — The upgraded WebSocket.
+/// * `receiver` — The task subscription's receiver.
+/// * `guard` — The task subscription's drop guard.
+/// * `name` — The stream name used in log messages.
+pub async fn forward_task_messages(
+    mut socket: WebSocket,
+    mut receiver: Receiver<TaskMessage>,
+    guard: TaskDropGuard,
+    name: String,
+) {
+    let _guard = guard;
+
+    loop {
+        tokio::select! {
+            message = receiver.recv() => match message {
+                Ok(TaskMessage::Data(_, line)) => {
+                    if socket.send(Message::Text(line.into())).await.is_err() {
+                        break;
+                    }
+                }
+                Ok(TaskMessage::Comment(_)) => {}
+                Ok(TaskMessage::Error { message, fatal: false }) => {
+                    warn!("WebSocket [{}] task error: {}", name, message);
+                }
+                Ok(TaskMessage::Error { message, fatal: true }) => {
+                    close(&mut socket, close_code::ERROR, error_reason(&message)).await;
+                    break;
+                }
+                Ok(TaskMessage::Eof(_)) | Err(RecvError::Closed) => {
+                    close(&mut socket, close_code::NORMAL, String::new()).await;
+                    break;
+                }
+                Err(RecvError::Lagged(n)) => {
+                    warn!("WebSocket [{}] message lagged: dropped {} messages", name, n);
+                }
+            },
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+
+    trace!("WebSocket [{}] closed", name);
+}
+
+/// Sends a close frame, ignoring failures (the peer may be gone already).
+async fn close(socket: &mut WebSocket, code: u16, reason: String) {
+    let frame = CloseFrame {
+        code,
+        reason: reason.into(),
+    };
+    let _ = socket.send(Message::Close(Some(frame))).await;
+}
+
+/// Builds the JSON close reason for `error`, shortening the message so the
+/// reason fits into a close frame.
+fn error_reason(error: &str) -> String {
+    let mut message = error;
+    loop {
+        let reason = serde_json::json!({ "error": message }).to_string();
+        if reason.len() <= MAX_CLOSE_REASON || message.is_empty() {
+            return reason;
+        }
+
+        let mut end = message.len() - (reason.len() - MAX_CLOSE_REASON).min(message.len());
+        while !message.is_char_boundary(end) {
+            end -= 1;
+        }
+        message = &message[..end];
+    }
+}
@@ -0,0 +1,116 @@
+//! Tests of the vnStat snapshot cache: its lifetime, and how it behaves
+//! while vnStat is slow to answer.
+
+mod support;
+
+use axum::http::StatusCode;
+use std::sync::Arc;
+use std::time::Duration;
+use support::{MockExecutor, get, router_with_state_of};
+use tokio::time::Instant;
+use vnstat_rs_api::config::AppConfig;
+
+/// How long the slow vnStat runs take.
+const SLOW: Duration = Duration::from_secs(20);
+
+#[tokio::test(start_paused = true)]
+async fn a_slow_refresh_does_not_hold_up_other_requests() {
+    let executor = Arc::new(MockExecutor::with_fixture("vnstat2.json"));
+    let (_, router) = router_with_state_of(&AppConfig::default(), executor.clone());
+    let (status, _) = get(&router, "/vnstat/eth0").await;
+    assert_eq!(status, StatusCode::OK);
+
+    // The snapshot expires, and refreshing it now hangs.
+    tokio::time::advance(Duration::from_secs(61)).await;
+    executor.set_delay(SLOW);
+    let refreshing = tokio::spawn({
+        let router = router.clone();
+        async move { get(&router, "/vnstat/eth0").await }
+    });
+    while executor.runs() < 2 {
+        tokio::task::yield_now().await;
+    }
+
+    let started = Instant::now();
+    let (status, body) = get(&router, "/vnstat/eth0").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["name"], "eth0");
+    assert!(started.elapsed() < SLOW, "waited for the refresh");
+    assert!(!refreshing.is_finished());
+    assert_eq!(executor.runs(), 2, "a single refresh runs");
+
+    let (status, _) = refreshing.await.expect("request task");
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test(start_paused = true)]
+async fn requests_do_not_run_vnstat_beside_a_background_refresh() {
+    let executor = Arc::new(MockExecutor::with_fixture("vnstat2.json"));
+    let (state, router) = router_with_state_of(&AppConfig::default(), executor.clone());
+    let (status, _) = get(&router, "/vnstat/eth0").await;
+    assert_eq!(status, StatusCode::OK);
+
+    tokio::time::advance(Duration::from_secs(61)).await;
+    executor.set_delay(SLOW);
+    let refresh = tokio::spawn({
+        let state = state.clone();
+        async move { state.vnstat.refresh().await }
+    });
+    while executor.runs() < 2 {
+        tokio::task::yield_now().await;
+    }
+
+    let started = Instant::now();
+    let (status, _) = get(&router, "/vnstat/eth0").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(started.elapsed() < SLOW, "waited for the refresh");
+    assert_eq!(executor.runs(), 2, "vnStat ran beside the refresh");
+
+    refresh.await.expect("refresh task").expect("refreshed");
+    let (_, body) = get(&router, "/vnstat/eth0").await;
+    assert_eq!(body["status"], "success");
+    assert_eq!(executor.runs(), 2, "the refreshed snapshot is served");
+}
+
+#[tokio::test(start_paused = true)]
+async fn the_cache_lifetime_is_configured_and_reloaded() {
+    let mut config = AppConfig::default();
+    config.cache.ttl_seconds = 10;
+    let executor = Arc::new(MockExecutor::with_fixture("vnstat2.json"));
+    let (state, router) = router_with_state_of(&config, executor.clone());
+    get(&router, "/vnstat/eth0").await;
+    tokio::time::advance(Duration::from_secs(11)).await;
+    get(&router, "/vnstat/eth0").await;
+    assert_eq!(executor.runs(), 2, "the snapshot outlived ttl_seconds");
+
+    config.cache.ttl_seconds = 120;
+    state.reload(&config).await.expect("reloaded");
+    let (_, body) = get(&router, "/info").await;
+    assert_eq!(body["data"]["cache"]["ttlSeconds"], 120);
+
+    get(&router, "/vnstat/eth0").await;
+    tokio::time::advance(Duration::from_secs(61)).await;
+    get(&router, "/vnstat/eth0").await;
+    assert_eq!(
+        executor.runs(),
+        3,
+        "the reloaded ttl_seconds is not applied"
+    );
+}
+
+#[tokio::test]
+async fn cached_interface_lists_report_the_current_age() {
+    let executor = Arc::new(MockExecutor::with_fixture("vnstat2.json"));
+    let (_, router) = router_with_state_of(&AppConfig::default(), executor.clone());
+
+    let (status, first) = get(&router, "/vnstat/interfaces").await;
+    assert_eq!(status, StatusCode::OK);
+    // The ages are counted in wall-clock seconds.
+    tokio::time::sleep(Duration::from_millis(1_100)).await;
+    let (_, second) = get(&router, "/vnstat/interfaces").await;
+
+    assert_eq!(executor.runs(), 1, "served from the cache");
+    let age = |body: &serde_json::Value| body["data"][0]["secondsSinceUpdate"].as_i64().unwrap();
+    assert!(age(&second) > age(&first), "{} then {}", first, second);
+    assert_eq!(second["data"][0]["stale"], true);
+}
@@ -0,0 +1,77 @@
+//! Tests of `GET /capabilities`, checking the reported matrix against the
+//! behaviour of the endpoints it describes.
+
+mod support;
+
+use axum::http::StatusCode;
+use std::sync::Arc;
+use support::{MockExecutor, Reply, get, router_with, state_with};
+use vnstat_rs_api::config::AppConfig;
+use vnstat_rs_api::router::router_with_state;
+
+#[tokio::test]
+async fn vnstat_down_with_a_cached_snapshot_is_degraded() {
+    let executor = Arc::new(MockExecutor::with_fixture("vnstat2.json"));
+    let state = state_with(&AppConfig::default(), executor.clone());
+    let router = router_with_state(state.clone());
+    let (status, _) = get(&router, "/vnstat").await;
+    assert_eq!(status, StatusCode::OK);
+
+    executor.set_reply(Reply::Fail {
+        code: 1,
+        stderr: "Error: Unable to open database".to_string(),
+    });
+    assert!(state.vnstat.refresh().await.is_err());
+
+    let (_, body) = get(&router, "/capabilities").await;
+    assert_eq!(body["data"]["data"]["status"], "degraded");
+    assert_eq!(body["data"]["data"]["reason"], "vnstat_unavailable");
+    assert_eq!(body["data"]["export"]["status"], "degraded");
+
+    let (status, body) = get(&router, "/vnstat/eth0").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["meta"]["stale"], true);
+}
+
+#[tokio::test]
+async fn live_streams_disabled_by_flag() {
+    let mut config = AppConfig::default();
+    config.server.features.live_streams = false;
+    let router = router_with(
+        &config,
+        Arc::new(MockExecutor::with_fixture("vnstat2.json")),
+    );
+
+    let (_, body) = get(&router, "/capabilities").await;
+    assert_eq!(body["data"]["live"]["status"], "disabled");
+    assert_eq!(body["data"]["live"]["reason"], "disabled_by_config");
+    assert_eq!(body["data"]["data"]["status"], "available");
+
+    let (status, body) = get(&router, "/vnstat/eth0/live/poll").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], 10014);
+}
+
+#[tokio::test]
+async fn admin_blocked_while_disabled() {
+    let router = router_with(
+        &AppConfig::default(),
+        Arc::new(MockExecutor::with_fixture("vnstat2.json")),
+    );
+
+    let (_, body) = get(&router, "/capabilities").await;
+    for group in ["admin", "metrics"] {
+        assert_eq!(body["data"][group]["status"], "disabled", "{}", group);
+        assert_eq!(
+            body["data"][group]["reason"], "disabled_by_config",
+            "{}",
+            group
+        );
+    }
+
+    for uri in ["/admin/tasks", "/admin/stats"] {
+        let (status, body) = get(&router, uri).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{}", uri);
+        assert_eq!(body["code"], 10002, "{}", uri);
+    }
+}
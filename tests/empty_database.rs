@@ -0,0 +1,48 @@
+//! Tests against the output of `vnstat --json` for a database without any
+//! interface, as on a freshly installed box.
+
+mod support;
+
+use axum::http::StatusCode;
+use std::sync::Arc;
+use support::{MockExecutor, get, router};
+
+fn empty_router() -> axum::Router {
+    router(Arc::new(MockExecutor::with_fixture("empty.json")))
+}
+
+#[tokio::test]
+async fn serves_the_empty_database() {
+    let (status, body) = get(&empty_router(), "/vnstat").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "success");
+    assert_eq!(body["data"]["vnstatversion"], "2.12");
+    assert_eq!(body["data"]["interfaces"], serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn interface_endpoints_suggest_adding_one() {
+    let router = empty_router();
+
+    for uri in ["/vnstat/interfaces", "/vnstat/eth0", "/vnstat/eth0/week"] {
+        let (status, body) = get(&router, uri).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{}", uri);
+        assert_eq!(body["code"], 10026, "{}", uri);
+        let message = body["message"].as_str().unwrap_or_default();
+        assert!(message.contains("vnstat --add"), "{}: {}", uri, message);
+    }
+}
+
+#[tokio::test]
+async fn reports_degraded_health_and_capabilities() {
+    let router = empty_router();
+
+    let (status, body) = get(&router, "/vnstat/health").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"], "degraded");
+
+    let (_, body) = get(&router, "/capabilities").await;
+    assert_eq!(body["data"]["data"]["status"], "degraded");
+    assert_eq!(body["data"]["data"]["reason"], "no_interfaces");
+}
@@ -0,0 +1,102 @@
+//! Tests of the `[server.features]` route groups: a disabled group answers
+//! like an unknown route, the others are unaffected.
+
+mod support;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode, header};
+use std::sync::Arc;
+use support::{MockExecutor, router_with, send};
+use vnstat_rs_api::config::AppConfig;
+use vnstat_rs_api::config::features::FeaturesConfig;
+
+const TOKEN: &str = "secret";
+
+/// One request per route group, by the name of its flag.
+const GROUPS: [(&str, Method, &str); 5] = [
+    (
+        "live_streams",
+        Method::GET,
+        "/vnstat/eth0/live/poll?timeout=1",
+    ),
+    ("raw_endpoints", Method::GET, "/vnstat/eth0/raw"),
+    ("management", Method::PUT, "/vnstat/interfaces/eth0/alias"),
+    ("admin", Method::GET, "/admin/tasks"),
+    ("metrics", Method::GET, "/admin/stats"),
+];
+
+/// Returns a configuration with the admin endpoints enabled.
+fn admin_config() -> AppConfig {
+    let mut config = AppConfig::default();
+    config.admin.enabled = true;
+    config.admin.token = Some(TOKEN.to_string());
+    config
+}
+
+/// Returns [`admin_config`] with the group `disabled` switched off.
+fn config_without(disabled: &str) -> AppConfig {
+    let mut config = admin_config();
+    let features: &mut FeaturesConfig = &mut config.server.features;
+    let flag = match disabled {
+        "live_streams" => &mut features.live_streams,
+        "raw_endpoints" => &mut features.raw_endpoints,
+        "management" => &mut features.management,
+        "admin" => &mut features.admin,
+        "metrics" => &mut features.metrics,
+        other => panic!("unknown group {}", other),
+    };
+    *flag = false;
+    config
+}
+
+/// Builds an authorized request, with a JSON body for writes.
+fn request(method: &Method, uri: &str) -> Request<Body> {
+    let body = if method == Method::GET {
+        Body::empty()
+    } else {
+        Body::from(r#"{"alias":"uplink"}"#)
+    };
+    Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(header::AUTHORIZATION, format!("Bearer {}", TOKEN))
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(body)
+        .expect("valid request")
+}
+
+#[tokio::test]
+async fn disabled_groups_look_like_unknown_routes() {
+    for (disabled, _, _) in GROUPS {
+        let executor = MockExecutor::with_fixture("vnstat2.json").with_live_lines(["{}"]);
+        let router = router_with(&config_without(disabled), Arc::new(executor));
+
+        for (group, method, uri) in &GROUPS {
+            let (status, body) = send(&router, request(method, uri)).await;
+            // The metrics are served under `/admin`.
+            if *group == disabled || (disabled == "admin" && *group == "metrics") {
+                assert_eq!(status, StatusCode::NOT_FOUND, "{} off: {}", disabled, uri);
+                assert_eq!(body["status"], "fail", "{} off: {}", disabled, uri);
+                assert_eq!(body["code"], 10014, "{} off: {}", disabled, uri);
+            } else {
+                assert_ne!(body["code"], 10014, "{} off: {}", disabled, uri);
+            }
+        }
+
+        let (status, _) = send(&router, request(&Method::GET, "/vnstat/eth0")).await;
+        assert_eq!(status, StatusCode::OK, "{} off", disabled);
+    }
+}
+
+#[tokio::test]
+async fn every_group_is_served_by_default() {
+    let mut config = admin_config();
+    config.vnstat.allow_management = true;
+    let executor = MockExecutor::with_fixture("vnstat2.json").with_live_lines(["{}"]);
+    let router = router_with(&config, Arc::new(executor));
+
+    for (group, method, uri) in &GROUPS {
+        let (status, body) = send(&router, request(method, uri)).await;
+        assert_eq!(status, StatusCode::OK, "{}: {}", group, body);
+    }
+}
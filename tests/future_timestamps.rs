@@ -0,0 +1,195 @@
+//! The `vnstat.future_timestamps` policies, end to end: `future.json` holds
+//! records dated 2099 next to ordinary ones.
+
+mod support;
+
+use axum::Router;
+use axum::http::StatusCode;
+use serde_json::Value;
+use std::sync::Arc;
+use support::{MockExecutor, get, router, router_with};
+use vnstat_rs_api::config::AppConfig;
+use vnstat_rs_api::config::vnstat::FutureTimestampPolicy;
+use vnstat_rs_api::utils::timestamp;
+
+/// Future-dated entries in `future.json`: a five-minute, two day, a month,
+/// a year and a top record, and the `updated` stamp.
+const FUTURE_RECORDS: u64 = 7;
+
+fn future_router(policy: FutureTimestampPolicy) -> Router {
+    let mut config = AppConfig::default();
+    config.vnstat.future_timestamps = policy;
+    config
+        .quota
+        .limits
+        .insert("eth0".to_string(), "1TB".parse().expect("valid size"));
+    router_with(&config, Arc::new(MockExecutor::with_fixture("future.json")))
+}
+
+/// Returns the `timestamp` fields of `records`.
+fn timestamps(records: &Value) -> Vec<i64> {
+    records
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["timestamp"].as_i64().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn clamp_moves_future_records_into_the_current_period() {
+    let router = future_router(FutureTimestampPolicy::Clamp);
+    let now = timestamp::get_in_secs();
+
+    let (status, body) = get(&router, "/vnstat/eth0").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["meta"]["futureRecords"], FUTURE_RECORDS);
+
+    let traffic = &body["data"]["traffic"];
+    let days = traffic["day"].as_array().unwrap();
+    assert_eq!(days.len(), 3);
+    let today = &days[2];
+    assert_eq!(
+        (today["rx"].as_u64(), today["tx"].as_u64()),
+        (Some(12), Some(2))
+    );
+    let (year, month, day) = timestamp::to_local_date(now).unwrap();
+    assert_eq!(today["date"]["year"], year);
+    assert_eq!(today["date"]["month"], month);
+    assert_eq!(today["date"]["day"], day);
+
+    for list in ["fiveminute", "hour", "day", "month", "year", "top"] {
+        let stamps = timestamps(&traffic[list]);
+        assert!(stamps.iter().all(|&ts| ts <= now), "{}: {:?}", list, stamps);
+    }
+    assert_eq!(traffic["month"][1]["date"]["month"], month);
+    assert_eq!(traffic["year"][1]["date"]["year"], year);
+    assert!(body["data"]["updated"]["timestamp"].as_i64().unwrap() <= now);
+    assert_eq!(body["data"]["updated"]["date"]["year"], year);
+
+    let (status, body) = get(&router, "/vnstat/eth0/busiest?granularity=day").await;
+    assert_eq!(status, StatusCode::OK);
+    let periods = timestamps(&body["data"]["periods"]);
+    assert_eq!(periods.len(), 3);
+    assert!(periods.iter().all(|&ts| ts <= now), "{:?}", periods);
+    assert_eq!(body["meta"]["futureRecords"], FUTURE_RECORDS);
+}
+
+#[tokio::test]
+async fn exclude_drops_future_records() {
+    let router = future_router(FutureTimestampPolicy::Exclude);
+    let now = timestamp::get_in_secs();
+
+    let (status, body) = get(&router, "/vnstat/eth0").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["meta"]["futureRecords"], FUTURE_RECORDS);
+
+    let traffic = &body["data"]["traffic"];
+    assert_eq!(timestamps(&traffic["day"]).len(), 2);
+    assert_eq!(timestamps(&traffic["fiveminute"]).len(), 1);
+    assert_eq!(timestamps(&traffic["top"]).len(), 1);
+    assert!(body["data"]["updated"]["timestamp"].as_i64().unwrap() <= now);
+
+    let (_, body) = get(&router, "/vnstat/eth0/busiest?granularity=day").await;
+    assert_eq!(timestamps(&body["data"]["periods"]).len(), 2);
+}
+
+#[tokio::test]
+async fn keep_serves_future_records_as_is() {
+    let router = future_router(FutureTimestampPolicy::Keep);
+    let now = timestamp::get_in_secs();
+
+    let (status, body) = get(&router, "/vnstat/eth0").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["meta"]["futureRecords"], FUTURE_RECORDS);
+
+    let days = &body["data"]["traffic"]["day"];
+    assert_eq!(timestamps(days).len(), 4);
+    assert_eq!(days[3]["date"]["year"], 2099);
+
+    let (_, body) = get(&router, "/vnstat/eth0/busiest?granularity=day").await;
+    let periods = timestamps(&body["data"]["periods"]);
+    assert_eq!(periods.len(), 4);
+    assert!(periods.iter().any(|&ts| ts > now), "{:?}", periods);
+}
+
+#[tokio::test]
+async fn omits_the_count_without_future_records() {
+    let router = router(Arc::new(MockExecutor::with_fixture("vnstat2.json")));
+
+    let (status, body) = get(&router, "/vnstat/eth0").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert!(
+        body["meta"].get("futureRecords").is_none(),
+        "{}",
+        body["meta"]
+    );
+}
+
+/// The computed endpoints, each over the records of `future.json`.
+const COMPUTED: [&str; 11] = [
+    "/vnstat/eth0",
+    "/vnstat/eth0/estimate",
+    "/vnstat/eth0/stats?granularity=fiveminute",
+    "/vnstat/eth0/stats?granularity=hour",
+    "/vnstat/eth0/delta?from=0&to=4102444800",
+    "/vnstat/eth0/week",
+    "/vnstat/eth0/billing-month",
+    "/vnstat/eth0/quota",
+    "/vnstat/eth0/busiest?granularity=day",
+    "/vnstat/total",
+    "/vnstat/compare?interfaces=eth0",
+];
+
+/// Collects every `timestamp` or `...Timestamp` field of `value`, however
+/// deeply nested.
+fn all_timestamps(value: &Value, out: &mut Vec<i64>) {
+    match value {
+        Value::Object(fields) => {
+            for (key, field) in fields {
+                match field.as_i64() {
+                    Some(ts) if key == "timestamp" || key.ends_with("Timestamp") => out.push(ts),
+                    _ => all_timestamps(field, out),
+                }
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|item| all_timestamps(item, out)),
+        _ => {}
+    }
+}
+
+#[tokio::test]
+async fn computed_endpoints_handle_every_policy() {
+    for policy in [
+        FutureTimestampPolicy::Clamp,
+        FutureTimestampPolicy::Exclude,
+        FutureTimestampPolicy::Keep,
+    ] {
+        let router = future_router(policy);
+        let now = timestamp::get_in_secs();
+
+        for uri in COMPUTED {
+            let (status, body) = get(&router, uri).await;
+            assert_eq!(status, StatusCode::OK, "{:?} {}: {}", policy, uri, body);
+            assert_eq!(body["status"], "success", "{:?} {}", policy, uri);
+            assert_eq!(
+                body["meta"]["futureRecords"], FUTURE_RECORDS,
+                "{:?} {}",
+                policy, uri
+            );
+
+            if policy != FutureTimestampPolicy::Keep {
+                let mut stamps = Vec::new();
+                all_timestamps(&body["data"], &mut stamps);
+                assert!(
+                    stamps.iter().all(|&ts| ts <= now),
+                    "{:?} {}: {:?}",
+                    policy,
+                    uri,
+                    stamps
+                );
+            }
+        }
+    }
+}
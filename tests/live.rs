@@ -0,0 +1,227 @@
+//! Router-level tests of the live endpoints against a [`MockExecutor`].
+
+mod support;
+
+use axum::Router;
+use axum::body::Body;
+use axum::http::{Request, StatusCode, header};
+use futures_util::StreamExt;
+use http_body_util::BodyExt;
+use std::sync::Arc;
+use support::{MockExecutor, get, router_with, send, serve, state_with, wait_for};
+use tokio_tungstenite::tungstenite;
+use tower::ServiceExt;
+use vnstat_rs_api::config::AppConfig;
+use vnstat_rs_api::router::router_with_state;
+use vnstat_rs_api::utils::interface_filter::InterfaceFilter;
+
+/// Returns a configuration hiding `wlan0`.
+fn hiding_wlan0() -> AppConfig {
+    let mut config = AppConfig::default();
+    config.vnstat.interface_filter =
+        InterfaceFilter::new(&[], &["wlan0".to_string()]).expect("valid pattern");
+    config
+}
+
+#[tokio::test]
+async fn hidden_interfaces_cannot_be_streamed() {
+    let executor = Arc::new(MockExecutor::with_fixture("vnstat2.json"));
+    let router = router_with(&hiding_wlan0(), executor.clone());
+    let addr = serve(router.clone()).await;
+
+    for name in ["wlan0", "eth9"] {
+        let request = Request::get(format!("/vnstat/{}/live", name))
+            .header(header::ACCEPT, "text/event-stream")
+            .body(Body::empty())
+            .unwrap();
+        let (status, body) = send(&router, request).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "SSE {}", name);
+        assert_eq!(body["code"], 10001);
+
+        let (status, body) = get(&router, &format!("/vnstat/{}/live/poll", name)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "poll {}", name);
+        assert_eq!(body["code"], 10001);
+
+        let url = format!("ws://{}/vnstat/{}/live/ws", addr, name);
+        match tokio_tungstenite::connect_async(url).await {
+            Err(tungstenite::Error::Http(response)) => {
+                assert_eq!(response.status(), StatusCode::NOT_FOUND, "WS {}", name)
+            }
+            other => panic!("WS {}: expected a 404, got {:?}", name, other.map(|_| ())),
+        }
+    }
+
+    assert_eq!(executor.live_started(), 0);
+}
+
+/// Returns an executor whose live process prints ten numbered lines.
+fn live_executor() -> Arc<MockExecutor> {
+    Arc::new(
+        MockExecutor::with_fixture("vnstat2.json")
+            .with_live_lines((1..=10).map(|i| format!("{{\"index\":{}}}", i))),
+    )
+}
+
+#[tokio::test]
+async fn polls_keep_a_single_lingering_subscription() {
+    let executor = live_executor();
+    let state = state_with(&AppConfig::default(), executor.clone());
+    let router = router_with_state(state.clone());
+
+    let (status, body) = get(&router, "/vnstat/eth0/live/poll?timeout=5").await;
+    assert_eq!(status, StatusCode::OK);
+    let mut since = body["data"][0]["seq"].as_u64().expect("a line");
+    for _ in 0..4 {
+        let uri = format!("/vnstat/eth0/live/poll?since={}&timeout=5", since);
+        let (status, body) = get(&router, &uri).await;
+        assert_eq!(status, StatusCode::OK);
+        let lines = body["data"].as_array().unwrap();
+        since = lines.last().unwrap()["seq"].as_u64().unwrap();
+        assert!(body.get("meta").is_none(), "{}", body);
+    }
+
+    assert_eq!(state.task_manager.subscriber_count(), 1);
+    assert_eq!(executor.live_started(), 1);
+    assert_eq!(executor.live_running(), 1);
+}
+
+#[tokio::test]
+async fn polls_report_missed_lines() {
+    let router = router_with(&AppConfig::default(), live_executor());
+
+    let (status, body) = get(&router, "/vnstat/eth0/live/poll?timeout=5").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.get("meta").is_none(), "{}", body);
+
+    // A sequence number the task never reached, e.g. from before a restart.
+    let (status, body) = get(&router, "/vnstat/eth0/live/poll?since=1000&timeout=1").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["meta"]["gap"], true, "{}", body);
+}
+
+#[tokio::test]
+async fn sse_and_websocket_clients_share_one_process() {
+    let executor = live_executor();
+    let router = router_with(&AppConfig::default(), executor.clone());
+    let addr = serve(router.clone()).await;
+
+    let request = Request::get("/vnstat/eth0/live")
+        .header(header::ACCEPT, "text/event-stream")
+        .body(Body::empty())
+        .unwrap();
+    let response = router.oneshot(request).await.expect("infallible");
+    assert_eq!(response.status(), StatusCode::OK);
+    let mut sse = response.into_body();
+    let frame = sse.frame().await.expect("an event").expect("readable");
+    assert!(frame.data_ref().is_some());
+
+    let url = format!("ws://{}/vnstat/eth0/live/ws", addr);
+    let (mut ws, _) = tokio_tungstenite::connect_async(url)
+        .await
+        .expect("WS upgrade");
+    loop {
+        match ws.next().await.expect("a frame").expect("readable") {
+            tungstenite::Message::Text(line) => {
+                assert!(line.contains("index"), "{}", line);
+                break;
+            }
+            tungstenite::Message::Ping(_) | tungstenite::Message::Pong(_) => {}
+            other => panic!("expected a line, got {:?}", other),
+        }
+    }
+    assert_eq!(executor.live_started(), 1);
+    assert_eq!(executor.live_running(), 1);
+
+    drop(sse);
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    assert_eq!(executor.live_running(), 1, "the WebSocket still listens");
+
+    ws.close(None).await.expect("close");
+    wait_for("the process to stop", || executor.live_running() == 0).await;
+    assert_eq!(executor.live_started(), 1);
+}
+
+#[tokio::test]
+async fn a_lagging_sse_client_recovers_and_reports_the_gap() {
+    let mut config = AppConfig::default();
+    config.vnstat.live_buffer_size = 1;
+    let executor = Arc::new(
+        MockExecutor::with_fixture("vnstat2.json")
+            .with_live_lines((1..=30).map(|i| format!("{{\"index\":{}}}", i))),
+    );
+    let router = router_with(&config, executor);
+
+    for (lag, report) in [
+        ("skip", ": Message dropped (lag)"),
+        ("latest", "skipped to the latest"),
+    ] {
+        let request = Request::get(format!("/vnstat/eth0/live?lag={}", lag))
+            .header(header::ACCEPT, "text/event-stream")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(request).await.expect("infallible");
+        assert_eq!(response.status(), StatusCode::OK);
+        let mut sse = response.into_body();
+        sse.frame().await.expect("an event").expect("readable");
+
+        // The producer runs on while the client reads nothing.
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let mut received = String::new();
+        while !received.contains("\"index\":30") {
+            let frame = tokio::time::timeout(std::time::Duration::from_secs(5), sse.frame())
+                .await
+                .unwrap_or_else(|_| panic!("lag={}: stuck after {:?}", lag, received))
+                .expect("an event")
+                .expect("readable");
+            if let Some(data) = frame.data_ref() {
+                received.push_str(&String::from_utf8_lossy(data));
+            }
+        }
+
+        assert!(received.contains(report), "lag={}: {}", lag, received);
+        assert!(
+            !received.contains("\"index\":15}"),
+            "lag={}: nothing was dropped: {}",
+            lag,
+            received
+        );
+    }
+}
+
+/// Opens an SSE stream of eth0 on `router` and waits for its first line.
+async fn subscribe(router: &Router) -> Body {
+    let request = Request::get("/vnstat/eth0/live")
+        .header(header::ACCEPT, "text/event-stream")
+        .body(Body::empty())
+        .unwrap();
+    let response = router.clone().oneshot(request).await.expect("infallible");
+    assert_eq!(response.status(), StatusCode::OK);
+    let mut sse = response.into_body();
+    sse.frame().await.expect("an event").expect("readable");
+    sse
+}
+
+#[tokio::test]
+async fn sse_clients_across_a_reload_share_the_task_manager() {
+    let executor = live_executor();
+    let state = state_with(&AppConfig::default(), executor.clone());
+    let before = router_with_state(state.clone());
+
+    let first = subscribe(&before).await;
+    let mut config = AppConfig::default();
+    config.cache.ttl_seconds = 5;
+    config.vnstat.max_stale_seconds = 600;
+    state.reload(&config).await.expect("reload");
+    let reloaded = state.clone();
+    let second = subscribe(&router_with_state(reloaded.clone())).await;
+    let third = subscribe(&before).await;
+
+    assert!(Arc::ptr_eq(&state.task_manager, &reloaded.task_manager));
+    assert_eq!(reloaded.task_manager.subscriber_count(), 3);
+    assert_eq!(executor.live_started(), 1);
+    assert_eq!(executor.live_running(), 1);
+
+    drop((first, second, third));
+    wait_for("the process to stop", || executor.live_running() == 0).await;
+    assert_eq!(executor.live_started(), 1);
+}
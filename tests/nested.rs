@@ -0,0 +1,112 @@
+//! Tests of the router nested under a prefix, as an application embedding
+//! the API mounts it.
+
+mod support;
+
+use axum::Router;
+use axum::body::Body;
+use axum::http::{Request, StatusCode, header};
+use std::sync::Arc;
+use support::{MockExecutor, get, router_with};
+use tower::ServiceExt;
+use vnstat_rs_api::config::AppConfig;
+
+/// Where the tests mount the API.
+const PREFIX: &str = "/dashboard/traffic";
+
+/// Returns an application serving the API under [`PREFIX`].
+fn nested(config: &AppConfig) -> Router {
+    let api = router_with(config, Arc::new(MockExecutor::with_fixture("vnstat2.json")));
+    Router::new().nest(PREFIX, api)
+}
+
+/// Sends `GET uri` to `app` and returns the status and `Location`.
+async fn redirect(app: &Router, uri: &str) -> (StatusCode, Option<String>) {
+    let request = Request::get(uri).body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(request).await.expect("infallible");
+    let location = response
+        .headers()
+        .get(header::LOCATION)
+        .map(|value| value.to_str().expect("ASCII location").to_string());
+    (response.status(), location)
+}
+
+#[tokio::test]
+async fn serves_the_api_under_the_prefix() {
+    let app = nested(&AppConfig::default());
+
+    for path in ["/vnstat", "/vnstat/eth0", "/vnstat/interfaces", "/info"] {
+        let (status, body) = get(&app, &format!("{}{}", PREFIX, path)).await;
+        assert_eq!(status, StatusCode::OK, "{}", path);
+        assert_eq!(body["status"], "success", "{}", path);
+    }
+
+    let (status, body) = get(&app, &format!("{}/nope", PREFIX)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], 10014);
+
+    let (status, _) = get(&app, "/vnstat/eth0").await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "served outside the prefix");
+}
+
+#[tokio::test]
+async fn legacy_redirects_link_under_the_prefix() {
+    let app = nested(&AppConfig::default());
+
+    for (legacy, target) in [
+        ("/vnstat/interfaces/eth0", "/vnstat/eth0"),
+        ("/vnstat/interfaces/eth0/traffic", "/vnstat/eth0"),
+        ("/vnstat/interfaces/eth0/traffic/week", "/vnstat/eth0/week"),
+        (
+            "/vnstat/interfaces/eth0/delta?from=1",
+            "/vnstat/eth0/delta?from=1",
+        ),
+    ] {
+        let (status, location) = redirect(&app, &format!("{}{}", PREFIX, legacy)).await;
+        assert_eq!(status, StatusCode::TEMPORARY_REDIRECT, "{}", legacy);
+        assert_eq!(
+            location.as_deref(),
+            Some(format!("{}{}", PREFIX, target).as_str()),
+            "{}",
+            legacy
+        );
+    }
+}
+
+#[tokio::test]
+async fn legacy_redirects_prepend_the_base_path() {
+    let mut config = AppConfig::default();
+    config.server.base_path = "/proxy".to_string();
+    let app = nested(&config);
+
+    let (status, location) = redirect(&app, &format!("{}/vnstat/interfaces/eth0", PREFIX)).await;
+
+    assert_eq!(status, StatusCode::TEMPORARY_REDIRECT);
+    assert_eq!(
+        location.as_deref(),
+        Some(format!("/proxy{}/vnstat/eth0", PREFIX).as_str())
+    );
+}
+
+#[tokio::test]
+async fn legacy_redirects_ignore_a_trailing_slash() {
+    let plain = router_with(
+        &AppConfig::default(),
+        Arc::new(MockExecutor::with_fixture("vnstat2.json")),
+    );
+    let app = nested(&AppConfig::default());
+
+    for (router, prefix) in [(&plain, ""), (&app, PREFIX)] {
+        for legacy in ["/vnstat/interfaces/eth0", "/vnstat/interfaces/eth0/"] {
+            let uri = format!("{}{}?unit=gib", prefix, legacy);
+            let (status, location) = redirect(router, &uri).await;
+            assert_eq!(status, StatusCode::TEMPORARY_REDIRECT, "{}", uri);
+            assert_eq!(
+                location.as_deref(),
+                Some(format!("{}/vnstat/eth0?unit=gib", prefix).as_str()),
+                "{}",
+                uri
+            );
+        }
+    }
+}
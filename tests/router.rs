@@ -0,0 +1,337 @@
+//! Router-level tests of the data endpoints against a [`MockExecutor`].
+
+mod support;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use std::sync::Arc;
+use std::time::Duration;
+use support::{MockExecutor, Reply, fixture, get, router, router_with, send};
+use vnstat_rs_api::config::AppConfig;
+use vnstat_rs_api::utils::interface_filter::InterfaceFilter;
+
+#[tokio::test]
+async fn serves_all_interfaces() {
+    let executor = Arc::new(MockExecutor::with_fixture("vnstat2.json"));
+    let router = router(executor.clone());
+
+    let (status, body) = get(&router, "/vnstat").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "success");
+    assert_eq!(body["data"]["vnstatversion"], "2.12");
+    let names: Vec<_> = body["data"]["interfaces"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|i| i["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["eth0", "wlan0"]);
+    assert_eq!(executor.runs(), 1);
+}
+
+#[tokio::test]
+async fn serves_one_interface_by_name_and_alias() {
+    let router = router(Arc::new(MockExecutor::with_fixture("vnstat2.json")));
+
+    for name in ["wlan0", "eth0", "uplink"] {
+        let (status, body) = get(&router, &format!("/vnstat/{}", name)).await;
+        assert_eq!(status, StatusCode::OK, "{}", name);
+        assert_eq!(body["status"], "success");
+    }
+
+    let (_, body) = get(&router, "/vnstat/uplink").await;
+    assert_eq!(body["data"]["name"], "eth0");
+}
+
+#[tokio::test]
+async fn lists_interfaces() {
+    let router = router(Arc::new(MockExecutor::with_fixture("vnstat2.json")));
+
+    let (status, body) = get(&router, "/vnstat/interfaces").await;
+
+    assert_eq!(status, StatusCode::OK);
+    let interfaces = body["data"].as_array().unwrap();
+    assert_eq!(interfaces.len(), 2);
+    assert_eq!(interfaces[0]["name"], "eth0");
+}
+
+#[tokio::test]
+async fn caches_vnstat_output() {
+    let executor = Arc::new(MockExecutor::with_fixture("vnstat2.json"));
+    let router = router(executor.clone());
+
+    for uri in [
+        "/vnstat",
+        "/vnstat/eth0",
+        "/vnstat/interfaces",
+        "/vnstat/total",
+    ] {
+        let (status, _) = get(&router, uri).await;
+        assert_eq!(status, StatusCode::OK, "{}", uri);
+    }
+
+    assert_eq!(executor.runs(), 1);
+}
+
+#[tokio::test]
+async fn passes_json_flag_to_vnstat() {
+    let executor = Arc::new(MockExecutor::with_fixture("vnstat2.json"));
+    let router = router(executor.clone());
+
+    get(&router, "/vnstat").await;
+
+    assert_eq!(executor.args(), [["--json"]]);
+}
+
+#[tokio::test]
+async fn reports_failed_vnstat_runs() {
+    let executor = Arc::new(MockExecutor::new(Reply::Fail {
+        code: 1,
+        stderr: "Error: Unable to open database".to_string(),
+    }));
+    let router = router(executor);
+
+    let (status, body) = get(&router, "/vnstat").await;
+
+    assert_eq!(status, StatusCode::BAD_GATEWAY);
+    assert_eq!(body["status"], "fail");
+    assert_eq!(body["code"], 10006);
+}
+
+#[tokio::test]
+async fn reports_vnstat_timeouts() {
+    let router = router(Arc::new(MockExecutor::new(Reply::Timeout)));
+
+    let (status, body) = get(&router, "/vnstat/eth0").await;
+
+    assert_eq!(status, StatusCode::GATEWAY_TIMEOUT);
+    assert_eq!(body["code"], 10004);
+}
+
+#[tokio::test]
+async fn reports_unparsable_output() {
+    let router = router(Arc::new(MockExecutor::new(Reply::Output(
+        "vnStat: database is locked".to_string(),
+    ))));
+
+    let (status, body) = get(&router, "/vnstat").await;
+
+    assert_eq!(status, StatusCode::BAD_GATEWAY);
+    assert_eq!(body["code"], 10007);
+}
+
+#[tokio::test]
+async fn health_runs_vnstat() {
+    let executor = Arc::new(MockExecutor::with_fixture("vnstat2.json"));
+    let router = router(executor.clone());
+
+    let (status, body) = get(&router, "/vnstat/health").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"], "ok");
+
+    executor.set_reply(Reply::Fail {
+        code: 1,
+        stderr: "Error: Unable to open database".to_string(),
+    });
+    let (status, body) = get(&router, "/vnstat/health").await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["status"], "error");
+}
+
+#[tokio::test]
+async fn answers_unknown_routes_with_jsend() {
+    let router = router(Arc::new(MockExecutor::with_fixture("vnstat2.json")));
+
+    let (status, body) = get(&router, "/nope").await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], 10014);
+}
+
+#[tokio::test]
+async fn answers_unsupported_methods_with_jsend() {
+    let router = router(Arc::new(MockExecutor::with_fixture("vnstat2.json")));
+
+    let request = Request::delete("/vnstat/eth0").body(Body::empty()).unwrap();
+    let (status, body) = send(&router, request).await;
+
+    assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(body["status"], "fail");
+    assert_eq!(body["code"], 10013);
+}
+
+#[tokio::test]
+async fn answers_rejected_parameters_with_jsend() {
+    let router = router(Arc::new(MockExecutor::with_fixture("vnstat2.json")));
+
+    // Not valid UTF-8 once percent-decoded.
+    let (status, body) = get(&router, "/vnstat/%FF").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["status"], "fail");
+    assert_eq!(body["code"], 10012);
+    assert!(body["message"].is_string(), "{}", body);
+
+    let (status, body) = get(&router, "/vnstat/eth0/delta?from=yesterday").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["status"], "fail");
+    assert_eq!(body["code"], 10008);
+    assert!(
+        body["message"].as_str().unwrap().contains("from"),
+        "{}",
+        body
+    );
+}
+
+#[tokio::test]
+async fn answers_panics_with_jsend() {
+    let executor = Arc::new(MockExecutor::new(Reply::Panic));
+    let router = router(executor.clone());
+
+    let (status, body) = get(&router, "/vnstat/eth0").await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(body["status"], "fail");
+    assert_eq!(body["code"], 99999);
+
+    // Nothing is left locked by the panicking request.
+    executor.set_reply(Reply::Output(support::fixture("vnstat2.json")));
+    let (status, _) = get(&router, "/vnstat/eth0").await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test(start_paused = true)]
+async fn answers_slow_requests_with_a_jsend_timeout() {
+    let mut config = AppConfig::default();
+    config.server.request_timeout_seconds = 1;
+    let executor = Arc::new(MockExecutor::with_fixture("vnstat2.json"));
+    executor.set_delay(Duration::from_secs(5));
+    let router = router_with(&config, executor);
+
+    let (status, body) = get(&router, "/vnstat/eth0").await;
+
+    assert_eq!(status, StatusCode::GATEWAY_TIMEOUT);
+    assert_eq!(body["status"], "fail");
+    assert_eq!(body["code"], 10022);
+}
+
+#[tokio::test]
+async fn hidden_interfaces_look_like_unknown_ones() {
+    let mut config = AppConfig::default();
+    config.vnstat.interface_filter =
+        InterfaceFilter::new(&[], &["wlan0".to_string()]).expect("valid pattern");
+    let router = router_with(
+        &config,
+        Arc::new(MockExecutor::with_fixture("vnstat2.json")),
+    );
+
+    for name in ["wlan0", "eth9"] {
+        for uri in [
+            format!("/vnstat/{}", name),
+            format!("/vnstat/{}/week", name),
+            format!("/vnstat/{}/busiest", name),
+        ] {
+            let (status, body) = get(&router, &uri).await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{}", uri);
+            assert_eq!(body["code"], 10001, "{}", uri);
+            assert_eq!(body["message"], "No such interface", "{}", uri);
+        }
+    }
+}
+
+#[tokio::test]
+async fn rejects_reversed_delta_windows() {
+    let router = router(Arc::new(MockExecutor::with_fixture("vnstat2.json")));
+
+    let (status, body) = get(&router, "/vnstat/eth0/delta?from=2000&to=1000").await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], 10008);
+}
+
+#[tokio::test]
+async fn serves_vnstat_1_output_like_vnstat_2() {
+    let router = router(Arc::new(MockExecutor::with_fixture("vnstat1.json")));
+
+    let (status, body) = get(&router, "/vnstat/uplink").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["name"], "eth0");
+    assert_eq!(body["data"]["traffic"]["total"]["rx"], 5u64 << 30);
+    assert!(body["data"]["traffic"]["day"][0]["timestamp"].as_i64() > Some(0));
+}
+
+#[tokio::test]
+async fn ranks_busiest_days_by_the_requested_direction() {
+    // eth0 gets an rx-heavy, a tx-heavy and a balanced day.
+    let mut data: serde_json::Value = serde_json::from_str(&fixture("vnstat2.json")).unwrap();
+    data["interfaces"][0]["traffic"]["day"] = serde_json::json!([
+        { "id": 0, "date": { "year": 2025, "month": 3, "day": 1 },
+          "timestamp": 1740787200, "rx": 900, "tx": 10 },
+        { "id": 1, "date": { "year": 2025, "month": 3, "day": 2 },
+          "timestamp": 1740873600, "rx": 10, "tx": 800 },
+        { "id": 2, "date": { "year": 2025, "month": 3, "day": 3 },
+          "timestamp": 1740960000, "rx": 500, "tx": 500 },
+    ]);
+    let router = router(Arc::new(MockExecutor::new(Reply::Output(data.to_string()))));
+
+    let (rx_day, tx_day, even_day) = (1740787200, 1740873600, 1740960000);
+    for (by, days) in [
+        ("rx", [rx_day, even_day, tx_day]),
+        ("tx", [tx_day, even_day, rx_day]),
+        ("total", [even_day, rx_day, tx_day]),
+    ] {
+        let uri = format!("/vnstat/eth0/busiest?granularity=day&by={}", by);
+        let (status, body) = get(&router, &uri).await;
+        assert_eq!(status, StatusCode::OK, "{}", uri);
+        assert_eq!(body["data"]["by"], by);
+
+        let ranked: Vec<_> = body["data"]["periods"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["timestamp"].as_i64().unwrap())
+            .collect();
+        assert_eq!(ranked, days, "{}", by);
+    }
+}
+
+#[tokio::test]
+async fn redirects_every_legacy_route_to_its_flat_route() {
+    let router = router(Arc::new(MockExecutor::with_fixture("vnstat2.json")));
+
+    for (legacy, target) in [
+        ("", ""),
+        ("/", ""),
+        ("/traffic", ""),
+        ("/stats", "/stats"),
+        ("/traffic/busiest", "/busiest"),
+        ("/traffic/week", "/week"),
+        ("/traffic/billing-month", "/billing-month"),
+        ("/estimate", "/estimate"),
+        ("/quota", "/quota"),
+        ("/delta", "/delta"),
+        ("/raw", "/raw"),
+        ("/live", "/live"),
+        ("/updates", "/updates"),
+    ] {
+        let request = Request::get(format!("/vnstat/interfaces/eth%200{}?a=1", legacy))
+            .body(Body::empty())
+            .unwrap();
+        let response = tower::ServiceExt::oneshot(router.clone(), request)
+            .await
+            .expect("infallible");
+
+        assert_eq!(
+            response.status(),
+            StatusCode::TEMPORARY_REDIRECT,
+            "{}",
+            legacy
+        );
+        assert_eq!(
+            response.headers()["location"],
+            format!("/vnstat/eth%200{}?a=1", target).as_str(),
+            "{}",
+            legacy
+        );
+    }
+}
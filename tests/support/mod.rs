@@ -0,0 +1,284 @@
+//! Harness shared by the integration tests.
+//!
+//! [`MockExecutor`] answers every vnStat call with canned output, so the
+//! routers under test run without vnStat installed. Each test binary
+//! includes this module with `mod support;` and uses what it needs.
+
+#![allow(dead_code)]
+
+use axum::Router;
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use http_body_util::BodyExt;
+use serde_json::Value;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tower::ServiceExt;
+use vnstat_rs_api::config::AppConfig;
+use vnstat_rs_api::router::{AppState, router_with_state};
+use vnstat_rs_api::service::error::VnstatError;
+use vnstat_rs_api::service::executor::{CommandFuture, CommandOutput, Program, VnstatExecutor};
+use vnstat_rs_api::service::vnstat_service::VnstatService;
+use vnstat_rs_api::task_handle::{TaskSource, WatcherFuture};
+
+/// Version printed for `vnstat --version`.
+pub const VNSTAT_VERSION: &str = "vnStat 2.12 by Teemu Toivola <tst at iki dot fi>";
+
+/// Returns the content of `tests/fixtures/<name>`.
+pub fn fixture(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name);
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("read {}: {}", path.display(), e))
+}
+
+/// What a [`MockExecutor`] answers to a `vnstat` run other than
+/// `--version`.
+#[derive(Debug, Clone)]
+pub enum Reply {
+    /// Exits successfully, printing `stdout`.
+    Output(String),
+    /// Exits with `code`, printing `stderr`.
+    Fail { code: i32, stderr: String },
+    /// Does not finish in time.
+    Timeout,
+    /// Panics, as a bug handling the run would.
+    Panic,
+}
+
+/// A [`VnstatExecutor`] serving canned output and counting its runs.
+pub struct MockExecutor {
+    reply: Mutex<Reply>,
+    /// How long each run takes before answering.
+    delay: Mutex<Duration>,
+    /// Lines printed by `vnstat -l`, one per `live_interval`, after which
+    /// the live "process" keeps running silently until it is stopped.
+    live_lines: Vec<String>,
+    live_interval: Duration,
+    runs: AtomicUsize,
+    live_started: Arc<AtomicUsize>,
+    live_running: Arc<AtomicUsize>,
+    args: Mutex<Vec<Vec<String>>>,
+}
+
+impl MockExecutor {
+    /// Creates an executor answering every data fetch with `reply`.
+    pub fn new(reply: Reply) -> Self {
+        Self {
+            reply: Mutex::new(reply),
+            delay: Mutex::default(),
+            live_lines: Vec::new(),
+            live_interval: Duration::from_millis(20),
+            runs: AtomicUsize::new(0),
+            live_started: Arc::default(),
+            live_running: Arc::default(),
+            args: Mutex::default(),
+        }
+    }
+
+    /// Creates an executor printing the fixture `name`.
+    pub fn with_fixture(name: &str) -> Self {
+        Self::new(Reply::Output(fixture(name)))
+    }
+
+    /// Sets the lines printed by live processes.
+    pub fn with_live_lines(self, lines: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            live_lines: lines.into_iter().map(Into::into).collect(),
+            ..self
+        }
+    }
+
+    /// Replaces the answer to the following runs.
+    pub fn set_reply(&self, reply: Reply) {
+        *self.reply.lock().unwrap_or_else(PoisonError::into_inner) = reply;
+    }
+
+    /// Makes the following runs take `delay` before answering.
+    pub fn set_delay(&self, delay: Duration) {
+        *self.delay.lock().unwrap_or_else(PoisonError::into_inner) = delay;
+    }
+
+    /// Returns the number of `vnstat` runs but `--version`.
+    pub fn runs(&self) -> usize {
+        self.runs.load(Ordering::SeqCst)
+    }
+
+    /// Returns the number of live processes started so far.
+    pub fn live_started(&self) -> usize {
+        self.live_started.load(Ordering::SeqCst)
+    }
+
+    /// Returns the number of live processes running now.
+    pub fn live_running(&self) -> usize {
+        self.live_running.load(Ordering::SeqCst)
+    }
+
+    /// Returns the arguments of every run and live process so far.
+    pub fn args(&self) -> Vec<Vec<String>> {
+        self.args
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    fn record_args(&self, args: &[impl AsRef<str>]) {
+        self.args
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(args.iter().map(|a| a.as_ref().to_string()).collect());
+    }
+}
+
+impl VnstatExecutor for MockExecutor {
+    fn run<'a>(
+        &'a self,
+        program: Program,
+        args: &'a [&'a str],
+        timeout: Duration,
+    ) -> CommandFuture<'a> {
+        Box::pin(async move {
+            if program == Program::Vnstati {
+                return Err(VnstatError::SpawnFailed("vnstati is not mocked".to_string()).into());
+            }
+
+            self.record_args(args);
+            if args.contains(&"--version") {
+                return Ok(CommandOutput {
+                    code: Some(0),
+                    stdout: VNSTAT_VERSION.as_bytes().to_vec(),
+                    stderr: Vec::new(),
+                });
+            }
+
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            let delay = *self.delay.lock().unwrap_or_else(PoisonError::into_inner);
+            tokio::time::sleep(delay).await;
+            let reply = self
+                .reply
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clone();
+            match reply {
+                Reply::Output(stdout) => Ok(CommandOutput {
+                    code: Some(0),
+                    stdout: stdout.into_bytes(),
+                    stderr: Vec::new(),
+                }),
+                Reply::Fail { code, stderr } => Ok(CommandOutput {
+                    code: Some(code),
+                    stdout: Vec::new(),
+                    stderr: stderr.into_bytes(),
+                }),
+                Reply::Timeout => Err(VnstatError::Timeout(timeout).into()),
+                Reply::Panic => panic!("mocked vnStat run panicked"),
+            }
+        })
+    }
+
+    fn live(&self, args: Vec<String>) -> TaskSource {
+        self.record_args(&args);
+        let lines = self.live_lines.clone();
+        let interval = self.live_interval;
+        let started = Arc::clone(&self.live_started);
+        let running = Arc::clone(&self.live_running);
+
+        TaskSource::Watcher(Box::new(move |sink| -> WatcherFuture {
+            Box::pin(async move {
+                started.fetch_add(1, Ordering::SeqCst);
+                running.fetch_add(1, Ordering::SeqCst);
+                let _running = RunningGuard(running);
+                for line in lines {
+                    tokio::time::sleep(interval).await;
+                    sink.data(line);
+                }
+                std::future::pending::<()>().await;
+            })
+        }))
+    }
+
+    fn is_available(&self, program: Program) -> bool {
+        program == Program::Vnstat
+    }
+}
+
+/// Counts a live "process" as stopped when its watcher is dropped.
+struct RunningGuard(Arc<AtomicUsize>);
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Returns the state serving `executor` with `config`.
+pub fn state_with(config: &AppConfig, executor: Arc<MockExecutor>) -> AppState {
+    let service = VnstatService::with_executor(config.vnstat.clone(), executor);
+    AppState::with_service(config, Arc::new(service))
+}
+
+/// Returns the router serving `executor` with the default configuration.
+pub fn router(executor: Arc<MockExecutor>) -> Router {
+    router_with(&AppConfig::default(), executor)
+}
+
+/// Returns the router serving `executor` with `config`.
+pub fn router_with(config: &AppConfig, executor: Arc<MockExecutor>) -> Router {
+    router_with_state(state_with(config, executor))
+}
+
+/// Sends `request` to `router` and returns the status and the JSON body
+/// (`Value::Null` if the body is not JSON).
+pub async fn send(router: &Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = router.clone().oneshot(request).await.expect("infallible");
+    let status = response.status();
+    let body = response
+        .into_body()
+        .collect()
+        .await
+        .expect("read body")
+        .to_bytes();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+/// Sends `GET uri` to `router`, see [`send`].
+pub async fn get(router: &Router, uri: &str) -> (StatusCode, Value) {
+    let request = Request::get(uri)
+        .body(Body::empty())
+        .expect("valid request");
+    send(router, request).await
+}
+
+/// Serves `router` on a local port until the test ends and returns its
+/// address, for clients that need a real connection (WebSockets).
+pub async fn serve(router: Router) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("local address");
+    let service = router.into_make_service_with_connect_info::<SocketAddr>();
+    tokio::spawn(async move { axum::serve(listener, service).await });
+    addr
+}
+
+/// Waits up to five seconds for `done` to hold, polling every 20 ms.
+pub async fn wait_for(what: &str, mut done: impl FnMut() -> bool) {
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+    while !done() {
+        assert!(
+            tokio::time::Instant::now() < deadline,
+            "timed out waiting for {}",
+            what
+        );
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+}
+
+/// Returns the state serving `executor` with `config` and its router, for
+/// tests that also drive the service directly.
+pub fn router_with_state_of(config: &AppConfig, executor: Arc<MockExecutor>) -> (AppState, Router) {
+    let state = state_with(config, executor);
+    (state.clone(), router_with_state(state))
+}
@@ -0,0 +1,115 @@
+//! Output of different vnStat 2.x releases, served alike: `vnstat2_2.json`
+//! has no timestamps, no record IDs, no year records and a numeric
+//! `jsonversion`; `vnstat2_6.json` has times as `"HH:MM"` strings, numeric
+//! strings as dates and no `created` / `updated` timestamps;
+//! `vnstat2_12.json` has every field. All three hold the same traffic.
+
+mod support;
+
+use axum::http::StatusCode;
+use serde_json::{Value, json};
+use std::sync::Arc;
+use support::{MockExecutor, get, router};
+use vnstat_rs_api::utils::timestamp;
+
+const RELEASES: [(&str, &str); 3] = [
+    ("2.2", "vnstat2_2.json"),
+    ("2.6", "vnstat2_6.json"),
+    ("2.12", "vnstat2_12.json"),
+];
+
+/// Returns the `[rx, tx]` pair of each of `records`.
+fn traffic(records: &Value) -> Vec<[u64; 2]> {
+    records
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| [r["rx"].as_u64().unwrap(), r["tx"].as_u64().unwrap()])
+        .collect()
+}
+
+/// Returns eth0 as served from `fixture`.
+async fn eth0(fixture: &str) -> Value {
+    let router = router(Arc::new(MockExecutor::with_fixture(fixture)));
+    let (status, mut body) = get(&router, "/vnstat/eth0").await;
+    assert_eq!(status, StatusCode::OK, "{}", fixture);
+    body["data"].take()
+}
+
+/// Returns the local timestamp of the given minute.
+fn local(year: i32, month: u8, day: u8, hour: u8, minute: u8) -> i64 {
+    timestamp::from_local_date(year, month, day, hour, minute).expect("valid date")
+}
+
+#[tokio::test]
+async fn every_release_serves_the_same_traffic() {
+    for (version, fixture) in RELEASES {
+        let router = router(Arc::new(MockExecutor::with_fixture(fixture)));
+
+        let (status, body) = get(&router, "/vnstat").await;
+        assert_eq!(status, StatusCode::OK, "{}", version);
+        assert_eq!(body["data"]["vnstatversion"], version);
+        assert_eq!(body["data"]["jsonversion"], "2", "{}", version);
+
+        let (status, body) = get(&router, "/vnstat/uplink").await;
+        assert_eq!(status, StatusCode::OK, "{}", version);
+        let eth0 = &body["data"];
+        assert_eq!(eth0["name"], "eth0", "{}", version);
+        let t = &eth0["traffic"];
+        assert_eq!(t["total"], json!({ "rx": 5000, "tx": 1500 }), "{}", version);
+        assert_eq!(traffic(&t["fiveminute"]), [[500, 200]], "{}", version);
+        assert_eq!(
+            traffic(&t["hour"]),
+            [[1500, 300], [500, 200]],
+            "{}",
+            version
+        );
+        assert_eq!(
+            traffic(&t["day"]),
+            [[3000, 1000], [2000, 500]],
+            "{}",
+            version
+        );
+        assert_eq!(traffic(&t["month"]), [[5000, 1500]], "{}", version);
+        assert_eq!(traffic(&t["top"]), [[3000, 1000]], "{}", version);
+        assert_eq!(t["hour"][0]["time"], json!({ "hour": 10, "minute": 0 }));
+        assert_eq!(t["hour"][0]["date"]["day"], 10, "{}", version);
+        assert_eq!(eth0["updated"]["time"], json!({ "hour": 11, "minute": 10 }));
+    }
+}
+
+#[tokio::test]
+async fn only_releases_with_year_records_serve_them() {
+    for ((version, fixture), years) in RELEASES.into_iter().zip([0, 1, 1]) {
+        let eth0 = eth0(fixture).await;
+
+        let year = eth0["traffic"]["year"].as_array().unwrap();
+        assert_eq!(year.len(), years, "{}", version);
+    }
+}
+
+#[tokio::test]
+async fn missing_timestamps_are_taken_from_the_dates() {
+    let eth0 = eth0("vnstat2_2.json").await;
+
+    let t = &eth0["traffic"];
+    assert_eq!(eth0["created"]["timestamp"], local(2024, 1, 15, 0, 0));
+    assert_eq!(eth0["updated"]["timestamp"], local(2025, 3, 10, 11, 10));
+    assert_eq!(t["fiveminute"][0]["timestamp"], local(2025, 3, 10, 11, 5));
+    assert_eq!(t["hour"][1]["timestamp"], local(2025, 3, 10, 11, 0));
+    assert_eq!(t["day"][0]["timestamp"], local(2025, 3, 9, 0, 0));
+    assert_eq!(t["month"][0]["timestamp"], local(2025, 3, 1, 0, 0));
+    assert_eq!(t["top"][0]["timestamp"], local(2025, 3, 9, 0, 0));
+}
+
+#[tokio::test]
+async fn timestamps_given_by_vnstat_are_kept() {
+    let v2_6 = eth0("vnstat2_6.json").await;
+    assert_eq!(v2_6["traffic"]["day"][1]["timestamp"], 1741564800);
+    assert_eq!(v2_6["updated"]["timestamp"], local(2025, 3, 10, 11, 10));
+
+    let v2_12 = eth0("vnstat2_12.json").await;
+    assert_eq!(v2_12["created"]["timestamp"], 1705276800);
+    assert_eq!(v2_12["updated"]["timestamp"], 1741605000);
+    assert_eq!(v2_12["traffic"]["year"][0]["timestamp"], 1735689600);
+}